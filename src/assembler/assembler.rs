@@ -5,117 +5,878 @@
 //! Author: mai21asm, c19hln
 //! Since: 2022-11-24
 //! Version: 1.0
+use crate::assembler::ast::{Line, StatementKind};
+use crate::assembler::diagnostics::{Diagnostic, Diagnostics};
+use crate::assembler::directives;
+use crate::assembler::expressions::Symbols;
 use crate::assembler::first_pass;
-use crate::assembler::second_pass;
-use darken_assignment1::utils;
-use self::utils::InstrRepresentable;
-
-pub fn run_assembler(args: Vec<String>) -> Result<Vec<Box<dyn InstrRepresentable>>, String> {
-    
-    
-    if args.len() < 1 {
-        println!("Usage: 'cargo run path/to/input.txt path/to/instruction_output.txt path/to/listing_output.txt'");
-        return Err("Usage: 'cargo run path/to/input.txt path/to/instruction_output.txt path/to/listing_output.txt'".to_string());
-    } 
-    let input_file_path = &args[1];
-    let instruction_output_path;
-    let listing_output_path;
-    if args.len() < 3 {
-        instruction_output_path = "default_instructions.txt";
-        listing_output_path = "default_listings.txt";
-    } else {
-        instruction_output_path = &args[2];
-        listing_output_path = &args[3];
-    }
-    
-
-    if let Ok(lines) = utils::read_lines(input_file_path) {
-        let table = first_pass::build_symbol_table(lines, &listing_output_path);
-        
-        let vec = second_pass::parse_write_instructions(
-            input_file_path,
-            instruction_output_path,
-            listing_output_path,
-            table,
-        );
+use crate::assembler::formats::{self, OutputFormat, FORMAT_NAMES};
+use crate::assembler::instructions::Instruction;
+use crate::assembler::lexer::Span;
+use crate::assembler::linker;
+use crate::assembler::listing::{self, ListingLine};
+use crate::assembler::object::{Binding, Object, ObjectSymbol, Section, SourceLocation};
+use crate::assembler::parser;
+use crate::assembler::second_pass::{self, SecondPassOutput};
+use crate::disassembler;
+use crate::elf::{ElfExecutable, ElfSymbol, Segment};
+use crate::loader::{self, MemoryImage};
+use crate::utils::InstrRepresentable;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Options for `assemble`.
+#[derive(Debug, Clone)]
+pub struct AssemblerOptions {
+    /// Name of the source being assembled, used in diagnostics.
+    pub file_name: String,
+    /// Directories to look for files in `.include` directives in,
+    /// after the directory of the including file.
+    pub include_dirs: Vec<PathBuf>,
+}
+
+impl Default for AssemblerOptions {
+    fn default() -> AssemblerOptions {
+        AssemblerOptions {
+            file_name: "<input>".to_string(),
+            include_dirs: Vec::new(),
+        }
+    }
+}
+
+/// An assembled program.
+#[derive(Debug)]
+pub struct Program {
+    /// The instructions of the text segment, the first one at address 0.
+    pub text: Vec<Instruction>,
+    /// The initial contents of data memory, starting at address 0.
+    pub data: Vec<u8>,
+    /// Every label in the source and the address it refers to.
+    pub symbols: HashMap<String, u32>,
+    /// The section of every label, telling instruction and data addresses apart.
+    pub label_sections: HashMap<String, Section>,
+    /// The contents of the listing file.
+    pub listing: String,
+    /// What every source line was assembled into, for the JSON listing.
+    pub listing_lines: Vec<ListingLine>,
+}
+
+impl Program {
+    /// The contents of the instruction output file, the hex representation
+    /// of one instruction on each line.
+    pub fn instruction_output(&self) -> String {
+        let mut output = String::new();
+        for instruction in &self.text {
+            output.push_str(instruction.to_hex_string().as_str());
+            output.push('\n');
+        }
+        output
+    }
 
-        if let Err(err_msg) = vec {
-            return Err(err_msg);
+    /// The text segment in the given format. The `Lines` format is the instruction output.
+    pub fn text_output(&self, format: OutputFormat) -> Vec<u8> {
+        match format {
+            OutputFormat::Lines => self.instruction_output().into_bytes(),
+            _ => {
+                let words: Vec<u32> = self.text.iter().map(|instruction| instruction.encode()).collect();
+                format.write(&words, 0)
+            }
         }
+    }
+
+    /// The initial contents of data memory in the given format.
+    pub fn data_output(&self, format: OutputFormat) -> Vec<u8> {
+        format.write(&formats::to_words(&self.data), 0)
+    }
+
+    /// The program as a big-endian MIPS ELF32 executable. The simulator keeps instructions
+    /// and data in separate memories, so both the `.text` and the `.data` section start at
+    /// address 0. The entry point is the first instruction.
+    pub fn elf_output(&self) -> Vec<u8> {
+        let text = self.text.iter().flat_map(|instruction| instruction.encode().to_be_bytes());
+        let mut symbols: Vec<ElfSymbol> = self
+            .symbols
+            .iter()
+            .map(|(name, value)| ElfSymbol {
+                name: name.clone(),
+                value: *value,
+                section: self.label_sections.get(name).copied().unwrap_or(Section::Text),
+            })
+            .collect();
+        symbols.sort_by(|a, b| (a.value, &a.name).cmp(&(b.value, &b.name)));
 
-        
-        vec
-    } else {
-        panic!("failed to read lines from input file {}", &args[1]);
+        let executable = ElfExecutable {
+            entry: 0,
+            segments: vec![
+                Segment {
+                    vaddr: 0,
+                    bytes: text.collect(),
+                    executable: true,
+                },
+                Segment {
+                    vaddr: 0,
+                    bytes: self.data.clone(),
+                    executable: false,
+                },
+            ],
+            symbols,
+        };
+        executable.to_bytes()
     }
 
-    
+    /// The JSON listing: every source line with its address, encoding and the labels it
+    /// defines, and the symbol table sorted by address.
+    pub fn json_listing(&self) -> String {
+        listing::to_json(&self.listing_lines, &self.symbols, &self.label_sections)
+    }
+
+    /// The instructions of the program, ready to be loaded into the simulator.
+    pub fn into_instructions(self) -> Vec<Box<dyn InstrRepresentable>> {
+        self.text
+            .into_iter()
+            .map(|instruction| Box::new(instruction) as Box<dyn InstrRepresentable>)
+            .collect()
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use darken_assignment1::utils::read_lines;
-    use std::fs::File;
+/// Assembles the given source without touching the filesystem.
+///
+/// input: source - the assembly source
+///        options - how to assemble it
+/// returns: the assembled program, or every error found in the source
+pub fn assemble(source: &str, options: &AssemblerOptions) -> Result<Program, Diagnostics> {
+    let mut diagnostics = Diagnostics::new();
+    let (lines, symbols, output) = run_passes(source, options, false, &mut diagnostics);
 
-    use super::*;
+    // without other files there is nothing to resolve `.extern` labels with
+    for (line, name, span) in declared_names(&lines, ".extern") {
+        if symbols.externs.contains(&name) {
+            diagnostics.push(Diagnostic::new(
+                line.file_name(&options.file_name),
+                line.number,
+                &line.text,
+                Some(span),
+                format!("label `{}` is declared `.extern`, but no other file is linked", name),
+                Some(format!("assemble this file together with the file defining `{}`", name)),
+            ));
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        diagnostics.sort_by_position();
+        return Err(diagnostics);
+    }
+
+    Ok(Program {
+        text: output.instructions,
+        data: output.data,
+        symbols: symbols.labels,
+        label_sections: symbols.label_sections,
+        listing: output.listing,
+        listing_lines: output.lines,
+    })
+}
+
+/// Assembles the given source into an object, to be linked with objects assembled
+/// from other files.
+///
+/// input: source - the assembly source
+///        options - how to assemble it
+/// returns: the object, or every error found in the source
+pub fn assemble_object(source: &str, options: &AssemblerOptions) -> Result<Object, Diagnostics> {
+    let mut diagnostics = Diagnostics::new();
+    let (lines, symbols, output) = run_passes(source, options, true, &mut diagnostics);
 
-    fn check_instruction_output_from_input_file(
-        input_path: &str,
-        expected_output_path: &str,
-        output_path: &str,
-    ) {
-        if let Ok(lines) = read_lines(input_path) {
-            File::create(output_path).expect("Could not open output file!");
-            let table = first_pass::build_symbol_table(lines, "");
-            second_pass::parse_write_instructions(
-                input_path,
-                output_path,
-                "test/test_listing_output.txt",
-                table,
-            ).unwrap();
-            let expected_lines = read_lines(expected_output_path)
-                .expect(format!("Could not open file {}", expected_output_path).as_str());
-            let actual_lines = read_lines(output_path)
-                .expect(format!("Could not open file {}", output_path).as_str());
-            let line_pairings = expected_lines.zip(actual_lines);
-            for (expected, actual) in line_pairings {
-                assert!(expected.is_ok());
-                assert!(actual.is_ok());
-                assert_eq!(expected.unwrap(), actual.unwrap());
+    if !diagnostics.is_empty() {
+        diagnostics.sort_by_position();
+        return Err(diagnostics);
+    }
+
+    let location = |line: &Line, span| SourceLocation {
+        file: line.file_name(&options.file_name).to_string(),
+        line: line.number,
+        text: line.text.clone(),
+        span: Some(span),
+    };
+    let mut object_symbols = Vec::new();
+    for line in &lines {
+        for label in &line.labels {
+            let binding = match symbols.globals.contains(&label.name) {
+                true => Binding::Global,
+                false => Binding::Local,
+            };
+            object_symbols.push(ObjectSymbol {
+                name: label.name.clone(),
+                binding,
+                section: symbols.label_sections.get(&label.name).copied(),
+                value: symbols.labels[&label.name],
+                location: location(line, label.span),
+            });
+        }
+    }
+    for (line, name, span) in declared_names(&lines, ".extern") {
+        object_symbols.push(ObjectSymbol {
+            name,
+            binding: Binding::Extern,
+            section: None,
+            value: 0,
+            location: location(line, span),
+        });
+    }
+
+    Ok(Object {
+        name: options.file_name.clone(),
+        text: output.instructions,
+        data: output.data,
+        symbols: object_symbols,
+        relocations: output.relocations,
+        listing: output.listing,
+        listing_lines: output.lines,
+    })
+}
+
+/// Parses the source and runs both passes of the assembler over it.
+fn run_passes(
+    source: &str,
+    options: &AssemblerOptions,
+    relocatable: bool,
+    diagnostics: &mut Diagnostics,
+) -> (Vec<Line>, Symbols, SecondPassOutput) {
+    let lines = parser::parse_source_with_includes(
+        source,
+        &options.file_name,
+        &options.include_dirs,
+        diagnostics,
+    );
+    let symbols = first_pass::build_symbol_table(&lines, &options.file_name, diagnostics);
+    let output = second_pass::parse_write_instructions(
+        &lines,
+        &options.file_name,
+        &symbols,
+        relocatable,
+        diagnostics,
+    );
+    (lines, symbols, output)
+}
+
+/// The names declared by every `.globl` or `.extern` directive in the source,
+/// with the line and span they are declared at.
+fn declared_names<'a>(
+    lines: &'a [Line],
+    directive: &str,
+) -> Vec<(&'a Line, String, Span)> {
+    let mut names = Vec::new();
+    for line in lines {
+        if let Some(statement) = &line.statement {
+            if statement.kind == StatementKind::Directive && statement.name == directive {
+                for (name, span) in directives::symbol_names(statement).unwrap_or_default() {
+                    names.push((line, name.to_string(), span));
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Writes the instruction output, in the given format, and listing of an assembled
+/// program to the given files.
+pub fn write_program(
+    program: &Program,
+    instruction_output_path: &str,
+    listing_output_path: &str,
+    format: OutputFormat,
+) -> io::Result<()> {
+    fs::write(instruction_output_path, program.text_output(format))?;
+    fs::write(listing_output_path, &program.listing)
+}
+
+/// Writes the JSON listing of an assembled program to the given file.
+pub fn write_json_listing(program: &Program, json_listing_path: &str) -> io::Result<()> {
+    fs::write(json_listing_path, program.json_listing())
+}
+
+/// Writes an assembled program as an ELF executable to the given file.
+pub fn write_elf(program: &Program, elf_output_path: &str) -> io::Result<()> {
+    fs::write(elf_output_path, program.elf_output())
+}
+
+/// The files named on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CommandLine {
+    inputs: Vec<String>,
+    instruction_output_path: String,
+    listing_output_path: String,
+    include_dirs: Vec<PathBuf>,
+    /// Write an object file for every input instead of linking them
+    objects_only: bool,
+    /// The output path given with `-o`
+    output_path: Option<String>,
+    /// Where to write the program as an ELF executable, given with `-e`
+    elf_output_path: Option<String>,
+    /// Where to write the JSON listing, given with `-j`
+    json_listing_path: Option<String>,
+    /// Where to write the data segment, given with `-d`
+    data_output_path: Option<String>,
+    /// The format of the instruction and data output, given with `-f`
+    format: OutputFormat,
+    /// The inputs are instruction and data output of the assembler to run, given with `-m`
+    machine_code: bool,
+}
+
+const USAGE: &str = "Usage: 'cargo run path/to/input.txt path/to/instruction_output.txt path/to/listing_output.txt'\n   \
+     or: 'cargo run input.asm [more.asm|more.o ...] [-o instruction_output.txt] [-l listing_output.txt] [-j listing.json]\n                  \
+     [-d data_output.txt] [-f lines|bin|bin-le|ihex|logisim|readmemh] [-e program.elf] [-I include/dir]'\n   \
+     or: 'cargo run -c input.asm [more.asm ...] [-o output.o] [-I include/dir]'\n   \
+     or: 'cargo run program.elf'\n   \
+     or: 'cargo run -m instruction_output.txt [data_output.txt]'\n\
+     Add '-r' to any of them to run the program without the user interface, or\n\
+     '-w waveform.vcd' to also write the datapath signals of every cycle to a VCD file, or\n\
+     '-t run.trace' to also write the instructions executed to a trace for 'cargo run --bin darken-trace-diff', or\n\
+     '-p profile.txt' to also write a report of the instructions that took the most cycles, or\n\
+     '-a annotated.txt' to also write the listing with the executions of every instruction, or\n\
+     '-P single-cycle|multi-cycle' to model the cycles of the profile on that datapath, multi-cycle by default, or\n\
+     '-v coverage.txt' to also write the listing with the lines and branch directions executed, or\n\
+     '-V coverage.info' to also write the coverage as an lcov tracefile, or\n\
+     '-x' to also check every instruction against the reference interpreter.";
+
+/// Reads the command line. Without any options it is `input [instruction_output listing_output]`,
+/// otherwise every argument that is not an option is an input file.
+fn parse_command_line(args: &[String]) -> Result<CommandLine, String> {
+    let mut command_line = CommandLine {
+        inputs: Vec::new(),
+        instruction_output_path: "default_instructions.txt".to_string(),
+        listing_output_path: "default_listings.txt".to_string(),
+        include_dirs: Vec::new(),
+        objects_only: false,
+        output_path: None,
+        elf_output_path: None,
+        json_listing_path: None,
+        data_output_path: None,
+        format: OutputFormat::Lines,
+        machine_code: false,
+    };
+    let args = args.get(1..).unwrap_or_default();
+
+    if !args.iter().any(|arg| arg.starts_with('-')) {
+        match args {
+            [input] | [input, _] => command_line.inputs.push(input.clone()),
+            [input, instruction_output, listing_output, ..] => {
+                command_line.inputs.push(input.clone());
+                command_line.instruction_output_path = instruction_output.clone();
+                command_line.listing_output_path = listing_output.clone();
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+        return Ok(command_line);
+    }
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("`{}` expects an argument after it\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "-o" => command_line.output_path = Some(value()?),
+            "-l" => command_line.listing_output_path = value()?,
+            "-e" => command_line.elf_output_path = Some(value()?),
+            "-d" => command_line.data_output_path = Some(value()?),
+            "-j" => command_line.json_listing_path = Some(value()?),
+            "-f" => {
+                let name = value()?;
+                command_line.format = OutputFormat::from_name(&name).ok_or_else(|| {
+                    let names: Vec<&str> = FORMAT_NAMES.iter().map(|(name, _)| *name).collect();
+                    format!("unknown output format `{}`, expected one of: {}\n{}", name, names.join(", "), USAGE)
+                })?;
             }
+            "-I" => command_line.include_dirs.push(PathBuf::from(value()?)),
+            "-c" => command_line.objects_only = true,
+            "-m" => command_line.machine_code = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`\n{}", arg, USAGE)),
+            _ => command_line.inputs.push(arg.clone()),
         }
     }
+    if command_line.inputs.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    if command_line.machine_code && (command_line.objects_only || command_line.inputs.len() > 2) {
+        return Err(format!("`-m` expects an instruction file and an optional data file\n{}", USAGE));
+    }
+    if command_line.objects_only && command_line.output_path.is_some() && command_line.inputs.len() > 1 {
+        return Err(format!("`-o` cannot be used with `-c` and several input files\n{}", USAGE));
+    }
+    if let (false, Some(path)) = (command_line.objects_only, &command_line.output_path) {
+        command_line.instruction_output_path = path.clone();
+    }
+    Ok(command_line)
+}
+
+/// Whether a file given on the command line is an object file rather than assembly source.
+fn is_object_file(path: &str) -> bool {
+    path.ends_with(".o")
+}
+
+/// Assembles the files given in the command line arguments and writes the
+/// instruction output and listing files. Several files are assembled separately
+/// and linked together, in the order they are given, along with any object files given.
+/// With `-c`, every file is assembled into an object file instead, and no
+/// program is returned. A single ELF executable is loaded as it is, without
+/// writing any files, and so is machine code given with `-m`.
+/// All errors found in the files are printed to stderr, after which an
+/// error summarizing how many there were is returned.
+pub fn run_assembler(args: Vec<String>) -> Result<Option<MemoryImage>, String> {
+    let command_line = match parse_command_line(&args) {
+        Ok(command_line) => command_line,
+        Err(usage) => {
+            println!("{}", usage);
+            return Err(usage);
+        }
+    };
+
+    if command_line.machine_code {
+        return load_machine_code(&command_line.inputs).map(Some);
+    }
+
+    let mut diagnostics = Diagnostics::new();
+    let mut objects = Vec::new();
+    let mut single_source = None;
+    for input_file_path in &command_line.inputs {
+        if is_object_file(input_file_path) && !command_line.objects_only {
+            let bytes = fs::read(input_file_path)
+                .map_err(|err| format!("failed to read input file {}: {}", input_file_path, err))?;
+            let object = Object::from_bytes(&bytes)
+                .map_err(|err| format!("failed to read object file {}: {}", input_file_path, err))?;
+            objects.push(object);
+            continue;
+        }
+
+        let bytes = fs::read(input_file_path)
+            .map_err(|err| format!("failed to read input file {}: {}", input_file_path, err))?;
+        if loader::is_elf(&bytes) {
+            if command_line.inputs.len() > 1 || command_line.objects_only {
+                return Err(format!(
+                    "{} is an ELF executable, which can only be run on its own",
+                    input_file_path
+                ));
+            }
+            return loader::load_elf(&bytes)
+                .map(Some)
+                .map_err(|err| format!("failed to load ELF executable {}: {}", input_file_path, err));
+        }
+        let source = String::from_utf8(bytes)
+            .map_err(|err| format!("failed to read input file {}: {}", input_file_path, err))?;
+        let options = AssemblerOptions {
+            file_name: input_file_path.to_string(),
+            include_dirs: command_line.include_dirs.clone(),
+        };
+        if command_line.inputs.len() == 1 && !command_line.objects_only {
+            single_source = Some((source, options));
+            continue;
+        }
+        match assemble_object(&source, &options) {
+            Ok(object) => objects.push(object),
+            Err(found) => found.iter().for_each(|d| diagnostics.push(d.clone())),
+        }
+    }
+
+    let result = match single_source {
+        Some((source, options)) => assemble(&source, &options),
+        None if !diagnostics.is_empty() => Err(diagnostics),
+        None if command_line.objects_only => {
+            for object in &objects {
+                let path = match &command_line.output_path {
+                    Some(path) => PathBuf::from(path),
+                    None => PathBuf::from(&object.name).with_extension("o"),
+                };
+                fs::write(&path, object.to_bytes()).map_err(|err| {
+                    format!("failed to write object file {}: {}", path.display(), err)
+                })?;
+            }
+            return Ok(None);
+        }
+        None => linker::link(objects),
+    };
+
+    match result {
+        Ok(program) => {
+            write_program(
+                &program,
+                &command_line.instruction_output_path,
+                &command_line.listing_output_path,
+                command_line.format,
+            )
+            .map_err(|err| format!("failed to write output files: {}", err))?;
+            if let Some(path) = &command_line.data_output_path {
+                fs::write(path, program.data_output(command_line.format))
+                    .map_err(|err| format!("failed to write data output {}: {}", path, err))?;
+            }
+            if let Some(path) = &command_line.json_listing_path {
+                write_json_listing(&program, path)
+                    .map_err(|err| format!("failed to write JSON listing {}: {}", path, err))?;
+            }
+            if let Some(path) = &command_line.elf_output_path {
+                write_elf(&program, path)
+                    .map_err(|err| format!("failed to write ELF executable {}: {}", path, err))?;
+            }
+            Ok(Some(MemoryImage::from(program)))
+        }
+        Err(diagnostics) => {
+            eprint!("{}", diagnostics);
+            Err(format!(
+                "could not assemble `{}` due to {} previous error{}",
+                command_line.inputs.join("`, `"),
+                diagnostics.len(),
+                if diagnostics.len() == 1 { "" } else { "s" }
+            ))
+        }
+    }
+}
+
+/// Loads the instruction file and the optional data file given with `-m`.
+fn load_machine_code(inputs: &[String]) -> Result<MemoryImage, String> {
+    let mut files = Vec::new();
+    for path in inputs {
+        files.push(fs::read(path).map_err(|err| format!("failed to read input file {}: {}", path, err))?);
+    }
+    disassembler::load_machine_code(&files[0], files.get(1).map(|data| data.as_slice()))
+        .map_err(|err| format!("failed to load machine code {}: {}", inputs.join(", "), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_instruction_output(source: &str, expected_output: &[&str]) {
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let output = program.instruction_output();
+        let actual_lines: Vec<&str> = output.lines().collect();
+        assert_eq!(expected_output, actual_lines.as_slice());
+    }
 
     #[test]
     fn integration_test_1() {
-        let input_path = "test/input1.txt";
-        let expected_output_path = "test/expected_output1.txt";
-        let output_path = "test/output1.txt";
-        check_instruction_output_from_input_file(input_path, expected_output_path, output_path);
+        let source = "\
+            addi $t0, $zero, 3      # counter
+            loop: beq $t0, $zero, end
+            addi $t0, $t0, -1
+            addi $t1, $t1, 1
+            j loop
+            end: exit
+        ";
+        let expected_output = [
+            "0x20080003",
+            "0x11000003",
+            "0x2108ffff",
+            "0x21290001",
+            "0x08000001",
+            "0xFFFFFFFF",
+        ];
+        check_instruction_output(source, &expected_output);
     }
 
     #[test]
     fn integration_test_2() {
-        let input_path = "test/input2.txt";
-        let expected_output_path = "test/expected_output2.txt";
-        let output_path = "test/output2.txt";
-        check_instruction_output_from_input_file(input_path, expected_output_path, output_path);
+        let source = "\
+            # store a value and load it back
+            main:   addi $t0, $zero, 100
+                    sw $t0, 8($zero)
+                    lw $t1, 8($zero)
+                    sub $t2, $t1, $t0
+                    jr $ra
+        ";
+        let expected_output = [
+            "0x20080064",
+            "0xac080008",
+            "0x8c090008",
+            "0x01285022",
+            "0x03e00008",
+        ];
+        check_instruction_output(source, &expected_output);
     }
 
     #[test]
-    // This does not seem to pass when running all tests, but passes otherwise
     fn canvas_test_1() {
-        let input_path = "test/canvas_test_1.txt";
-        let expected_output_path = "test/canvas_output_instr_1.txt";
-        let output_path = "test/test_output.txt";
-        check_instruction_output_from_input_file(input_path, expected_output_path, output_path);
+        let source = "\
+            addi $t0, $zero, 64
+            ori $t1, $t0, 2
+            and $t2, $t0, $t1
+            or $t3, $t0, $t1
+            nor $t4, $t0, $t1
+            slt $t5, $t0, $t1
+        ";
+        let expected_output = [
+            "0x20080040",
+            "0x35090002",
+            "0x01095024",
+            "0x01095825",
+            "0x01096027",
+            "0x0109682a",
+        ];
+        check_instruction_output(source, &expected_output);
     }
 
     #[test]
     fn canvas_test_2() {
-        let input_path = "test/canvas_test_2.txt";
-        let expected_output_path = "test/canvas_output_instr_2.txt";
-        let output_path = "test/test_canvas_output.txt";
-        check_instruction_output_from_input_file(input_path, expected_output_path, output_path);
+        let source = "\
+            start:  sll $t0, $t1, 2
+                    srl $t0, $t1, 2
+                    sra $t0, $t1, 4
+                    nop
+                    beq $t0, $t1, start
+        ";
+        let expected_output = [
+            "0x00094080",
+            "0x00094082",
+            "0x00094103",
+            "0x00000000",
+            "0x1109fffb",
+        ];
+        check_instruction_output(source, &expected_output);
+    }
+
+    #[test]
+    fn test_assemble_collects_symbols_and_listing() {
+        let program = assemble("start: nop\nend: exit\n", &AssemblerOptions::default()).unwrap();
+        assert_eq!(program.symbols.get("start"), Some(&0));
+        assert_eq!(program.symbols.get("end"), Some(&4));
+        assert!(program.data.is_empty());
+        assert!(program.listing.starts_with("0x00000000     0x00000000"));
+        assert!(program.listing.ends_with("Symbols\nstart     0x00000000\nend     0x00000004\n"));
+    }
+
+    #[test]
+    fn test_json_listing() {
+        let source = "\t.eqv SIZE, 2\nmain: addi $t0, $zero, SIZE\n\n\t.data\nvalue: .half 1\nmore: .word 2\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&program.json_listing()).unwrap();
+
+        let lines = json["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0]["address"], serde_json::Value::Null);
+        assert_eq!(lines[1]["line"], 2);
+        assert_eq!(lines[1]["section"], "text");
+        assert_eq!(lines[1]["address"], 0);
+        assert_eq!(lines[1]["encoding"], 0x2008_0002);
+        assert_eq!(lines[1]["expansion"], "addi $t0, $zero, 2");
+        assert_eq!(lines[1]["labels"], serde_json::json!(["main"]));
+        // `.word` is aligned after the `.half`
+        assert_eq!(lines[5]["section"], "data");
+        assert_eq!(lines[5]["address"], 4);
+        assert_eq!(lines[5]["size"], 4);
+
+        let symbols: Vec<(&str, u64)> = json["symbols"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|symbol| (symbol["name"].as_str().unwrap(), symbol["address"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(symbols, vec![("main", 0), ("value", 0), ("more", 4)]);
+    }
+
+    #[test]
+    fn test_assemble_reports_all_errors() {
+        let options = AssemblerOptions {
+            file_name: "broken.asm".to_string(),
+            ..AssemblerOptions::default()
+        };
+        let source = "abd $t1, $t2, $t3\nadd $t1, $a9, $t3\nj nowhere\n";
+        let diagnostics = assemble(source, &options).unwrap_err();
+        assert_eq!(diagnostics.len(), 3);
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.line).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+        assert!(diagnostics.iter().all(|d| d.file == "broken.asm"));
+    }
+
+    #[test]
+    fn test_assemble_data_section() {
+        let source = "\t.data\n\
+                      msg:   .asciiz \"hi#\"   # strings may contain #\n\
+                      count: .word 3\n\
+                      \t.text\n\
+                      main:  lw $t0,count( $zero )\n\
+                      \texit\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        assert_eq!(program.data, vec![b'h', b'i', b'#', 0, 0, 0, 0, 3]);
+        assert_eq!(program.symbols.get("count"), Some(&4));
+        assert_eq!(program.instruction_output(), "0x8c080004\n0xFFFFFFFF\n");
+    }
+
+    #[test]
+    fn test_assemble_reports_statements_in_wrong_section() {
+        let source = ".word 1\n.data\nadd $t0, $t0, $t0\n";
+        let diagnostics = assemble(source, &AssemblerOptions::default()).unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`.word` is only allowed in the .data section",
+                "instruction `add` in the .data section"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_constant_expressions() {
+        let source = "\t.eqv SIZE, 4\n\
+                      \t.equ MASK 0xff00\n\
+                      start: addi $t0, $zero, SIZE * 2 - 1\n\
+                      \tori $t1, $zero, MASK | 'a'\n\
+                      \tlw $t2, SIZE($sp)\n\
+                      \tbeq $t0, $t1, end\n\
+                      \taddi $t3, $zero, end - start\n\
+                      end: exit\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        assert_eq!(
+            program.instruction_output(),
+            "0x20080007\n0x3409ff61\n0x8faa0004\n0x11090001\n0x200b0014\n0xFFFFFFFF\n"
+        );
+        assert!(!program.symbols.contains_key("SIZE"));
+    }
+
+    #[test]
+    fn test_assemble_reports_values_out_of_range() {
+        let source = "addi $t0, $zero, 0x8000\n\
+                      ori $t0, $zero, -1\n\
+                      sll $t0, $t0, 32\n\
+                      lw $t0, 1 << 15($sp)\n\
+                      beq $t0, $t0, 2\n";
+        let diagnostics = assemble(source, &AssemblerOptions::default()).unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "immediate `32768` is out of range",
+                "immediate `-1` is out of range",
+                "shift amount `32` is out of range",
+                "offset `1 << 15` is out of range",
+                "branch target `2` is not word aligned",
+            ]
+        );
+        let hint = diagnostics.iter().nth(3).unwrap().hint.clone();
+        assert_eq!(
+            hint,
+            Some("`1 << 15` evaluates to 32768, but the offset must be between -32768 and 32767".to_string())
+        );
+    }
+
+    #[test]
+    fn test_assemble_macros() {
+        let source = ".macro push(%reg)\n\
+                      \taddi $sp, $sp, -4\n\
+                      \tsw %reg, 0($sp)\n\
+                      .end_macro\n\
+                      main: push($t0)\n\
+                      \tpush($t1)\n\
+                      end: exit\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        assert_eq!(
+            program.instruction_output(),
+            "0x23bdfffc\n0xafa80000\n0x23bdfffc\n0xafa90000\n0xFFFFFFFF\n"
+        );
+        assert_eq!(program.symbols.get("main"), Some(&0));
+        assert_eq!(program.symbols.get("end"), Some(&16));
+        assert!(program.listing.contains("0x0000000c     0xafa90000                    \tsw $t1, 0($sp)"));
+    }
+
+    #[test]
+    fn test_assemble_reports_unresolved_externs() {
+        let diagnostics = assemble("\t.extern printf\nj printf\n", &AssemblerOptions::default())
+            .unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["label `printf` is declared `.extern`, but no other file is linked"]
+        );
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let source = "\taddi $t0, $zero, 3\n\texit\n\t.data\n\t.byte 1, 2\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        assert_eq!(
+            program.text_output(OutputFormat::Lines),
+            program.instruction_output().into_bytes()
+        );
+        assert_eq!(
+            program.text_output(OutputFormat::Readmemh),
+            b"20080003\nffffffff\n".to_vec()
+        );
+        assert_eq!(program.data_output(OutputFormat::BinaryLittleEndian), vec![0, 0, 2, 1]);
+        assert_eq!(
+            program.data_output(OutputFormat::Logisim),
+            b"v2.0 raw\n1020000\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_elf_output_round_trip() {
+        let source = "main: addi $t0, $zero, 1\n\
+                      loop: beq $t0, $zero, main\n\
+                      \tj loop\n\
+                      \t.data\n\
+                      \t.byte 7\n\
+                      value: .word main\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let image = loader::load_elf(&program.elf_output()).unwrap();
+
+        let words: Vec<u32> = image.text.iter().map(|instruction| instruction.encode()).collect();
+        let expected: Vec<u32> = program.text.iter().map(|instruction| instruction.encode()).collect();
+        assert_eq!(words, expected);
+        assert_eq!((image.text_base, image.data_base, image.entry), (0, 0, 0));
+        assert_eq!(image.data, program.data);
+        assert_eq!(image.symbols, program.symbols);
+        assert_eq!(image.label_sections, program.label_sections);
+    }
+
+    #[test]
+    fn test_parse_command_line() {
+        let args = |args: &[&str]| -> Vec<String> { args.iter().map(|arg| arg.to_string()).collect() };
+
+        let legacy = parse_command_line(&args(&["darken", "in.asm", "out.txt", "list.txt"])).unwrap();
+        assert_eq!(legacy.inputs, vec!["in.asm"]);
+        assert_eq!(legacy.instruction_output_path, "out.txt");
+        assert_eq!(legacy.listing_output_path, "list.txt");
+
+        let linked =
+            parse_command_line(&args(&["darken", "a.asm", "-I", "lib", "b.asm", "-o", "out.txt"])).unwrap();
+        assert_eq!(linked.inputs, vec!["a.asm", "b.asm"]);
+        assert_eq!(linked.instruction_output_path, "out.txt");
+        assert_eq!(linked.listing_output_path, "default_listings.txt");
+        assert_eq!(linked.include_dirs, vec![PathBuf::from("lib")]);
+        assert_eq!(linked.elf_output_path, None);
+
+        let elf = parse_command_line(&args(&["darken", "a.asm", "-e", "a.elf"])).unwrap();
+        assert_eq!(elf.elf_output_path, Some("a.elf".to_string()));
+        assert_eq!(elf.instruction_output_path, "default_instructions.txt");
+        assert_eq!(elf.format, OutputFormat::Lines);
+
+        let formatted =
+            parse_command_line(&args(&["darken", "a.asm", "-f", "ihex", "-d", "data.hex"])).unwrap();
+        assert_eq!(formatted.format, OutputFormat::IntelHex);
+        assert_eq!(formatted.data_output_path, Some("data.hex".to_string()));
+        let json = parse_command_line(&args(&["darken", "a.asm", "-j", "a.json"])).unwrap();
+        assert_eq!(json.json_listing_path, Some("a.json".to_string()));
+        assert!(parse_command_line(&args(&["darken", "a.asm", "-f", "srec"]))
+            .unwrap_err()
+            .starts_with("unknown output format `srec`, expected one of: lines, bin, bin-le"));
+
+        let objects = parse_command_line(&args(&["darken", "-c", "a.asm", "-o", "a.o"])).unwrap();
+        assert!(objects.objects_only);
+        assert_eq!(objects.output_path, Some("a.o".to_string()));
+        assert_eq!(objects.instruction_output_path, "default_instructions.txt");
+        assert!(parse_command_line(&args(&["darken", "-c", "a.asm", "b.asm", "-o", "a.o"])).is_err());
+
+        let machine_code = parse_command_line(&args(&["darken", "-m", "out.txt", "data.txt"])).unwrap();
+        assert!(machine_code.machine_code);
+        assert_eq!(machine_code.inputs, vec!["out.txt", "data.txt"]);
+        assert!(parse_command_line(&args(&["darken", "-m", "a.txt", "b.txt", "c.txt"])).is_err());
+        assert!(parse_command_line(&args(&["darken", "-m", "-c", "a.txt"])).is_err());
+
+        assert!(parse_command_line(&args(&["darken"])).is_err());
+        assert!(parse_command_line(&args(&["darken", "a.asm", "-o"])).is_err());
+        assert!(parse_command_line(&args(&["darken", "a.asm", "-x"])).is_err());
     }
 }
@@ -0,0 +1,192 @@
+//! The syntax tree of an assembly source, produced by the parser and used by both passes.
+//!
+//! A source is a list of `Line`s, one for every line in it. A line can define any
+//! number of labels and hold at most one statement, which is either an instruction
+//! or a directive. Immediates are kept as expressions and evaluated by the passes
+//! once the labels and constants they refer to are known.
+//!
+//! File: ast.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::lexer::Span;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    /// The included file the line is from, or none for the file being assembled
+    pub file: Option<String>,
+    /// 1-based line number in its file
+    pub number: usize,
+    /// The line as written in the source
+    pub text: String,
+    pub labels: Vec<Label>,
+    pub statement: Option<Statement>,
+    /// Set if the line could not be parsed. The error has already been reported,
+    /// but the line still takes up the place of an instruction.
+    pub malformed: bool,
+}
+
+impl Line {
+    /// The name of the file the line is from, given the name of the file being assembled.
+    pub fn file_name<'a>(&'a self, main_file: &'a str) -> &'a str {
+        self.file.as_deref().unwrap_or(main_file)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    Instruction,
+    Directive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    /// The mnemonic of an instruction or the name of a directive, including its dot
+    pub name: String,
+    pub name_span: Span,
+    pub operands: Vec<Operand>,
+    /// The whole statement, from its name to its last operand
+    pub span: Span,
+}
+
+impl Statement {
+    /// The span of the operands, or of the name if there are none.
+    pub fn operands_span(&self) -> Span {
+        match (self.operands.first(), self.operands.last()) {
+            (Some(first), Some(last)) => first.span.to(last.span),
+            _ => self.name_span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operand {
+    pub kind: OperandKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperandKind {
+    Register(String),
+    /// An integer, a label or a constant, or arithmetic on them
+    Expression(Expr),
+    Str(String),
+    /// `offset(base)`, where the offset is optional
+    Memory {
+        offset: Option<Box<Operand>>,
+        base: Box<Operand>,
+    },
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            OperandKind::Register(name) => write!(f, "{}", name),
+            OperandKind::Expression(expr) => write!(f, "{}", expr),
+            OperandKind::Str(value) => write!(f, "{:?}", value),
+            OperandKind::Memory { offset, base } => match offset {
+                Some(offset) => write!(f, "{}({})", offset, base),
+                None => write!(f, "({})", base),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind {
+    Integer(i64),
+    /// A label or a constant defined with `.eqv`
+    Symbol(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+    /// `%hi(x)`, the upper half of an address, adjusted for adding `%lo(x)` sign-extended
+    High,
+    /// `%lo(x)`, the lower half of an address, as a signed 16-bit value
+    Low,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Or,
+}
+
+impl Expr {
+    /// The value of the expression if it is a plain integer literal.
+    pub fn as_integer(&self) -> Option<i64> {
+        match self.kind {
+            ExprKind::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The name if the expression is a single label or constant.
+    pub fn as_symbol(&self) -> Option<&str> {
+        match &self.kind {
+            ExprKind::Symbol(name) => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // operands that are themselves binary expressions are parenthesized
+        let operand = |expr: &Expr| match expr.kind {
+            ExprKind::Binary(..) => format!("({})", expr),
+            _ => expr.to_string(),
+        };
+        match &self.kind {
+            ExprKind::Integer(value) => write!(f, "{}", value),
+            ExprKind::Symbol(name) => write!(f, "{}", name),
+            ExprKind::Unary(UnaryOp::Negate, expr) => write!(f, "-{}", operand(expr)),
+            ExprKind::Unary(UnaryOp::Not, expr) => write!(f, "~{}", operand(expr)),
+            ExprKind::Unary(UnaryOp::High, expr) => write!(f, "%hi({})", expr),
+            ExprKind::Unary(UnaryOp::Low, expr) => write!(f, "%lo({})", expr),
+            ExprKind::Binary(op, lhs, rhs) => {
+                write!(f, "{} {} {}", operand(lhs), op, operand(rhs))
+            }
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::ShiftLeft => "<<",
+            BinaryOp::ShiftRight => ">>",
+            BinaryOp::And => "&",
+            BinaryOp::Or => "|",
+        };
+        write!(f, "{}", op)
+    }
+}
@@ -0,0 +1,318 @@
+//! Diagnostics reported by the assembler.
+//!
+//! Every error found while assembling a file is collected as a `Diagnostic` that knows
+//! which file, line and column it belongs to. This lets the assembler keep going after
+//! the first error and report all of them in one run, formatted the same way as rustc
+//! formats its errors:
+//!
+//! ```text
+//! error: unknown instruction `abd`
+//!  --> program.asm:3:5
+//!   |
+//! 3 |     abd $t1, $t2, $t3
+//!   |     ^^^
+//!   = help: did you mean `add`?
+//! ```
+//!
+//! File: diagnostics.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::lexer::Span;
+use std::fmt;
+
+/// Width a tab is expanded to when a source line is printed in a diagnostic.
+const TAB_WIDTH: usize = 4;
+
+/// An error found in a single line of assembly, before it is known which
+/// file and line it came from.
+///
+/// `span` is where the offending piece of the line is, if there is one, and is used
+/// to place the caret when the error is turned into a `Diagnostic`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    pub message: String,
+    pub span: Option<Span>,
+    pub hint: Option<String>,
+}
+
+impl LineError {
+    pub fn new(message: String) -> LineError {
+        LineError {
+            message,
+            span: None,
+            hint: None,
+        }
+    }
+
+    /// Points the error at the given part of the line.
+    pub fn at(mut self, span: Span) -> LineError {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_hint(mut self, hint: Option<String>) -> LineError {
+        self.hint = hint;
+        self
+    }
+}
+
+/// A located error, ready to be shown to the user.
+/// `line` and `column` are 1-based, `length` is the number of characters to underline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub message: String,
+    pub hint: Option<String>,
+    pub source_line: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic pointing at `span` in `source_line`,
+    /// or at the whole statement of the line if no span is given.
+    pub fn new(
+        file: &str,
+        line: usize,
+        source_line: &str,
+        span: Option<Span>,
+        message: String,
+        hint: Option<String>,
+    ) -> Diagnostic {
+        let (column, length) = match span {
+            Some(span) => (span.column, span.length),
+            None => statement_position(source_line),
+        };
+
+        Diagnostic {
+            file: file.to_string(),
+            line,
+            column,
+            length,
+            message,
+            hint,
+            source_line: source_line.to_string(),
+        }
+    }
+
+    /// Places a `LineError` found on line `line` of `file`.
+    pub fn from_line_error(file: &str, line: usize, source_line: &str, err: LineError) -> Diagnostic {
+        Diagnostic::new(
+            file,
+            line,
+            source_line,
+            err.span,
+            err.message,
+            err.hint,
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        let shown_line = expand_tabs(&self.source_line);
+        let caret_offset = expand_tabs(
+            &self
+                .source_line
+                .chars()
+                .take(self.column.saturating_sub(1))
+                .collect::<String>(),
+        )
+        .chars()
+        .count();
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{}--> {}:{}:{}", gutter, self.file, self.line, self.column)?;
+        // the text of the line is not always known for errors found while linking
+        if !self.source_line.is_empty() {
+            writeln!(f, "{} |", gutter)?;
+            writeln!(f, "{} | {}", self.line, shown_line.trim_end())?;
+            writeln!(
+                f,
+                "{} | {}{}",
+                gutter,
+                " ".repeat(caret_offset),
+                "^".repeat(self.length.max(1))
+            )?;
+        }
+        if let Some(hint) = &self.hint {
+            writeln!(f, "{} = help: {}", gutter, hint)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// All diagnostics found while assembling, in the order they were found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics {
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    /// Orders the diagnostics by where in the source they were found,
+    /// as errors from different passes are otherwise reported out of order.
+    pub fn sort_by_position(&mut self) {
+        self.diagnostics
+            .sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the candidate closest to `word`, if it is close enough to be
+/// a plausible typo of it. Used for "did you mean ...?" hints.
+pub fn closest_match<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = (word.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != word)
+        .map(|candidate| (edit_distance(word, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edit distance between two strings, where swapping two adjacent
+/// characters counts as a single edit (optimal string alignment distance).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b_chars.len() + 1]; a_chars.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in distances[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a_chars.len() {
+        for j in 1..=b_chars.len() {
+            let cost = if a_chars[i - 1] == b_chars[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a_chars[i - 1] == b_chars[j - 2] && a_chars[i - 2] == b_chars[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a_chars.len()][b_chars.len()]
+}
+
+/// Column and length of everything on the line that is not whitespace or a comment.
+fn statement_position(line: &str) -> (usize, usize) {
+    let code = line.split('#').next().unwrap_or("");
+    let leading = code.chars().take_while(|c| c.is_whitespace()).count();
+    let length = code.trim().chars().count();
+    (leading + 1, length)
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_match() {
+        let mnemonics = ["add", "addi", "sub", "beq"];
+        assert_eq!(closest_match("abd", mnemonics), Some("add"));
+        assert_eq!(closest_match("addu", mnemonics), Some("add"));
+        assert_eq!(closest_match("xyzzy", mnemonics), None);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("add", "add"), 0);
+        assert_eq!(edit_distance("abd", "add"), 1);
+        assert_eq!(edit_distance("", "sub"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("strat", "start"), 1);
+    }
+
+    #[test]
+    fn test_diagnostic_points_at_span() {
+        let line = "loop: add $t1, $a9, $t3 # comment";
+        let span = Span::new(4, 16, 3);
+        let diagnostic = Diagnostic::new("prog.asm", 4, line, Some(span), "unknown register `$a9`".to_string(), None);
+        assert_eq!(diagnostic.column, 16);
+        assert_eq!(diagnostic.length, 3);
+    }
+
+    #[test]
+    fn test_diagnostic_without_span_underlines_statement() {
+        let line = "    add $t1, $t2 # missing operand";
+        let diagnostic = Diagnostic::new("prog.asm", 1, line, None, "".to_string(), None);
+        assert_eq!(diagnostic.column, 5);
+        assert_eq!(diagnostic.length, 12);
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let line = "    abd $t1, $t2, $t3";
+        let diagnostic = Diagnostic::new(
+            "prog.asm",
+            3,
+            line,
+            Some(Span::new(3, 5, 3)),
+            "unknown instruction `abd`".to_string(),
+            Some("did you mean `add`?".to_string()),
+        );
+        let expected = "error: unknown instruction `abd`\n \
+                        --> prog.asm:3:5\n  \
+                        |\n\
+                        3 |     abd $t1, $t2, $t3\n  \
+                        |     ^^^\n  \
+                        = help: did you mean `add`?\n";
+        assert_eq!(diagnostic.to_string(), expected);
+    }
+
+    #[test]
+    fn test_diagnostic_display_expands_tabs() {
+        let line = "\tabd $t1, $t2, $t3";
+        let diagnostic = Diagnostic::new("prog.asm", 12, line, Some(Span::new(12, 2, 3)), "x".to_string(), None);
+        let rendered = diagnostic.to_string();
+        assert!(rendered.contains("12 |     abd $t1, $t2, $t3\n"));
+        assert!(rendered.contains("   |     ^^^\n"));
+    }
+}
@@ -0,0 +1,341 @@
+//! Assembler directives: switching between the text and data sections and
+//! laying out the initial contents of data memory.
+//!
+//! Supported directives are `.text`, `.data`, `.word`, `.half`, `.byte`,
+//! `.ascii`, `.asciiz`, `.space` and `.align`. `.word` and `.half` align their
+//! data to the size of their elements. `.eqv` and `.equ` define constants, which
+//! are collected by the first pass and take up no space. `.globl` exports labels to
+//! other files and `.extern` declares labels defined in other files, for linking.
+//!
+//! File: directives.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::ast::{Expr, Operand, OperandKind, Statement};
+use crate::assembler::diagnostics::{closest_match, LineError};
+use crate::assembler::expressions::{check_range, Symbols};
+use crate::assembler::lexer::Span;
+use serde::Serialize;
+
+static VALID_DIRECTIVE: [&str; 14] = [
+    ".text", ".data", ".word", ".half", ".byte", ".ascii", ".asciiz", ".space", ".align",
+    ".eqv", ".equ", ".globl", ".extern", ".include",
+];
+
+/// The section statements are currently assembled into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    Text,
+    Data,
+}
+
+/// section_directive: the section a `.text` or `.data` directive switches to.
+///
+/// input: a directive statement
+/// returns: the section, or none if the directive does not switch section
+///
+pub fn section_directive(statement: &Statement) -> Option<Section> {
+    match statement.name.as_str() {
+        ".text" => Some(Section::Text),
+        ".data" => Some(Section::Data),
+        _ => None,
+    }
+}
+
+/// data_alignment: the alignment of the data a directive lays out.
+///
+/// input: statement - a directive statement
+///        symbols - the constants known so far
+/// returns: the alignment in bytes
+///
+pub fn data_alignment(statement: &Statement, symbols: &Symbols) -> u32 {
+    match statement.name.as_str() {
+        ".word" => 4,
+        ".half" => 2,
+        ".align" => match statement.operands.first().map(|operand| constant_operand(operand, symbols)) {
+            Some(Ok(power)) if (0..=3).contains(&power) => 1 << power,
+            _ => 1,
+        },
+        _ => 1,
+    }
+}
+
+/// align: rounds the address up to the given alignment.
+pub fn align(addr: u32, alignment: u32) -> u32 {
+    addr.div_ceil(alignment) * alignment
+}
+
+/// data_size: the number of bytes a directive lays out, not counting alignment.
+/// Checks that the directive is known and that its operands have the right kind.
+///
+/// input: statement - a directive statement
+///        symbols - the constants known so far
+/// returns: the size in bytes, or an error pointing at what is wrong with the directive
+///
+pub fn data_size(statement: &Statement, symbols: &Symbols) -> Result<u32, LineError> {
+    let operands = &statement.operands;
+    match statement.name.as_str() {
+        ".text" | ".data" => {
+            expect_operand_count(statement, 0)?;
+            Ok(0)
+        }
+        ".eqv" | ".equ" => {
+            constant_definition(statement)?;
+            Ok(0)
+        }
+        ".globl" | ".extern" => {
+            symbol_names(statement)?;
+            Ok(0)
+        }
+        ".word" | ".half" | ".byte" => {
+            if operands.is_empty() {
+                return Err(LineError::new(format!(
+                    "`{}` expects at least one value",
+                    statement.name
+                ))
+                .at(statement.name_span));
+            }
+            let element_size = match statement.name.as_str() {
+                ".word" => 4,
+                ".half" => 2,
+                _ => 1,
+            };
+            for operand in operands {
+                if !matches!(operand.kind, OperandKind::Expression(_)) {
+                    return Err(unexpected_operand(operand, "an integer"));
+                }
+            }
+            Ok(element_size * operands.len() as u32)
+        }
+        ".ascii" | ".asciiz" => {
+            expect_operand_count(statement, 1)?;
+            match &operands[0].kind {
+                OperandKind::Str(value) => {
+                    let terminator = if statement.name == ".asciiz" { 1 } else { 0 };
+                    Ok(value.len() as u32 + terminator)
+                }
+                _ => Err(unexpected_operand(&operands[0], "a string")),
+            }
+        }
+        ".space" => {
+            expect_operand_count(statement, 1)?;
+            let (size, expr) = constant_operand_expr(&operands[0], symbols, "a number of bytes")?;
+            check_range(size, expr, 0, u16::MAX as i64, "size")?;
+            Ok(size as u32)
+        }
+        ".align" => {
+            expect_operand_count(statement, 1)?;
+            let (power, expr) =
+                constant_operand_expr(&operands[0], symbols, "a power of two between 0 and 3")?;
+            check_range(power, expr, 0, 3, "alignment")?;
+            Ok(0)
+        }
+        name => {
+            let suggestion = closest_match(name, VALID_DIRECTIVE.iter().copied());
+            Err(LineError::new(format!("unknown directive `{}`", name))
+                .at(statement.name_span)
+                .with_hint(suggestion.map(|directive| format!("did you mean `{}`?", directive))))
+        }
+    }
+}
+
+/// encode_data: the bytes a data directive lays out, in big-endian order.
+///
+/// input: statement - a directive statement that `data_size` accepted
+///        symbols - the labels and constants of the source
+/// returns: the bytes, or an error if a value does not fit or a label is missing
+///
+pub fn encode_data(statement: &Statement, symbols: &Symbols) -> Result<Vec<u8>, LineError> {
+    let mut bytes = Vec::new();
+    match statement.name.as_str() {
+        ".word" | ".half" | ".byte" => {
+            let (element_size, min, max): (usize, i64, i64) = match statement.name.as_str() {
+                ".word" => (4, i32::MIN as i64, u32::MAX as i64),
+                ".half" => (2, i16::MIN as i64, u16::MAX as i64),
+                _ => (1, i8::MIN as i64, u8::MAX as i64),
+            };
+            for operand in &statement.operands {
+                let value = match &operand.kind {
+                    OperandKind::Expression(expr) => symbols.evaluate(expr)?,
+                    _ => return Err(unexpected_operand(operand, "an integer")),
+                };
+                if value < min || value > max {
+                    let hint = match operand.kind {
+                        OperandKind::Expression(ref expr) if expr.as_integer().is_none() => {
+                            Some(format!("`{}` evaluates to {}", operand, value))
+                        }
+                        _ => None,
+                    };
+                    return Err(LineError::new(format!(
+                        "value `{}` does not fit in {} bits",
+                        operand,
+                        element_size * 8
+                    ))
+                    .at(operand.span)
+                    .with_hint(hint));
+                }
+                bytes.extend_from_slice(&(value as u32).to_be_bytes()[4 - element_size..]);
+            }
+        }
+        ".ascii" | ".asciiz" => {
+            if let OperandKind::Str(value) = &statement.operands[0].kind {
+                bytes.extend_from_slice(value.as_bytes());
+            }
+            if statement.name == ".asciiz" {
+                bytes.push(0);
+            }
+        }
+        _ => bytes.resize(data_size(statement, symbols)? as usize, 0),
+    }
+
+    Ok(bytes)
+}
+
+/// constant_definition: the name and value expression of a `.eqv` or `.equ` directive.
+///
+/// input: a `.eqv` or `.equ` statement
+/// returns: the name of the constant and the expression it is defined as
+///
+pub fn constant_definition(statement: &Statement) -> Result<(&str, &Expr), LineError> {
+    expect_operand_count(statement, 2)?;
+    let name = match &statement.operands[0].kind {
+        OperandKind::Expression(expr) => expr.as_symbol(),
+        _ => None,
+    }
+    .ok_or_else(|| unexpected_operand(&statement.operands[0], "a name"))?;
+    match &statement.operands[1].kind {
+        OperandKind::Expression(expr) => Ok((name, expr)),
+        _ => Err(unexpected_operand(&statement.operands[1], "an integer")),
+    }
+}
+
+/// symbol_names: the labels named by a `.globl` or `.extern` directive.
+///
+/// input: a `.globl` or `.extern` statement
+/// returns: the names and where they are written
+///
+pub fn symbol_names(statement: &Statement) -> Result<Vec<(&str, Span)>, LineError> {
+    if statement.operands.is_empty() {
+        return Err(LineError::new(format!("`{}` expects at least one label", statement.name))
+            .at(statement.name_span));
+    }
+    statement
+        .operands
+        .iter()
+        .map(|operand| match &operand.kind {
+            OperandKind::Expression(expr) => expr.as_symbol().map(|name| (name, operand.span)),
+            _ => None,
+        }
+        .ok_or_else(|| unexpected_operand(operand, "a label")))
+        .collect()
+}
+
+fn constant_operand(operand: &Operand, symbols: &Symbols) -> Result<i64, LineError> {
+    constant_operand_expr(operand, symbols, "an integer").map(|(value, _)| value)
+}
+
+fn constant_operand_expr<'a>(
+    operand: &'a Operand,
+    symbols: &Symbols,
+    expected: &str,
+) -> Result<(i64, &'a Expr), LineError> {
+    match &operand.kind {
+        OperandKind::Expression(expr) => Ok((symbols.evaluate_constant(expr)?, expr)),
+        _ => Err(unexpected_operand(operand, expected)),
+    }
+}
+
+fn expect_operand_count(statement: &Statement, expected: usize) -> Result<(), LineError> {
+    if statement.operands.len() == expected {
+        return Ok(());
+    }
+    Err(LineError::new(format!(
+        "`{}` expects {} operand{}, found {}",
+        statement.name,
+        expected,
+        if expected == 1 { "" } else { "s" },
+        statement.operands.len()
+    ))
+    .at(statement.operands_span()))
+}
+
+fn unexpected_operand(operand: &Operand, expected: &str) -> LineError {
+    LineError::new(format!("expected {}, found `{}`", expected, operand)).at(operand.span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::parser::parse_line;
+
+    fn statement(text: &str) -> Statement {
+        parse_line(text, 1).unwrap().statement.unwrap()
+    }
+
+    #[test]
+    fn test_data_size_and_alignment() {
+        assert_eq!(data_size(&statement(".word 1, 2, 3"), &Symbols::new()).unwrap(), 12);
+        assert_eq!(data_alignment(&statement(".word 1"), &Symbols::new()), 4);
+        assert_eq!(data_size(&statement(".half 1"), &Symbols::new()).unwrap(), 2);
+        assert_eq!(data_size(&statement(".asciiz \"hi\""), &Symbols::new()).unwrap(), 3);
+        assert_eq!(data_size(&statement(".ascii \"hi\""), &Symbols::new()).unwrap(), 2);
+        assert_eq!(data_size(&statement(".space 10"), &Symbols::new()).unwrap(), 10);
+        assert_eq!(data_alignment(&statement(".align 3"), &Symbols::new()), 8);
+        assert_eq!(align(5, 4), 8);
+        assert_eq!(align(8, 4), 8);
+    }
+
+    #[test]
+    fn test_encode_data() {
+        let mut symbols = Symbols::new();
+        symbols.labels.insert("msg".to_string(), 0x10);
+        assert_eq!(
+            encode_data(&statement(".word -1, msg"), &symbols).unwrap(),
+            vec![0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0x10]
+        );
+        assert_eq!(
+            encode_data(&statement(".half 0x1234"), &symbols).unwrap(),
+            vec![0x12, 0x34]
+        );
+        assert_eq!(
+            encode_data(&statement(".asciiz \"a\\n\""), &symbols).unwrap(),
+            vec![b'a', b'\n', 0]
+        );
+        assert_eq!(encode_data(&statement(".space 2"), &symbols).unwrap(), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_directive_errors() {
+        let err = encode_data(&statement(".byte 256"), &Symbols::new()).unwrap_err();
+        assert_eq!(err.message, "value `256` does not fit in 8 bits");
+
+        let err = data_size(&statement(".wrod 1"), &Symbols::new()).unwrap_err();
+        assert_eq!(err.message, "unknown directive `.wrod`");
+        assert_eq!(err.hint, Some("did you mean `.word`?".to_string()));
+
+        let err = data_size(&statement(".asciiz 5"), &Symbols::new()).unwrap_err();
+        assert_eq!(err.message, "expected a string, found `5`");
+
+        let err = data_size(&statement(".space 1 << 16"), &Symbols::new()).unwrap_err();
+        assert_eq!(err.message, "size `1 << 16` is out of range");
+    }
+
+    #[test]
+    fn test_constant_expressions() {
+        let mut symbols = Symbols::new();
+        symbols.constants.insert("N".to_string(), 3);
+        symbols.labels.insert("buf".to_string(), 0x20);
+        assert_eq!(data_size(&statement(".space N * 4"), &symbols).unwrap(), 12);
+        assert_eq!(data_alignment(&statement(".align N - 1"), &symbols), 4);
+        assert_eq!(
+            encode_data(&statement(".half buf + N, 'z' - 'a'"), &symbols).unwrap(),
+            vec![0, 0x23, 0, 25]
+        );
+
+        let err = data_size(&statement(".space buf"), &symbols).unwrap_err();
+        assert_eq!(err.message, "expected a constant, found label `buf`");
+        let err = encode_data(&statement(".byte N << 8"), &symbols).unwrap_err();
+        assert_eq!(err.message, "value `N << 8` does not fit in 8 bits");
+        assert_eq!(err.hint, Some("`N << 8` evaluates to 768".to_string()));
+    }
+}
@@ -0,0 +1,392 @@
+//! Evaluation of the constant expressions used as immediates, offsets and data values.
+//!
+//! Expressions are evaluated with 64-bit integers, so intermediate results can exceed
+//! the field they end up in; the caller checks that the final value fits.
+//!
+//! File: expressions.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::ast::{BinaryOp, Expr, ExprKind, UnaryOp};
+use crate::assembler::diagnostics::{closest_match, LineError};
+use crate::assembler::directives::Section;
+use std::collections::{HashMap, HashSet};
+
+/// The names an expression can refer to: labels, which are addresses,
+/// constants defined with `.eqv` or `.equ`, and labels declared `.extern`,
+/// which are defined in another file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Symbols {
+    pub labels: HashMap<String, u32>,
+    pub constants: HashMap<String, i64>,
+    /// The section each label is defined in
+    pub label_sections: HashMap<String, Section>,
+    /// Labels declared `.globl`, which other files can refer to
+    pub globals: HashSet<String>,
+    /// Labels declared `.extern`. Their address is not known until linking,
+    /// and is taken to be 0 until then.
+    pub externs: HashSet<String>,
+}
+
+/// What the value of an expression moves with when files are linked together.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RelocationTarget {
+    /// The start of the text or data section of the file
+    Section(Section),
+    /// A label defined in another file
+    Symbol(String),
+}
+
+impl Symbols {
+    pub fn new() -> Symbols {
+        Symbols::default()
+    }
+
+    /// Symbols containing only the given labels.
+    pub fn from_labels(labels: HashMap<String, u32>) -> Symbols {
+        Symbols {
+            labels,
+            ..Symbols::default()
+        }
+    }
+
+    /// evaluate: the value of an expression that may refer to labels and constants.
+    ///
+    /// input: the expression
+    /// returns: its value, or an error pointing at the part that cannot be evaluated
+    ///
+    pub fn evaluate(&self, expr: &Expr) -> Result<i64, LineError> {
+        self.evaluate_with(expr, true)
+    }
+
+    /// evaluate_constant: like `evaluate`, but for places whose value must be known
+    /// before the addresses of labels are, such as the size of a `.space`.
+    pub fn evaluate_constant(&self, expr: &Expr) -> Result<i64, LineError> {
+        self.evaluate_with(expr, false)
+    }
+
+    fn evaluate_with(&self, expr: &Expr, allow_labels: bool) -> Result<i64, LineError> {
+        match &expr.kind {
+            ExprKind::Integer(value) => Ok(*value),
+            ExprKind::Symbol(name) => self.lookup(name, expr, allow_labels),
+            ExprKind::Unary(op, operand) => {
+                let value = self.evaluate_with(operand, allow_labels)?;
+                match op {
+                    UnaryOp::Negate => value.checked_neg().ok_or_else(|| overflow(expr)),
+                    UnaryOp::Not => Ok(!value),
+                    UnaryOp::High => Ok(((value + 0x8000) >> 16) & 0xffff),
+                    UnaryOp::Low => Ok(((value & 0xffff) ^ 0x8000) - 0x8000),
+                }
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs_val = self.evaluate_with(lhs, allow_labels)?;
+                let rhs_val = self.evaluate_with(rhs, allow_labels)?;
+                let result = match op {
+                    BinaryOp::Add => lhs_val.checked_add(rhs_val),
+                    BinaryOp::Sub => lhs_val.checked_sub(rhs_val),
+                    BinaryOp::Mul => lhs_val.checked_mul(rhs_val),
+                    BinaryOp::Div => {
+                        if rhs_val == 0 {
+                            return Err(LineError::new("division by zero".to_string()).at(rhs.span));
+                        }
+                        lhs_val.checked_div(rhs_val)
+                    }
+                    BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                        if !(0..64).contains(&rhs_val) {
+                            return Err(LineError::new(format!(
+                                "cannot shift by {}, expected a number between 0 and 63",
+                                rhs_val
+                            ))
+                            .at(rhs.span));
+                        }
+                        if *op == BinaryOp::ShiftLeft {
+                            lhs_val.checked_mul(1 << rhs_val)
+                        } else {
+                            Some(lhs_val >> rhs_val)
+                        }
+                    }
+                    BinaryOp::And => Some(lhs_val & rhs_val),
+                    BinaryOp::Or => Some(lhs_val | rhs_val),
+                };
+                result.ok_or_else(|| overflow(expr))
+            }
+        }
+    }
+
+    /// relocation: what the value of an expression depends on when the file it is in
+    /// is linked with others, i.e. the base address the value is relative to.
+    ///
+    /// input: the expression
+    /// returns: none if the value does not change when linking, or the target the value
+    ///          is relative to and the value relative to it. An error if the value depends
+    ///          on addresses in a way that linking cannot fix.
+    ///
+    pub fn relocation(&self, expr: &Expr) -> Result<Option<(RelocationTarget, i64)>, LineError> {
+        let (value, terms) = self.linear(expr)?;
+        let mut terms = terms.into_iter().filter(|(_, coefficient)| *coefficient != 0);
+        match (terms.next(), terms.next()) {
+            (None, _) => Ok(None),
+            (Some((target, 1)), None) => Ok(Some((target, value))),
+            _ => Err(not_relocatable(expr)),
+        }
+    }
+
+    /// Evaluates an expression as `value + sum(coefficient * base)`, where the bases are
+    /// the addresses the sections and external labels end up at after linking.
+    fn linear(&self, expr: &Expr) -> Result<(i64, Vec<(RelocationTarget, i64)>), LineError> {
+        let absolute = |value| Ok((value, Vec::new()));
+        match &expr.kind {
+            ExprKind::Symbol(name) if !self.constants.contains_key(name) => {
+                if self.externs.contains(name) {
+                    return Ok((0, vec![(RelocationTarget::Symbol(name.clone()), 1)]));
+                }
+                let value = self.evaluate(expr)?;
+                match self.label_sections.get(name) {
+                    Some(section) => Ok((value, vec![(RelocationTarget::Section(*section), 1)])),
+                    None => absolute(value),
+                }
+            }
+            ExprKind::Unary(UnaryOp::Negate, operand) => {
+                let (value, terms) = self.linear(operand)?;
+                Ok((-value, scale(terms, -1)))
+            }
+            ExprKind::Binary(op @ (BinaryOp::Add | BinaryOp::Sub), lhs, rhs) => {
+                let (_, mut terms) = self.linear(lhs)?;
+                let (_, rhs_terms) = self.linear(rhs)?;
+                let sign = if *op == BinaryOp::Add { 1 } else { -1 };
+                for (target, coefficient) in rhs_terms {
+                    match terms.iter_mut().find(|(existing, _)| *existing == target) {
+                        Some((_, existing)) => *existing += sign * coefficient,
+                        None => terms.push((target, sign * coefficient)),
+                    }
+                }
+                Ok((self.evaluate(expr)?, terms))
+            }
+            ExprKind::Binary(BinaryOp::Mul, lhs, rhs) => {
+                let (lhs_val, lhs_terms) = self.linear(lhs)?;
+                let (rhs_val, rhs_terms) = self.linear(rhs)?;
+                let value = self.evaluate(expr)?;
+                match (lhs_terms.is_empty(), rhs_terms.is_empty()) {
+                    (_, true) => Ok((value, scale(lhs_terms, rhs_val))),
+                    (true, false) => Ok((value, scale(rhs_terms, lhs_val))),
+                    _ => Err(not_relocatable(expr)),
+                }
+            }
+            // the remaining operators need values that do not change when linking
+            ExprKind::Unary(_, operand) => {
+                self.expect_absolute(operand)?;
+                absolute(self.evaluate(expr)?)
+            }
+            ExprKind::Binary(_, lhs, rhs) => {
+                self.expect_absolute(lhs)?;
+                self.expect_absolute(rhs)?;
+                absolute(self.evaluate(expr)?)
+            }
+            ExprKind::Integer(_) | ExprKind::Symbol(_) => absolute(self.evaluate(expr)?),
+        }
+    }
+
+    fn expect_absolute(&self, expr: &Expr) -> Result<(), LineError> {
+        let (_, terms) = self.linear(expr)?;
+        match terms.iter().all(|(_, coefficient)| *coefficient == 0) {
+            true => Ok(()),
+            false => Err(not_relocatable(expr)),
+        }
+    }
+
+    fn lookup(&self, name: &str, expr: &Expr, allow_labels: bool) -> Result<i64, LineError> {
+        if let Some(value) = self.constants.get(name) {
+            return Ok(*value);
+        }
+
+        if self.externs.contains(name) && allow_labels {
+            return Ok(0);
+        }
+
+        if let Some(addr) = self.labels.get(name) {
+            if allow_labels {
+                return Ok(*addr as i64);
+            }
+            return Err(
+                LineError::new(format!("expected a constant, found label `{}`", name))
+                    .at(expr.span)
+                    .with_hint(Some(
+                        "the value must be known before the addresses of labels are".to_string(),
+                    )),
+            );
+        }
+
+        let candidates = self
+            .constants
+            .keys()
+            .chain(self.labels.keys())
+            .map(|key| key.as_str());
+        let suggestion = closest_match(name, candidates);
+        Err(LineError::new(format!("cannot find label `{}`", name))
+            .at(expr.span)
+            .with_hint(suggestion.map(|name| format!("did you mean `{}`?", name))))
+    }
+}
+
+fn scale(terms: Vec<(RelocationTarget, i64)>, factor: i64) -> Vec<(RelocationTarget, i64)> {
+    terms
+        .into_iter()
+        .map(|(target, coefficient)| (target, coefficient * factor))
+        .collect()
+}
+
+fn not_relocatable(expr: &Expr) -> LineError {
+    LineError::new(format!("`{}` cannot be linked", expr))
+        .at(expr.span)
+        .with_hint(Some(
+            "an address can only be used on its own or plus or minus a constant".to_string(),
+        ))
+}
+
+fn overflow(expr: &Expr) -> LineError {
+    LineError::new(format!("`{}` overflows a 64-bit integer", expr)).at(expr.span)
+}
+
+/// check_range: checks that the value of an expression fits in a field.
+///
+/// input: value - the value of `expr`
+///        expr - the expression, used in the error
+///        min, max - the range of values the field can hold
+///        what - describes the field, e.g. "immediate"
+/// returns: an error pointing at the expression if the value is out of range
+///
+pub fn check_range(
+    value: i64,
+    expr: &Expr,
+    min: i64,
+    max: i64,
+    what: &str,
+) -> Result<(), LineError> {
+    if (min..=max).contains(&value) {
+        return Ok(());
+    }
+
+    let evaluates_to = match expr.as_integer() {
+        Some(_) => String::new(),
+        None => format!("`{}` evaluates to {}, but ", expr, value),
+    };
+    Err(
+        LineError::new(format!("{} `{}` is out of range", what, expr))
+            .at(expr.span)
+            .with_hint(Some(format!(
+                "{}the {} must be between {} and {}",
+                evaluates_to, what, min, max
+            ))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::ast::OperandKind;
+    use crate::assembler::parser::parse_line;
+
+    fn expr(text: &str) -> Expr {
+        let line = parse_line(&format!(".word {}", text), 1).unwrap();
+        match line.statement.unwrap().operands.remove(0).kind {
+            OperandKind::Expression(expr) => expr,
+            other => panic!("expected an expression, found {:?}", other),
+        }
+    }
+
+    fn symbols() -> Symbols {
+        let mut symbols = Symbols::new();
+        symbols.labels.insert("start".to_string(), 8);
+        symbols.labels.insert("end".to_string(), 40);
+        symbols.constants.insert("SIZE".to_string(), 16);
+        symbols
+    }
+
+    #[test]
+    fn test_evaluate_precedence() {
+        let symbols = Symbols::new();
+        assert_eq!(symbols.evaluate(&expr("1 + 2 * 3")).unwrap(), 7);
+        assert_eq!(symbols.evaluate(&expr("(1 + 2) * 3")).unwrap(), 9);
+        assert_eq!(symbols.evaluate(&expr("1 << 4 + 1")).unwrap(), 32);
+        assert_eq!(
+            symbols.evaluate(&expr("0xff & 0x0f | 0x100")).unwrap(),
+            0x10f
+        );
+        assert_eq!(symbols.evaluate(&expr("-8 >> 1")).unwrap(), -4);
+        assert_eq!(symbols.evaluate(&expr("~0")).unwrap(), -1);
+        assert_eq!(symbols.evaluate(&expr("7 / 2 - -1")).unwrap(), 4);
+        assert_eq!(symbols.evaluate(&expr("'a' + 0b1")).unwrap(), 98);
+    }
+
+    #[test]
+    fn test_evaluate_symbols() {
+        let symbols = symbols();
+        assert_eq!(symbols.evaluate(&expr("end - start")).unwrap(), 32);
+        assert_eq!(symbols.evaluate(&expr("SIZE * 4")).unwrap(), 64);
+        assert_eq!(symbols.evaluate_constant(&expr("SIZE")).unwrap(), 16);
+
+        let err = symbols.evaluate_constant(&expr("end")).unwrap_err();
+        assert_eq!(err.message, "expected a constant, found label `end`");
+
+        let err = symbols.evaluate(&expr("SIZ + 1")).unwrap_err();
+        assert_eq!(err.message, "cannot find label `SIZ`");
+        assert_eq!(err.hint, Some("did you mean `SIZE`?".to_string()));
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        let symbols = Symbols::new();
+        let err = symbols.evaluate(&expr("1 / (2 - 2)")).unwrap_err();
+        assert_eq!(err.message, "division by zero");
+        let err = symbols
+            .evaluate(&expr("0x7fffffffffffffff + 1"))
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "`9223372036854775807 + 1` overflows a 64-bit integer"
+        );
+    }
+
+    #[test]
+    fn test_check_range() {
+        let value_expr = expr("SIZE * 4096");
+        let err = check_range(65536, &value_expr, -32768, 32767, "immediate").unwrap_err();
+        assert_eq!(err.message, "immediate `SIZE * 4096` is out of range");
+        assert_eq!(
+            err.hint,
+            Some("`SIZE * 4096` evaluates to 65536, but the immediate must be between -32768 and 32767".to_string())
+        );
+        assert!(check_range(-32768, &expr("-32768"), -32768, 32767, "immediate").is_ok());
+    }
+
+    #[test]
+    fn test_relocation() {
+        let mut symbols = symbols();
+        symbols.label_sections.insert("start".to_string(), Section::Text);
+        symbols.label_sections.insert("end".to_string(), Section::Text);
+        symbols.externs.insert("printf".to_string());
+
+        let text = RelocationTarget::Section(Section::Text);
+        let printf = RelocationTarget::Symbol("printf".to_string());
+        assert_eq!(symbols.relocation(&expr("SIZE * 2")).unwrap(), None);
+        assert_eq!(symbols.relocation(&expr("end - start")).unwrap(), None);
+        assert_eq!(symbols.relocation(&expr("start + 4")).unwrap(), Some((text, 12)));
+        assert_eq!(symbols.relocation(&expr("printf - SIZE")).unwrap(), Some((printf, -16)));
+        assert_eq!(symbols.evaluate(&expr("printf + 8")).unwrap(), 8);
+
+        let err = symbols.relocation(&expr("start * 2")).unwrap_err();
+        assert_eq!(err.message, "`start * 2` cannot be linked");
+        let err = symbols.relocation(&expr("(printf >> 2) + 1")).unwrap_err();
+        assert_eq!(err.message, "`printf` cannot be linked");
+        assert!(symbols.relocation(&expr("printf - start")).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_address_halves() {
+        let mut symbols = Symbols::new();
+        symbols.labels.insert("far".to_string(), 0x1234_9abc);
+        assert_eq!(symbols.evaluate(&expr("%hi(far)")).unwrap(), 0x1235);
+        assert_eq!(symbols.evaluate(&expr("%lo(far)")).unwrap(), -0x6544);
+        assert_eq!(symbols.evaluate(&expr("(%hi(far) << 16) + %lo(far)")).unwrap(), 0x1234_9abc);
+        assert_eq!(expr("%lo(far + 4)").to_string(), "%lo(far + 4)");
+    }
+}
@@ -1,7 +1,10 @@
-use crate::assembler::parser;
-use darken_assignment1::utils::print_to_file;
+use crate::assembler::ast::{Line, StatementKind};
+use crate::assembler::diagnostics::{Diagnostic, Diagnostics, LineError};
+use crate::assembler::directives::{self, Section};
+use crate::assembler::expressions::Symbols;
+use crate::assembler::lexer::Span;
 /**
- * The first pass of the assembler will save all labels detected and return them in a symbol table
+ * The first pass of the assembler will save all labels and constants detected and return them in a symbol table
  *
 * File: first_pass.rs
 * Author: mai17asm, c19hln
@@ -10,88 +13,326 @@ use darken_assignment1::utils::print_to_file;
 *
 **/
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufReader, Lines};
-use std::process::exit;
 
-/// builds and returns the table containing labels and their corresponding memory location.
+/// builds and returns the table containing labels and their corresponding memory location,
+/// along with the constants defined with `.eqv` or `.equ`.
+/// Labels in the text section refer to instruction addresses and labels in the data section
+/// to data memory addresses. Constants are collected first, in the order they are defined,
+/// so that the sizes of data directives may use them wherever they are defined.
+/// Names that are defined more than once are reported to `diagnostics`, the first
+/// definition of a name is the one kept in the table. Labels declared `.globl` must be
+/// defined in the source, and labels declared `.extern` must not be.
+/// Errors in other statements are left to the second pass to report.
 ///
-/// input: lines - buffered reader with input.
-/// returns: HashMap<String, u32> - the symbol table with labels as keys.
+/// input: lines - the parsed assembly source to build the table from.
+///        file_name - name of the source, used in diagnostics.
+///        diagnostics - where errors found in the labels and constants are reported.
+/// returns: Symbols - the labels and constants of the source.
 ///
 pub fn build_symbol_table(
-    lines: Lines<BufReader<File>>,
-    assembly_listing_path: &str,
-) -> HashMap<String, u32> {
-    let mut symbol_table = HashMap::new();
+    lines: &[Line],
+    file_name: &str,
+    diagnostics: &mut Diagnostics,
+) -> Symbols {
+    let mut symbols = Symbols::new();
+    let mut defined_on_line: HashMap<String, usize> = HashMap::new();
+    let declarations = collect_declarations(lines, file_name, &mut symbols, diagnostics);
+    collect_constants(lines, file_name, &mut symbols, &mut defined_on_line, diagnostics);
+
+    let mut section = Section::Text;
     let mut next_instruction = 0;
+    let mut next_data = 0;
 
     for line in lines {
-        if let Ok(line) = line {
-            parser::line_is_empty("");
+        let directive = line
+            .statement
+            .as_ref()
+            .filter(|statement| statement.kind == StatementKind::Directive);
+        if let Some(new_section) = directive.and_then(directives::section_directive) {
+            section = new_section;
+        }
 
-            if parser::line_is_empty(&line) {
-                continue;
-            }
+        let addr = match section {
+            Section::Text => next_instruction,
+            Section::Data => match directive {
+                Some(statement) => {
+                    directives::align(next_data, directives::data_alignment(statement, &symbols))
+                }
+                None => next_data,
+            },
+        };
 
-            let maybe_line = parser::remove_comment_from_line(line.as_str());
-            if maybe_line.is_none() {
-                continue;
+        for label in &line.labels {
+            if let Some(first_line) = defined_on_line.get(&label.name) {
+                let message = if symbols.constants.contains_key(&label.name) {
+                    format!("label `{}` has the same name as a constant", label.name)
+                } else {
+                    format!("label `{}` is defined multiple times", label.name)
+                };
+                diagnostics.push(Diagnostic::new(
+                    line.file_name(file_name),
+                    line.number,
+                    &line.text,
+                    Some(label.span),
+                    message,
+                    Some(format!("`{}` is first defined on line {}", label.name, first_line)),
+                ));
+            } else {
+                defined_on_line.insert(label.name.clone(), line.number);
+                symbols.labels.insert(label.name.clone(), addr);
+                symbols.label_sections.insert(label.name.clone(), section);
             }
+        }
 
-            let line = maybe_line.unwrap();
-
-            let maybe_label = parser::get_label_from_line(&line);
-            if let Some(label) = maybe_label {
-                if label.contains(" ") {
-                    print_to_file(
-                        assembly_listing_path,
-                        "Found whitespace in label!".to_string(),
-                    )
-                    .expect("Could not write to file");
-                    exit(1);
+        let is_instruction = line.malformed
+            || line
+                .statement
+                .as_ref()
+                .is_some_and(|statement| statement.kind == StatementKind::Instruction);
+        match section {
+            Section::Text if is_instruction => next_instruction += 4,
+            Section::Data => {
+                if let Some(statement) = directive {
+                    next_data = addr + directives::data_size(statement, &symbols).unwrap_or(0);
                 }
+            }
+            _ => (),
+        }
+    }
 
-                if symbol_table.contains_key(&label) {
-                    print_to_file(assembly_listing_path, format!("Label {} is defined more than once in the given input file!", label).to_string())
-                        .expect("Could not write to listings file");
-                    exit(1);
-                }
+    for (line, name, span, is_extern) in declarations {
+        let defined = symbols.labels.contains_key(&name);
+        let (message, hint) = match (is_extern, defined) {
+            (true, true) => (
+                format!("label `{}` is declared `.extern` but defined in this file", name),
+                format!("`{}` is defined on line {}", name, defined_on_line[&name]),
+            ),
+            (false, false) => (
+                format!("global label `{}` is not defined", name),
+                format!("declare it with `.extern {}` if it is defined in another file", name),
+            ),
+            _ => continue,
+        };
+        symbols.externs.remove(&name);
+        diagnostics.push(Diagnostic::new(
+            line.file_name(file_name),
+            line.number,
+            &line.text,
+            Some(span),
+            message,
+            Some(hint),
+        ));
+    }
 
-                symbol_table.insert(label, next_instruction);
-            }
+    symbols
+}
 
-            if parser::has_instruction(&line) {
-                next_instruction += 4;
+/// Records the labels declared with `.globl` and `.extern`.
+/// Returns: the line and span of every declared name, and whether it is declared `.extern`
+fn collect_declarations<'a>(
+    lines: &'a [Line],
+    file_name: &str,
+    symbols: &mut Symbols,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(&'a Line, String, Span, bool)> {
+    let mut declarations = Vec::new();
+    for line in lines {
+        let statement = match &line.statement {
+            Some(statement)
+                if statement.kind == StatementKind::Directive
+                    && matches!(statement.name.as_str(), ".globl" | ".extern") =>
+            {
+                statement
             }
-        } else {
-            panic!("Error reading line from file!");
+            _ => continue,
+        };
+        let is_extern = statement.name == ".extern";
+        match directives::symbol_names(statement) {
+            Ok(names) => {
+                for (name, span) in names {
+                    if is_extern {
+                        symbols.externs.insert(name.to_string());
+                    } else {
+                        symbols.globals.insert(name.to_string());
+                    }
+                    declarations.push((line, name.to_string(), span, is_extern));
+                }
+            }
+            Err(err) => diagnostics.push(Diagnostic::from_line_error(
+                line.file_name(file_name),
+                line.number,
+                &line.text,
+                err,
+            )),
+        }
+    }
+    declarations
+}
+
+/// Evaluates the `.eqv` and `.equ` directives of the source in order. A constant can
+/// use the constants defined before it, but not labels, as their addresses are not known yet.
+fn collect_constants(
+    lines: &[Line],
+    file_name: &str,
+    symbols: &mut Symbols,
+    defined_on_line: &mut HashMap<String, usize>,
+    diagnostics: &mut Diagnostics,
+) {
+    // the labels are known by name only, so that using one is reported as such
+    for line in lines {
+        for label in &line.labels {
+            symbols.labels.insert(label.name.clone(), 0);
         }
     }
+    for name in &symbols.externs {
+        symbols.labels.insert(name.clone(), 0);
+    }
 
-    symbol_table
+    for line in lines {
+        let statement = match &line.statement {
+            Some(statement)
+                if statement.kind == StatementKind::Directive
+                    && matches!(statement.name.as_str(), ".eqv" | ".equ") =>
+            {
+                statement
+            }
+            _ => continue,
+        };
+        let result = directives::constant_definition(statement).and_then(|(name, expr)| {
+            if let Some(first_line) = defined_on_line.get(name) {
+                return Err(LineError::new(format!("constant `{}` is defined multiple times", name))
+                    .at(statement.operands[0].span)
+                    .with_hint(Some(format!("`{}` is first defined on line {}", name, first_line))));
+            }
+            let value = symbols.evaluate_constant(expr)?;
+            defined_on_line.insert(name.to_string(), line.number);
+            symbols.constants.insert(name.to_string(), value);
+            Ok(())
+        });
+        if let Err(err) = result {
+            diagnostics.push(Diagnostic::from_line_error(line.file_name(file_name), line.number, &line.text, err));
+        }
+    }
+    symbols.labels.clear();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::{BufRead, BufReader};
+    use crate::assembler::parser::parse_source;
 
-    /// file test/label_addr.txt seems to not exist :)
-    #[allow(unused)]
+    #[test]
     fn test_build_symbol_table() {
-        let maybe_file = File::open("test/label_addr.txt");
-        assert!(maybe_file.is_ok());
-        let file = maybe_file.unwrap();
-
-        let lines = BufReader::new(file).lines();
-        let st = build_symbol_table(lines, "");
-        assert!(st.contains_key("start"));
-        assert!(st.contains_key("mid"));
-        assert!(st.contains_key("end"));
-        assert!(st.get("start").is_some());
-        assert_eq!(st.get("start").unwrap().to_owned(), 0);
-        assert_eq!(st.get("mid").unwrap().to_owned(), 8);
-        assert_eq!(st.get("end").unwrap().to_owned(), 20);
+        let source = "start: addi $t0, $zero, 1 # first\n\
+                      \n\
+                      # only a comment\n\
+                      \tadd $t1, $t0, $t0\n\
+                      mid:\n\
+                      \tadd $t1, $t0, $t0\n\
+                      \tadd $t1, $t0, $t0\n\
+                      \tadd $t1, $t0, $t0\n\
+                      end: exit\n";
+        let mut diagnostics = Diagnostics::new();
+        let lines = parse_source(source, "label_addr.asm", &mut diagnostics);
+        let st = build_symbol_table(&lines, "label_addr.asm", &mut diagnostics);
+        assert!(diagnostics.is_empty());
+        assert!(st.labels.contains_key("start"));
+        assert!(st.labels.contains_key("mid"));
+        assert!(st.labels.contains_key("end"));
+        assert_eq!(st.labels.get("start").unwrap().to_owned(), 0);
+        assert_eq!(st.labels.get("mid").unwrap().to_owned(), 8);
+        assert_eq!(st.labels.get("end").unwrap().to_owned(), 20);
+    }
+
+    #[test]
+    fn test_duplicate_label_is_reported() {
+        let source = "loop: nop\nloop: nop\n";
+        let mut diagnostics = Diagnostics::new();
+        let lines = parse_source(source, "dup.asm", &mut diagnostics);
+        let st = build_symbol_table(&lines, "dup.asm", &mut diagnostics);
+        assert_eq!(st.labels.get("loop").unwrap().to_owned(), 0);
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = diagnostics.iter().next().unwrap();
+        assert_eq!(diagnostic.line, 2);
+        assert_eq!(diagnostic.column, 1);
+        assert_eq!(diagnostic.message, "label `loop` is defined multiple times");
+    }
+
+    #[test]
+    fn test_data_labels() {
+        let source = "\t.data\n\
+                      str: .asciiz \"abc\"\n\
+                      num: .word 1, 2\n\
+                      \t.text\n\
+                      main: lw $t0, num($zero)\n";
+        let mut diagnostics = Diagnostics::new();
+        let lines = parse_source(source, "data.asm", &mut diagnostics);
+        let st = build_symbol_table(&lines, "data.asm", &mut diagnostics);
+        assert!(diagnostics.is_empty());
+        assert_eq!(st.labels.get("str").unwrap().to_owned(), 0);
+        assert_eq!(st.labels.get("num").unwrap().to_owned(), 4);
+        assert_eq!(st.labels.get("main").unwrap().to_owned(), 0);
+    }
+
+    #[test]
+    fn test_constants() {
+        let source = "\t.eqv SIZE, 4\n\
+                      \t.equ DOUBLE 2 * SIZE\n\
+                      \t.data\n\
+                      buf: .space DOUBLE + BYTES\n\
+                      end: .word 0\n\
+                      \t.eqv BYTES, SIZE\n";
+        let mut diagnostics = Diagnostics::new();
+        let lines = parse_source(source, "eqv.asm", &mut diagnostics);
+        let st = build_symbol_table(&lines, "eqv.asm", &mut diagnostics);
+        assert!(diagnostics.is_empty());
+        assert_eq!(st.constants.get("DOUBLE").unwrap().to_owned(), 8);
+        assert_eq!(st.labels.get("end").unwrap().to_owned(), 12);
+    }
+
+    #[test]
+    fn test_constant_errors() {
+        let source = "\t.eqv N, start\n\
+                      start: nop\n\
+                      \t.eqv M, 1\n\
+                      M: nop\n\
+                      \t.eqv M, 2\n";
+        let mut diagnostics = Diagnostics::new();
+        let lines = parse_source(source, "eqv.asm", &mut diagnostics);
+        build_symbol_table(&lines, "eqv.asm", &mut diagnostics);
+        diagnostics.sort_by_position();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "expected a constant, found label `start`",
+                "label `M` has the same name as a constant",
+                "constant `M` is defined multiple times",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_global_and_extern_labels() {
+        let source = "\t.globl main, missing\n\
+                      \t.extern printf, main\n\
+                      main: j printf\n\
+                      \t.data\n\
+                      msg: .word 1\n";
+        let mut diagnostics = Diagnostics::new();
+        let lines = parse_source(source, "linked.asm", &mut diagnostics);
+        let st = build_symbol_table(&lines, "linked.asm", &mut diagnostics);
+        assert!(st.globals.contains("main"));
+        assert!(st.externs.contains("printf"));
+        assert!(!st.externs.contains("main"));
+        assert_eq!(st.label_sections.get("msg"), Some(&Section::Data));
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "global label `missing` is not defined",
+                "label `main` is declared `.extern` but defined in this file",
+            ]
+        );
     }
 }
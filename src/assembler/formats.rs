@@ -0,0 +1,290 @@
+//! Machine code output formats, for loading assembled programs into FPGA block RAM,
+//! Logisim memories or other tools. Every format writes a segment as a sequence of
+//! 32-bit words, the data segment is padded with zeroes to a whole number of words.
+//!
+//! File: formats.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use std::collections::BTreeMap;
+
+/// The format of an output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One `0x` prefixed word on each line, the default output of the assembler.
+    Lines,
+    /// Raw bytes, most significant byte of each word first.
+    BinaryBigEndian,
+    /// Raw bytes, least significant byte of each word first.
+    BinaryLittleEndian,
+    /// Intel HEX records of the big-endian bytes, with byte addresses.
+    IntelHex,
+    /// A Logisim `v2.0 raw` memory image, eight words on each line.
+    Logisim,
+    /// A file for Verilog's `$readmemh`, one word on each line.
+    Readmemh,
+}
+
+/// The names of the formats on the command line.
+pub const FORMAT_NAMES: [(&str, OutputFormat); 6] = [
+    ("lines", OutputFormat::Lines),
+    ("bin", OutputFormat::BinaryBigEndian),
+    ("bin-le", OutputFormat::BinaryLittleEndian),
+    ("ihex", OutputFormat::IntelHex),
+    ("logisim", OutputFormat::Logisim),
+    ("readmemh", OutputFormat::Readmemh),
+];
+
+/// The number of data bytes in each Intel HEX data record.
+const INTEL_HEX_RECORD_SIZE: usize = 16;
+
+impl OutputFormat {
+    /// from_name: the format with the given command line name.
+    ///
+    /// input: name - the name of the format, such as `ihex`
+    /// returns: the format, or none if no format has that name
+    ///
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        FORMAT_NAMES
+            .iter()
+            .find(|(format_name, _)| *format_name == name)
+            .map(|(_, format)| *format)
+    }
+
+    /// write: writes a segment in this format.
+    ///
+    /// input: words - the contents of the segment
+    ///        base - the byte address of the first word, used by formats with addresses
+    /// returns: the contents of the output file
+    ///
+    pub fn write(&self, words: &[u32], base: u32) -> Vec<u8> {
+        match self {
+            OutputFormat::Lines => words
+                .iter()
+                .map(|word| format!("{:#010x}\n", word))
+                .collect::<String>()
+                .into_bytes(),
+            OutputFormat::BinaryBigEndian => words.iter().flat_map(|word| word.to_be_bytes()).collect(),
+            OutputFormat::BinaryLittleEndian => words.iter().flat_map(|word| word.to_le_bytes()).collect(),
+            OutputFormat::IntelHex => intel_hex(words, base).into_bytes(),
+            OutputFormat::Logisim => {
+                let mut output = "v2.0 raw\n".to_string();
+                for line in words.chunks(8) {
+                    let line: Vec<String> = line.iter().map(|word| format!("{:x}", word)).collect();
+                    output.push_str(&line.join(" "));
+                    output.push('\n');
+                }
+                output.into_bytes()
+            }
+            OutputFormat::Readmemh => words
+                .iter()
+                .map(|word| format!("{:08x}\n", word))
+                .collect::<String>()
+                .into_bytes(),
+        }
+    }
+}
+
+/// to_words: groups bytes into big-endian words, padding the last word with zeroes.
+///
+/// input: bytes - the bytes to group
+/// returns: the words
+///
+pub fn to_words(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u32::from_be_bytes(word)
+        })
+        .collect()
+}
+
+/// read_intel_hex: reads Intel HEX records, as written in the `ihex` format. Data records
+/// are placed by the extended linear and extended segment address records before them,
+/// start address records are skipped.
+///
+/// input: text - the records, one on each line
+/// returns: the address of the lowest byte and the bytes from there up to the highest
+///          one, with the bytes no record gives set to 0, or an error naming the line of
+///          a record that cannot be read
+///
+pub fn read_intel_hex(text: &str) -> Result<(u32, Vec<u8>), String> {
+    let mut bytes: BTreeMap<u32, u8> = BTreeMap::new();
+    let mut upper = 0;
+    for (index, line) in text.lines().enumerate() {
+        let error = |what: &str| format!("Intel HEX record on line {}: {}", index + 1, what);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let hex = line
+            .strip_prefix(':')
+            .ok_or_else(|| error("expected a record starting with `:`"))?;
+        let record: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|at| hex.get(at..at + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+            .collect::<Option<_>>()
+            .ok_or_else(|| error("expected hexadecimal bytes"))?;
+        if record.len() < 5 || record.len() != record[0] as usize + 5 {
+            return Err(error("the length of the record does not match its byte count"));
+        }
+        if record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(error("wrong checksum"));
+        }
+        let addr = u16::from_be_bytes([record[1], record[2]]);
+        let data = &record[4..record.len() - 1];
+        match (record[3], data) {
+            (0, _) => {
+                for (offset, byte) in data.iter().enumerate() {
+                    // addresses wrap around within the 64 KiB the upper bits select
+                    bytes.insert(upper + addr.wrapping_add(offset as u16) as u32, *byte);
+                }
+            }
+            (1, _) => break,
+            (2, [high, low]) => upper = (u16::from_be_bytes([*high, *low]) as u32) << 4,
+            (4, [high, low]) => upper = (u16::from_be_bytes([*high, *low]) as u32) << 16,
+            (3 | 5, _) => (),
+            (record_type, _) => return Err(error(&format!("unsupported record type {}", record_type))),
+        }
+    }
+    let (base, end) = match (bytes.keys().next(), bytes.keys().next_back()) {
+        (Some(base), Some(last)) => (*base, *last),
+        _ => return Ok((0, Vec::new())),
+    };
+    let mut output = vec![0; (end - base) as usize + 1];
+    for (addr, byte) in bytes {
+        output[(addr - base) as usize] = byte;
+    }
+    Ok((base, output))
+}
+
+/// Writes the bytes of the words as Intel HEX data records, with an extended
+/// linear address record whenever the upper 16 bits of the address change.
+fn intel_hex(words: &[u32], base: u32) -> String {
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let mut output = String::new();
+    let mut upper = 0;
+    let mut written = 0;
+    while written < bytes.len() {
+        let addr = base.wrapping_add(written as u32);
+        if addr >> 16 != upper {
+            upper = addr >> 16;
+            output.push_str(&intel_hex_record(0, 4, &(upper as u16).to_be_bytes()));
+        }
+        // a record must not cross into the next 64 KiB
+        let until_boundary = 0x10000 - (addr & 0xffff) as usize;
+        let size = INTEL_HEX_RECORD_SIZE.min(until_boundary).min(bytes.len() - written);
+        output.push_str(&intel_hex_record(addr as u16, 0, &bytes[written..written + size]));
+        written += size;
+    }
+    output.push_str(&intel_hex_record(0, 1, &[]));
+    output
+}
+
+/// One Intel HEX record, `:LLAAAATT` followed by the data and the checksum.
+fn intel_hex_record(addr: u16, record_type: u8, data: &[u8]) -> String {
+    let mut record = vec![data.len() as u8];
+    record.extend(addr.to_be_bytes());
+    record.push(record_type);
+    record.extend(data);
+    let checksum = record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)).wrapping_neg();
+    record.push(checksum);
+    let hex: String = record.iter().map(|byte| format!("{:02X}", byte)).collect();
+    format!(":{}\n", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [u32; 2] = [0x2004_0003, 0xffff_ffff];
+
+    fn write(format: OutputFormat, words: &[u32], base: u32) -> String {
+        String::from_utf8(format.write(words, base)).unwrap()
+    }
+
+    #[test]
+    fn test_text_formats() {
+        assert_eq!(write(OutputFormat::Lines, &WORDS, 0), "0x20040003\n0xffffffff\n");
+        assert_eq!(write(OutputFormat::Readmemh, &WORDS, 0), "20040003\nffffffff\n");
+        assert_eq!(write(OutputFormat::Logisim, &WORDS, 0), "v2.0 raw\n20040003 ffffffff\n");
+        let nine = [0; 9];
+        assert_eq!(write(OutputFormat::Logisim, &nine, 0), "v2.0 raw\n0 0 0 0 0 0 0 0\n0\n");
+    }
+
+    #[test]
+    fn test_binary_formats() {
+        assert_eq!(
+            OutputFormat::BinaryBigEndian.write(&WORDS, 0),
+            vec![0x20, 0x04, 0x00, 0x03, 0xff, 0xff, 0xff, 0xff]
+        );
+        assert_eq!(
+            OutputFormat::BinaryLittleEndian.write(&WORDS, 0),
+            vec![0x03, 0x00, 0x04, 0x20, 0xff, 0xff, 0xff, 0xff]
+        );
+    }
+
+    #[test]
+    fn test_intel_hex() {
+        assert_eq!(
+            write(OutputFormat::IntelHex, &WORDS, 0),
+            ":0800000020040003FFFFFFFFD5\n:00000001FF\n"
+        );
+        let words = [0; 5];
+        assert_eq!(
+            write(OutputFormat::IntelHex, &words, 0xfff0),
+            ":10FFF0000000000000000000000000000000000001\n\
+             :020000040001F9\n\
+             :0400000000000000FC\n\
+             :00000001FF\n"
+        );
+        // the record that would cross into the next 64 KiB is split
+        let words = [0; 2];
+        assert_eq!(
+            write(OutputFormat::IntelHex, &words, 0xfffc),
+            ":04FFFC000000000001\n\
+             :020000040001F9\n\
+             :0400000000000000FC\n\
+             :00000001FF\n"
+        );
+    }
+
+    #[test]
+    fn test_read_intel_hex() {
+        let hex = write(OutputFormat::IntelHex, &WORDS, 0);
+        assert_eq!(read_intel_hex(&hex), Ok((0, vec![0x20, 0x04, 0x00, 0x03, 0xff, 0xff, 0xff, 0xff])));
+        // records across 64 KiB, placed by extended linear address records
+        let hex = write(OutputFormat::IntelHex, &[1, 2, 3, 4, 5], 0xfff8);
+        let (base, bytes) = read_intel_hex(&hex).unwrap();
+        assert_eq!((base, to_words(&bytes)), (0xfff8, vec![1, 2, 3, 4, 5]));
+        // an extended segment address record, and a gap between records
+        let (base, bytes) = read_intel_hex(":020000021000EC
+:0100100001EE
+:0100130002EA
+").unwrap();
+        assert_eq!((base, bytes), (0x10010, vec![1, 0, 0, 2]));
+
+        assert_eq!(
+            read_intel_hex(":0800000020040003FFFFFFFFD6
+"),
+            Err("Intel HEX record on line 1: wrong checksum".to_string())
+        );
+        assert_eq!(
+            read_intel_hex("\n:0400000020040003\n"),
+            Err("Intel HEX record on line 2: the length of the record does not match its byte count".to_string())
+        );
+        assert!(read_intel_hex(":00000001FF
+0x20040003
+").is_ok());
+        assert!(read_intel_hex(":0000000G
+").unwrap_err().ends_with("expected hexadecimal bytes"));
+    }
+
+    #[test]
+    fn test_to_words() {
+        assert_eq!(to_words(&[1, 2, 3, 4, 5]), vec![0x0102_0304, 0x0500_0000]);
+        assert_eq!(OutputFormat::from_name("ihex"), Some(OutputFormat::IntelHex));
+        assert_eq!(OutputFormat::from_name("hex"), None);
+    }
+}
@@ -0,0 +1,239 @@
+//! Resolution of `.include "file.asm"`, which assembles the lines of another file
+//! in place of the directive.
+//!
+//! An included file is looked for next to the file including it first, and then in
+//! the include directories given to the assembler. Included files may include other
+//! files, but not themselves.
+//!
+//! File: includes.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::diagnostics::{Diagnostic, Diagnostics, LineError};
+use crate::assembler::lexer::{tokenize_line, Token, TokenKind};
+use crate::assembler::macros::{label_count, SourceLine};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// resolve_includes: replaces every `.include` in the source with the lines of the file.
+///
+/// input: source - the assembly source
+///        file_name - path of the source, included files are looked for next to it
+///        include_dirs - other directories to look for included files in
+///        diagnostics - where files that cannot be included are reported
+/// returns: the lines of the source with the included lines in place
+///
+pub fn resolve_includes(
+    source: &str,
+    file_name: &str,
+    include_dirs: &[PathBuf],
+    diagnostics: &mut Diagnostics,
+) -> Vec<SourceLine> {
+    let mut output = Vec::new();
+    let mut stack = vec![PathBuf::from(file_name)];
+    include_lines(
+        SourceLine::from_source(source, None),
+        file_name,
+        include_dirs,
+        &mut stack,
+        &mut output,
+        diagnostics,
+    );
+    output
+}
+
+/// Adds the lines of one file to `output`, including the files it includes.
+/// `stack` holds the paths of the files currently being included, the last one
+/// being the file the lines are from.
+fn include_lines(
+    lines: Vec<SourceLine>,
+    file_name: &str,
+    include_dirs: &[PathBuf],
+    stack: &mut Vec<PathBuf>,
+    output: &mut Vec<SourceLine>,
+    diagnostics: &mut Diagnostics,
+) {
+    for line in lines {
+        let tokens = match tokenize_line(&line.code, line.number) {
+            Ok(tokens) => tokens,
+            Err(_) => {
+                output.push(line);
+                continue;
+            }
+        };
+        let label_end = label_count(&tokens) * 2;
+        match &tokens[label_end].kind {
+            TokenKind::Directive(name) if name == ".include" => (),
+            _ => {
+                output.push(line);
+                continue;
+            }
+        }
+
+        // the labels of the line stay in place, in front of the included lines
+        let labels_code: String = match label_end {
+            0 => String::new(),
+            _ => {
+                let end = tokens[label_end - 1].span;
+                line.code.chars().take(end.column + end.length - 1).collect()
+            }
+        };
+        output.push(line.derived(&line.text, &labels_code));
+
+        let included = included_path(&tokens[label_end..], stack, include_dirs).and_then(
+            |(path, display_name)| {
+                let source = fs::read_to_string(&path).map_err(|err| {
+                    LineError::new(format!("cannot read `{}`: {}", display_name, err))
+                        .at(tokens[label_end + 1].span)
+                })?;
+                Ok((path, display_name, source))
+            },
+        );
+        match included {
+            Ok((path, display_name, source)) => {
+                stack.push(path);
+                include_lines(
+                    SourceLine::from_source(&source, Some(&display_name)),
+                    file_name,
+                    include_dirs,
+                    stack,
+                    output,
+                    diagnostics,
+                );
+                stack.pop();
+            }
+            Err(err) => {
+                let file = line.file.as_deref().unwrap_or(file_name);
+                diagnostics.push(Diagnostic::from_line_error(
+                    file,
+                    line.number,
+                    &line.code,
+                    err,
+                ));
+            }
+        }
+    }
+}
+
+/// Finds the file an `.include` directive refers to.
+///
+/// input: tokens - the tokens of the directive, starting at `.include`
+///        stack - the paths of the files being included
+///        include_dirs - other directories to look for the file in
+/// returns: the path of the file and the name to show for it in diagnostics
+///
+fn included_path(
+    tokens: &[Token],
+    stack: &[PathBuf],
+    include_dirs: &[PathBuf],
+) -> Result<(PathBuf, String), LineError> {
+    let kinds = (
+        tokens.get(1).map(|token| &token.kind),
+        tokens.get(2).map(|token| &token.kind),
+    );
+    let (name, span) = match kinds {
+        (Some(TokenKind::Str(name)), Some(TokenKind::Newline)) => (name, tokens[1].span),
+        _ => {
+            let span = tokens[0].span.to(tokens[tokens.len() - 1].span);
+            return Err(
+                LineError::new("`.include` expects the name of a file".to_string())
+                    .at(span)
+                    .with_hint(Some("expected `.include \"file.asm\"`".to_string())),
+            );
+        }
+    };
+
+    let including_dir = stack
+        .last()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let candidates: Vec<PathBuf> = std::iter::once(&including_dir)
+        .chain(include_dirs)
+        .map(|dir| dir.join(name))
+        .collect();
+    let path = match candidates.iter().find(|path| path.is_file()) {
+        Some(path) => path.clone(),
+        None => {
+            return Err(LineError::new(format!("cannot find `{}`", name))
+                .at(span)
+                .with_hint(Some(format!(
+                    "looked for it in {}",
+                    candidates
+                        .iter()
+                        .map(|path| format!("`{}`", path.display()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))))
+        }
+    };
+
+    let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+    let is_open = stack
+        .iter()
+        .any(|open| fs::canonicalize(open).is_ok_and(|open| open == canonical));
+    if is_open {
+        return Err(LineError::new(format!("`{}` includes itself", name))
+            .at(span)
+            .with_hint(Some("a file cannot be included while it is being included".to_string())));
+    }
+
+    Ok((path.clone(), path.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// Creates an empty directory for the files of a test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("darken-includes-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_resolve_includes() {
+        let dir = test_dir("resolve");
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(dir.join("defs.asm"), ".eqv SIZE, 4\n.include \"nested.asm\"\n").unwrap();
+        fs::write(dir.join("lib").join("nested.asm"), "nop\n").unwrap();
+        let main = dir.join("main.asm");
+        let source = "start: .include \"defs.asm\"\nexit\n";
+
+        let mut diagnostics = Diagnostics::new();
+        let lines = resolve_includes(
+            source,
+            main.to_str().unwrap(),
+            &[dir.join("lib")],
+            &mut diagnostics,
+        );
+        assert!(diagnostics.is_empty(), "{}", diagnostics);
+        let code: Vec<&str> = lines.iter().map(|line| line.code.as_str()).collect();
+        assert_eq!(code, vec!["start:", ".eqv SIZE, 4", "", "nop", "exit"]);
+        assert_eq!(lines[3].number, 1);
+        assert!(lines[3].file.as_ref().unwrap().ends_with("nested.asm"));
+        assert_eq!(lines[4].file, None);
+    }
+
+    #[test]
+    fn test_include_errors() {
+        let dir = test_dir("errors");
+        fs::write(dir.join("loop.asm"), ".include \"loop.asm\"\n").unwrap();
+        let main = dir.join("main.asm");
+        let source = ".include \"missing.asm\"\n.include \"loop.asm\"\n.include missing\n";
+
+        let mut diagnostics = Diagnostics::new();
+        resolve_includes(source, main.to_str().unwrap(), &[], &mut diagnostics);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "cannot find `missing.asm`",
+                "`loop.asm` includes itself",
+                "`.include` expects the name of a file",
+            ]
+        );
+    }
+}
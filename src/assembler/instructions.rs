@@ -1,5 +1,10 @@
-use crate::assembler::parser::{has_instruction, remove_comment_from_line, remove_label_from_line};
-use darken_assignment1::utils::InstrRepresentable;
+use crate::assembler::diagnostics::{closest_match, LineError};
+use crate::assembler::ast::{Expr, Operand, OperandKind, Statement, StatementKind};
+use crate::assembler::expressions::{check_range, Symbols};
+use crate::assembler::object::RelocationKind;
+use crate::assembler::parser::parse_line;
+use crate::assembler::spec::{self, DecodedInstruction, Fields, Format};
+use crate::utils::InstrRepresentable;
 /**
  * Implements the building of the supported instructions as well as the logic for parsing
  *  instructions with the help of functions from the parser.rs file
@@ -11,308 +16,52 @@ use darken_assignment1::utils::InstrRepresentable;
 **/
 use std::collections::HashMap;
 
-use ux::{u10, u26, u5, u6};
+use ux::{u5, u6};
 
 // exit:
 #[derive(Debug)]
 pub struct TerminateInstruction {}
-impl TerminateInstruction{
-    pub fn to_hex_string(&self) -> String {
-        "0xFFFFFFFF".to_string()
-    }
-}
 impl InstrRepresentable for TerminateInstruction {
-    fn to_hex_string(&self) -> String {
-        self.to_hex_string()
-    }
-
-    fn to_bin_string(&self) -> String {
-        "1".repeat(32)
-    }
-
-    fn get_rs_str(&self) -> Option<String> {
-        self.get_bits(25, 21)
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        self.get_bits(20, 16)
-    }
-
-    fn get_rd_str(&self) -> Option<String> {
-        self.get_bits(15, 11)
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        self.get_bits(10, 6)
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        self.get_bits(5, 0)
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        self.get_bits(15, 0)
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        self.get_bits(25, 0)
-    }
-
-    fn to_mnemonic_string(&self) -> String {
-        "exit".to_string()
-    }
-
-    fn get_op_val(&self) -> i32 {
-        63 // 111111
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        Some(31) // 11111
-    }
-
-    fn get_rt_val(&self) -> Option<i32> {
-        Some(31) // 11111
-    }
-    fn get_rd_val(&self) -> Option<i32> {
-        Some(31) // 11111
-    }
-
-    fn get_shamt_val(&self) -> Option<i32> {
-        Some(31) // 11111
-    }
-
-    fn get_funct_val(&self) -> Option<i32> {
-        Some(63) // 111111
-    }
-
-    fn get_imm_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_jump_address_val(&self) -> Option<i32> {
-        None
+    fn decoded(&self) -> DecodedInstruction {
+        DecodedInstruction::new(spec::lookup("exit").unwrap(), Fields::default())
     }
 }
 
 #[derive(Debug)]
 pub struct NopTypeInstruction {}
-impl NopTypeInstruction {
-    pub fn to_hex_string(&self) -> String {
-        "0x00000000".to_string()
-    }
-}
-
 impl InstrRepresentable for NopTypeInstruction {
-    fn to_hex_string(&self) -> String {
-        self.to_hex_string()
-    }
-
-    fn to_bin_string(&self) -> String {
-        "0".repeat(32)
-    }
-
-    fn get_rs_str(&self) -> Option<String> {
-        self.get_bits(25, 21)
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        self.get_bits(20, 16)
-    }
-
-    fn get_rd_str(&self) -> Option<String> {
-        self.get_bits(15, 11)
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        self.get_bits(10, 6)
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        self.get_bits(5, 0)
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        self.get_bits(15, 0)
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        self.get_bits(25, 0)
-    }
-
-    fn to_mnemonic_string(&self) -> String {
-        "nop".to_string()
-    }
-
-    fn get_op_val(&self) -> i32 {
-        0
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        Some(0)
-    }
-
-    fn get_rt_val(&self) -> Option<i32> {
-        Some(0)
-    }
-    fn get_rd_val(&self) -> Option<i32> {
-        Some(0)
-    }
-
-    fn get_shamt_val(&self) -> Option<i32> {
-        Some(0)
-    }
-
-    fn get_funct_val(&self) -> Option<i32> {
-        Some(0)
-    }
-
-    fn get_imm_val(&self) -> Option<i32> {
-        Some(0)
-    }
-
-    fn get_jump_address_val(&self) -> Option<i32> {
-        Some(0)
+    fn decoded(&self) -> DecodedInstruction {
+        DecodedInstruction::new(spec::lookup("nop").unwrap(), Fields::default())
     }
-
-
 }
 
 #[derive(Debug)]
 pub struct JRTypeInstruction {
-    op: u6,
-    rs: u5,
-    zero: u10,
-    hint: u5,
-    func: u6,
+    instruction: DecodedInstruction,
 }
 
 impl JRTypeInstruction {
     pub fn new(instruction: &str, rs: &str) -> Option<JRTypeInstruction> {
-        let num_op = get_numeric_op(instruction)?;
-        let rs_num = get_register_number(rs)?;
-        let func_num: u6 = get_func(instruction)?;
-
+        let spec = spec::lookup(instruction)?;
+        let fields = Fields {
+            rs: get_register_number(rs)?.into(),
+            ..Fields::default()
+        };
         Some(JRTypeInstruction {
-            op: num_op,
-            rs: rs_num,
-            zero: u10::new(0),
-            hint: u5::new(0),
-            func: func_num,
+            instruction: DecodedInstruction::new(spec, fields),
         })
     }
-
-    pub fn to_hex_string(&self) -> String {
-        let mut hex_instr: u32 = 0;
-        let rs_val: u32 = self.rs.into();
-        let func_val: u32 = self.func.into();
-
-        hex_instr += rs_val << 21;
-        hex_instr += func_val;
-
-        format!("{:#010x}", hex_instr).to_string()
-    }
-
-    pub fn to_bin_string(&self) -> String {
-        let op_val = self.op;
-        let rs_val = self.rs;
-        let hint_val = self.hint;
-        let func_val = self.func;
-        let op_str = format!("{op_val:06b}");
-        let rs_str = format!("{rs_val:05b}");
-        let hint_str = format!("{hint_val:05b}");
-        let func_str = format!("{func_val:06b}");
-        let zeros_str = "0".repeat(10); //TODO: Make sure all JR instructions have 10 0's in the middle
-        format!("{}{}{}{}{}", op_str, rs_str, zeros_str, hint_str, func_str)
-    }
 }
 
 impl InstrRepresentable for JRTypeInstruction {
-    fn to_hex_string(&self) -> String {
-        self.to_hex_string()
-    }
-
-    // this is complex becasue attribute accesses aren't allowed in format!() for some reason
-    fn to_bin_string(&self) -> String {
-        self.to_bin_string()
-    }
-
-    fn get_rs_str(&self) -> Option<String> {
-        self.get_bits(25, 21)
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        self.get_bits(20, 16)
-    }
-
-    fn get_rd_str(&self) -> Option<String> {
-        self.get_bits(15, 11)
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        self.get_bits(10, 6)
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        self.get_bits(5, 0)
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        None
-    }
-    fn to_mnemonic_string(&self) -> String {
-        let mut mnemonic = String::new();
-        mnemonic.push_str("jr ");
-        let reg = get_register_name(self.rs).unwrap();
-        mnemonic.push_str(&reg);
-
-        mnemonic
-    }
-
-    fn get_op_val(&self) -> i32 {
-        let op_u32: u32 = self.op.into();
-        op_u32 as i32
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        let rs_u32:u32 = self.rs.into();
-        Some(rs_u32 as i32)
-    }
-
-    fn get_rt_val(&self) -> Option<i32> {
-        None
-    }
-    
-    fn get_rd_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_shamt_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_funct_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_imm_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_jump_address_val(&self) -> Option<i32> {
-        None
+    fn decoded(&self) -> DecodedInstruction {
+        self.instruction
     }
 }
 
 #[derive(Debug)]
 pub struct MemoryAccessTypeInstruction {
-    op: u6,
-    base: u5,
-    rt: u5,
-    offset: i16,
+    instruction: DecodedInstruction,
 }
 
 impl MemoryAccessTypeInstruction {
@@ -322,149 +71,28 @@ impl MemoryAccessTypeInstruction {
         offset: &str,
         base: &str,
     ) -> Option<MemoryAccessTypeInstruction> {
-        let num_op = get_numeric_op(instruction)?;
-        let rt_num = get_register_number(rt)?;
-        let base_num = get_register_number(base)?;
-        let offset_num = i16::from_str_radix(offset, 10);
-        if offset_num.is_err() {
-            return None;
-        }
-        let offset_num = offset_num.unwrap();
+        let spec = spec::lookup(instruction)?;
+        let offset = i16::from_str_radix(offset, 10).ok()?;
+        let fields = Fields {
+            rs: get_register_number(base)?.into(),
+            rt: get_register_number(rt)?.into(),
+            imm: offset as u16,
+            ..Fields::default()
+        };
         Some(MemoryAccessTypeInstruction {
-            op: num_op,
-            base: base_num,
-            rt: rt_num,
-            offset: offset_num,
+            instruction: DecodedInstruction::new(spec, fields),
         })
     }
-
-    pub fn to_hex_string(&self) -> String {
-        let mut hex_instr: u32 = 0;
-        let op_val: u32 = self.op.into();
-        let rt_val: u32 = self.rt.into();
-        let base_val: u32 = self.base.into();
-        hex_instr += op_val << 26;
-        hex_instr += base_val << 21;
-        hex_instr += rt_val << 16;
-        hex_instr += (self.offset as u16) as u32;
-        format!("{:#010x}", hex_instr).to_string()
-    }
-
-    pub fn to_bin_string(&self) -> String {
-        // op, base, rt, offset
-        let op_val = self.op;
-        let base_val = self.base;
-        let rt_val = self.rt;
-        let offset_val = self.offset;
-
-        let op_str = format!("{op_val:05b}");
-        let base_str = format!("{base_val:05b}");
-        let rt_str = format!("{rt_val:05b}");
-        let offset_str = format!("{offset_val:016b}");
-
-        format!("{}{}{}{}", op_str, base_str, rt_str, offset_str)
-    }
 }
 
 impl InstrRepresentable for MemoryAccessTypeInstruction {
-    fn to_hex_string(&self) -> String {
-        self.to_hex_string()
-    }
-
-    fn to_bin_string(&self) -> String {
-        self.to_bin_string()
-    }
-
-    fn get_rs_str(&self) -> Option<String> {
-        self.get_bits(25, 21)
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        self.get_bits(20, 16)
+    fn decoded(&self) -> DecodedInstruction {
+        self.instruction
     }
-
-    fn get_rd_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        self.get_bits(15, 0)
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        None
-    }
-
-    fn to_mnemonic_string(&self) -> String {
-        let mut mnemonic = String::new();
-        let operation = get_operation(self.op, u6::new(0)).unwrap();
-        let rt = get_register_name(self.rt).unwrap();
-        let base = get_register_name(self.base).unwrap();
-        mnemonic.push_str(&operation);
-        mnemonic.push_str(" ");
-        mnemonic.push_str(&rt);
-        mnemonic.push_str(", ");
-        mnemonic.push_str(&self.offset.to_string());
-        mnemonic.push_str("(");
-        mnemonic.push_str(&base);
-        mnemonic.push_str(")");
-
-        mnemonic
-    }
-
-    fn get_op_val(&self) -> i32 {
-        let op_u32: u32 = self.op.into();
-        op_u32 as i32
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        let base: u32 = self.base.into();
-        Some(base as i32)
-    }
-
-    fn get_rt_val(&self) -> Option<i32> {
-        let rt: u32 = self.rt.into();
-        Some(rt as i32) 
-    }
-
-    fn get_rd_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_shamt_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_funct_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_imm_val(&self) -> Option<i32> {
-        Some(self.offset.into())
-    }
-
-    fn get_jump_address_val(&self) -> Option<i32> {
-        None
-    }
-
 }
 #[derive(Debug)]
-
 pub struct RTypeInstruction {
-    op: u6,
-    rs: u5,
-    rt: u5,
-    rd: u5,
-    shamt: u5,
-    func: u6,
+    instruction: DecodedInstruction,
 }
 
 impl RTypeInstruction {
@@ -472,194 +100,67 @@ impl RTypeInstruction {
         if instruction.contains("sll") || instruction.contains("sra") || instruction.contains("srl") {
             return RTypeInstruction::shift(instruction, rd, rs, rt);
         }
-        let numeric_op = get_numeric_op(instruction)?;
-        let rs_num = get_register_number(rs)?;
-        let rt_num = get_register_number(rt)?;
-        let rd_num = get_register_number(rd)?;
-        let numeric_func = get_func(instruction)?;
+        let spec = spec::lookup(instruction).filter(|spec| spec.opcode == 0)?;
+        let fields = Fields {
+            rs: get_register_number(rs)?.into(),
+            rt: get_register_number(rt)?.into(),
+            rd: get_register_number(rd)?.into(),
+            ..Fields::default()
+        };
         Some(RTypeInstruction {
-            op: numeric_op,
-            rs: rs_num,
-            rt: rt_num,
-            rd: rd_num,
-            shamt: u5::new(0),
-            func: numeric_func,
+            instruction: DecodedInstruction::new(spec, fields),
         })
     }
 
     pub fn shift(instruction: &str, rd: &str, rt: &str, shamt: &str) -> Option<RTypeInstruction> {
-        let rd_num = get_register_number(rd)?;
-        let rt_num = get_register_number(rt)?;
-        let shamt_num = shamt.parse::<u8>().unwrap();
-
+        let spec = spec::lookup(instruction).filter(|spec| spec.opcode == 0)?;
+        let fields = Fields {
+            rt: get_register_number(rt)?.into(),
+            rd: get_register_number(rd)?.into(),
+            shamt: shamt.parse::<u8>().unwrap(),
+            ..Fields::default()
+        };
         Some(RTypeInstruction {
-            op: u6::new(0),
-            rs: u5::new(0),
-            rt: rt_num,
-            rd: rd_num,
-            shamt: u5::new(shamt_num),
-            func: get_func(instruction)?,
+            instruction: DecodedInstruction::new(spec, fields),
         })
     }
 
 
-    pub fn to_hex_string(&self) -> String {
-        let mut hex_instr: u32 = 0;
-        let op_val: u32 = self.get_op().into();
-        let rs_val: u32 = self.get_rs().into();
-        let rt_val: u32 = self.get_rt().into();
-        let rd_val: u32 = self.get_rd().into();
-        let shamt_val: u32 = self.get_shamt().into();
-        let func_val: u32 = self.get_func().into();
-        hex_instr += op_val << 26;
-        hex_instr += rs_val << 21;
-        hex_instr += rt_val << 16;
-        hex_instr += rd_val << 11;
-        hex_instr += shamt_val << 6;
-        hex_instr += func_val;
-        format!("{:#010x}", hex_instr).to_string()
-    }
-
     pub fn get_op(&self) -> u6 {
-        self.op
+        u6::new(self.instruction.spec.opcode)
     }
 
     pub fn get_rs(&self) -> u5 {
-        self.rs
+        u5::new(self.instruction.fields.rs)
     }
 
     pub fn get_rt(&self) -> u5 {
-        self.rt
+        u5::new(self.instruction.fields.rt)
     }
 
     pub fn get_rd(&self) -> u5 {
-        self.rd
+        u5::new(self.instruction.fields.rd)
     }
 
     pub fn get_shamt(&self) -> u5 {
-        self.shamt
+        u5::new(self.instruction.fields.shamt)
     }
 
     pub fn get_func(&self) -> u6 {
-        self.func
+        u6::new(self.instruction.spec.funct)
     }
 }
 
 impl InstrRepresentable for RTypeInstruction {
-    fn to_hex_string(&self) -> String {
-        self.to_hex_string()
-    }
-
-    fn to_bin_string(&self) -> String {
-        let op_val = self.op;
-        let rs_val = self.rs;
-        let rt_val = self.rt;
-        let rd_val = self.rd;
-        let shamt_val = self.shamt;
-        let func_val = self.func;
-        let op_str = format!("{op_val:06b}");
-        let rs_str = format!("{rs_val:05b}");
-        let rt_str = format!("{rt_val:05b}");
-        let rd_str = format!("{rd_val:05b}");
-        let shamt_str = format!("{shamt_val:05b}");
-        let func_str = format!("{func_val:06b}");
-        format!("{}{}{}{}{}{}", op_str, rs_str, rt_str, rd_str, shamt_str, func_str)
-    }
-
-    fn get_rs_str(&self) -> Option<String> {
-        self.get_bits(25, 21)
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        self.get_bits(20,16)
-    }
-
-    fn get_rd_str(&self) -> Option<String> {
-        self.get_bits(15, 11)
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        self.get_bits(10, 6)
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        self.get_bits(5, 0)
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        None
-    }
-
-    fn to_mnemonic_string(&self) -> String {
-        let operation = get_operation(self.op, self.func).unwrap();
-        let rs = get_register_name(self.rs).unwrap();
-        let rt = get_register_name(self.rt).unwrap();
-        let rd = get_register_name(self.rd).unwrap();
-        let mut mnemonic = String::new();
-        
-        mnemonic.push_str(&operation);
-        mnemonic.push_str(" ");
-        mnemonic.push_str(&rd);
-        mnemonic.push_str(", ");
-        mnemonic.push_str(&rs);
-        mnemonic.push_str(", ");
-        mnemonic.push_str(&rt);
-
-
-        mnemonic
-        
-    }
-
-    fn get_op_val(&self) -> i32 {
-        let op_val: u32 = self.op.into();
-        op_val as i32
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        let rs_val: u32 = self.rs.into();
-        Some(rs_val as i32)
-    }
-
-    fn get_rt_val(&self) -> Option<i32> {
-        let rt_val: u32 = self.rt.into();
-        Some(rt_val as i32)
-    }
-
-    fn get_shamt_val(&self) -> Option<i32> {
-        let shamt_val: u32 = self.shamt.into();
-        Some(shamt_val as i32)
-    }
-
-    fn get_funct_val(&self) -> Option<i32> {
-        let func_val: u32 = self.func.into();
-        Some(func_val as i32)
-    }
-
-    fn get_imm_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_jump_address_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_rd_val(&self) -> Option<i32> {
-        let rd_val: u32 = self.rd.into();
-        Some(rd_val as i32)
+    fn decoded(&self) -> DecodedInstruction {
+        self.instruction
     }
 }
     
 
 #[derive(Debug)]
 pub struct ITypeInstruction {
-    op: u6,
-    rs: u5,
-    rt: u5,
-    imm: i16,
+    instruction: DecodedInstruction,
 }
 
 impl ITypeInstruction {
@@ -674,19 +175,8 @@ impl ITypeInstruction {
             return ITypeInstruction::beq(instruction, rt, rs, imm, current_addr);
             // Switch these because MIPS is weird
         }
-        let rt_num = get_register_number(rt)?;
-        let rs_num = get_register_number(rs)?;
-        let numeric_op = get_numeric_op(instruction)?;
-        let imm_numeric = i16::from_str_radix(imm, 10);
-        if imm_numeric.is_err() {
-            return None;
-        }
-        Some(ITypeInstruction {
-            op: numeric_op,
-            rs: rs_num,
-            rt: rt_num,
-            imm: imm_numeric.unwrap(),
-        })
+        let imm_numeric = i16::from_str_radix(imm, 10).ok()?;
+        ITypeInstruction::with_imm(instruction, rs, rt, imm_numeric as u16)
     }
 
     pub fn beq(
@@ -696,539 +186,175 @@ impl ITypeInstruction {
         imm: &str,
         current_addr: u32,
     ) -> Option<ITypeInstruction> {
-        let rt_num = get_register_number(rt)?;
-        let rs_num = get_register_number(rs)?;
-        let numeric_op = get_numeric_op(instruction)?;
-        let imm_numeric = i16::from_str_radix(imm, 10);
-        if imm_numeric.is_err() {
-            return None;
-        }
-
-        let offset: i16 = imm_numeric.unwrap() - (current_addr as i16 + 4);
+        let target = imm.parse::<i64>().ok()?;
+        // the offset is encoded in words
+        let offset = i16::try_from((target - (current_addr as i64 + 4)) >> 2).ok()?;
+        ITypeInstruction::with_imm(instruction, rs, rt, offset as u16)
+    }
+
+    /// with_imm: the instruction with the 16 bit immediate field as it is encoded.
+    fn with_imm(instruction: &str, rs: &str, rt: &str, imm: u16) -> Option<ITypeInstruction> {
+        let spec = spec::lookup(instruction)?;
+        let fields = Fields {
+            rs: get_register_number(rs)?.into(),
+            rt: get_register_number(rt)?.into(),
+            imm,
+            ..Fields::default()
+        };
         Some(ITypeInstruction {
-            op: numeric_op,
-            rs: rs_num,
-            rt: rt_num,
-            imm: offset,
+            instruction: DecodedInstruction::new(spec, fields),
         })
     }
 
-    pub fn to_hex_string(&self) -> String {
-        if self.op == get_numeric_op("beq").unwrap() {
-            return self.to_hex_string_beq();
-        }
-        let mut hex_instr: u32 = 0;
-        hex_instr += u32::from(self.get_op()) << 26;
-        hex_instr += u32::from(self.get_rs()) << 21;
-        hex_instr += u32::from(self.get_rt()) << 16;
-        hex_instr += (self.imm as u16) as u32;
-        format!("{:#010x}", hex_instr).to_string()
-    }
-
-    pub fn to_hex_string_beq(&self) -> String {
-        let mut hex_instr: u32 = 0;
-        hex_instr += u32::from(self.get_op()) << 26;
-        hex_instr += u32::from(self.get_rs()) << 21;
-        hex_instr += u32::from(self.get_rt()) << 16;
-        // TODO: Might be something weird with overflowing shr here...
-        if self.get_imm().is_negative() {
-            // want to shift in 1's from the left
-            let (shifted_imm, _) = self.get_imm().overflowing_shr(2);
-            hex_instr += (shifted_imm as u16) as u32;
-        } else {
-            // want to shift in 0's from the left
-            hex_instr += (self.get_imm() as u16) as u32 >> 2;
-        }
-        format!("{:#010x}", hex_instr).to_string()
-    }
-
-    pub fn to_bin_string(&self) -> String {
-        // op, rs, rt, imm
-        let op_val = self.op;
-        let rs_val = self.rs;
-        let rt_val = self.rt;
-        let imm_val = self.imm;
-        let op_str = format!("{op_val:06b}");
-        let rs_str = format!("{rs_val:05b}");
-        let rt_str = format!("{rt_val:05b}");
-        let imm_str = format!("{imm_val:016b}");
-        format!("{}{}{}{}", op_str, rs_str, rt_str, imm_str)
-    }
-
     pub fn get_op(&self) -> u6 {
-        self.op
+        u6::new(self.instruction.spec.opcode)
     }
 
     pub fn get_rs(&self) -> u5 {
-        self.rs
+        u5::new(self.instruction.fields.rs)
     }
 
     pub fn get_rt(&self) -> u5 {
-        self.rt
+        u5::new(self.instruction.fields.rt)
     }
 
-    pub fn get_imm(&self) -> i16 {
-        self.imm
+    /// get_imm: the immediate, the offset in bytes from the next instruction for beq.
+    pub fn get_imm(&self) -> i32 {
+        match self.instruction.spec.format {
+            Format::Branch => self.instruction.branch_offset(),
+            _ => self.instruction.fields.imm as i16 as i32,
+        }
     }
 }
 
 impl InstrRepresentable for ITypeInstruction {
-    fn to_hex_string(&self) -> String {
-        self.to_hex_string()
-    }
-
-    fn to_bin_string(&self) -> String {
-        self.to_bin_string()
-    }
-
-    fn get_rs_str(&self) -> Option<String> {
-        self.get_bits(25, 21)
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        self.get_bits(20, 16)
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        self.get_bits(15, 0)
-    }
-
-    fn get_rd_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        None
-    }
-
-    fn to_mnemonic_string(&self) -> String {
-        let operation = get_operation(self.op, u6::new(0)).unwrap();
-        let rs = get_register_name(self.rs).unwrap();
-        let rt = get_register_name(self.rt).unwrap();
-        let mut mnemonic = String::new();
-        
-        mnemonic.push_str(&operation);
-        mnemonic.push_str(" ");
-        mnemonic.push_str(&rt);
-        mnemonic.push_str(", ");
-        mnemonic.push_str(&rs);
-        mnemonic.push_str(", ");
-        mnemonic.push_str(&self.imm.to_string());
-
-
-        mnemonic
-    }
-
-    fn get_op_val(&self) -> i32 {
-        let op_val: u32 = self.op.into();
-        op_val as i32
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        let rs_val: u32 = self.rs.into();
-        Some(rs_val as i32)
-    }
-
-    fn get_rt_val(&self) -> Option<i32> {
-        let rt_val: u32 = self.rt.into();
-        Some(rt_val as i32)
+    fn decoded(&self) -> DecodedInstruction {
+        self.instruction
     }
-
-    fn get_shamt_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_funct_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_imm_val(&self) -> Option<i32> {
-        Some(self.imm as i32)
-    }
-
-    fn get_jump_address_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_rd_val(&self) -> Option<i32> {
-        None
-    }
-
 }
 
 #[derive(Debug)]
 pub struct JTypeInstruction {
-    op: u6,
-    addr: u26,
+    instruction: DecodedInstruction,
 }
 
 impl JTypeInstruction {
     pub fn new(op: &str, target: &str) -> Option<JTypeInstruction> {
-        let numeric_op = get_numeric_op(op).unwrap();
-        let maybe_numeric_target = u32::from_str_radix(target, 10);
-        //.expect(format!("Could not parse decimal value from given target {}", target).as_str());
-        if let Ok(numeric_target) = maybe_numeric_target {
-            return Some(JTypeInstruction {
-                op: numeric_op,
-                addr: u26::new(numeric_target),
-            });
-        }
-
-        None
-    }
-
-    pub fn to_hex_string(&self) -> String {
-        let mut hex_instr: u32 = 0;
-        let op_val: u32 = self.get_op().into();
-        let addr_val: u32 = self.get_addr().into();
-        hex_instr += op_val << 26;
-        // kanske ett bättre sätt att göra det på? ska alltid va 2 nollor i slutet pga word aligned..
-        hex_instr += addr_val >> 2;
-
-        format!("{:#010x}", hex_instr).to_string()
-    }
-
-    pub fn to_bin_string(&self) -> String {
-        let op_val: u32 = self.op.into();
-        let addr_val: u32 = self.addr.into();
-        let mut instr_val: u32 = 0;
-        instr_val += op_val << 26;
-        instr_val += addr_val >> 2;
-        format!("{instr_val:032b}")
-    }
-
-    pub fn get_op(&self) -> u6 {
-        self.op
-    }
-
-    pub fn get_addr(&self) -> u26 {
-        self.addr
-    }
-
-    
-}
-
-impl InstrRepresentable for JTypeInstruction {
-    fn to_hex_string(&self) -> String {
-        self.to_hex_string()
-    }
-
-    fn to_bin_string(&self) -> String {
-        self.to_bin_string()
-    }
-
-    fn get_rs_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_rd_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        None
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        self.get_bits(25, 0)
-    }
-
-    // will give you target in decimal form, not the label...
-    fn to_mnemonic_string(&self) -> String {
-        let mut mnemonic = String::new();
-        mnemonic.push_str("j ");
-        mnemonic.push_str(&self.addr.to_string());
-        
-        mnemonic
-    }
-
-    fn get_op_val(&self) -> i32 {
-        let val: u32 = self.op.into();
-        val as i32
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_rt_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_shamt_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_funct_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_imm_val(&self) -> Option<i32> {
-        None
-    }
-
-    fn get_jump_address_val(&self) -> Option<i32> {
-        let val: u32 = self.addr.into();
-        Some(val as i32)
-    }
-
-    fn get_rd_val(&self) -> Option<i32> {
-        None
-    }
-
-}
-
-#[derive(Debug)]
-pub enum Instruction {
-    RType(RTypeInstruction),
-    IType(ITypeInstruction),
-    JType(JTypeInstruction),
-    NopType(NopTypeInstruction),
-    JRType(JRTypeInstruction),
-    MemoryAccessType(MemoryAccessTypeInstruction),
-    TerminateInstructionType(TerminateInstruction),
-}
-
-// is probably better to implement this for each type of instruction? Maybe?
-impl InstrRepresentable for Instruction {
-    fn to_hex_string(&self) -> String {
-        match self {
-            // Should be a better way to do this, right?
-            Self::RType(instr) => instr.to_hex_string(),
-            Self::IType(instr) => instr.to_hex_string(),
-            Self::JType(instr) => instr.to_hex_string(),
-            Self::NopType(instr) => instr.to_hex_string(),
-            Self::JRType(instr) => instr.to_hex_string(),
-            Self::MemoryAccessType(instr) => instr.to_hex_string(),
-            Self::TerminateInstructionType(instr) => instr.to_hex_string(),
-        }
-    }
-
-    fn to_bin_string(&self) -> String {
-        match self {
-            Self::RType(instr) => instr.to_bin_string(),
-            Self::IType(instr) => instr.to_bin_string(),
-            Self::JType(instr) => instr.to_bin_string(),
-            Self::NopType(instr) => instr.to_bin_string(),
-            Self::JRType(instr) => instr.to_bin_string(),
-            Self::MemoryAccessType(instr) => instr.to_bin_string(),
-            Self::TerminateInstructionType(instr) => instr.to_bin_string(),
-        }
-    }
-
-    // TODO: Should Instruction even implement this trait? Is it ever used?
-    fn get_rs_str(&self) -> Option<String> {
-        match self {
-            Self::RType(instr) => instr.get_rs_str(),
-            Self::IType(instr) => instr.get_rs_str(),
-            Self::JType(instr) => instr.get_rs_str(),
-            Self::NopType(instr) => instr.get_rs_str(),
-            Self::JRType(instr) => instr.get_rs_str(),
-            Self::MemoryAccessType(instr) => instr.get_rs_str(),
-            Self::TerminateInstructionType(instr) => instr.get_rs_str(),
-        }
-    }
-
-    fn get_rt_str(&self) -> Option<String> {
-        match self {
-            Self::RType(instr) => instr.get_rt_str(),
-            Self::IType(instr) => instr.get_rt_str(),
-            Self::JType(instr) => instr.get_rt_str(),
-            Self::NopType(instr) => instr.get_rt_str(),
-            Self::JRType(instr) => instr.get_rt_str(),
-            Self::MemoryAccessType(instr) => instr.get_rt_str(),
-            Self::TerminateInstructionType(instr) => instr.get_rt_str(),
-        }
-    }
-
-    fn get_rd_str(&self) -> Option<String> {
-        match self {
-            Self::RType(instr) => instr.get_rd_str(),
-            Self::IType(instr) => instr.get_rd_str(),
-            Self::JType(instr) => instr.get_rd_str(),
-            Self::NopType(instr) => instr.get_rd_str(),
-            Self::JRType(instr) => instr.get_rd_str(),
-            Self::MemoryAccessType(instr) => instr.get_rd_str(),
-            Self::TerminateInstructionType(instr) => instr.get_rd_str(),
-        }
-    }
-
-    fn get_shamt_str(&self) -> Option<String> {
-        match self {
-            Self::RType(instr) => instr.get_shamt_str(),
-            Self::IType(instr) => instr.get_shamt_str(),
-            Self::JType(instr) => instr.get_shamt_str(),
-            Self::NopType(instr) => instr.get_shamt_str(),
-            Self::JRType(instr) => instr.get_shamt_str(),
-            Self::MemoryAccessType(instr) => instr.get_shamt_str(),
-            Self::TerminateInstructionType(instr) => instr.get_shamt_str(),
-        }
-    }
-
-    fn get_funct_str(&self) -> Option<String> {
-        match self {
-            Self::RType(instr) => instr.get_funct_str(),
-            Self::IType(instr) => instr.get_funct_str(),
-            Self::JType(instr) => instr.get_funct_str(),
-            Self::NopType(instr) => instr.get_funct_str(),
-            Self::JRType(instr) => instr.get_funct_str(),
-            Self::MemoryAccessType(instr) => instr.get_funct_str(),
-            Self::TerminateInstructionType(instr) => instr.get_funct_str(),
-        }
-    }
-
-    fn get_imm_str(&self) -> Option<String> {
-        match self {
-            Self::RType(instr) => instr.get_imm_str(),
-            Self::IType(instr) => instr.get_imm_str(),
-            Self::JType(instr) => instr.get_imm_str(),
-            Self::NopType(instr) => instr.get_imm_str(),
-            Self::JRType(instr) => instr.get_imm_str(),
-            Self::MemoryAccessType(instr) => instr.get_imm_str(),
-            Self::TerminateInstructionType(instr) => instr.get_imm_str(),
-        }
-    }
-
-    fn get_jump_address_str(&self) -> Option<String> {
-        match self {
-            Self::RType(instr) => instr.get_jump_address_str(),
-            Self::IType(instr) => instr.get_jump_address_str(),
-            Self::JType(instr) => instr.get_jump_address_str(),
-            Self::NopType(instr) => instr.get_jump_address_str(),
-            Self::JRType(instr) => instr.get_jump_address_str(),
-            Self::MemoryAccessType(instr) => instr.get_jump_address_str(),
-            Self::TerminateInstructionType(instr) => instr.get_jump_address_str(),
-        }
-    }
-    
-    fn to_mnemonic_string(&self) -> String {
-        match self {
-            Self::RType(instr) => instr.to_mnemonic_string(),
-            Self::IType(instr) => instr.to_mnemonic_string(),
-            Self::JType(instr) => instr.to_mnemonic_string(),
-            Self::NopType(instr) => instr.to_mnemonic_string(),
-            Self::JRType(instr) => instr.to_mnemonic_string(),
-            Self::MemoryAccessType(instr) => instr.to_mnemonic_string(),
-            Self::TerminateInstructionType(instr) => instr.to_mnemonic_string(),
-        }
-    }
-
-    fn get_op_val(&self) -> i32 {
-        match self {
-            Self::RType(instr) => instr.get_op_val(),
-            Self::IType(instr) => instr.get_op_val(),
-            Self::JType(instr) => instr.get_op_val(),
-            Self::NopType(instr) => instr.get_op_val(),
-            Self::JRType(instr) => instr.get_op_val(),
-            Self::MemoryAccessType(instr) => instr.get_op_val(),
-            Self::TerminateInstructionType(instr) => instr.get_op_val(),
-        }
-    }
-
-    fn get_rs_val(&self) -> Option<i32> {
-        match self {
-            Self::RType(instr) => instr.get_rs_val(),
-            Self::IType(instr) => instr.get_rs_val(),
-            Self::JType(instr) => instr.get_rs_val(),
-            Self::NopType(instr) => instr.get_rs_val(),
-            Self::JRType(instr) => instr.get_rs_val(),
-            Self::MemoryAccessType(instr) => instr.get_rs_val(),
-            Self::TerminateInstructionType(instr) => instr.get_rs_val(),
+        let spec = spec::lookup(op)?;
+        let numeric_target = u32::from_str_radix(target, 10).ok()?;
+        // the target is encoded in words, in 26 bits
+        if numeric_target >= 1 << 28 {
+            return None;
         }
+        let fields = Fields {
+            target: numeric_target >> 2,
+            ..Fields::default()
+        };
+        Some(JTypeInstruction {
+            instruction: DecodedInstruction::new(spec, fields),
+        })
     }
 
-    fn get_rt_val(&self) -> Option<i32> {
-        match self {
-            Self::RType(instr) => instr.get_rt_val(),
-            Self::IType(instr) => instr.get_rt_val(),
-            Self::JType(instr) => instr.get_rt_val(),
-            Self::NopType(instr) => instr.get_rt_val(),
-            Self::JRType(instr) => instr.get_rt_val(),
-            Self::MemoryAccessType(instr) => instr.get_rt_val(),
-            Self::TerminateInstructionType(instr) => instr.get_rt_val(),
-        }
+    pub fn get_op(&self) -> u6 {
+        u6::new(self.instruction.spec.opcode)
     }
 
-    fn get_shamt_val(&self) -> Option<i32> {
-        match self {
-            Self::RType(instr) => instr.get_shamt_val(),
-            Self::IType(instr) => instr.get_shamt_val(),
-            Self::JType(instr) => instr.get_shamt_val(),
-            Self::NopType(instr) => instr.get_shamt_val(),
-            Self::JRType(instr) => instr.get_shamt_val(),
-            Self::MemoryAccessType(instr) => instr.get_shamt_val(),
-            Self::TerminateInstructionType(instr) => instr.get_shamt_val(),
-        }
+    /// get_addr: the address jumped to, in bytes.
+    pub fn get_addr(&self) -> u32 {
+        self.instruction.jump_address()
     }
+}
 
-    fn get_funct_val(&self) -> Option<i32> {
-        match self {
-            Self::RType(instr) => instr.get_funct_val(),
-            Self::IType(instr) => instr.get_funct_val(),
-            Self::JType(instr) => instr.get_funct_val(),
-            Self::NopType(instr) => instr.get_funct_val(),
-            Self::JRType(instr) => instr.get_funct_val(),
-            Self::MemoryAccessType(instr) => instr.get_funct_val(),
-            Self::TerminateInstructionType(instr) => instr.get_funct_val(),
-        }
+impl InstrRepresentable for JTypeInstruction {
+    fn decoded(&self) -> DecodedInstruction {
+        self.instruction
     }
+}
+
+#[derive(Debug)]
+pub enum Instruction {
+    RType(RTypeInstruction),
+    IType(ITypeInstruction),
+    JType(JTypeInstruction),
+    NopType(NopTypeInstruction),
+    JRType(JRTypeInstruction),
+    MemoryAccessType(MemoryAccessTypeInstruction),
+    TerminateInstructionType(TerminateInstruction),
+}
 
-    fn get_imm_val(&self) -> Option<i32> {
+impl InstrRepresentable for Instruction {
+    fn decoded(&self) -> DecodedInstruction {
         match self {
-            Self::RType(instr) => instr.get_imm_val(),
-            Self::IType(instr) => instr.get_imm_val(),
-            Self::JType(instr) => instr.get_imm_val(),
-            Self::NopType(instr) => instr.get_imm_val(),
-            Self::JRType(instr) => instr.get_imm_val(),
-            Self::MemoryAccessType(instr) => instr.get_imm_val(),
-            Self::TerminateInstructionType(instr) => instr.get_imm_val(),
+            Self::RType(instr) => instr.decoded(),
+            Self::IType(instr) => instr.decoded(),
+            Self::JType(instr) => instr.decoded(),
+            Self::NopType(instr) => instr.decoded(),
+            Self::JRType(instr) => instr.decoded(),
+            Self::MemoryAccessType(instr) => instr.decoded(),
+            Self::TerminateInstructionType(instr) => instr.decoded(),
         }
     }
+}
 
-    fn get_jump_address_val(&self) -> Option<i32> {
-        match self {
-            Self::RType(instr) => instr.get_jump_address_val(),
-            Self::IType(instr) => instr.get_jump_address_val(),
-            Self::JType(instr) => instr.get_jump_address_val(),
-            Self::NopType(instr) => instr.get_jump_address_val(),
-            Self::JRType(instr) => instr.get_jump_address_val(),
-            Self::MemoryAccessType(instr) => instr.get_jump_address_val(),
-            Self::TerminateInstructionType(instr) => instr.get_jump_address_val(),
+impl Instruction {
+    /// encode: the machine word of the instruction.
+    pub fn encode(&self) -> u32 {
+        self.decoded().encode()
+    }
+
+    /// decode: the instruction a machine word encodes.
+    ///
+    /// input: word - the machine word
+    /// returns: the instruction, or none if the word is not a supported instruction or
+    ///          has bits set that the instruction does not use
+    ///
+    pub fn decode(word: u32) -> Option<Instruction> {
+        DecodedInstruction::decode(word).map(|decoded| Instruction::from_decoded(&decoded))
+    }
+
+    /// from_decoded: the instruction holding the fields of a row of the instruction table.
+    ///
+    /// input: decoded - the instruction and its fields
+    /// returns: the instruction
+    ///
+    pub fn from_decoded(decoded: &DecodedInstruction) -> Instruction {
+        let instruction = *decoded;
+        match decoded.spec.format {
+            Format::Register | Format::Shift => Instruction::RType(RTypeInstruction { instruction }),
+            Format::Immediate | Format::UnsignedImmediate | Format::Branch => {
+                Instruction::IType(ITypeInstruction { instruction })
+            }
+            Format::Memory => Instruction::MemoryAccessType(MemoryAccessTypeInstruction { instruction }),
+            Format::Jump => Instruction::JType(JTypeInstruction { instruction }),
+            Format::JumpRegister => Instruction::JRType(JRTypeInstruction { instruction }),
+            Format::Fixed(_) if decoded.spec.mnemonic == "nop" => Instruction::NopType(NopTypeInstruction {}),
+            Format::Fixed(_) => Instruction::TerminateInstructionType(TerminateInstruction {}),
         }
     }
 
-    fn get_rd_val(&self) -> Option<i32> {
-        match self {
-            Self::RType(instr) => instr.get_rd_val(),
-            Self::IType(instr) => instr.get_rd_val(),
-            Self::JType(instr) => instr.get_rd_val(),
-            Self::NopType(instr) => instr.get_rd_val(),
-            Self::JRType(instr) => instr.get_rd_val(),
-            Self::MemoryAccessType(instr) => instr.get_rd_val(),
-            Self::TerminateInstructionType(instr) => instr.get_rd_val(),
+    /// set_field: replaces the immediate, the offset or the jump target of the instruction.
+    /// Used by the linker to patch in addresses, which must already be checked to fit.
+    ///
+    /// input: value - the new value of the field, a byte address for jumps and a byte
+    ///                offset for branches
+    /// returns: false if the instruction has no such field
+    ///
+    pub fn set_field(&mut self, value: i64) -> bool {
+        let instruction = match self {
+            Self::RType(_) | Self::NopType(_) | Self::JRType(_) | Self::TerminateInstructionType(_) => {
+                return false
+            }
+            Self::IType(instr) => &mut instr.instruction,
+            Self::MemoryAccessType(instr) => &mut instr.instruction,
+            Self::JType(instr) => &mut instr.instruction,
+        };
+        match instruction.spec.format {
+            Format::Branch => instruction.fields.imm = (value >> 2) as u16,
+            Format::Jump => instruction.fields.target = (value as u32 >> 2) & 0x3ff_ffff,
+            _ => instruction.fields.imm = value as u16,
         }
+        true
     }
-
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1242,45 +368,82 @@ pub enum InstructionType {
     TerminateInstructionType,
 }
 
-static VALID_INSTRUCTION: [&str; 18] = [
-    "add", "sub", "and", "or", "nor", "slt", "sll", "jr", "nop", "lw", "sw", "beq", "addi", "j",
-    "ori", "srl", "sra","exit",
-];
-static ZERO_OP: [&str; 11] = [
-    "add", "sub", "and", "or", "nor", "slt", "sll", "jr", "nop", "sra", "srl",
-];
-
 /// parse_instruction: Parses the instruction represented on the line given as input.
 ///
 /// input: line - string value of an instruction to be parsed
 ///        symbol_table - table with labels and corresponding memory locations.
 ///        current_addr - current address of program counter.
-/// returns: instruction that was represented from the given line packeted in one of the above structures or none
+/// returns: instruction that was represented from the given line packeted in one of the above structures or none,
+///          or an error pointing at the part of the line that could not be parsed
 ///
 pub fn parse_instruction(
     line: String,
     symbol_table: &HashMap<String, u32>,
     current_addr: u32,
-) -> Result<Option<Instruction>, String> {
-    if let Some(line) = remove_comment_and_label(line.as_str()) {
-        if has_instruction(&line) {
-            if contains_supported_instruction(&line) {
-                let res =
-                    parse_instruction_without_comment_label(&line, symbol_table, current_addr);
-                if let Ok(instr) = res {
-                    return Ok(Some(instr));
-                }
-                if let Err(err) = res {
-                    return Err(err);
-                }
-            }
-            return Err(
-                format!("Could not parse instruction from given line {}", line).to_string(),
-            );
+) -> Result<Option<Instruction>, LineError> {
+    match parse_line(&line, 1)?.statement {
+        Some(statement) if statement.kind == StatementKind::Instruction => {
+            let symbols = Symbols::from_labels(symbol_table.clone());
+            build_instruction(&statement, &symbols, current_addr).map(Some)
         }
+        Some(statement) => Err(LineError::new(format!(
+            "expected an instruction, found directive `{}`",
+            statement.name
+        ))
+        .at(statement.name_span)),
+        None => Ok(None),
     }
+}
+
+/// build_instruction: creates the instruction a parsed statement stands for.
+///
+/// input: statement - an instruction statement from the parser
+///        symbols - the labels and constants of the source.
+///        current_addr - address of the instruction.
+/// returns: the instruction, or an error pointing at the part of the statement that is wrong
+///
+pub fn build_instruction(
+    statement: &Statement,
+    symbols: &Symbols,
+    current_addr: u32,
+) -> Result<Instruction, LineError> {
+    let instruction = statement.name.as_str();
+    let instruction_type =
+        get_instruction_type(instruction).map_err(|_| unknown_instruction_error(statement))?;
+
+    let expected_num_args = get_expected_num_arguments(&instruction_type);
+    if statement.operands.len() != expected_num_args {
+        return Err(LineError::new(format!(
+            "invalid operands for `{}`: expected {} operand{}, found {}",
+            instruction,
+            expected_num_args,
+            if expected_num_args == 1 { "" } else { "s" },
+            statement.operands.len()
+        ))
+        .at(statement.operands_span())
+        .with_hint(Some(format!(
+            "expected `{}`",
+            instruction_syntax(instruction)
+        ))));
+    }
+
+    create_instruction(
+        &statement.operands,
+        &instruction_type,
+        instruction,
+        symbols,
+        current_addr,
+    )
+}
 
-    Ok(None)
+/// unknown_instruction_error: creates the error for a mnemonic that is not supported,
+/// suggesting the closest supported one if the mnemonic looks like a typo.
+fn unknown_instruction_error(statement: &Statement) -> LineError {
+    let mnemonic = statement.name.as_str();
+    let suggestion = closest_match(mnemonic, spec::INSTRUCTIONS.iter().map(|spec| spec.mnemonic));
+    LineError::new(format!("unknown instruction `{}`", mnemonic))
+        .at(statement.name_span)
+        .with_hint(suggestion.map(|instr| format!("did you mean `{}`?", instr)))
 }
 
 /// get_instruction_type
@@ -1289,351 +452,291 @@ pub fn parse_instruction(
 /// returns: JRType, NopType,JType,IType, MemoryAccessType or none
 ///
 fn get_instruction_type(instruction: &str) -> Result<InstructionType, String> {
-    match instruction {
-        "nop" => Ok(InstructionType::NopType),
-        "exit" => Ok(InstructionType::TerminateInstructionType),
-        "srl" | "sra" | "add" | "sub" | "and" | "or" | "nor" | "slt" | "sll" => {
-            Ok(InstructionType::RType)
-        }
-        "ori" | "addi" | "beq" => Ok(InstructionType::IType),
-        "j" => Ok(InstructionType::JType),
-        "jr" => Ok(InstructionType::JRType),
-        "lw" | "sw" => Ok(InstructionType::MemoryAccessType),
-        _ => Err(format!("{} is not a valid instruction", instruction).to_string()),
-    }
+    let spec = spec::lookup(instruction).ok_or_else(|| format!("{} is not a valid instruction", instruction))?;
+    Ok(match spec.format {
+        Format::Register | Format::Shift => InstructionType::RType,
+        Format::Immediate | Format::UnsignedImmediate | Format::Branch => InstructionType::IType,
+        Format::Memory => InstructionType::MemoryAccessType,
+        Format::Jump => InstructionType::JType,
+        Format::JumpRegister => InstructionType::JRType,
+        Format::Fixed(0) => InstructionType::NopType,
+        Format::Fixed(_) => InstructionType::TerminateInstructionType,
+    })
 }
 
-/// get_arguments_instruction_type_instruction:
+/// register_operand: looks up the register an operand refers to.
 ///
-/// input:
-/// returns:
+/// input: the operand as parsed from the source
+/// returns: the name of the register, or an error suggesting a similarly named register
 ///
-fn get_arguments_instruction_type_instruction(
-    line: &str,
-) -> Result<(Vec<String>, InstructionType, &str), String> {
-    let line_trimmed = line.trim();
-
-    let maybe_instruction_delim = line_trimmed.find(" ");
-
-    let instruction: &str;
-    let argument_string: &str;
+fn register_operand(operand: &Operand) -> Result<String, LineError> {
+    let register = match &operand.kind {
+        OperandKind::Register(register) => register,
+        _ => {
+            return Err(
+                LineError::new(format!("expected a register, found `{}`", operand)).at(operand.span),
+            )
+        }
+    };
 
-    if maybe_instruction_delim.is_none() {
-        instruction = &line_trimmed[..];
-        argument_string = "";
-    } else {
-        let instruction_delim = maybe_instruction_delim.unwrap();
-        instruction = &line_trimmed[..instruction_delim];
-        argument_string = &line_trimmed[instruction_delim..];
+    if let Some(reg_num) = get_register_number(register) {
+        return Ok(get_register_name(reg_num).unwrap());
     }
 
-    let instruction_type = get_instruction_type(instruction)?;
+    let register_names: Vec<String> = (0..32)
+        .filter_map(|num| get_register_name(u5::new(num)))
+        .collect();
+    let suggestion = closest_match(register, register_names.iter().map(|name| name.as_str()));
+    Err(LineError::new(format!("unknown register `{}`", register))
+        .at(operand.span)
+        .with_hint(suggestion.map(|name| format!("did you mean `{}`?", name))))
+}
 
-    let maybe_arguments = parse_arguments(argument_string, &instruction_type);
-    if maybe_arguments.is_err() && instruction_type != InstructionType::NopType {
-        return Err(format!(
-            "Failed to parse arguments from string {} for an instruction of type {:?}",
-            argument_string, instruction_type
-        ));
+/// expression_operand: the value of an operand that is an integer, a label or a constant,
+/// or an expression made of them.
+///
+/// input: operand - the operand as parsed from the source
+///        symbols - the labels and constants of the source
+/// returns: the value of the operand and the expression it was computed from
+///
+fn expression_operand<'a>(
+    operand: &'a Operand,
+    symbols: &Symbols,
+) -> Result<(i64, &'a Expr), LineError> {
+    match &operand.kind {
+        OperandKind::Expression(expr) => Ok((symbols.evaluate(expr)?, expr)),
+        _ => Err(LineError::new(format!(
+            "expected an integer or a label, found `{}`",
+            operand
+        ))
+        .at(operand.span)),
     }
+}
 
-    let arguments = maybe_arguments.unwrap();
+/// relocation_operand: the operand of an instruction that may hold an address,
+/// and the field of the instruction it is encoded in.
+///
+/// input: an instruction statement
+/// returns: the kind of field and the expression of the operand, or none if the
+///          instruction has no such operand
+///
+pub fn relocation_operand(statement: &Statement) -> Option<(RelocationKind, &Expr)> {
+    let operands = &statement.operands;
+    match statement.name.as_str() {
+        "j" => Some((RelocationKind::Jump, expression(operands.first()?)?)),
+        "beq" => Some((RelocationKind::Branch, expression(operands.get(2)?)?)),
+        "addi" => Some((RelocationKind::Immediate, expression(operands.get(2)?)?)),
+        "ori" => Some((RelocationKind::UnsignedImmediate, expression(operands.get(2)?)?)),
+        "lw" | "sw" => match &operands.get(1)?.kind {
+            OperandKind::Memory {
+                offset: Some(offset),
+                ..
+            } => Some((RelocationKind::Immediate, expression(offset)?)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
-    Ok((arguments, instruction_type, instruction))
+fn expression(operand: &Operand) -> Option<&Expr> {
+    match &operand.kind {
+        OperandKind::Expression(expr) => Some(expr),
+        _ => None,
+    }
 }
 
 /// create_instruction:
 ///
-/// input:
+/// input: operands - the operands of the instruction
+///        instruction_type - the type of the instruction
+///        instruction - the mnemonic of the instruction
+///        symbols - the labels and constants of the source
+///        current_addr - address of the instruction
 /// returns: Instruction of any of the types: JRType, NopType, JType, IType, MemoryAccessType
 ///
 fn create_instruction(
-    arguments: Vec<String>,
+    operands: &[Operand],
     instruction_type: &InstructionType,
     instruction: &str,
-    symbol_table: &HashMap<String, u32>,
+    symbols: &Symbols,
     current_addr: u32,
-) -> Result<Instruction, String> {
+) -> Result<Instruction, LineError> {
     match instruction_type {
         InstructionType::NopType => Ok(Instruction::NopType(NopTypeInstruction {})),
         InstructionType::TerminateInstructionType=> Ok(Instruction::TerminateInstructionType(TerminateInstruction  {})),
         InstructionType::RType => {
-            let maybe_instr = RTypeInstruction::new(
-                instruction,
-                &arguments[1].as_str(),
-                &arguments[2].as_str(),
-                &arguments[0].as_str(),
-            );
+            let is_shift = matches!(instruction, "sll" | "srl" | "sra");
+            let rd = register_operand(&operands[0])?;
+            let rs_or_rt = register_operand(&operands[1])?;
+            let rt_or_shamt = if is_shift {
+                let (shamt, expr) = expression_operand(&operands[2], symbols)?;
+                check_range(shamt, expr, 0, 31, "shift amount")?;
+                shamt.to_string()
+            } else {
+                register_operand(&operands[2])?
+            };
+
+            let maybe_instr = RTypeInstruction::new(instruction, &rs_or_rt, &rt_or_shamt, &rd);
             if let Some(instr) = maybe_instr {
                 return Ok(Instruction::RType(instr));
             }
-            Err(format!(
-                "Could not create R-Type instruction from given string!
-                Instruction: {}
-                Arguments: {:#?}
-                Current address: {}
-                ",instruction, arguments, current_addr
-            ))
+            Err(LineError::new(format!(
+                "could not create R-Type instruction `{}` at address {}",
+                instruction, current_addr
+            )))
         }
         InstructionType::IType => {
-            let imm = &arguments[2];
-            let maybe_instr = ITypeInstruction::new(
-                instruction,
-                &arguments[1].as_str(),
-                &arguments[0].as_str(),
-                &arguments[2].as_str(),
-                current_addr,
-            );
-            if maybe_instr.is_none() {
-                // imm could have been a label, not an actual value
-                let maybe_addr = symbol_table.get(imm);
-                if maybe_addr.is_none() {
-                    return Err(format!(
-                        "Could not find an address for label {} in the given symbol table.",
-                        imm
-                    ));
+            let rt = register_operand(&operands[0])?;
+            let rs = register_operand(&operands[1])?;
+            let (imm_val, imm) = expression_operand(&operands[2], symbols)?;
+            let imm_str = match instruction {
+                "beq" => {
+                    check_branch_target(imm_val, imm, current_addr)?;
+                    imm_val.to_string()
                 }
-
-                let maybe_instr = ITypeInstruction::new(
-                    instruction,
-                    &arguments[1].as_str(),
-                    &arguments[0].as_str(),
-                    maybe_addr.unwrap().to_string().as_str(),
-                    current_addr,
-                );
-
-                if let Some(instr) = maybe_instr {
-                    return Ok(Instruction::IType(instr));
-                } else {
-                    return Err(format!(
-                        "Could not create I-Type instruction from given string!
-                        Instruction: {}
-                        Arguments: {:#?}
-                        Current address: {}
-                        ",instruction, arguments, current_addr
-                    ))
+                // ori zero-extends its immediate
+                "ori" => {
+                    check_range(imm_val, imm, 0, u16::MAX as i64, "immediate")?;
+                    (imm_val as u16 as i16).to_string()
                 }
-            } else {
-                return Ok(Instruction::IType(maybe_instr.unwrap()));
+                _ => {
+                    check_range(imm_val, imm, i16::MIN as i64, i16::MAX as i64, "immediate")?;
+                    imm_val.to_string()
+                }
+            };
+
+            let maybe_instr =
+                ITypeInstruction::new(instruction, &rs, &rt, &imm_str, current_addr);
+            if let Some(instr) = maybe_instr {
+                return Ok(Instruction::IType(instr));
             }
+            Err(LineError::new(format!(
+                "could not create I-Type instruction `{}` at address {}",
+                instruction, current_addr
+            ))
+            .at(imm.span))
         }
         InstructionType::JType => {
-            let target_label = &arguments[0];
-            if let Some(target) = symbol_table.get(target_label) {
-                let maybe_instr = JTypeInstruction::new(instruction, &target.to_string().as_str());
-                if let Some(instr) = maybe_instr {
-                    return Ok(Instruction::JType(instr));
-                } else {
-                    return Err(format!(
-                        "Could not create J-Type instruction with op: {} and target: {}",
-                        instruction, target
-                    ));
-                }
-            } else {
-                return Err(format!(
-                    "Could not find address for label {} in symbol table",
-                    target_label
-                ));
+            let (target, expr) = expression_operand(&operands[0], symbols)?;
+            // the target is encoded in words, in 26 bits
+            check_range(target, expr, 0, (1 << 28) - 4, "jump target")?;
+            check_word_aligned(target, expr, "jump target")?;
+            let maybe_instr = JTypeInstruction::new(instruction, &target.to_string());
+            if let Some(instr) = maybe_instr {
+                return Ok(Instruction::JType(instr));
             }
+            Err(LineError::new(format!(
+                "could not create J-Type instruction with op: {} and target: {}",
+                instruction, target
+            ))
+            .at(operands[0].span))
         }
         InstructionType::JRType => {
-            let rs = &arguments[0];
-            if let Some(instruction) = JRTypeInstruction::new(instruction, rs) {
+            let rs = register_operand(&operands[0])?;
+            if let Some(instruction) = JRTypeInstruction::new(instruction, &rs) {
                 return Ok(Instruction::JRType(instruction));
             }
-            return Err(format!("Could not create JR-Type instruction from line").to_string());
+            Err(LineError::new("could not create JR-Type instruction from line".to_string()))
         }
         InstructionType::MemoryAccessType => {
             // lw rt, offset(base)
-            let rt = &arguments[0];
-            let offset = &arguments[1];
-            let base = &arguments[2];
-
-            let maybe_instr = MemoryAccessTypeInstruction::new(instruction, rt, offset, base);
-            if maybe_instr.is_none() {
-                return Err(format!(
-                    "Could not create I-Type instruction from given string!
-                    Instruction: {}
-                    Arguments: {:#?}
-                    Current address: {}
-                    ",instruction, arguments, current_addr
-                ))
+            let rt = register_operand(&operands[0])?;
+            let (offset, base) = match &operands[1].kind {
+                OperandKind::Memory { offset, base } => (offset, base),
+                _ => {
+                    return Err(LineError::new(format!(
+                        "expected a memory operand, found `{}`",
+                        operands[1]
+                    ))
+                    .at(operands[1].span)
+                    .with_hint(Some(format!("expected `{}`", instruction_syntax(instruction)))))
+                }
+            };
+            let base_reg = register_operand(base)?;
+            // `lw $rt, ($base)` has an offset of 0
+            let offset_val = match offset {
+                Some(offset) => {
+                    let (offset_val, offset_expr) = expression_operand(offset, symbols)?;
+                    check_range(offset_val, offset_expr, i16::MIN as i64, i16::MAX as i64, "offset")?;
+                    offset_val
+                }
+                None => 0,
+            };
+
+            let maybe_instr = MemoryAccessTypeInstruction::new(
+                instruction,
+                &rt,
+                &offset_val.to_string(),
+                &base_reg,
+            );
+            if let Some(instr) = maybe_instr {
+                return Ok(Instruction::MemoryAccessType(instr));
             }
 
-            return Ok(Instruction::MemoryAccessType(maybe_instr.unwrap()));
+            Err(LineError::new(format!(
+                "invalid offset `{}`, expected a 16-bit integer",
+                offset_val
+            ))
+            .at(operands[1].span))
         }
-        _ => Err("Unable to parse instruction parts into valid instruction.".to_string()),
-    }
-}
-
-/// parse_instruction_without_comment_label:
-///
-/// input:
-/// returns:
-///
-fn parse_instruction_without_comment_label(
-    line: &str,
-    symbol_table: &HashMap<String, u32>,
-    current_addr: u32,
-) -> Result<Instruction, String> {
-    if let Ok((arguments, instruction_type, instruction)) =
-        get_arguments_instruction_type_instruction(line)
-    {
-        return create_instruction(
-            arguments,
-            &instruction_type,
-            instruction,
-            symbol_table,
-            current_addr,
-        );
     }
-
-    Err(format!(
-        "Failed to parse arguments or instruction from line {}",
-        line
-    ))
 }
 
-/// get_expected_num_arguments: accesser for the number of arguments needed for a given instruction type.
+/// check_branch_target: checks that a branch can reach its target.
+/// The offset from the instruction after the branch is encoded in words, in 16 bits.
 ///
-/// input:
-/// returns:
+/// input: target - the address branched to
+///        expr - the expression the target was computed from, used in errors
+///        current_addr - address of the branch
+/// returns: an error pointing at the target if it cannot be encoded
 ///
-fn get_expected_num_arguments(instruction_type: &InstructionType) -> usize {
-    match instruction_type {
-        InstructionType::RType | InstructionType::IType | InstructionType::MemoryAccessType => 3,
-        InstructionType::JType | InstructionType::JRType => 1,
-        InstructionType::NopType | InstructionType::TerminateInstructionType => 0,
-    }
+fn check_branch_target(target: i64, expr: &Expr, current_addr: u32) -> Result<(), LineError> {
+    check_word_aligned(target, expr, "branch target")?;
+    let offset = target - (current_addr as i64 + 4);
+    if (i16::MIN as i64..=i16::MAX as i64).contains(&(offset / 4)) {
+        return Ok(());
+    }
+    Err(LineError::new(format!("branch target `{}` is too far away", expr))
+        .at(expr.span)
+        .with_hint(Some(format!(
+            "the target is {} bytes from the next instruction, but a branch reaches at most {} bytes back and {} bytes forward",
+            offset,
+            -(i16::MIN as i64) * 4,
+            i16::MAX as i64 * 4
+        ))))
 }
 
-/// parse_memory_access_arguments: Parses the arguments for the instruction type MemoryAccessType
-///
-/// input:
-/// returns:
-///
-fn parse_memory_access_arguments(argument_string: &str) -> Result<Vec<String>, String> {
-    let mut arg_vec = Vec::new();
-
-    argument_string
-        .split(',')
-        .for_each(|arg| arg_vec.push(arg.trim().to_string()));
-
-    if arg_vec.len() < 2 {
-        return Err(
-            "Argument string did not contain comma, expects comma separating arguments".to_string(),
-        );
-    }
-
-    if !arg_vec[1].contains('(') {
-        return Err("Memory access instruction does not contain opening parentheses".to_string());
-    }
-
-    let offset_base: Vec<&str> = arg_vec[1].split('(').collect();
-    let untrimmed_offset = offset_base[0];
-    let base = offset_base[1];
-
-    let end_idx = base.find(')');
-    if end_idx.is_none() {
-        return Err("Could not find closing parenthesis in JR instruction".to_string());
-    }
-
-    let end_idx = end_idx.unwrap();
-
-    let (untrimmed_base, _) = base.split_at(end_idx);
-    let expected_num_args = get_expected_num_arguments(&InstructionType::MemoryAccessType);
-
-    let return_vec = vec![
-        arg_vec[0].to_string(),
-        untrimmed_offset.trim().to_string(),
-        untrimmed_base.trim().to_string(),
-    ];
-
-    if get_expected_num_arguments(&InstructionType::MemoryAccessType) != return_vec.len() {
-        return Err(format!(
-            "Expected {} arguments, got {}",
-            expected_num_args,
-            arg_vec.len()
-        )
-        .to_string());
+/// check_word_aligned: checks that an address points at an instruction.
+fn check_word_aligned(addr: i64, expr: &Expr, what: &str) -> Result<(), LineError> {
+    if addr % 4 == 0 {
+        return Ok(());
     }
-
-    Ok(return_vec)
-}
-
-/// parse_jr_arguments: Parses the arguments for the instruction type JR
-///
-/// input:
-/// returns:
-///
-fn parse_jr_arguments(argument_string: &str) -> Result<Vec<String>, String> {
-    let mut arg_vec = Vec::new();
-    argument_string
-        .split(',')
-        .for_each(|arg| arg_vec.push(arg.trim().to_string()));
-    let num_args = arg_vec.len();
-    let expected_num_args = get_expected_num_arguments(&InstructionType::JRType);
-    if num_args == expected_num_args {
-        return Ok(vec![arg_vec[0].trim().to_string()]);
-    }
-
-    println!(
-        "Expected_num_args: {}, num_args: {}",
-        expected_num_args, num_args
-    );
-
-    Err(format!(
-        "Expected {} argument(s), got {}.",
-        expected_num_args, num_args
-    ))
+    Err(LineError::new(format!("{} `{}` is not word aligned", what, expr))
+        .at(expr.span)
+        .with_hint(Some(format!("`{}` evaluates to {}, which is not a multiple of 4", expr, addr))))
 }
 
-/// parse_arguments:
+/// instruction_syntax: the operand syntax of a supported instruction, used in hints.
 ///
-/// input: argument_string - string with no comment or label
-///        instruction_type - JType, JRType, IType, MemoryAccessType,
-/// returns: a string-vector with the arguments depending on type of instruction.
+/// input: mnemonic of the instruction
+/// returns: a string showing how the instruction is written
 ///
-fn parse_arguments(
-    argument_string: &str,
-    instruction_type: &InstructionType,
-) -> Result<Vec<String>, String> {
-    return match instruction_type {
-        InstructionType::MemoryAccessType => parse_memory_access_arguments(argument_string),
-        InstructionType::JRType => parse_jr_arguments(argument_string),
-        _ => {
-            let mut arg_vec = Vec::new();
-            argument_string.split(',').for_each(|arg| {
-                if !arg.trim().to_string().is_empty() {
-                    arg_vec.push(arg.trim().to_string())
-                }
-            });
-
-            let expected_num_args = get_expected_num_arguments(instruction_type);
-
-            if arg_vec.len() != expected_num_args {
-                return Err(format!(
-                    "Expected {} arguments, got {}, instruction type: {:?}, argument string: {}",
-                    expected_num_args,
-                    arg_vec.len(),
-                    instruction_type,
-                    argument_string
-                ));
-            }
-
-            Ok(arg_vec)
-        }
-    };
+fn instruction_syntax(instruction: &str) -> &'static str {
+    spec::lookup(instruction).map_or("a supported instruction", |spec| spec.syntax)
 }
 
-/// contains_supported_instruction: checks if the instruction is supported by the assembler.
+/// get_expected_num_arguments: accesser for the number of operands needed for a given instruction type.
 ///
-/// input: full instruction as a string reference
-/// returns: true if supported instruction or false
+/// input: the type of the instruction
+/// returns: the number of operands, where `offset($base)` counts as one
 ///
-fn contains_supported_instruction(instruction: &str) -> bool {
-    let split_str: Vec<&str> = instruction.trim().split(" ").collect();
-    VALID_INSTRUCTION.contains(&split_str[0])
+fn get_expected_num_arguments(instruction_type: &InstructionType) -> usize {
+    match instruction_type {
+        InstructionType::RType | InstructionType::IType => 3,
+        InstructionType::MemoryAccessType => 2,
+        InstructionType::JType | InstructionType::JRType => 1,
+        InstructionType::NopType | InstructionType::TerminateInstructionType => 0,
+    }
 }
 
 /// get_func: getter for the func-value of an instruction string if such one exist for the instruction
@@ -1642,26 +745,9 @@ fn contains_supported_instruction(instruction: &str) -> bool {
 /// returns: func associated with instruction or none
 ///
 pub fn get_func(instruction: &str) -> Option<u6> {
-    let maybe_val = match instruction {
-        "add" => Some(32),
-        "sub" => Some(34),
-        "and" => Some(36),
-        "or" => Some(37),
-        "nor" => Some(39),
-        "slt" => Some(42),
-        "sll" => Some(0),
-        "jr" => Some(8),
-        "nop" => Some(0),
-        "sra" => Some(3),
-        "srl" => Some(2),
-        _ => None,
-    };
-
-    if let Some(val) = maybe_val {
-        return Some(u6::new(val));
-    }
-
-    None
+    spec::lookup(instruction)
+        .filter(|spec| spec.opcode == 0)
+        .map(|spec| u6::new(spec.funct))
 }
 
 /// get_numeric_op:
@@ -1670,25 +756,7 @@ pub fn get_func(instruction: &str) -> Option<u6> {
 /// returns: the op code from the instruction, none if instruction is not supported
 ///
 pub fn get_numeric_op(instruction: &str) -> Option<u6> {
-    if ZERO_OP.contains(&instruction) {
-        return Some(u6::new(0));
-    }
-
-    let maybe_val = match instruction {
-        "lw" => Some(35),
-        "sw" => Some(43),
-        "beq" => Some(4),
-        "addi" => Some(8),
-        "ori" => Some(13),
-        "j" => Some(2),
-        _ => None,
-    };
-
-    if let Some(val) = maybe_val {
-        return Some(u6::new(val));
-    }
-
-    None
+    spec::lookup(instruction).map(|spec| u6::new(spec.opcode))
 }
 
 /// get_register_name: Maps a register number tot a register name
@@ -1740,113 +808,31 @@ pub fn get_register_name(register_number: u5) -> Option<String> {
 /// input: op: u6 (op code), func: u6 func code
 /// returns: the name of the operation supported, or none if not supported in the assembler
 ///
-fn get_operation(op: u6, func: u6) -> Option<String>{
-    if op == u6::new(0) {
-        
-        return match u8::from(func) {
-            32 => Some("add".to_string()),
-            34 => Some("sub".to_string()),
-            36 => Some("and".to_string()),
-            37 => Some("or".to_string()),
-            39 => Some("nor".to_string()),
-            42 => Some("slt".to_string()),
-            0 => Some("sll".to_string()),
-            8 => Some("jr".to_string()),
-            3 => Some("sra".to_string()),
-            2 => Some("srl".to_string()),
-            _=> None,
-        }
-    }
-
-
-    match u8::from(op) {
-        35 => Some("lw".to_string()),
-        43 => Some("sw".to_string()),
-        4 => Some("beq".to_string()),
-        8 => Some("addi".to_string()),
-        2 => Some("j".to_string()),
-        13 => Some("ori".to_string()),
-        _=> None, 
-    }
-
-    
-
-    
+pub fn get_operation(op: u6, func: u6) -> Option<String> {
+    spec::find(op.into(), func.into()).map(|spec| spec.mnemonic.to_string())
 }
 
 /// get_register_number:
 ///
-/// input: string that starts with $
+/// input: string that starts with $, either the name of a register or its number
 /// returns: the number representing the given register in MIPS, None if an invalid register name is given
 ///
 pub fn get_register_number(register: &str) -> Option<u5> {
     let ascii_lowercase = register.to_ascii_lowercase();
-    let chars: Vec<char> = ascii_lowercase.chars().collect();
-
-    if chars.len() < 3 {
-        return None;
-    }
-
-    let reg_num: i32 = match register {
-        "$zero" => 0,
-        "$at" => 1,
-        "$a0" => 4,
-        "$a1" => 5,
-        "$a2" => 6,
-        "$a3" => 7,
-        "$gp" => 28,
-        "$sp" => 29,
-        "$fp" => 30,
-        "$ra" => 31,
-
-        _ => {
-            let spec_reg_nr = chars[2];
-            match chars[1] {
-                's' => to_decimal_digit(spec_reg_nr)? as i32 + 16,
-                't' => {
-                    if (to_decimal_digit(spec_reg_nr)? as i32) < 8 {
-                        to_decimal_digit(spec_reg_nr)? as i32 + 8
-                    } else {
-                        to_decimal_digit(spec_reg_nr)? as i32 + 16
-                    }
-                }
-                'k' => to_decimal_digit(spec_reg_nr)? as i32 + 26,
-                'v' => to_decimal_digit(spec_reg_nr)? as i32 + 2,
-                _ => -1,
-            }
-        }
-    };
-
-    if reg_num < 0 {
-        return None;
-    }
+    let name = ascii_lowercase.strip_prefix('$')?;
 
-    Some(u5::new(reg_num as u8))
-}
-
-/// to_decimal_digit: Parses the character at the given index into a decimal digit
-/// input: character to convert
-/// returns: converted character to 32-bit integer
-///
-fn to_decimal_digit(to_convert: char) -> Option<u32> {
-    // was -> i32
-    to_convert.to_digit(10)
-}
-
-///
-/// remove_comment_and_label:
-///
-/// input: line - reference to a string
-/// output: An option, either none if the line is empty or a new string value without the comment and label
-///
-fn remove_comment_and_label(line: &str) -> Option<String> {
-    if let Some(line) = remove_comment_from_line(&line) {
-        if let Some(line) = remove_label_from_line(&line) {
-            return Some(line.to_string());
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()) {
+        // $0 to $31, without leading zeros
+        let reg_num = name.parse::<u8>().ok()?;
+        if reg_num > 31 || name.len() != reg_num.to_string().len() {
+            return None;
         }
+        return Some(u5::new(reg_num));
     }
 
-    None
+    (0..32)
+        .map(u5::new)
+        .find(|reg_num| get_register_name(*reg_num).as_deref() == Some(ascii_lowercase.as_str()))
 }
 
 #[cfg(test)]
@@ -1854,6 +840,7 @@ mod tests {
     use core::panic;
 
     use super::*;
+    use crate::assembler::lexer::Span;
 
     #[test]
     fn test_ascii() {
@@ -2033,67 +1020,10 @@ mod tests {
         assert_eq!(maybe_regnr.unwrap(), u5::new(31));
     }
 
-    #[test]
-    fn test_contains_supported_instruction() {
-        assert!(contains_supported_instruction(
-            "add $t1, $2, $t3    # A comment"
-        ));
-        assert!(contains_supported_instruction("sub"));
-        assert!(contains_supported_instruction("and"));
-        assert!(contains_supported_instruction("or"));
-        assert!(contains_supported_instruction("nor"));
-        assert!(contains_supported_instruction("slt"));
-        assert!(contains_supported_instruction("lw"));
-        assert!(contains_supported_instruction("sw"));
-        assert!(contains_supported_instruction("beq"));
-        assert!(contains_supported_instruction(
-            "addi  $t1, $zero, 1   # A comment"
-        ));
-        assert!(contains_supported_instruction("sll"));
-        assert!(contains_supported_instruction("j"));
-        assert!(contains_supported_instruction("jr"));
-        assert!(contains_supported_instruction("nop"));
-    }
 
-    #[test]
-    fn test_parse_rtype_arguments_successful() {
-        let arg_str = "$t0, $t1, $t2";
-        let instr_type = InstructionType::RType;
-        let parse_result = parse_arguments(arg_str, &instr_type);
-        assert!(parse_result.is_ok());
-        let parsed_args = parse_result.unwrap();
-        assert_eq!(parsed_args[0], "$t0");
-        assert_eq!(parsed_args[1], "$t1");
-        assert_eq!(parsed_args[2], "$t2");
-    }
 
-    #[test]
-    fn test_parse_rtype_arguments_unsuccessful() {
-        let arg_str = "$t0, $t1";
-        let instr_type = InstructionType::RType;
-        let parse_result = parse_arguments(arg_str, &instr_type);
-        assert!(parse_result.is_err());
-    }
 
-    #[test]
-    fn test_parse_itype_arguments_successful() {
-        let arg_str = "$t0, $t1, 5";
-        let instr_type = InstructionType::IType;
-        let parse_result = parse_arguments(arg_str, &instr_type);
-        assert!(parse_result.is_ok());
-        let parsed_args = parse_result.unwrap();
-        assert_eq!("$t0", parsed_args[0]);
-        assert_eq!("$t1", parsed_args[1]);
-        assert_eq!("5", parsed_args[2]);
-    }
 
-    #[test]
-    fn test_parse_itype_arguments_unsuccessful() {
-        let arg_str = "$t0, $t1";
-        let instr_type = InstructionType::IType;
-        let parse_result = parse_arguments(arg_str, &instr_type);
-        assert!(parse_result.is_err());
-    }
 
     // Probably want to add one test for each supported instruction
     #[test]
@@ -2105,11 +1035,11 @@ mod tests {
         let maybe_instr = RTypeInstruction::new(instr, rs, rt, rd); // add $t2, $t0, $t1
         assert!(maybe_instr.is_some());
         let add_instr = maybe_instr.unwrap();
-        assert_eq!(add_instr.op, get_numeric_op(instr).unwrap());
-        assert_eq!(add_instr.rs, get_register_number(rs).unwrap());
-        assert_eq!(add_instr.rt, get_register_number(rt).unwrap());
-        assert_eq!(add_instr.rd, get_register_number(rd).unwrap());
-        assert_eq!(add_instr.shamt, u5::new(0));
+        assert_eq!(add_instr.get_op(), get_numeric_op(instr).unwrap());
+        assert_eq!(add_instr.get_rs(), get_register_number(rs).unwrap());
+        assert_eq!(add_instr.get_rt(), get_register_number(rt).unwrap());
+        assert_eq!(add_instr.get_rd(), get_register_number(rd).unwrap());
+        assert_eq!(add_instr.get_shamt(), u5::new(0));
     }
 
     #[test]
@@ -2121,10 +1051,10 @@ mod tests {
         let addi_instr = ITypeInstruction::new(instr, rs, rt, imm, 0); // rs, rt, imm
         assert!(addi_instr.is_some());
         let addi_instr = addi_instr.unwrap();
-        assert_eq!(addi_instr.op, get_numeric_op(instr).unwrap());
-        assert_eq!(addi_instr.imm, 15);
-        assert_eq!(addi_instr.rs, get_register_number(rs).unwrap());
-        assert_eq!(addi_instr.rt, get_register_number(rt).unwrap());
+        assert_eq!(addi_instr.get_op(), get_numeric_op(instr).unwrap());
+        assert_eq!(addi_instr.get_imm(), 15);
+        assert_eq!(addi_instr.get_rs(), get_register_number(rs).unwrap());
+        assert_eq!(addi_instr.get_rt(), get_register_number(rt).unwrap());
     }
 
     #[test]
@@ -2132,9 +1062,9 @@ mod tests {
         let instr = "j";
         let target = "40";
         let jmp_instr = JTypeInstruction::new(instr, target).unwrap();
-        assert_eq!(get_numeric_op(instr).unwrap(), jmp_instr.op);
+        assert_eq!(get_numeric_op(instr).unwrap(), jmp_instr.get_op());
         // detta är ej snyggt I know, måste fråga tomas/oskar
-        assert_eq!(jmp_instr.addr, u26::new(40));
+        assert_eq!(jmp_instr.get_addr(), 40);
     }
     #[test]
     fn test_create_sll_instruction() {
@@ -2143,10 +1073,10 @@ mod tests {
         let instr = "sll";
         let shamt = "4";
         let sll_instr = RTypeInstruction::shift(instr, rd, rt, shamt).unwrap(); // add $t2, $t0, $t1
-        assert_eq!(sll_instr.op, get_numeric_op(instr).unwrap());
-        assert_eq!(sll_instr.rt, get_register_number(rt).unwrap());
-        assert_eq!(sll_instr.rd, get_register_number(rd).unwrap());
-        assert_eq!(sll_instr.shamt.to_string(), shamt);
+        assert_eq!(sll_instr.get_op(), get_numeric_op(instr).unwrap());
+        assert_eq!(sll_instr.get_rt(), get_register_number(rt).unwrap());
+        assert_eq!(sll_instr.get_rd(), get_register_number(rd).unwrap());
+        assert_eq!(sll_instr.get_shamt().to_string(), shamt);
     }
 
     #[test]
@@ -2157,7 +1087,7 @@ mod tests {
         let imm = "2"; //0010
         let instr = "ori";
         let ori_instr = ITypeInstruction::new(instr, rs, rt, imm, 0).unwrap();
-        assert_eq!(ori_instr.op, get_numeric_op(instr).unwrap());
+        assert_eq!(ori_instr.get_op(), get_numeric_op(instr).unwrap());
         assert_eq!(ori_instr.get_rs(), get_register_number("$t1").unwrap());
         assert_eq!(ori_instr.get_rt(), get_register_number("$t0").unwrap());
         assert_eq!(ori_instr.get_imm(), 2);
@@ -2193,34 +1123,9 @@ mod tests {
         assert_eq!(srl_instr.get_func(), get_func(instr).unwrap());
     }
 
-    #[test]
-    fn test_extract_instruction_successful() {
-        let instruction = "label1: addi  $t1, $zero, 1   # A comment";
-        let expected = " addi  $t1, $zero, 1   ";
-        let got = remove_comment_and_label(instruction);
-        assert_eq!(expected, got.unwrap());
-    }
 
-    #[test]
-    fn test_extract_instruction_only_label() {
-        let instruction = "label1:";
-        let got = remove_comment_and_label(instruction);
-        assert!(got.is_none());
-    }
 
-    #[test]
-    fn test_extract_instruction_label_and_comment() {
-        let instruction = "label1: # A comment";
-        let got = remove_comment_and_label(instruction);
-        assert!(got.is_none());
-    }
 
-    #[test]
-    fn test_extract_instruction_only_comment() {
-        let instruction = " #this is a comment";
-        let got = remove_comment_and_label(instruction);
-        assert!(got.is_none());
-    }
 
     #[test]
     fn test_parse_add_instruction_with_comment() {
@@ -2241,57 +1146,24 @@ mod tests {
         };
     }
 
-    #[test]
-    fn test_remove_comment_label_only_label() {
-        let line = "label:";
-        let maybe_label = remove_comment_and_label(line);
-        assert!(maybe_label.is_none());
-    }
 
-    #[test]
-    fn test_remove_comment_label_only_comment() {
-        let line = "# this is a comment";
-        let maybe_comment = remove_comment_and_label(line);
-        assert!(maybe_comment.is_none());
-    }
 
-    #[test]
-    fn test_remove_comment_label_whole_line() {
-        let whole_line = "label: add $t0, $t1, $t2 # this is a comment";
-        let maybe_instr = remove_comment_and_label(whole_line);
-        assert!(maybe_instr.is_some());
-        assert_eq!(maybe_instr.unwrap().as_str(), " add $t0, $t1, $t2 "); // Whitespace is preserved
-    }
 
-    #[test]
-    fn test_has_instruction_after_removing_label_comment() {
-        let line_after_removed_label_comment = " add $t0, $t1, $t2 "; // This is what string should look like after removing label and comment
-        assert!(has_instruction(line_after_removed_label_comment));
-    }
 
-    #[test]
-    fn test_contains_supported_instruction_after_removing_label_comment() {
-        let line_after_removed_label_comment = " add $t0, $t1, $t2 ";
-        assert!(contains_supported_instruction(
-            line_after_removed_label_comment
-        ));
-    }
 
     #[test]
     fn test_parse_without_comment_label() {
         let comment_label_removed = " add $t0, $t1, $t2 ";
-        let maybe_instr =
-            parse_instruction_without_comment_label(comment_label_removed, &HashMap::new(), 0);
-        assert!(maybe_instr.is_ok());
+        let maybe_instr = parse_instruction(comment_label_removed.to_string(), &HashMap::new(), 0);
+        assert!(maybe_instr.unwrap().is_some());
     }
 
     #[test]
     fn test_parse_beq_no_comment_no_label() {
         let only_beq_instr = "beq $t0, $t1, 8"; // format: beq rs, rt, imm
-        let maybe_instr =
-            parse_instruction_without_comment_label(only_beq_instr, &HashMap::new(), 0);
+        let maybe_instr = parse_instruction(only_beq_instr.to_string(), &HashMap::new(), 0);
         assert!(maybe_instr.is_ok());
-        let instr = maybe_instr.unwrap();
+        let instr = maybe_instr.unwrap().unwrap();
         match instr {
             Instruction::IType(instr) => {
                 assert_eq!(instr.get_op(), get_numeric_op("beq").unwrap());
@@ -2305,6 +1177,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_far_branch_and_jump() {
+        // the offset of a branch is 16 bits in words
+        let beq = parse_instruction("beq $t0, $t1, 131072".to_string(), &HashMap::new(), 0);
+        assert_eq!(beq.unwrap().unwrap().encode(), 0x11097fff);
+        let beq = parse_instruction("beq $t0, $t1, 4".to_string(), &HashMap::new(), 131072);
+        assert_eq!(beq.unwrap().unwrap().encode(), 0x11098000);
+        let err = parse_instruction("beq $t0, $t1, 131076".to_string(), &HashMap::new(), 0).unwrap_err();
+        assert_eq!(err.message, "branch target `131076` is too far away");
+        assert_eq!(
+            err.hint,
+            Some("the target is 131072 bytes from the next instruction, but a branch reaches at most 131072 bytes back and 131068 bytes forward".to_string())
+        );
+        // the target of a jump is 26 bits in words
+        let j = parse_instruction("j 0x0ffffffc".to_string(), &HashMap::new(), 0);
+        assert_eq!(j.unwrap().unwrap().encode(), 0x0bffffff);
+        assert!(parse_instruction("j 0x10000000".to_string(), &HashMap::new(), 0).is_err());
+    }
+
     #[test]
     fn test_parse_add_instruction_with_label() {
         let line = "label: add $t0, $t1, $t2";
@@ -2361,13 +1252,37 @@ mod tests {
         let maybe_instr = parse_instruction(invalid_instr.to_string(), &HashMap::new(), 0);
         assert!(maybe_instr.is_err());
         if let Err(e) = maybe_instr {
-            assert_eq!(
-                "Could not parse instruction from given line abd $t1, $t2, $t3",
-                e
-            );
+            assert_eq!("unknown instruction `abd`", e.message);
+            assert_eq!(Some(Span::new(1, 1, 3)), e.span);
+            assert_eq!(Some("did you mean `add`?".to_string()), e.hint);
         }
     }
 
+    #[test]
+    fn test_parse_instruction_unknown_register_points_at_register() {
+        let maybe_instr = parse_instruction("add $t1, $t2, $a9".to_string(), &HashMap::new(), 0);
+        let err = maybe_instr.unwrap_err();
+        assert_eq!("unknown register `$a9`", err.message);
+        assert_eq!(Some(Span::new(1, 15, 3)), err.span);
+    }
+
+    #[test]
+    fn test_parse_instruction_unknown_label_suggests_label() {
+        let mut symbol_table = HashMap::new();
+        symbol_table.insert("loop".to_string(), 8);
+        let maybe_instr = parse_instruction("j lop".to_string(), &symbol_table, 0);
+        let err = maybe_instr.unwrap_err();
+        assert_eq!("cannot find label `lop`", err.message);
+        assert_eq!(Some("did you mean `loop`?".to_string()), err.hint);
+    }
+
+    #[test]
+    fn test_parse_instruction_wrong_operand_count_hints_syntax() {
+        let maybe_instr = parse_instruction("sub $t1, $t2".to_string(), &HashMap::new(), 0);
+        let err = maybe_instr.unwrap_err();
+        assert_eq!(Some("expected `sub $rd, $rs, $rt`".to_string()), err.hint);
+    }
+
     #[test]
     fn test_parse_add_immediate() {
         let add_imm = "addi $t0, $t1, 5";
@@ -2375,10 +1290,10 @@ mod tests {
         assert!(maybe_instr.is_ok());
         match maybe_instr.unwrap().unwrap() {
             Instruction::IType(instr) => {
-                assert_eq!(instr.op, get_numeric_op("addi").unwrap());
-                assert_eq!(instr.rt, get_register_number("$t0").unwrap());
-                assert_eq!(instr.rs, get_register_number("$t1").unwrap());
-                assert_eq!(instr.imm, 5);
+                assert_eq!(instr.get_op(), get_numeric_op("addi").unwrap());
+                assert_eq!(instr.get_rt(), get_register_number("$t0").unwrap());
+                assert_eq!(instr.get_rs(), get_register_number("$t1").unwrap());
+                assert_eq!(instr.get_imm(), 5);
             }
             _ => panic!("Expected I-Type instruction. Got other type."),
         };
@@ -2392,11 +1307,11 @@ mod tests {
 
         match maybe_instr.unwrap().unwrap() {
             Instruction::RType(instr) => {
-                assert_eq!(instr.op, get_numeric_op("sra").unwrap());
-                assert_eq!(instr.rd, get_register_number("$t0").unwrap());
-                assert_eq!(instr.rt, get_register_number("$t1").unwrap());
-                assert_eq!(instr.shamt, u5::new(4));
-                assert_eq!(instr.func, get_func("sra").unwrap());
+                assert_eq!(instr.get_op(), get_numeric_op("sra").unwrap());
+                assert_eq!(instr.get_rd(), get_register_number("$t0").unwrap());
+                assert_eq!(instr.get_rt(), get_register_number("$t1").unwrap());
+                assert_eq!(instr.get_shamt(), u5::new(4));
+                assert_eq!(instr.get_func(), get_func("sra").unwrap());
             }
             _ => panic!("Expected R-Type instruction. Got other type."),
         };
@@ -2410,16 +1325,24 @@ mod tests {
 
         match maybe_instr.unwrap().unwrap() {
             Instruction::RType(instr) => {
-                assert_eq!(instr.op, get_numeric_op("srl").unwrap());
-                assert_eq!(instr.rd, get_register_number("$t1").unwrap());
-                assert_eq!(instr.rt, get_register_number("$t2").unwrap());
-                assert_eq!(instr.shamt, u5::new(7));
-                assert_eq!(instr.func, get_func("srl").unwrap());
+                assert_eq!(instr.get_op(), get_numeric_op("srl").unwrap());
+                assert_eq!(instr.get_rd(), get_register_number("$t1").unwrap());
+                assert_eq!(instr.get_rt(), get_register_number("$t2").unwrap());
+                assert_eq!(instr.get_shamt(), u5::new(7));
+                assert_eq!(instr.get_func(), get_func("srl").unwrap());
             }
             _ => panic!("Expected R-Type instruction. Got other type."),
         };
     }
 
+    #[test]
+    fn test_shift_and_ori_mnemonic() {
+        for line in ["sll $t0, $t1, 1", "srl $t1, $t2, 7", "sra $t0, $t1, 31", "ori $t0, $t1, 65535", "addi $t0, $t1, -1"] {
+            let instr = parse_instruction(line.to_string(), &HashMap::new(), 0).unwrap().unwrap();
+            assert_eq!(instr.to_mnemonic_string(), line);
+        }
+    }
+
     #[test]
     fn test_parse_only_label() {
         let label = "label:";
@@ -2451,31 +1374,32 @@ mod tests {
 
     #[test]
     fn test_parse_memory_access_arguments() {
-        let argument_string = "$t2, 4($t5)";
-        let got = parse_memory_access_arguments(argument_string).unwrap();
-        println!("{:?}", parse_memory_access_arguments(argument_string));
-        assert_eq!(
-            get_register_number("$t2"),
-            get_register_number(&got[0].to_string())
-        );
-        assert_eq!(
-            get_register_number("$t5"),
-            get_register_number(&got[2].to_string())
-        );
-        assert_eq!("4", &got[1].to_string());
+        // lw $t2, 4($t5) written without and with extra whitespace
+        for line in ["lw $t2,4($t5)", "lw\t$t2 , 4( $t5 )"] {
+            let parse_res = parse_instruction(line.to_string(), &HashMap::new(), 0);
+            assert_eq!(parse_res.unwrap().unwrap().to_hex_string(), "0x8daa0004");
+        }
     }
 
     #[test]
-    fn test_parse_memory_access_arguments_faulty() {
-        let one_argument = "$t2";
-        let two_arguments: &str = "t2, $t3";
-        let brute_force: &str = "(), base(offset)()()()()()";
+    fn test_parse_memory_access_with_label_offset() {
+        let mut symbol_table = HashMap::new();
+        symbol_table.insert("value".to_string(), 8);
+        let parse_res = parse_instruction("sw $t0, value($zero)".to_string(), &symbol_table, 0);
+        assert_eq!(parse_res.unwrap().unwrap().to_hex_string(), "0xac080008");
+    }
 
-        assert!(parse_memory_access_arguments(one_argument).is_err());
-        assert!(parse_memory_access_arguments(two_arguments).is_err());
-        assert!(parse_memory_access_arguments(brute_force).is_ok());
+    #[test]
+    fn test_parse_numeric_registers_and_literals() {
+        let parse_res = parse_instruction("addi $9, $8, 0x10".to_string(), &HashMap::new(), 0);
+        assert_eq!(parse_res.unwrap().unwrap().to_hex_string(), "0x21090010");
+        let parse_res = parse_instruction("ori $t0, $zero, 'a'".to_string(), &HashMap::new(), 0);
+        assert_eq!(parse_res.unwrap().unwrap().to_hex_string(), "0x34080061");
+        let parse_res = parse_instruction("add $t10, $t0, $t0".to_string(), &HashMap::new(), 0);
+        assert_eq!(parse_res.unwrap_err().message, "unknown register `$t10`");
     }
 
+
     #[test]
     fn test_parse_jr_successful() {
         let line = String::from("label: jr $t0 #comment");
@@ -2494,6 +1418,14 @@ mod tests {
         assert_eq!(res.unwrap().to_hex_string(), "0x8d280005");
     }
 
+    #[test]
+    fn test_parse_lw_without_offset() {
+        let res = parse_instruction(String::from("lw $t0, ($t1)"), &HashMap::new(), 0).unwrap();
+        assert_eq!(res.unwrap().to_hex_string(), "0x8d280000");
+        let res = parse_instruction(String::from("sw $t0, ($t1)"), &HashMap::new(), 0).unwrap();
+        assert_eq!(res.unwrap().to_mnemonic_string(), "sw $t0, 0($t1)");
+    }
+
     #[test]
     fn test_parse_sw_successful() {
         let line = String::from("label: sw $t0, 5($t1)");
@@ -2512,7 +1444,8 @@ mod tests {
 
     #[test]
     fn test_parse_lw_unsuccessful() {
-        let line = String::from("label: lw $t0, ($t1) #comments are nice");
+        // a base register without parentheses, `($t1)` alone is an offset of 0
+        let line = String::from("label: lw $t0, $t1 #comments are nice");
         let parse_res = parse_instruction(line, &HashMap::new(), 0);
         assert!(parse_res.is_err());
     }
@@ -2749,4 +1682,32 @@ mod tests {
         assert_eq!(get_operation(u6::new(0), u6::new(3)).unwrap(), "sra");
     }
 
+    #[test]
+    fn test_decode() {
+        let words = [
+            0x012a4020, 0x00094080, 0x03e00008, 0x8d280005, 0xad28fffc, 0x1109fffb, 0x2108ffff,
+            0x3409ff61, 0x08000003, 0x00000000, 0xFFFFFFFF,
+        ];
+        for word in words {
+            let instruction = Instruction::decode(word).unwrap();
+            assert_eq!(instruction.encode(), word, "{:#010x}", word);
+        }
+        assert_eq!(Instruction::decode(0x1109fffb).unwrap().get_imm_val(), Some(-20));
+        // branches and jumps as far as their fields reach
+        assert_eq!(Instruction::decode(0x11098000).unwrap().get_imm_val(), Some(-131072));
+        assert_eq!(Instruction::decode(0x11097fff).unwrap().get_imm_val(), Some(131068));
+        assert_eq!(Instruction::decode(0x0bffffff).unwrap().get_jump_address_val(), Some(0x0fff_fffc));
+        // the fields shown are the ones of the format, a fixed word shows the R-type ones
+        let jr = Instruction::decode(0x03e00008).unwrap();
+        assert_eq!((jr.get_rs_val(), jr.get_rt_val(), jr.get_funct_val()), (Some(31), None, Some(8)));
+        assert_eq!(jr.get_rs_str().as_deref(), Some("11111"));
+        assert_eq!(jr.get_imm_str(), None);
+        let exit = Instruction::decode(0xffffffff).unwrap();
+        assert_eq!((exit.get_op_val(), exit.get_rd_val(), exit.get_imm_val()), (63, Some(31), None));
+        assert_eq!(exit.to_hex_string(), "0xFFFFFFFF");
+        // unknown op code, unknown function and a `jr` with its middle bits set
+        assert!(Instruction::decode(0xfc000000).is_none());
+        assert!(Instruction::decode(0x0000003f).is_none());
+        assert!(Instruction::decode(0x01000408).is_none());
+    }
 }
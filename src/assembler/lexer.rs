@@ -0,0 +1,451 @@
+//! Tokenizer for assembly source.
+//!
+//! Turns the source into a stream of tokens, each knowing where in the source it was
+//! found. Comments are dropped, and every line ends with a `Newline` token so the parser
+//! can treat each line as one statement.
+//!
+//! File: lexer.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::diagnostics::LineError;
+use std::fmt;
+
+/// Where a token is found in the source.
+/// `line` and `column` are 1-based, `length` is counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, length: usize) -> Span {
+        Span {
+            line,
+            column,
+            length,
+        }
+    }
+
+    /// The span from the start of `self` to the end of `other`, which must be on the same line.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            line: self.line,
+            column: self.column,
+            length: (other.column + other.length).saturating_sub(self.column),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Mnemonics, labels and other names, e.g. `add` or `loop`
+    Identifier(String),
+    /// A name starting with a dot, e.g. `.data`
+    Directive(String),
+    /// A register including its dollar sign, e.g. `$t0` or `$8`
+    Register(String),
+    /// A macro parameter including its percent sign, e.g. `%reg`
+    MacroParam(String),
+    /// A decimal, hexadecimal (`0x`), binary (`0b`) or character (`'a'`) literal
+    Integer(i64),
+    /// A double quoted string with its escape sequences resolved
+    Str(String),
+    Comma,
+    Colon,
+    LParen,
+    RParen,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    ShiftLeft,
+    ShiftRight,
+    Ampersand,
+    Pipe,
+    Tilde,
+    Newline,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Identifier(name)
+            | TokenKind::Directive(name)
+            | TokenKind::Register(name)
+            | TokenKind::MacroParam(name) => write!(f, "{}", name),
+            TokenKind::Integer(value) => write!(f, "{}", value),
+            TokenKind::Str(value) => write!(f, "{:?}", value),
+            TokenKind::Comma => write!(f, ","),
+            TokenKind::Colon => write!(f, ":"),
+            TokenKind::LParen => write!(f, "("),
+            TokenKind::RParen => write!(f, ")"),
+            TokenKind::Plus => write!(f, "+"),
+            TokenKind::Minus => write!(f, "-"),
+            TokenKind::Star => write!(f, "*"),
+            TokenKind::Slash => write!(f, "/"),
+            TokenKind::ShiftLeft => write!(f, "<<"),
+            TokenKind::ShiftRight => write!(f, ">>"),
+            TokenKind::Ampersand => write!(f, "&"),
+            TokenKind::Pipe => write!(f, "|"),
+            TokenKind::Tilde => write!(f, "~"),
+            TokenKind::Newline => write!(f, "end of line"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// tokenize_line: splits one line of source into tokens, ending with a `Newline` token.
+///
+/// input: line - the line without its line break
+///        line_number - 1-based number of the line, used in the spans of the tokens
+/// returns: the tokens of the line, or an error pointing at the first character that
+///          cannot be tokenized
+///
+pub fn tokenize_line(line: &str, line_number: usize) -> Result<Vec<Token>, LineError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        let start = idx;
+
+        if c.is_whitespace() {
+            idx += 1;
+            continue;
+        }
+
+        if c == '#' {
+            break;
+        }
+
+        let kind = match c {
+            ',' => punctuation(&mut idx, TokenKind::Comma),
+            ':' => punctuation(&mut idx, TokenKind::Colon),
+            '(' => punctuation(&mut idx, TokenKind::LParen),
+            ')' => punctuation(&mut idx, TokenKind::RParen),
+            '+' => punctuation(&mut idx, TokenKind::Plus),
+            '-' => punctuation(&mut idx, TokenKind::Minus),
+            '*' => punctuation(&mut idx, TokenKind::Star),
+            '/' => punctuation(&mut idx, TokenKind::Slash),
+            '&' => punctuation(&mut idx, TokenKind::Ampersand),
+            '|' => punctuation(&mut idx, TokenKind::Pipe),
+            '~' => punctuation(&mut idx, TokenKind::Tilde),
+            '<' | '>' if chars.get(idx + 1) == Some(&c) => {
+                idx += 2;
+                if c == '<' {
+                    TokenKind::ShiftLeft
+                } else {
+                    TokenKind::ShiftRight
+                }
+            }
+            '$' => {
+                idx += 1;
+                idx = take_while(&chars, idx, |c| c.is_ascii_alphanumeric());
+                if idx == start + 1 {
+                    return Err(LineError::new("expected a register name after `$`".to_string())
+                        .at(Span::new(line_number, start + 1, 1)));
+                }
+                TokenKind::Register(chars[start..idx].iter().collect())
+            }
+            '%' => {
+                idx = take_while(&chars, idx + 1, is_identifier_char);
+                if idx == start + 1 {
+                    return Err(LineError::new("expected a parameter name after `%`".to_string())
+                        .at(Span::new(line_number, start + 1, 1)));
+                }
+                TokenKind::MacroParam(chars[start..idx].iter().collect())
+            }
+            '.' => {
+                idx = take_while(&chars, idx + 1, is_identifier_char);
+                TokenKind::Directive(chars[start..idx].iter().collect())
+            }
+            '"' => {
+                let (value, end) = string_literal(&chars, idx, line_number)?;
+                idx = end;
+                TokenKind::Str(value)
+            }
+            '\'' => {
+                let (value, end) = char_literal(&chars, idx, line_number)?;
+                idx = end;
+                TokenKind::Integer(value)
+            }
+            c if c.is_ascii_digit() => {
+                idx = take_while(&chars, idx, is_identifier_char);
+                let text: String = chars[start..idx].iter().collect();
+                let value = integer_literal(&text).ok_or_else(|| {
+                    LineError::new(format!("invalid integer literal `{}`", text))
+                        .at(Span::new(line_number, start + 1, idx - start))
+                })?;
+                TokenKind::Integer(value)
+            }
+            c if is_identifier_char(c) => {
+                idx = take_while(&chars, idx, is_identifier_char);
+                TokenKind::Identifier(chars[start..idx].iter().collect())
+            }
+            _ => {
+                return Err(LineError::new(format!("unexpected character `{}`", c))
+                    .at(Span::new(line_number, start + 1, 1)));
+            }
+        };
+
+        tokens.push(Token {
+            kind,
+            span: Span::new(line_number, start + 1, idx - start),
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Newline,
+        span: Span::new(line_number, chars.len() + 1, 0),
+    });
+
+    Ok(tokens)
+}
+
+fn punctuation(idx: &mut usize, kind: TokenKind) -> TokenKind {
+    *idx += 1;
+    kind
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Returns the index of the first character from `idx` that does not satisfy `pred`.
+fn take_while(chars: &[char], mut idx: usize, pred: fn(char) -> bool) -> usize {
+    while idx < chars.len() && pred(chars[idx]) {
+        idx += 1;
+    }
+    idx
+}
+
+/// integer_literal: parses a decimal, `0x` hexadecimal or `0b` binary literal.
+///
+/// input: the literal as written in the source, without a sign
+/// returns: the value, or none if it is not a valid literal or does not fit in 64 bits
+///
+pub fn integer_literal(text: &str) -> Option<i64> {
+    let lowercase = text.to_ascii_lowercase();
+    let (digits, radix) = if let Some(hex) = lowercase.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(bin) = lowercase.strip_prefix("0b") {
+        (bin, 2)
+    } else {
+        (lowercase.as_str(), 10)
+    };
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    i64::from_str_radix(digits, radix).ok()
+}
+
+/// Resolves the escape sequence starting after a backslash at `idx`.
+fn escape(chars: &[char], idx: usize) -> Option<char> {
+    match chars.get(idx)? {
+        'n' => Some('\n'),
+        't' => Some('\t'),
+        'r' => Some('\r'),
+        '0' => Some('\0'),
+        '\\' => Some('\\'),
+        '\'' => Some('\''),
+        '"' => Some('"'),
+        _ => None,
+    }
+}
+
+/// Reads the string literal starting at the quote at `start`.
+/// Returns its value and the index after the closing quote.
+fn string_literal(chars: &[char], start: usize, line_number: usize) -> Result<(String, usize), LineError> {
+    let mut value = String::new();
+    let mut idx = start + 1;
+
+    while idx < chars.len() {
+        match chars[idx] {
+            '"' => return Ok((value, idx + 1)),
+            '\\' => {
+                let escaped = escape(chars, idx + 1).ok_or_else(|| {
+                    LineError::new("unknown escape sequence in string".to_string())
+                        .at(Span::new(line_number, idx + 1, 2))
+                })?;
+                value.push(escaped);
+                idx += 2;
+            }
+            c => {
+                value.push(c);
+                idx += 1;
+            }
+        }
+    }
+
+    Err(LineError::new("unterminated string".to_string())
+        .at(Span::new(line_number, start + 1, chars.len() - start)))
+}
+
+/// Reads the character literal starting at the quote at `start`.
+/// Returns its value and the index after the closing quote.
+fn char_literal(chars: &[char], start: usize, line_number: usize) -> Result<(i64, usize), LineError> {
+    let (value, end) = match chars.get(start + 1) {
+        Some('\\') => (escape(chars, start + 2), start + 3),
+        Some(c) if *c != '\'' => (Some(*c), start + 2),
+        _ => (None, start + 1),
+    };
+
+    match (value, chars.get(end)) {
+        (Some(c), Some('\'')) => Ok((c as i64, end + 1)),
+        _ => Err(LineError::new("invalid character literal".to_string())
+            .at(Span::new(line_number, start + 1, (end - start).max(1)))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(line: &str) -> Vec<TokenKind> {
+        tokenize_line(line, 1)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_instruction() {
+        assert_eq!(
+            kinds("loop:add $t0,$t1, -4 # a comment, with: punctuation"),
+            vec![
+                TokenKind::Identifier("loop".to_string()),
+                TokenKind::Colon,
+                TokenKind::Identifier("add".to_string()),
+                TokenKind::Register("$t0".to_string()),
+                TokenKind::Comma,
+                TokenKind::Register("$t1".to_string()),
+                TokenKind::Comma,
+                TokenKind::Minus,
+                TokenKind::Integer(4),
+                TokenKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_memory_operand_with_spaces() {
+        assert_eq!(
+            kinds("lw $t0,4( $t1 )"),
+            vec![
+                TokenKind::Identifier("lw".to_string()),
+                TokenKind::Register("$t0".to_string()),
+                TokenKind::Comma,
+                TokenKind::Integer(4),
+                TokenKind::LParen,
+                TokenKind::Register("$t1".to_string()),
+                TokenKind::RParen,
+                TokenKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_literals() {
+        assert_eq!(
+            kinds("0x1F 0b101 42 'a' '\\n'"),
+            vec![
+                TokenKind::Integer(31),
+                TokenKind::Integer(5),
+                TokenKind::Integer(42),
+                TokenKind::Integer(97),
+                TokenKind::Integer(10),
+                TokenKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_string_containing_comment_char() {
+        assert_eq!(
+            kinds(".asciiz \"# not a comment\\n\" # a comment"),
+            vec![
+                TokenKind::Directive(".asciiz".to_string()),
+                TokenKind::Str("# not a comment\n".to_string()),
+                TokenKind::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_operators() {
+        assert_eq!(
+            kinds("~(a+1)*2/4<<1>>2&3|x"),
+            vec![
+                TokenKind::Tilde,
+                TokenKind::LParen,
+                TokenKind::Identifier("a".to_string()),
+                TokenKind::Plus,
+                TokenKind::Integer(1),
+                TokenKind::RParen,
+                TokenKind::Star,
+                TokenKind::Integer(2),
+                TokenKind::Slash,
+                TokenKind::Integer(4),
+                TokenKind::ShiftLeft,
+                TokenKind::Integer(1),
+                TokenKind::ShiftRight,
+                TokenKind::Integer(2),
+                TokenKind::Ampersand,
+                TokenKind::Integer(3),
+                TokenKind::Pipe,
+                TokenKind::Identifier("x".to_string()),
+                TokenKind::Newline,
+            ]
+        );
+        assert!(tokenize_line("a < b", 1).is_err());
+    }
+
+    #[test]
+    fn test_tokenize_macro_parameters() {
+        assert_eq!(
+            kinds(".macro push(%reg)"),
+            vec![
+                TokenKind::Directive(".macro".to_string()),
+                TokenKind::Identifier("push".to_string()),
+                TokenKind::LParen,
+                TokenKind::MacroParam("%reg".to_string()),
+                TokenKind::RParen,
+                TokenKind::Newline,
+            ]
+        );
+        assert!(tokenize_line("addi $t0, $t0, % 4", 1).is_err());
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let tokens = tokenize_line("\tadd $t0, $t1, $t2", 7).unwrap();
+        assert_eq!(tokens[0].span, Span::new(7, 2, 3));
+        assert_eq!(tokens[1].span, Span::new(7, 6, 3));
+        assert_eq!(tokens[2].span, Span::new(7, 9, 1));
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        let err = tokenize_line(".asciiz \"unterminated", 1).unwrap_err();
+        assert_eq!(err.message, "unterminated string");
+        assert_eq!(err.span, Some(Span::new(1, 9, 13)));
+
+        let err = tokenize_line("addi $t0, $t0, 0x", 1).unwrap_err();
+        assert_eq!(err.message, "invalid integer literal `0x`");
+
+        let err = tokenize_line("add $t0, $t1 ; $t2", 1).unwrap_err();
+        assert_eq!(err.message, "unexpected character `;`");
+        assert_eq!(err.span, Some(Span::new(1, 14, 1)));
+    }
+}
@@ -0,0 +1,375 @@
+//! Links objects assembled from separate files into a single program.
+//!
+//! The text sections of the objects are placed one after the other in the order
+//! the objects are given, and so are their data sections, each starting at a word
+//! aligned address. Labels declared `.globl` in one object can be used by the others
+//! after declaring them `.extern`. Once every section has its address, the addresses
+//! the objects refer to are patched in.
+//!
+//! File: linker.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::assembler::Program;
+use crate::assembler::diagnostics::{Diagnostics, LineError};
+use crate::assembler::directives::align;
+use crate::assembler::object::{
+    Binding, Object, ObjectSymbol, Relocation, RelocationKind, RelocationTarget, Section,
+};
+use crate::utils::InstrRepresentable;
+use std::collections::HashMap;
+
+/// Where the sections of an object are placed in the linked program.
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    text: u32,
+    data: u32,
+}
+
+impl Placement {
+    fn base(&self, section: Section) -> u32 {
+        match section {
+            Section::Text => self.text,
+            Section::Data => self.data,
+        }
+    }
+}
+
+/// link: places the objects one after the other and resolves the references between them.
+///
+/// input: objects - the objects to link, the first one is placed at address 0
+/// returns: the linked program, or every global label that is undefined or defined
+///          multiple times and every address that does not fit where it is used
+///
+pub fn link(mut objects: Vec<Object>) -> Result<Program, Diagnostics> {
+    let mut diagnostics = Diagnostics::new();
+
+    let mut placements = Vec::new();
+    let (mut text_end, mut data_end) = (0, 0);
+    for object in &objects {
+        let placement = Placement {
+            text: text_end,
+            data: align(data_end, 4),
+        };
+        text_end = placement.text + object.text.len() as u32 * 4;
+        data_end = placement.data + object.data.len() as u32;
+        placements.push(placement);
+    }
+
+    let globals = global_addresses(&objects, &placements, &mut diagnostics);
+    // the files defining a label without `.globl`, for hints on undefined labels
+    let mut local_definitions: HashMap<String, String> = HashMap::new();
+    for object in &objects {
+        for symbol in object.symbols_with(Binding::Local) {
+            local_definitions
+                .entry(symbol.name.clone())
+                .or_insert_with(|| object.name.clone());
+        }
+    }
+
+    for (index, object) in objects.iter_mut().enumerate() {
+        let placement = placements[index];
+        for relocation in std::mem::take(&mut object.relocations) {
+            let target = match &relocation.target {
+                RelocationTarget::Section(section) => placement.base(*section),
+                RelocationTarget::Symbol(name) => match globals.get(name) {
+                    Some(addr) => *addr,
+                    None => {
+                        let err = undefined_label(name, local_definitions.get(name));
+                        diagnostics.push(relocation.location.diagnostic(err));
+                        continue;
+                    }
+                },
+            };
+            let value = target as i64 + relocation.addend;
+            if let Err(err) = patch(object, &relocation, placement, value) {
+                diagnostics.push(relocation.location.diagnostic(err));
+            }
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        diagnostics.sort_by_position();
+        return Err(diagnostics);
+    }
+
+    // global labels first, so that a local label does not hide the global one of the same name
+    let mut label_sections = HashMap::new();
+    for binding in [Binding::Global, Binding::Local] {
+        for symbol in objects.iter().flat_map(|object| object.symbols_with(binding)) {
+            if let Some(section) = symbol.section {
+                label_sections.entry(symbol.name.clone()).or_insert(section);
+            }
+        }
+    }
+
+    let mut symbols = globals;
+    let mut text = Vec::new();
+    let mut data = Vec::new();
+    let mut listing = String::new();
+    let mut listing_lines = Vec::new();
+    for (object, placement) in objects.into_iter().zip(placements) {
+        for symbol in object.symbols_with(Binding::Local) {
+            if let Some(section) = symbol.section {
+                symbols
+                    .entry(symbol.name.clone())
+                    .or_insert(placement.base(section) + symbol.value);
+            }
+        }
+        listing.push_str(&format!(
+            "# {}: text at {:#010x}, data at {:#010x}\n",
+            object.name, placement.text, placement.data
+        ));
+        listing.push_str(&object.listing);
+        listing.push('\n');
+        for mut line in object.listing_lines {
+            let (section, addr) = match (line.section, line.address) {
+                (Some(section), Some(addr)) => (section, addr),
+                _ => {
+                    listing_lines.push(line);
+                    continue;
+                }
+            };
+            // show the instruction as it is after its relocations were applied
+            if let (Section::Text, Some(_)) = (section, line.encoding) {
+                let instruction = &object.text[addr as usize / 4];
+                line.encoding = Some(instruction.encode());
+                line.expansion = Some(instruction.to_mnemonic_string());
+            }
+            line.address = Some(placement.base(section) + addr);
+            listing_lines.push(line);
+        }
+        text.extend(object.text);
+        data.resize(placement.data as usize, 0);
+        data.extend(object.data);
+    }
+
+    Ok(Program {
+        text,
+        data,
+        symbols,
+        label_sections,
+        listing,
+        listing_lines,
+    })
+}
+
+/// Collects the addresses of the global labels of all objects.
+/// Global labels that are defined by more than one object are reported to `diagnostics`.
+fn global_addresses(
+    objects: &[Object],
+    placements: &[Placement],
+    diagnostics: &mut Diagnostics,
+) -> HashMap<String, u32> {
+    let mut addresses = HashMap::new();
+    let mut defined_by: HashMap<&str, &ObjectSymbol> = HashMap::new();
+    for (object, placement) in objects.iter().zip(placements) {
+        for symbol in object.symbols_with(Binding::Global) {
+            let section = match symbol.section {
+                Some(section) => section,
+                None => continue,
+            };
+            if let Some(first) = defined_by.get(symbol.name.as_str()) {
+                let err = LineError::new(format!(
+                    "global label `{}` is defined multiple times",
+                    symbol.name
+                ))
+                .with_hint(Some(format!(
+                    "`{}` is first defined in `{}` on line {}",
+                    symbol.name, first.location.file, first.location.line
+                )));
+                diagnostics.push(symbol.location.diagnostic(err));
+                continue;
+            }
+            defined_by.insert(&symbol.name, symbol);
+            addresses.insert(symbol.name.clone(), placement.base(section) + symbol.value);
+        }
+    }
+    addresses
+}
+
+/// The error for a label no object defines as global.
+/// `defined_locally_in` is the file defining the label without `.globl`, if any.
+fn undefined_label(name: &str, defined_locally_in: Option<&String>) -> LineError {
+    let hint = match defined_locally_in {
+        Some(file) => format!(
+            "`{}` is defined in `{}`, but not declared `.globl` there",
+            name, file
+        ),
+        None => format!("no file declares `{}` with `.globl`", name),
+    };
+    LineError::new(format!("undefined label `{}`", name)).with_hint(Some(hint))
+}
+
+/// Writes the linked value of a relocation into the instruction or data it belongs to.
+/// Returns: an error if the value does not fit
+fn patch(
+    object: &mut Object,
+    relocation: &Relocation,
+    placement: Placement,
+    value: i64,
+) -> Result<(), LineError> {
+    let addr = placement.base(relocation.section) as i64 + relocation.offset as i64;
+    let (field, what, min, max) = match relocation.kind {
+        // jump targets and branch offsets are encoded in words
+        RelocationKind::Jump => (value, "jump target", 0, (1 << 28) - 4),
+        RelocationKind::Branch => (
+            value - (addr + 4),
+            "branch offset",
+            i16::MIN as i64 * 4,
+            i16::MAX as i64 * 4,
+        ),
+        RelocationKind::Immediate => (value, "immediate", i16::MIN as i64, i16::MAX as i64),
+        RelocationKind::UnsignedImmediate => (value, "immediate", 0, u16::MAX as i64),
+        // the halves of an address always fit in 16 bits
+        RelocationKind::High => (((value + 0x8000) >> 16) & 0xffff, "immediate", 0, u16::MAX as i64),
+        RelocationKind::Low => (
+            ((value & 0xffff) ^ 0x8000) - 0x8000,
+            "immediate",
+            i16::MIN as i64,
+            i16::MAX as i64,
+        ),
+        RelocationKind::Word => (value, "value", i32::MIN as i64, u32::MAX as i64),
+        RelocationKind::Half => (value, "value", i16::MIN as i64, u16::MAX as i64),
+        RelocationKind::Byte => (value, "value", i8::MIN as i64, u8::MAX as i64),
+    };
+    if field < min || field > max {
+        return Err(LineError::new(format!("{} does not fit after linking", what)).with_hint(
+            Some(format!(
+                "the {} is {}, but must be between {} and {}",
+                what, field, min, max
+            )),
+        ));
+    }
+    if matches!(relocation.kind, RelocationKind::Jump | RelocationKind::Branch) && value % 4 != 0 {
+        return Err(LineError::new(format!("{} is not word aligned", what))
+            .with_hint(Some(format!("the target address is {}", value))));
+    }
+
+    let offset = relocation.offset as usize;
+    let size = match relocation.kind {
+        RelocationKind::Word => 4,
+        RelocationKind::Half => 2,
+        RelocationKind::Byte => 1,
+        _ => {
+            object.text[offset / 4].set_field(field);
+            return Ok(());
+        }
+    };
+    object.data[offset..offset + size].copy_from_slice(&(field as u32).to_be_bytes()[4 - size..]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assembler::{assemble_object, AssemblerOptions};
+
+    fn object(name: &str, source: &str) -> Object {
+        let options = AssemblerOptions {
+            file_name: name.to_string(),
+            ..AssemblerOptions::default()
+        };
+        assemble_object(source, &options).unwrap()
+    }
+
+    #[test]
+    fn test_link() {
+        let main = object(
+            "main.asm",
+            "\t.globl main, back\n\
+             \t.extern square, result\n\
+             main: addi $a0, $zero, 3\n\
+             \tj square\n\
+             back: sw $v0, result($zero)\n\
+             \texit\n\
+             \t.data\n\
+             \t.byte 1\n",
+        );
+        let lib = object(
+            "square.asm",
+            "\t.globl square, result\n\
+             \t.extern back\n\
+             \t.data\n\
+             result: .word 0\n\
+             return: .word back\n\
+             \t.text\n\
+             square: nop\n\
+             \tbeq $zero, $zero, back\n",
+        );
+
+        let program = link(vec![main, lib]).unwrap();
+        assert_eq!(
+            program.instruction_output(),
+            "0x20040003\n0x08000004\n0xac020004\n0xFFFFFFFF\n\
+             0x00000000\n0x1000fffc\n"
+        );
+        assert_eq!(program.data, vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8]);
+        assert_eq!(program.symbols.get("square"), Some(&16));
+        assert_eq!(program.symbols.get("result"), Some(&4));
+        assert_eq!(program.symbols.get("back"), Some(&8));
+        assert_eq!(program.label_sections.get("result"), Some(&Section::Data));
+        assert_eq!(program.label_sections.get("back"), Some(&Section::Text));
+        assert!(program.listing.contains("# square.asm: text at 0x00000010, data at 0x00000004"));
+
+        // the JSON listing has the linked addresses and the patched instructions
+        let jump = program.listing_lines.iter().find(|line| line.source == "\tj square").unwrap();
+        assert_eq!((jump.address, jump.encoding), (Some(4), Some(0x0800_0004)));
+        assert_eq!(jump.expansion.as_deref(), Some("j 16"));
+        let result = program.listing_lines.iter().find(|line| line.labels == ["result"]).unwrap();
+        assert_eq!((result.file.as_str(), result.address), ("square.asm", Some(4)));
+    }
+
+    #[test]
+    fn test_link_errors() {
+        let first = object("first.asm", "\t.globl main\n\t.extern helper\nmain: j helper\n");
+        let second = object("second.asm", "\t.globl main\nmain: nop\nhelper: nop\n");
+
+        let diagnostics = link(vec![first, second]).unwrap_err();
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["undefined label `helper`", "global label `main` is defined multiple times"]
+        );
+        let hints: Vec<Option<String>> = diagnostics.iter().map(|d| d.hint.clone()).collect();
+        assert_eq!(
+            hints,
+            vec![
+                Some("`helper` is defined in `second.asm`, but not declared `.globl` there".to_string()),
+                Some("`main` is first defined in `first.asm` on line 3".to_string()),
+            ]
+        );
+        assert_eq!(diagnostics.iter().next().unwrap().column, 9);
+    }
+
+    #[test]
+    fn test_link_far_branch() {
+        // branch offsets reach 16 bits of words, past 32 KB
+        let main = object("main.asm", "\t.extern far\nmain: beq $zero, $zero, far\n");
+        let padding = object("padding.asm", &"\tnop\n".repeat(9000));
+        let far = object("far.asm", "\t.globl far\nfar: exit\n");
+
+        let program = link(vec![main, padding, far]).unwrap();
+        assert_eq!(program.symbols.get("far"), Some(&36004));
+        assert_eq!(program.text[0].encode(), 0x1000_2328);
+    }
+
+    #[test]
+    fn test_link_address_halves() {
+        let main = object(
+            "main.asm",
+            "\t.extern values\n\
+             main: ori $t0, $zero, %hi(values)\n\
+             \tsll $t0, $t0, 16\n\
+             \taddi $t0, $t0, %lo(values + 8)\n\
+             \t.data\n\
+             \t.word 0\n",
+        );
+        let values = object("values.asm", "\t.globl values\n\t.data\nvalues: .word 1, 2, 3\n");
+
+        let program = link(vec![main, values]).unwrap();
+        assert_eq!(
+            program.instruction_output(),
+            "0x34080000\n0x00084400\n0x2108000c\n"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! The machine-readable listing: what every source line was assembled into, and a
+//! symbol table sorted by address. It is written as JSON, so that editors and graders
+//! can map an address in the program back to the line of source it came from.
+//!
+//! File: listing.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::object::Section;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// What a single source line was assembled into.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListingLine {
+    /// The file the line is from
+    pub file: String,
+    /// 1-based line number in its file
+    pub line: usize,
+    /// The line as written in the source
+    pub source: String,
+    /// The section the line was assembled into, none for lines that take up no space
+    pub section: Option<Section>,
+    /// The address of the instruction or data of the line
+    pub address: Option<u32>,
+    /// The number of bytes the line takes up
+    pub size: u32,
+    /// The machine word of an instruction
+    pub encoding: Option<u32>,
+    /// The instruction the line was encoded as, with every operand resolved
+    pub expansion: Option<String>,
+    /// The labels defined on the line
+    pub labels: Vec<String>,
+    /// Why the line could not be assembled
+    pub error: Option<String>,
+}
+
+impl ListingLine {
+    /// The address of the instruction on the line, none if the line holds no instruction.
+    pub fn instruction_address(&self) -> Option<u32> {
+        match self.section == Some(Section::Text) && self.size > 0 {
+            true => self.address,
+            false => None,
+        }
+    }
+}
+
+/// A label and the address it refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListingSymbol {
+    pub name: String,
+    pub address: u32,
+    pub section: Option<Section>,
+}
+
+#[derive(Serialize)]
+struct JsonListing<'a> {
+    lines: &'a [ListingLine],
+    symbols: Vec<ListingSymbol>,
+}
+
+/// symbol_table: the labels of a program, sorted by address and then by name.
+///
+/// input: symbols - every label and its address
+///        sections - the section of every label
+/// returns: the sorted symbol table
+///
+pub fn symbol_table(symbols: &HashMap<String, u32>, sections: &HashMap<String, Section>) -> Vec<ListingSymbol> {
+    let mut table: Vec<ListingSymbol> = symbols
+        .iter()
+        .map(|(name, address)| ListingSymbol {
+            name: name.clone(),
+            address: *address,
+            section: sections.get(name).copied(),
+        })
+        .collect();
+    table.sort_by(|a, b| (a.address, &a.name).cmp(&(b.address, &b.name)));
+    table
+}
+
+/// to_json: the listing as a JSON document, an object with the `lines` and the
+/// sorted `symbols` of the program.
+///
+/// input: lines - the lines of the listing
+///        symbols - every label and its address
+///        sections - the section of every label
+/// returns: the JSON document
+///
+pub fn to_json(
+    lines: &[ListingLine],
+    symbols: &HashMap<String, u32>,
+    sections: &HashMap<String, Section>,
+) -> String {
+    let listing = JsonListing {
+        lines,
+        symbols: symbol_table(symbols, sections),
+    };
+    // the listing holds only strings and numbers, which always serialize
+    let mut json = serde_json::to_string_pretty(&listing).unwrap();
+    json.push('\n');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_table_is_sorted() {
+        let symbols = HashMap::from([
+            ("loop".to_string(), 4),
+            ("value".to_string(), 0),
+            ("main".to_string(), 0),
+        ]);
+        let sections = HashMap::from([
+            ("loop".to_string(), Section::Text),
+            ("value".to_string(), Section::Data),
+            ("main".to_string(), Section::Text),
+        ]);
+        let names: Vec<String> = symbol_table(&symbols, &sections).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["main", "value", "loop"]);
+    }
+}
@@ -0,0 +1,681 @@
+//! Expansion of MARS style macros, done on the source before it is parsed.
+//!
+//! ```text
+//! .macro push(%reg)
+//!     addi $sp, $sp, -4
+//!     sw %reg, 0($sp)
+//! .end_macro
+//!
+//!     push($t0)
+//! ```
+//!
+//! A macro is used by writing its name followed by its arguments, either in parentheses
+//! or separated by commas like the operands of an instruction. Every `%parameter` in the
+//! body is replaced by the text of its argument, and labels defined in the body are
+//! renamed to `label_M<n>`, where `n` counts the expansions, so that every expansion
+//! gets its own labels. A macro must be defined before it is used.
+//!
+//! File: macros.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::diagnostics::{Diagnostic, Diagnostics, LineError};
+use crate::assembler::lexer::{tokenize_line, Span, Token, TokenKind};
+use crate::assembler::parser::RELOCATION_OPERATORS;
+use std::collections::HashMap;
+
+/// How deeply macros may use other macros, to stop a macro that uses itself.
+const MAX_EXPANSION_DEPTH: usize = 16;
+
+/// A line of source after resolving includes and expanding macros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    /// The file the line is in, or none for the file being assembled
+    pub file: Option<String>,
+    /// 1-based number of the line in its file. All lines of an expansion
+    /// have the number of the line the macro is used on.
+    pub number: usize,
+    /// The line as shown in the listing
+    pub text: String,
+    /// The part of the line to assemble. This is empty for the lines of a macro
+    /// definition and only holds the labels of a line that uses a macro.
+    pub code: String,
+}
+
+impl SourceLine {
+    /// The lines of a source, each to be assembled as it is written.
+    pub fn from_source(source: &str, file: Option<&str>) -> Vec<SourceLine> {
+        source
+            .lines()
+            .enumerate()
+            .map(|(idx, text)| SourceLine {
+                file: file.map(|file| file.to_string()),
+                number: idx + 1,
+                text: text.to_string(),
+                code: text.to_string(),
+            })
+            .collect()
+    }
+
+    /// A line found at the same place in the source as `self`.
+    pub fn derived(&self, text: &str, code: &str) -> SourceLine {
+        SourceLine {
+            file: self.file.clone(),
+            number: self.number,
+            text: text.to_string(),
+            code: code.to_string(),
+        }
+    }
+}
+
+struct Macro {
+    params: Vec<String>,
+    /// The lines of the body with their tokens, or none if the line could not be tokenized
+    body: Vec<(String, Option<Vec<Token>>)>,
+    /// Labels defined in the body
+    labels: Vec<String>,
+    /// The number of the line with `.macro`
+    number: usize,
+}
+
+impl Macro {
+    fn signature(&self, name: &str) -> String {
+        format!("{}({})", name, self.params.join(", "))
+    }
+}
+
+struct MacroExpander<'a> {
+    file_name: &'a str,
+    macros: HashMap<String, Macro>,
+    expansions: usize,
+    output: Vec<SourceLine>,
+}
+
+/// expand_macros: collects the macro definitions of the source and replaces every use
+/// of a macro with its body.
+///
+/// input: lines - the lines of the source, with includes resolved
+///        file_name - name of the source, used in diagnostics
+///        diagnostics - where errors in definitions and uses of macros are reported
+/// returns: the lines of the expanded source
+///
+pub fn expand_macros(
+    lines: Vec<SourceLine>,
+    file_name: &str,
+    diagnostics: &mut Diagnostics,
+) -> Vec<SourceLine> {
+    let mut expander = MacroExpander {
+        file_name,
+        macros: HashMap::new(),
+        expansions: 0,
+        output: Vec::new(),
+    };
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = &lines[idx];
+        let tokens = match tokenize_line(&line.code, line.number) {
+            Ok(tokens) => tokens,
+            // left for the parser to report
+            Err(_) => {
+                expander.output.push(line.clone());
+                idx += 1;
+                continue;
+            }
+        };
+
+        let first = &tokens[label_count(&tokens) * 2];
+        match &first.kind {
+            TokenKind::Directive(name) if name == ".macro" => {
+                idx = expander.define(&lines, idx, &tokens, diagnostics);
+            }
+            TokenKind::Directive(name) if name == ".end_macro" => {
+                expander.report(
+                    line,
+                    LineError::new("`.end_macro` without a matching `.macro`".to_string())
+                        .at(first.span),
+                    diagnostics,
+                );
+                expander.output.push(line.derived(&line.text, ""));
+                idx += 1;
+            }
+            _ => {
+                expander.line(line, &line.text, &line.code, &tokens, 0, diagnostics);
+                idx += 1;
+            }
+        }
+    }
+
+    expander.output
+}
+
+impl MacroExpander<'_> {
+    fn report(&self, line: &SourceLine, err: LineError, diagnostics: &mut Diagnostics) {
+        let file = line.file.as_deref().unwrap_or(self.file_name);
+        diagnostics.push(Diagnostic::from_line_error(file, line.number, &line.code, err));
+    }
+
+    /// Reads the macro defined from the `.macro` line at `start` to its `.end_macro`.
+    /// Returns: the index of the line after the definition
+    fn define(
+        &mut self,
+        lines: &[SourceLine],
+        start: usize,
+        tokens: &[Token],
+        diagnostics: &mut Diagnostics,
+    ) -> usize {
+        let header_line = &lines[start];
+        let number = header_line.number;
+        let header = match macro_header(tokens) {
+            Ok(header) => Some(header),
+            Err(err) => {
+                self.report(header_line, err, diagnostics);
+                None
+            }
+        };
+        self.output.push(header_line.derived(&header_line.text, ""));
+
+        let mut body = Vec::new();
+        let mut labels = Vec::new();
+        let mut idx = start + 1;
+        let mut closed = false;
+        while idx < lines.len() {
+            let line = &lines[idx];
+            self.output.push(line.derived(&line.text, ""));
+            idx += 1;
+
+            let tokens = match tokenize_line(&line.code, line.number) {
+                Ok(tokens) => tokens,
+                Err(err) => {
+                    self.report(line, err, diagnostics);
+                    body.push((line.code.clone(), None));
+                    continue;
+                }
+            };
+            let label_end = label_count(&tokens);
+            match &tokens[label_end * 2].kind {
+                TokenKind::Directive(name) if name == ".end_macro" => {
+                    closed = true;
+                    break;
+                }
+                TokenKind::Directive(name) if name == ".macro" => {
+                    self.report(
+                        line,
+                        LineError::new("macros cannot be defined inside a macro".to_string())
+                            .at(tokens[label_end * 2].span)
+                            .with_hint(Some(format!(
+                                "add `.end_macro` before it to end the macro on line {}",
+                                number
+                            ))),
+                        diagnostics,
+                    );
+                }
+                _ => (),
+            }
+            labels.extend(
+                tokens[..label_end * 2]
+                    .iter()
+                    .filter_map(|token| match &token.kind {
+                        TokenKind::Identifier(name) => Some(name.clone()),
+                        _ => None,
+                    }),
+            );
+            body.push((line.code.clone(), Some(tokens)));
+        }
+
+        let (name, name_span, params) = match header {
+            Some(header) => header,
+            None => return idx,
+        };
+        if !closed {
+            self.report(
+                header_line,
+                LineError::new(format!("macro `{}` has no `.end_macro`", name)).at(name_span),
+                diagnostics,
+            );
+            return idx;
+        }
+
+        // every parameter used in the body must be declared
+        for (offset, (_, tokens)) in body.iter().enumerate() {
+            for token in tokens.iter().flatten() {
+                if let TokenKind::MacroParam(param) = &token.kind {
+                    if !params.contains(param) && !RELOCATION_OPERATORS.contains(&param.as_str()) {
+                        self.report(
+                            &lines[start + 1 + offset],
+                            LineError::new(format!(
+                                "macro `{}` has no parameter `{}`",
+                                name, param
+                            ))
+                            .at(token.span),
+                            diagnostics,
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(previous) = self.macros.get(&name) {
+            self.report(
+                header_line,
+                LineError::new(format!("macro `{}` is defined multiple times", name))
+                    .at(name_span)
+                    .with_hint(Some(format!(
+                        "`{}` is first defined on line {}",
+                        name, previous.number
+                    ))),
+                diagnostics,
+            );
+            return idx;
+        }
+        self.macros.insert(
+            name,
+            Macro {
+                params,
+                body,
+                labels,
+                number,
+            },
+        );
+        idx
+    }
+
+    /// Adds a line outside of a macro definition to the output, expanding it
+    /// if it uses a macro.
+    ///
+    /// input: origin - the line in the source, or the macro use it is expanded from
+    ///        text - the line as shown in the listing
+    ///        code - the line to assemble, which `tokens` were read from
+    ///        depth - how many macro expansions the line is inside of
+    fn line(
+        &mut self,
+        origin: &SourceLine,
+        text: &str,
+        code: &str,
+        tokens: &[Token],
+        depth: usize,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let label_end = label_count(tokens) * 2;
+        let name = match &tokens[label_end].kind {
+            TokenKind::Identifier(name) if self.macros.contains_key(name) => name.clone(),
+            _ => {
+                self.output.push(origin.derived(text, code));
+                return;
+            }
+        };
+
+        // the labels of the line stay in place, in front of the expansion
+        let labels_code = match label_end {
+            0 => String::new(),
+            _ => slice(code, tokens[0].span.to(tokens[label_end - 1].span)),
+        };
+        self.output.push(origin.derived(text, &labels_code));
+
+        let result = self
+            .arguments(&name, code, &tokens[label_end..])
+            .and_then(|args| {
+                if depth >= MAX_EXPANSION_DEPTH {
+                    return Err(LineError::new(format!(
+                        "macro `{}` is nested more than {} levels deep",
+                        name, MAX_EXPANSION_DEPTH
+                    ))
+                    .at(tokens[label_end].span)
+                    .with_hint(Some("a macro cannot use itself".to_string())));
+                }
+                Ok(args)
+            });
+        let args = match result {
+            Ok(args) => args,
+            Err(err) => {
+                self.report(&origin.derived(text, code), err, diagnostics);
+                return;
+            }
+        };
+
+        self.expansions += 1;
+        let suffix = format!("_M{}", self.expansions);
+        let lines: Vec<Option<String>> = {
+            let definition = &self.macros[&name];
+            definition
+                .body
+                .iter()
+                .map(|(text, tokens)| {
+                    tokens
+                        .as_ref()
+                        .map(|tokens| substitute(text, tokens, definition, &args, &suffix))
+                })
+                .collect()
+        };
+
+        for line in lines {
+            // lines that could not be tokenized were reported with the definition
+            let line = match line {
+                Some(line) => line,
+                None => continue,
+            };
+            match tokenize_line(&line, origin.number) {
+                Ok(tokens) => self.line(origin, &line, &line, &tokens, depth + 1, diagnostics),
+                Err(_) => self.output.push(origin.derived(&line, &line)),
+            }
+        }
+    }
+
+    /// Reads the arguments of a macro use, starting at the name of the macro.
+    /// Returns: the text of each argument
+    fn arguments(
+        &self,
+        name: &str,
+        code: &str,
+        tokens: &[Token],
+    ) -> Result<Vec<String>, LineError> {
+        let definition = &self.macros[name];
+        let rest = &tokens[1..tokens.len() - 1];
+
+        // `name(a, b)` or `name a, b`
+        let in_parens = rest.first().map(|token| &token.kind) == Some(&TokenKind::LParen)
+            && rest.last().map(|token| &token.kind) == Some(&TokenKind::RParen)
+            && closing_paren(rest) == Some(rest.len() - 1);
+        let arg_tokens = if in_parens {
+            &rest[1..rest.len() - 1]
+        } else {
+            rest
+        };
+
+        let mut args = Vec::new();
+        if !arg_tokens.is_empty() {
+            let mut depth = 0;
+            let mut arg_start = 0;
+            for (idx, token) in arg_tokens.iter().enumerate() {
+                match token.kind {
+                    TokenKind::LParen => depth += 1,
+                    TokenKind::RParen => depth -= 1,
+                    TokenKind::Comma if depth == 0 => {
+                        args.push(argument(code, &arg_tokens[arg_start..idx], token)?);
+                        arg_start = idx + 1;
+                    }
+                    _ => (),
+                }
+            }
+            let end = tokens.last().unwrap();
+            args.push(argument(code, &arg_tokens[arg_start..], end)?);
+        }
+
+        if args.len() != definition.params.len() {
+            let span = match (rest.first(), rest.last()) {
+                (Some(first), Some(last)) => first.span.to(last.span),
+                _ => tokens[0].span,
+            };
+            return Err(LineError::new(format!(
+                "macro `{}` expects {} argument{}, found {}",
+                name,
+                definition.params.len(),
+                if definition.params.len() == 1 {
+                    ""
+                } else {
+                    "s"
+                },
+                args.len()
+            ))
+            .at(span)
+            .with_hint(Some(format!(
+                "`{}` is defined on line {}",
+                definition.signature(name),
+                definition.number
+            ))));
+        }
+
+        Ok(args)
+    }
+}
+
+/// The number of `label:` pairs the tokens start with.
+pub fn label_count(tokens: &[Token]) -> usize {
+    tokens
+        .chunks(2)
+        .take_while(|pair| {
+            matches!(
+                (&pair[0].kind, pair.get(1).map(|token| &token.kind)),
+                (TokenKind::Identifier(_), Some(TokenKind::Colon))
+            )
+        })
+        .count()
+}
+
+/// Reads `.macro name(%a, %b)`, where the parentheses are optional.
+/// Returns: the name of the macro, where it is written and its parameters
+fn macro_header(tokens: &[Token]) -> Result<(String, Span, Vec<String>), LineError> {
+    if label_count(tokens) > 0 {
+        return Err(
+            LineError::new("a `.macro` line cannot have labels".to_string()).at(tokens[0].span),
+        );
+    }
+
+    let (name, name_span) = match &tokens[1].kind {
+        TokenKind::Identifier(name) => (name.clone(), tokens[1].span),
+        _ => return Err(unexpected(&tokens[1], "the name of the macro")),
+    };
+
+    let mut rest = &tokens[2..tokens.len() - 1];
+    if rest.first().map(|token| &token.kind) == Some(&TokenKind::LParen) {
+        match rest.last() {
+            Some(Token {
+                kind: TokenKind::RParen,
+                ..
+            }) if rest.len() > 1 => rest = &rest[1..rest.len() - 1],
+            _ => return Err(unexpected(&tokens[tokens.len() - 1], "`)`")),
+        }
+    }
+
+    let mut params: Vec<String> = Vec::new();
+    for (idx, token) in rest.iter().enumerate() {
+        match (&token.kind, idx % 2) {
+            (TokenKind::MacroParam(param), 0) => {
+                if params.contains(param) {
+                    return Err(LineError::new(format!(
+                        "parameter `{}` is declared multiple times",
+                        param
+                    ))
+                    .at(token.span));
+                }
+                params.push(param.clone());
+            }
+            (TokenKind::Comma, 1) => (),
+            (_, 0) => return Err(unexpected(token, "a parameter like `%name`")),
+            _ => return Err(unexpected(token, "`,` between parameters")),
+        }
+    }
+    if rest.last().map(|token| &token.kind) == Some(&TokenKind::Comma) {
+        return Err(unexpected(
+            &tokens[tokens.len() - 1],
+            "a parameter like `%name`",
+        ));
+    }
+
+    Ok((name, name_span, params))
+}
+
+fn unexpected(token: &Token, expected: &str) -> LineError {
+    let found = match token.kind {
+        TokenKind::Newline => "end of line".to_string(),
+        _ => format!("`{}`", token.kind),
+    };
+    LineError::new(format!("expected {}, found {}", expected, found)).at(token.span)
+}
+
+/// The index of the parenthesis closing the one the tokens start with.
+fn closing_paren(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::LParen => depth += 1,
+            TokenKind::RParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// The text of one argument of a macro use.
+/// `end` is the token after the argument, used in the error if the argument is empty.
+fn argument(code: &str, tokens: &[Token], end: &Token) -> Result<String, LineError> {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => Ok(slice(code, first.span.to(last.span))),
+        _ => Err(unexpected(end, "an argument")),
+    }
+}
+
+/// The part of a line covered by a span.
+fn slice(line: &str, span: Span) -> String {
+    line.chars()
+        .skip(span.column - 1)
+        .take(span.length)
+        .collect()
+}
+
+/// Replaces the parameters of a line of a macro body with the arguments,
+/// and renames the labels defined in the body by adding `suffix`.
+fn substitute(
+    text: &str,
+    tokens: &[Token],
+    definition: &Macro,
+    args: &[String],
+    suffix: &str,
+) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::new();
+    let mut copied = 0;
+
+    for token in tokens {
+        let replacement = match &token.kind {
+            TokenKind::MacroParam(param) => definition
+                .params
+                .iter()
+                .position(|name| name == param)
+                .map(|idx| args[idx].clone()),
+            TokenKind::Identifier(name) if definition.labels.contains(name) => {
+                Some(format!("{}{}", name, suffix))
+            }
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            let start = token.span.column - 1;
+            result.extend(&chars[copied..start]);
+            result.push_str(&replacement);
+            copied = start + token.span.length;
+        }
+    }
+    result.extend(&chars[copied..]);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(source: &str) -> Vec<String> {
+        let mut diagnostics = Diagnostics::new();
+        let lines = expand_macros(SourceLine::from_source(source, None), "macro.asm", &mut diagnostics);
+        assert!(diagnostics.is_empty(), "{}", diagnostics);
+        lines.into_iter().map(|line| line.code).collect()
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        let mut diagnostics = Diagnostics::new();
+        expand_macros(SourceLine::from_source(source, None), "macro.asm", &mut diagnostics);
+        diagnostics.iter().map(|d| d.message.clone()).collect()
+    }
+
+    #[test]
+    fn test_expand_with_parameters() {
+        let source = ".macro push(%reg)\n\
+                      \taddi $sp, $sp, -4\n\
+                      \tsw %reg, 0($sp)\n\
+                      .end_macro\n\
+                      main: push($t0)\n\
+                      \tpush $t1\n";
+        assert_eq!(
+            code(source),
+            vec![
+                "",
+                "",
+                "",
+                "",
+                "main:",
+                "\taddi $sp, $sp, -4",
+                "\tsw $t0, 0($sp)",
+                "",
+                "\taddi $sp, $sp, -4",
+                "\tsw $t1, 0($sp)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_local_labels_and_nested_macros() {
+        let source = ".macro wait %n\n\
+                      \taddi $t0, $zero, %n\n\
+                      loop: addi $t0, $t0, -1\n\
+                      \tbeq $t0, $zero, done\n\
+                      \tj loop\n\
+                      done:\n\
+                      .end_macro\n\
+                      .macro twice(%n)\n\
+                      \twait(%n)\n\
+                      \twait(%n * 2)\n\
+                      .end_macro\n\
+                      \ttwice(3)\n";
+        let lines: Vec<String> = code(source)
+            .into_iter()
+            .filter(|line| !line.is_empty())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "\taddi $t0, $zero, 3",
+                "loop_M2: addi $t0, $t0, -1",
+                "\tbeq $t0, $zero, done_M2",
+                "\tj loop_M2",
+                "done_M2:",
+                "\taddi $t0, $zero, 3 * 2",
+                "loop_M3: addi $t0, $t0, -1",
+                "\tbeq $t0, $zero, done_M3",
+                "\tj loop_M3",
+                "done_M3:",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_macro_errors() {
+        assert_eq!(
+            errors(".macro m(%a)\nadd %a, %b, $t0\n.end_macro\n"),
+            vec!["macro `m` has no parameter `%b`"]
+        );
+        assert_eq!(
+            errors(".macro m\nnop\n"),
+            vec!["macro `m` has no `.end_macro`"]
+        );
+        assert_eq!(
+            errors(".end_macro\n"),
+            vec!["`.end_macro` without a matching `.macro`"]
+        );
+        assert_eq!(
+            errors(".macro m(%a, %b)\n.end_macro\nm($t0)\n"),
+            vec!["macro `m` expects 2 arguments, found 1"]
+        );
+        assert_eq!(
+            errors(".macro m\n.end_macro\n.macro m\n.end_macro\n"),
+            vec!["macro `m` is defined multiple times"]
+        );
+        assert_eq!(
+            errors(".macro m\nm\n.end_macro\nm\n"),
+            vec!["macro `m` is nested more than 16 levels deep"]
+        );
+    }
+}
@@ -1,8 +1,20 @@
 mod assembler;
-pub use assembler::run_assembler;
+pub use assembler::{assemble, run_assembler, write_program, AssemblerOptions, Program};
 pub use instructions::{get_register_name};
 
+pub mod ast;
+pub mod diagnostics;
+mod directives;
+pub mod expressions;
 mod first_pass;
+pub mod formats;
+pub mod includes;
 pub mod instructions;
-mod parser;
+pub mod lexer;
+pub mod linker;
+pub mod listing;
+pub mod macros;
+pub mod object;
+pub mod parser;
 mod second_pass;
+pub mod spec;
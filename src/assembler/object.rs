@@ -0,0 +1,600 @@
+//! Relocatable objects: files assembled on their own, to be linked with others.
+//!
+//! An object holds the text and data of one file as if both sections started at
+//! address 0, along with the symbols the file defines or needs and the places in the
+//! text and data that must be patched once the addresses of the sections and of the
+//! labels in other files are known.
+//!
+//! Objects are written to disk in a binary format, with every number in big-endian
+//! order like the words of the machine:
+//!
+//! ```text
+//! header       magic "DKOB", version: u16, reserved: u16, name: str, text size: u32,
+//!              data size: u32, symbol count: u32, relocation count: u32,
+//!              string table size: u32, listing: str
+//! text         one word for each instruction
+//! data         the bytes of the data section, padded to a multiple of 4
+//! symbols      name: str, value: u32, section: u8, binding: u8, reserved: u16, location
+//! relocations  section: u8, kind: u8, target kind: u8, reserved: u8, offset: u32,
+//!              target: u32, addend: i64, location
+//! strings      NUL-terminated strings, `str` fields are offsets into them
+//! location     file: str, line: u32, column: u16, length: u16
+//! ```
+//!
+//! A relocation whose target kind is a symbol refers to the symbol by its index in
+//! the symbol table, otherwise the target is the section of the same number. Every
+//! relocation carries its own addend, so `%hi` and `%lo` relocations need no pairing.
+//!
+//! File: object.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::diagnostics::{Diagnostic, LineError};
+use crate::assembler::instructions::Instruction;
+use crate::assembler::lexer::Span;
+use crate::assembler::listing::ListingLine;
+use crate::utils::InstrRepresentable;
+use std::collections::HashMap;
+
+pub use crate::assembler::directives::Section;
+pub use crate::assembler::expressions::RelocationTarget;
+
+/// The result of assembling a single file on its own.
+#[derive(Debug)]
+pub struct Object {
+    /// Name of the file the object was assembled from
+    pub name: String,
+    pub text: Vec<Instruction>,
+    pub data: Vec<u8>,
+    pub symbols: Vec<ObjectSymbol>,
+    pub relocations: Vec<Relocation>,
+    pub listing: String,
+    /// The lines of the JSON listing. They are not written to object files,
+    /// so an object read from a file has none.
+    pub listing_lines: Vec<ListingLine>,
+}
+
+/// Whether a symbol can be seen from other objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    /// A label only the object itself refers to
+    Local,
+    /// A label declared `.globl`
+    Global,
+    /// A label declared `.extern`, defined by another object
+    Extern,
+}
+
+/// A label of an object. Extern symbols have no section and a value of 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectSymbol {
+    pub name: String,
+    pub binding: Binding,
+    pub section: Option<Section>,
+    /// The address of the label relative to the start of its section
+    pub value: u32,
+    pub location: SourceLocation,
+}
+
+/// The field of an instruction or the data a relocation patches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationKind {
+    /// The target of a `j`, an absolute word aligned address in 26 bits
+    Jump,
+    /// The target of a `beq`, relative to the instruction after it
+    Branch,
+    /// A signed 16-bit immediate, or the offset of a `lw` or `sw`
+    Immediate,
+    /// The zero-extended 16-bit immediate of an `ori`
+    UnsignedImmediate,
+    /// An immediate or offset written `%hi(address)`
+    High,
+    /// An immediate or offset written `%lo(address)`
+    Low,
+    Word,
+    Half,
+    Byte,
+}
+
+/// A place in an object that holds an address which is not known until linking.
+/// The value patched in is the address of `target` plus `addend`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocation {
+    pub section: Section,
+    /// Offset of the instruction or data in its section
+    pub offset: u32,
+    pub kind: RelocationKind,
+    pub target: RelocationTarget,
+    pub addend: i64,
+    pub location: SourceLocation,
+}
+
+/// Where in the source a symbol or relocation comes from, so that errors found
+/// while linking can point at it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: usize,
+    /// The text of the line, which is empty if it is not known
+    pub text: String,
+    pub span: Option<Span>,
+}
+
+impl SourceLocation {
+    /// Places an error found while linking at this location.
+    pub fn diagnostic(&self, err: LineError) -> Diagnostic {
+        let err = match err.span {
+            Some(_) => err,
+            None => LineError { span: self.span, ..err },
+        };
+        Diagnostic::from_line_error(&self.file, self.line, &self.text, err)
+    }
+}
+
+impl Object {
+    /// The symbols of the object with the given binding.
+    pub fn symbols_with(&self, binding: Binding) -> impl Iterator<Item = &ObjectSymbol> {
+        self.symbols.iter().filter(move |symbol| symbol.binding == binding)
+    }
+}
+
+/// The first bytes of every object file.
+pub const OBJECT_MAGIC: &[u8; 4] = b"DKOB";
+const OBJECT_VERSION: u16 = 1;
+const HEADER_SIZE: usize = 36;
+const SYMBOL_SIZE: usize = 24;
+const RELOCATION_SIZE: usize = 32;
+
+const SECTIONS: [Section; 2] = [Section::Text, Section::Data];
+const BINDINGS: [Binding; 3] = [Binding::Local, Binding::Global, Binding::Extern];
+const RELOCATION_KINDS: [RelocationKind; 9] = [
+    RelocationKind::Jump,
+    RelocationKind::Branch,
+    RelocationKind::Immediate,
+    RelocationKind::UnsignedImmediate,
+    RelocationKind::High,
+    RelocationKind::Low,
+    RelocationKind::Word,
+    RelocationKind::Half,
+    RelocationKind::Byte,
+];
+
+/// The strings of an object file, each stored once.
+struct StringTable {
+    bytes: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl StringTable {
+    fn new() -> StringTable {
+        // offset 0 is the empty string
+        StringTable {
+            bytes: vec![0],
+            offsets: HashMap::from([(String::new(), 0)]),
+        }
+    }
+
+    fn add(&mut self, string: &str) -> u32 {
+        if let Some(offset) = self.offsets.get(string) {
+            return *offset;
+        }
+        let offset = self.bytes.len() as u32;
+        self.bytes.extend_from_slice(string.as_bytes());
+        self.bytes.push(0);
+        self.offsets.insert(string.to_string(), offset);
+        offset
+    }
+}
+
+impl Object {
+    /// to_bytes: the object in the object file format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut strings = StringTable::new();
+        let mut symbols = self.symbols.clone();
+        let mut symbol_bytes = Vec::new();
+        let mut relocation_bytes = Vec::new();
+
+        for relocation in &self.relocations {
+            let (target_kind, target) = match &relocation.target {
+                RelocationTarget::Section(section) => (0, index_of(&SECTIONS, section) as u32),
+                RelocationTarget::Symbol(name) => {
+                    let index = match symbols.iter().position(|symbol| symbol.name == *name) {
+                        Some(index) => index,
+                        None => {
+                            symbols.push(ObjectSymbol {
+                                name: name.clone(),
+                                binding: Binding::Extern,
+                                section: None,
+                                value: 0,
+                                location: relocation.location.clone(),
+                            });
+                            symbols.len() - 1
+                        }
+                    };
+                    (1, index as u32)
+                }
+            };
+            relocation_bytes.push(index_of(&SECTIONS, &relocation.section) as u8);
+            relocation_bytes.push(index_of(&RELOCATION_KINDS, &relocation.kind) as u8);
+            relocation_bytes.extend_from_slice(&[target_kind, 0]);
+            relocation_bytes.extend_from_slice(&relocation.offset.to_be_bytes());
+            relocation_bytes.extend_from_slice(&target.to_be_bytes());
+            relocation_bytes.extend_from_slice(&relocation.addend.to_be_bytes());
+            write_location(&mut relocation_bytes, &relocation.location, &mut strings);
+        }
+
+        for symbol in &symbols {
+            symbol_bytes.extend_from_slice(&strings.add(&symbol.name).to_be_bytes());
+            symbol_bytes.extend_from_slice(&symbol.value.to_be_bytes());
+            let section = match symbol.section {
+                Some(section) => index_of(&SECTIONS, &section) as u8 + 1,
+                None => 0,
+            };
+            symbol_bytes.extend_from_slice(&[section, index_of(&BINDINGS, &symbol.binding) as u8, 0, 0]);
+            write_location(&mut symbol_bytes, &symbol.location, &mut strings);
+        }
+
+        let name = strings.add(&self.name);
+        let listing = strings.add(&self.listing);
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(OBJECT_MAGIC);
+        bytes.extend_from_slice(&OBJECT_VERSION.to_be_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        for field in [
+            name,
+            self.text.len() as u32 * 4,
+            self.data.len() as u32,
+            symbols.len() as u32,
+            self.relocations.len() as u32,
+            strings.bytes.len() as u32,
+            listing,
+        ] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        for instruction in &self.text {
+            bytes.extend_from_slice(&instruction.encode().to_be_bytes());
+        }
+        bytes.extend_from_slice(&self.data);
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+        bytes.extend_from_slice(&symbol_bytes);
+        bytes.extend_from_slice(&relocation_bytes);
+        bytes.extend_from_slice(&strings.bytes);
+        bytes
+    }
+
+    /// from_bytes: reads an object in the object file format.
+    ///
+    /// input: bytes - the contents of an object file
+    /// returns: the object, or a description of what is wrong with the file
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<Object, String> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != OBJECT_MAGIC {
+            return Err("not an object file".to_string());
+        }
+        let mut reader = Reader { bytes, pos: 4 };
+        let version = reader.u16()?;
+        if version != OBJECT_VERSION {
+            return Err(format!(
+                "unsupported object file version {}, expected {}",
+                version, OBJECT_VERSION
+            ));
+        }
+        reader.u16()?;
+        let name = reader.u32()?;
+        let text_size = reader.u32()? as usize;
+        let data_size = reader.u32()? as usize;
+        let symbol_count = reader.u32()? as usize;
+        let relocation_count = reader.u32()? as usize;
+        let strings_size = reader.u32()? as usize;
+        let listing = reader.u32()?;
+
+        let sections_size = text_size + data_size.next_multiple_of(4);
+        let strings_start = HEADER_SIZE
+            + sections_size
+            + symbol_count * SYMBOL_SIZE
+            + relocation_count * RELOCATION_SIZE;
+        if !text_size.is_multiple_of(4) || bytes.len() != strings_start + strings_size {
+            return Err("the sizes in the header do not match the size of the file".to_string());
+        }
+        let strings = &bytes[strings_start..];
+        let string = |offset: u32| -> Result<String, String> {
+            let tail = strings
+                .get(offset as usize..)
+                .ok_or_else(|| format!("string offset {} is outside the string table", offset))?;
+            let end = tail
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or_else(|| format!("string at offset {} is not terminated", offset))?;
+            String::from_utf8(tail[..end].to_vec())
+                .map_err(|_| format!("string at offset {} is not valid UTF-8", offset))
+        };
+
+        let mut text = Vec::new();
+        for index in 0..text_size / 4 {
+            let word = reader.u32()?;
+            let instruction = Instruction::decode(word).ok_or_else(|| {
+                format!("word {:#010x} at {:#010x} is not an instruction", word, index * 4)
+            })?;
+            text.push(instruction);
+        }
+        let data = reader.take(data_size)?.to_vec();
+        reader.take(data_size.next_multiple_of(4) - data_size)?;
+
+        let mut symbols = Vec::new();
+        for _ in 0..symbol_count {
+            let name = string(reader.u32()?)?;
+            let value = reader.u32()?;
+            let section = match reader.u8()? {
+                0 => None,
+                section => Some(*lookup(&SECTIONS, section as usize - 1, "section")?),
+            };
+            let binding = *lookup(&BINDINGS, reader.u8()? as usize, "symbol binding")?;
+            reader.u16()?;
+            let location = read_location(&mut reader, &string)?;
+            symbols.push(ObjectSymbol {
+                name,
+                binding,
+                section,
+                value,
+                location,
+            });
+        }
+
+        let mut relocations = Vec::new();
+        for _ in 0..relocation_count {
+            let section = *lookup(&SECTIONS, reader.u8()? as usize, "section")?;
+            let kind = *lookup(&RELOCATION_KINDS, reader.u8()? as usize, "relocation kind")?;
+            let target_kind = reader.u8()?;
+            reader.u8()?;
+            let offset = reader.u32()?;
+            let target = reader.u32()? as usize;
+            let target = match target_kind {
+                0 => RelocationTarget::Section(*lookup(&SECTIONS, target, "section")?),
+                1 => RelocationTarget::Symbol(lookup(&symbols, target, "symbol")?.name.clone()),
+                other => return Err(format!("unknown relocation target kind {}", other)),
+            };
+            let addend = i64::from_be_bytes(reader.take(8)?.try_into().unwrap());
+            let location = read_location(&mut reader, &string)?;
+            let section_size = match section {
+                Section::Text => text_size,
+                Section::Data => data_size,
+            };
+            if offset as usize >= section_size {
+                return Err(format!("relocation at {:#010x} is outside its section", offset));
+            }
+            relocations.push(Relocation {
+                section,
+                offset,
+                kind,
+                target,
+                addend,
+                location,
+            });
+        }
+
+        Ok(Object {
+            name: string(name)?,
+            text,
+            data,
+            symbols,
+            relocations,
+            listing: string(listing)?,
+            listing_lines: Vec::new(),
+        })
+    }
+}
+
+impl Object {
+    /// dump: a readable description of the object, listing its sections,
+    /// symbols and relocations.
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        let section_name = |section: Option<Section>| match section {
+            Some(Section::Text) => ".text",
+            Some(Section::Data) => ".data",
+            None => "*UND*",
+        };
+
+        out.push_str(&format!("object `{}`\n\n", self.name));
+        out.push_str(&format!(
+            "Sections:\n  .text  {} bytes\n  .data  {} bytes\n",
+            self.text.len() * 4,
+            self.data.len()
+        ));
+
+        out.push_str("\nText:\n");
+        for (index, instruction) in self.text.iter().enumerate() {
+            out.push_str(&format!(
+                "  {:#010x}  {}  {}\n",
+                index * 4,
+                instruction.to_hex_string(),
+                instruction.to_mnemonic_string()
+            ));
+        }
+
+        out.push_str("\nData:\n");
+        for (index, chunk) in self.data.chunks(16).enumerate() {
+            let bytes: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+            out.push_str(&format!("  {:#010x}  {}\n", index * 16, bytes.join(" ")));
+        }
+
+        out.push_str("\nSymbols:\n  Value       Section  Binding  Name\n");
+        for symbol in &self.symbols {
+            let binding = match symbol.binding {
+                Binding::Local => "local",
+                Binding::Global => "global",
+                Binding::Extern => "extern",
+            };
+            out.push_str(&format!(
+                "  {:#010x}  {:<7}  {:<7}  {}\n",
+                symbol.value,
+                section_name(symbol.section),
+                binding,
+                symbol.name
+            ));
+        }
+
+        out.push_str("\nRelocations:\n  Offset      Section  Kind                Target\n");
+        for relocation in &self.relocations {
+            let target = match &relocation.target {
+                RelocationTarget::Section(section) => section_name(Some(*section)).to_string(),
+                RelocationTarget::Symbol(name) => name.clone(),
+            };
+            out.push_str(&format!(
+                "  {:#010x}  {:<7}  {:<18}  {} {:+}  ({}:{})\n",
+                relocation.offset,
+                section_name(Some(relocation.section)),
+                format!("{:?}", relocation.kind),
+                target,
+                relocation.addend,
+                relocation.location.file,
+                relocation.location.line
+            ));
+        }
+
+        out
+    }
+}
+
+fn index_of<T: PartialEq>(values: &[T], value: &T) -> usize {
+    values.iter().position(|candidate| candidate == value).unwrap()
+}
+
+fn lookup<'a, T>(values: &'a [T], index: usize, what: &str) -> Result<&'a T, String> {
+    values
+        .get(index)
+        .ok_or_else(|| format!("unknown {} {}", what, index))
+}
+
+fn write_location(bytes: &mut Vec<u8>, location: &SourceLocation, strings: &mut StringTable) {
+    let (column, length) = location
+        .span
+        .map_or((0, 0), |span| (span.column as u16, span.length as u16));
+    bytes.extend_from_slice(&strings.add(&location.file).to_be_bytes());
+    bytes.extend_from_slice(&(location.line as u32).to_be_bytes());
+    bytes.extend_from_slice(&column.to_be_bytes());
+    bytes.extend_from_slice(&length.to_be_bytes());
+}
+
+/// Reads a location. The text of the line is not kept in object files.
+fn read_location(
+    reader: &mut Reader,
+    string: &dyn Fn(u32) -> Result<String, String>,
+) -> Result<SourceLocation, String> {
+    let file = string(reader.u32()?)?;
+    let line = reader.u32()? as usize;
+    let column = reader.u16()? as usize;
+    let length = reader.u16()? as usize;
+    Ok(SourceLocation {
+        file,
+        line,
+        text: String::new(),
+        span: (column > 0).then(|| Span::new(line, column, length)),
+    })
+}
+
+/// Reads big-endian numbers from an object file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + count)
+            .ok_or_else(|| "the object file ends unexpectedly".to_string())?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assembler::{assemble_object, AssemblerOptions};
+
+    fn object(source: &str) -> Object {
+        let options = AssemblerOptions {
+            file_name: "main.asm".to_string(),
+            ..AssemblerOptions::default()
+        };
+        assemble_object(source, &options).unwrap()
+    }
+
+    #[test]
+    fn test_object_file_round_trip() {
+        let original = object(
+            "\t.globl main\n\
+             \t.extern table\n\
+             main: ori $t0, $zero, %hi(table)\n\
+             \tsll $t0, $t0, 16\n\
+             \tlw $t1, %lo(table + 4)($t0)\n\
+             loop: beq $t1, $zero, loop\n\
+             \tj main\n\
+             \t.data\n\
+             bytes: .byte 1, 2, 3\n\
+             \t.word loop\n",
+        );
+        assert_eq!(original.relocations.len(), 4);
+
+        let read = Object::from_bytes(&original.to_bytes()).unwrap();
+        assert_eq!(read.name, "main.asm");
+        assert_eq!(read.listing, original.listing);
+        assert_eq!(read.data, original.data);
+        let words = |object: &Object| -> Vec<u32> { object.text.iter().map(Instruction::encode).collect() };
+        assert_eq!(words(&read), words(&original));
+        assert_eq!(read.symbols.len(), original.symbols.len());
+        for (read, original) in read.symbols.iter().zip(&original.symbols) {
+            assert_eq!((&read.name, read.binding, read.section, read.value), (&original.name, original.binding, original.section, original.value));
+            assert_eq!(read.location.span, original.location.span);
+        }
+        for (read, original) in read.relocations.iter().zip(&original.relocations) {
+            assert_eq!(read.location.text, "");
+            assert_eq!(
+                Relocation { location: original.location.clone(), ..read.clone() },
+                *original
+            );
+        }
+        let kinds: Vec<RelocationKind> = read.relocations.iter().map(|relocation| relocation.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![RelocationKind::High, RelocationKind::Low, RelocationKind::Jump, RelocationKind::Word]
+        );
+        assert_eq!(read.relocations[1].addend, 4);
+
+        let dump = read.dump();
+        assert!(dump.contains("  0x00000000  .text    global   main\n"));
+        assert!(dump.contains("  0x00000000  *UND*    extern   table\n"));
+        assert!(dump.contains("  0x00000008  .text    Low                 table +4  (main.asm:5)\n"));
+        assert!(dump.contains("  0x00000000  01 02 03 00 00 00 00 0c\n"));
+    }
+
+    #[test]
+    fn test_object_file_errors() {
+        let bytes = object("main: j main\n").to_bytes();
+        assert_eq!(Object::from_bytes(b"not an object").unwrap_err(), "not an object file");
+        assert_eq!(
+            Object::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+            "the sizes in the header do not match the size of the file"
+        );
+        let mut corrupt = bytes.clone();
+        corrupt[36..40].copy_from_slice(&0xfc00_0000u32.to_be_bytes());
+        assert_eq!(
+            Object::from_bytes(&corrupt).unwrap_err(),
+            "word 0xfc000000 at 0x00000000 is not an instruction"
+        );
+    }
+}
@@ -1,274 +1,520 @@
-//!  helper functions for parsing strings
-//!  
+//!  Parser turning assembly source into the syntax tree in ast.rs
+//!
+//!  Includes are resolved and macros expanded first, see includes.rs and macros.rs.
+//!  Every line is then tokenized by the lexer and parsed on its own, following
+//!
+//!  ```text
+//!  line      := label* statement? NEWLINE
+//!  label     := IDENTIFIER ':'
+//!  statement := (IDENTIFIER | DIRECTIVE) (operand (',' operand)*)?
+//!  operand   := REGISTER | STRING | expr | expr? '(' REGISTER ')'
+//!  expr      := and ('|' and)*
+//!  and       := shift ('&' shift)*
+//!  shift     := sum (('<<' | '>>') sum)*
+//!  sum       := product (('+' | '-') product)*
+//!  product   := unary (('*' | '/') unary)*
+//!  unary     := ('-' | '+' | '~') unary | ('%hi' | '%lo') '(' expr ')'
+//!             | INTEGER | IDENTIFIER | '(' expr ')'
+//!  ```
+//!
+//!  The first operand of `.eqv` and `.equ` may be followed by the value without a comma.
+//!
 //! File: parser.rs
 //! Author: mai21asm, c19hln
 //! Since: 2022-11-24
-//! Version: 1.0
-
-/// begins_with_space: checks if the given instruction begins with a whitespace
+//! Version: 2.0
+use crate::assembler::ast::{
+    BinaryOp, Expr, ExprKind, Label, Line, Operand, OperandKind, Statement, StatementKind, UnaryOp,
+};
+use crate::assembler::diagnostics::{Diagnostic, Diagnostics, LineError};
+use crate::assembler::lexer::{tokenize_line, Span, Token, TokenKind};
+use crate::assembler::includes::resolve_includes;
+use crate::assembler::macros::expand_macros;
+use std::path::PathBuf;
+
+/// Operators that take the upper or lower half of an address. They are written like
+/// macro parameters, so macros must not mistake them for one.
+pub const RELOCATION_OPERATORS: [&str; 2] = ["%hi", "%lo"];
+
+/// parse_source: resolves the includes and expands the macros of the given source
+/// and parses every resulting line.
+/// Included files are looked for next to `file_name`.
+/// Lines that cannot be parsed are reported to `diagnostics` and marked as malformed.
 ///
-/// input: instruction string
-/// returns: true if begins with whitespace or false
+/// input: source - the assembly source
+///        file_name - path of the source, used in diagnostics
+///        diagnostics - where syntax errors are reported
+/// returns: one `Line` for every line in the source, in the files it includes
+///          and in the expansions of its macros
 ///
-pub fn begins_with_space(instruction: &str) -> bool {
-    if instruction.len() == 0 {
-        return false;
-    }
-
-    let char_vec: Vec<char> = instruction.chars().collect();
-    char_vec[0].is_whitespace()
+pub fn parse_source(source: &str, file_name: &str, diagnostics: &mut Diagnostics) -> Vec<Line> {
+    parse_source_with_includes(source, file_name, &[], diagnostics)
 }
 
-/// has_instruction:
-/// Checks whether a given line contains an instruction.
-/// Does not verify that the instruction in the line is a valid instruction.
-/// input: the line to check if it contains an instruction
-/// returns: a boolean representing whether the given line contains an instruction
-pub fn has_instruction(line: &str) -> bool {
-    if line.contains("#") {
-        let maybe_without_comment = remove_comment_from_line(line);
-        if maybe_without_comment.is_none() {
-            return false;
-        }
+/// parse_source_with_includes: like `parse_source`, but also looks for
+/// included files in `include_dirs`.
+pub fn parse_source_with_includes(
+    source: &str,
+    file_name: &str,
+    include_dirs: &[PathBuf],
+    diagnostics: &mut Diagnostics,
+) -> Vec<Line> {
+    let lines = resolve_includes(source, file_name, include_dirs, diagnostics);
+    expand_macros(lines, file_name, diagnostics)
+        .into_iter()
+        .map(|line| match parse_line(&line.code, line.number) {
+            Ok(parsed) => Line {
+                file: line.file,
+                text: line.text,
+                ..parsed
+            },
+            Err(err) => {
+                let file = line.file.as_deref().unwrap_or(file_name);
+                diagnostics.push(Diagnostic::from_line_error(file, line.number, &line.code, err));
+                Line {
+                    file: line.file,
+                    number: line.number,
+                    text: line.text,
+                    labels: Vec::new(),
+                    statement: None,
+                    malformed: true,
+                }
+            }
+        })
+        .collect()
+}
 
-        let line = maybe_without_comment.unwrap();
-        let split_str: Vec<&str> = line.split(":").collect();
-        return split_str.len() == 1 || (split_str.len() > 1 && split_str[1].trim().len() > 0);
+/// parse_line: parses a single line of source.
+///
+/// input: text - the line without its line break
+///        number - 1-based number of the line
+/// returns: the parsed line, or an error pointing at where parsing failed
+///
+pub fn parse_line(text: &str, number: usize) -> Result<Line, LineError> {
+    let tokens = tokenize_line(text, number)?;
+    let mut parser = LineParser { tokens, pos: 0 };
+
+    let mut labels = Vec::new();
+    while let (TokenKind::Identifier(name), TokenKind::Colon) =
+        (&parser.peek().kind, &parser.peek_second().kind)
+    {
+        labels.push(Label {
+            name: name.clone(),
+            span: parser.peek().span,
+        });
+        parser.advance();
+        parser.advance();
     }
 
-    let split_str: Vec<&str> = line.trim().split(":").collect();
+    let statement = match parser.peek().kind {
+        TokenKind::Newline => None,
+        _ => Some(parser.statement()?),
+    };
 
-    split_str.len() == 1 && split_str[0].len() > 0
-        || (split_str.len() > 1 && split_str[1].trim().len() > 0)
+    let end = parser.advance();
+    if end.kind != TokenKind::Newline {
+        return Err(unexpected(&end, "end of line"));
+    }
+
+    Ok(Line {
+        file: None,
+        number,
+        text: text.to_string(),
+        labels,
+        statement,
+        malformed: false,
+    })
 }
 
-/// line_is_empty:
-/// Checks if the given line is empty.
-/// Is empty if it either has a length of 0 or consists of only whitespace.
-/// input: the line to check if it is empty
-/// returns: a bool representing whether the line is empty
-pub fn line_is_empty(line: &str) -> bool {
-    return line.len() == 0 || line.trim().is_empty();
+struct LineParser {
+    tokens: Vec<Token>,
+    pos: usize,
 }
 
-/// remove_comment_from_line:
-/// Returns the given line without a comment, or None if the comment
-/// was the entire line.
-/// If the given line does not contain a comment, the original line is returned.
-/// input: reference to a string, not consumed after this function call
-/// returns: new line without the comment or none
-pub fn remove_comment_from_line(line: &str) -> Option<String> {
-    let split_vec: Vec<&str> = line.split("#").collect();
-    if split_vec[0].trim().to_string().len() == 0 {
-        return None;
+impl LineParser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
     }
 
-    Some(split_vec[0].to_string())
-}
+    fn peek_second(&self) -> &Token {
+        self.tokens.get(self.pos + 1).unwrap_or(self.peek())
+    }
 
-/// get_comment_from_line:
-/// Extracts the substring that is the comment from the line.
-/// If the given line contains no comment, None is returned.
-/// input: reference to the line
-/// returns: a comment or none
-#[allow(unused)]
-pub fn get_comment_from_line(line: &str) -> Option<String> {
-    let split_str: Vec<&str> = line.split("#").collect();
-    if split_str.len() < 2 {
-        return None;
+    /// Moves past the current token and returns it. The final newline is never moved past.
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
     }
-    let mut comment: String = String::from("");
 
-    comment.push_str("#");
-    comment.push_str(split_str[1]);
-    Some(comment)
-}
+    fn statement(&mut self) -> Result<Statement, LineError> {
+        let name_token = self.advance();
+        let (kind, name) = match name_token.kind {
+            TokenKind::Identifier(name) => (StatementKind::Instruction, name),
+            TokenKind::Directive(name) => (StatementKind::Directive, name),
+            _ => return Err(unexpected(&name_token, "an instruction or a directive")),
+        };
+
+        let mut operands = Vec::new();
+        if self.peek().kind != TokenKind::Newline {
+            operands.push(self.operand()?);
+            let comma_optional = matches!(name.as_str(), ".eqv" | ".equ");
+            if comma_optional && !matches!(self.peek().kind, TokenKind::Comma | TokenKind::Newline) {
+                operands.push(self.operand()?);
+            }
+            while self.peek().kind == TokenKind::Comma {
+                self.advance();
+                operands.push(self.operand()?);
+            }
+        }
+
+        if self.peek().kind == TokenKind::Colon {
+            return Err(LineError::new("unexpected `:`".to_string())
+                .at(self.peek().span)
+                .with_hint(Some(
+                    "a label is a single name directly followed by `:`".to_string(),
+                )));
+        }
+        if self.peek().kind != TokenKind::Newline {
+            return Err(unexpected(self.peek(), "`,` between operands"));
+        }
 
-/// get_label_from_line:
-/// Extracts the label from the given line.
-/// If the given line contains no label, None is returned.
-/// input: reference to the line
-/// returns: a label or none
-pub fn get_label_from_line(line: &str) -> Option<String> {
-    let split_str: Vec<&str> = line.split(":").collect();
-    if split_str.len() < 2 || split_str[0].contains("#") {
-        return None;
+        let span = match operands.last() {
+            Some(last) => name_token.span.to(last.span),
+            None => name_token.span,
+        };
+
+        Ok(Statement {
+            kind,
+            name,
+            name_span: name_token.span,
+            operands,
+            span,
+        })
     }
 
-    Some(split_str[0].to_string())
-}
+    fn operand(&mut self) -> Result<Operand, LineError> {
+        let token = self.peek().clone();
+        let operand = match token.kind {
+            TokenKind::Register(name) => {
+                self.advance();
+                return Ok(Operand {
+                    kind: OperandKind::Register(name),
+                    span: token.span,
+                });
+            }
+            TokenKind::Str(value) => {
+                self.advance();
+                return Ok(Operand {
+                    kind: OperandKind::Str(value),
+                    span: token.span,
+                });
+            }
+            TokenKind::LParen if matches!(self.peek_second().kind, TokenKind::Register(_)) => {
+                return self.memory(None)
+            }
+            _ => {
+                let expr = self.expression()?;
+                Operand {
+                    span: expr.span,
+                    kind: OperandKind::Expression(expr),
+                }
+            }
+        };
+
+        if self.peek().kind == TokenKind::LParen {
+            return self.memory(Some(operand));
+        }
 
-/// remove_label_from_line:
-///
-/// input: reference to a string, not consumed after this function call
-/// returns: new line without the label or none
-pub fn remove_label_from_line(line: &str) -> Option<String> {
-    let split_vec: Vec<&str> = line.split(":").collect();
-
-    if split_vec.len() == 1 {
-        // no label in line
-        return Some(split_vec[0].to_string());
+        Ok(operand)
     }
 
-    if split_vec[1].trim().to_string().len() == 0 {
-        // only label in line..
-        return None;
+    fn expression(&mut self) -> Result<Expr, LineError> {
+        self.binary(0)
     }
 
-    Some(split_vec[1].to_string())
-}
+    /// Parses the binary operators of the given precedence level and above,
+    /// where level 0 binds the loosest.
+    fn binary(&mut self, level: usize) -> Result<Expr, LineError> {
+        const LEVELS: [&[(TokenKind, BinaryOp)]; 5] = [
+            &[(TokenKind::Pipe, BinaryOp::Or)],
+            &[(TokenKind::Ampersand, BinaryOp::And)],
+            &[
+                (TokenKind::ShiftLeft, BinaryOp::ShiftLeft),
+                (TokenKind::ShiftRight, BinaryOp::ShiftRight),
+            ],
+            &[(TokenKind::Plus, BinaryOp::Add), (TokenKind::Minus, BinaryOp::Sub)],
+            &[(TokenKind::Star, BinaryOp::Mul), (TokenKind::Slash, BinaryOp::Div)],
+        ];
+
+        if level == LEVELS.len() {
+            return self.unary();
+        }
 
-/// extract_mnemonic_arguments_from_line:
-///
-/// input: &str
-/// returns: string with mnemonics or none
-#[allow(unused)]
-pub fn extract_mnemonic_arguments_from_line(line: &str) -> Option<String> {
-    let without_comment = remove_comment_from_line(line)?;
-    let only_instr = remove_label_from_line(&without_comment)?;
-    if only_instr.trim().len() > 0 {
-        return Some(only_instr.trim().to_string());
-    }
+        let mut lhs = self.binary(level + 1)?;
+        while let Some((_, op)) = LEVELS[level]
+            .iter()
+            .find(|(kind, _)| *kind == self.peek().kind)
+        {
+            self.advance();
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr {
+                span: lhs.span.to(rhs.span),
+                kind: ExprKind::Binary(*op, Box::new(lhs), Box::new(rhs)),
+            };
+        }
 
-    None
-}
+        Ok(lhs)
+    }
 
-/// trim_instruction_string: checks if the instruction starts with blank space and
-///                           gets rid of that blank space.
-///
-/// input: string that expects no comment or label for now
-/// returns: result vector.
-#[allow(unused)]
-pub fn trim_instruction_string(instruction: &str) -> Result<String, String> {
-    if !begins_with_space(instruction) {
-        // maybe not the best error message.......
-        return Err(format!("{} does not start with blank space", instruction).to_string());
+    fn unary(&mut self) -> Result<Expr, LineError> {
+        let token = self.advance();
+        let kind = match token.kind {
+            TokenKind::Integer(value) => ExprKind::Integer(value),
+            TokenKind::Identifier(name) => ExprKind::Symbol(name),
+            TokenKind::Plus => return self.unary(),
+            TokenKind::Minus | TokenKind::Tilde => {
+                let op = if token.kind == TokenKind::Minus {
+                    UnaryOp::Negate
+                } else {
+                    UnaryOp::Not
+                };
+                let operand = self.unary()?;
+                // fold negative literals so `-4` stays a plain integer
+                if let (UnaryOp::Negate, ExprKind::Integer(value)) = (op, &operand.kind) {
+                    return Ok(Expr {
+                        kind: ExprKind::Integer(-value),
+                        span: token.span.to(operand.span),
+                    });
+                }
+                return Ok(Expr {
+                    span: token.span.to(operand.span),
+                    kind: ExprKind::Unary(op, Box::new(operand)),
+                });
+            }
+            TokenKind::MacroParam(ref name) if RELOCATION_OPERATORS.contains(&name.as_str()) => {
+                if self.peek().kind != TokenKind::LParen {
+                    return Err(unexpected(self.peek(), "`(` after the operator"));
+                }
+                let operand = self.unary()?;
+                let op = if name == "%hi" { UnaryOp::High } else { UnaryOp::Low };
+                return Ok(Expr {
+                    span: token.span.to(operand.span),
+                    kind: ExprKind::Unary(op, Box::new(operand)),
+                });
+            }
+            TokenKind::LParen => {
+                let inner = self.expression()?;
+                let close = self.advance();
+                if close.kind != TokenKind::RParen {
+                    return Err(unexpected(&close, "`)`"));
+                }
+                return Ok(Expr {
+                    kind: inner.kind,
+                    span: token.span.to(close.span),
+                });
+            }
+            _ => return Err(unexpected(&token, "an operand")),
+        };
+
+        Ok(Expr {
+            kind,
+            span: token.span,
+        })
     }
 
-    let split_vec: Vec<&str> = instruction.split(" ").collect();
-    let mut trimmed_instr = String::new();
-    trimmed_instr.push_str(split_vec[1]);
+    /// Parses `(base)`, where the offset in front of it has already been parsed.
+    fn memory(&mut self, offset: Option<Operand>) -> Result<Operand, LineError> {
+        let open = self.advance();
+        let base_token = self.advance();
+        let base = match base_token.kind {
+            TokenKind::Register(ref name) => Operand {
+                kind: OperandKind::Register(name.clone()),
+                span: base_token.span,
+            },
+            _ => return Err(unexpected(&base_token, "a base register")),
+        };
+
+        let close = self.advance();
+        if close.kind != TokenKind::RParen {
+            return Err(unexpected(&close, "`)` after the base register"));
+        }
 
-    let iterator = split_vec.iter();
-    for text in iterator.skip(2) {
-        trimmed_instr.push_str(" ");
-        trimmed_instr.push_str(text);
+        let start = offset.as_ref().map(|offset| offset.span).unwrap_or(open.span);
+        Ok(Operand {
+            kind: OperandKind::Memory {
+                offset: offset.map(Box::new),
+                base: Box::new(base),
+            },
+            span: start.to(close.span),
+        })
     }
+}
 
-    Ok(trimmed_instr)
+/// Error for a token that is not what the grammar expects at its position.
+fn unexpected(token: &Token, expected: &str) -> LineError {
+    let found = match token.kind {
+        TokenKind::Newline => "end of line".to_string(),
+        _ => format!("`{}`", token.kind),
+    };
+    let span = match token.kind {
+        TokenKind::Newline => Span::new(token.span.line, token.span.column, 1),
+        _ => token.span,
+    };
+    LineError::new(format!("expected {}, found {}", expected, found)).at(span)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_name() {}
-    #[test]
-    fn test_remove_comment_from_line() {
-        let line_with_comment = "I have a comment # this is my comment";
-        let maybe_line = remove_comment_from_line(line_with_comment);
-        assert!(maybe_line.is_some());
-        let line = maybe_line.unwrap();
-        assert_eq!(line, "I have a comment ");
+    fn operand_kinds(text: &str) -> Vec<OperandKind> {
+        parse_line(text, 1)
+            .unwrap()
+            .statement
+            .unwrap()
+            .operands
+            .into_iter()
+            .map(|operand| operand.kind)
+            .collect()
     }
 
-    #[test]
-    fn test_get_comment_from_line() {
-        let line_with_comment = "I have a comment # this is my comment";
-        let maybe_line = get_comment_from_line(line_with_comment);
-        assert!(maybe_line.is_some());
-        let line = maybe_line.unwrap();
-        assert_eq!(line, "# this is my comment");
+    fn register(name: &str) -> OperandKind {
+        OperandKind::Register(name.to_string())
     }
 
-    #[test]
-    fn test_remove_comment_without_comment() {
-        let line_no_comment = "I have no comment";
-        let maybe_line = remove_comment_from_line(line_no_comment);
-        assert!(maybe_line.is_some());
-        let line = maybe_line.unwrap();
-        assert_eq!(line_no_comment, line);
+    fn integer(value: i64, span: Span) -> OperandKind {
+        OperandKind::Expression(Expr {
+            kind: ExprKind::Integer(value),
+            span,
+        })
     }
 
     #[test]
-    fn test_get_label() {
-        let line_with_label = "label: add $rd $rt $rs";
-        let maybe_label = get_label_from_line(line_with_label);
-        assert!(maybe_label.is_some());
-        let label = maybe_label.unwrap();
-        assert_eq!(label, "label");
+    fn test_parse_label_instruction_comment() {
+        let line = parse_line("label: addi $t1, $zero, 1 # good comment", 3).unwrap();
+        assert_eq!(line.number, 3);
+        assert_eq!(line.labels.len(), 1);
+        assert_eq!(line.labels[0].name, "label");
+        assert_eq!(line.labels[0].span, Span::new(3, 1, 5));
+        let statement = line.statement.unwrap();
+        assert_eq!(statement.kind, StatementKind::Instruction);
+        assert_eq!(statement.name, "addi");
+        assert_eq!(statement.span, Span::new(3, 8, 18));
+        assert_eq!(
+            statement.operands.iter().map(|o| o.kind.clone()).collect::<Vec<_>>(),
+            vec![register("$t1"), register("$zero"), integer(1, Span::new(3, 25, 1))]
+        );
     }
 
     #[test]
-    fn test_get_label_only_label() {
-        let only_label = "label:";
-        let maybe_label = get_label_from_line(only_label);
-        assert!(maybe_label.is_some());
-        let label = maybe_label.unwrap();
-        assert_eq!(label, "label");
+    fn test_parse_label_without_space() {
+        let line = parse_line("loop:j loop", 1).unwrap();
+        assert_eq!(line.labels[0].name, "loop");
+        assert_eq!(line.statement.unwrap().name, "j");
     }
 
     #[test]
-    fn test_get_label_no_label() {
-        let no_label = "add $rd $rt $rs";
-        let maybe_label = get_label_from_line(no_label);
-        assert!(maybe_label.is_none());
+    fn test_parse_without_statement() {
+        for text in ["label:", "label: # A comment", " #this is a comment", "", "   \t"] {
+            let line = parse_line(text, 1).unwrap();
+            assert!(line.statement.is_none(), "{}", text);
+        }
+        assert_eq!(parse_line("a: b: # two labels", 1).unwrap().labels.len(), 2);
     }
 
     #[test]
-    fn test_has_instruction_label_comment() {
-        let line_with_instruction = "label: addi $rd $rt 1 # good comment";
-        assert!(has_instruction(line_with_instruction));
-    }
+    fn test_parse_memory_operand() {
+        assert_eq!(
+            operand_kinds("lw $t0,4( $t1 )"),
+            vec![
+                register("$t0"),
+                OperandKind::Memory {
+                    offset: Some(Box::new(Operand {
+                        kind: integer(4, Span::new(1, 8, 1)),
+                        span: Span::new(1, 8, 1),
+                    })),
+                    base: Box::new(Operand {
+                        kind: register("$t1"),
+                        span: Span::new(1, 11, 3),
+                    }),
+                },
+            ]
+        );
+
+        match &operand_kinds("sw $t2, -8($sp)")[1] {
+            OperandKind::Memory { offset, .. } => {
+                assert_eq!(offset.as_ref().unwrap().kind, integer(-8, Span::new(1, 9, 2)))
+            }
+            other => panic!("expected a memory operand, found {:?}", other),
+        }
 
-    #[test]
-    fn test_has_instruction_only_comment() {
-        let comment = "#not an instruction";
-        assert!(!has_instruction(comment));
+        match &operand_kinds("lw $t0, ($t1)")[1] {
+            OperandKind::Memory { offset, .. } => assert!(offset.is_none()),
+            other => panic!("expected a memory operand, found {:?}", other),
+        }
     }
 
     #[test]
-    fn test_has_instruction_only_label() {
-        let label = "label:";
-        assert!(!has_instruction(label));
-    }
+    fn test_parse_expression() {
+        let operands = operand_kinds("addi $t0, $t0, (end - start) * 4 + ~1 << 2");
+        match &operands[2] {
+            OperandKind::Expression(expr) => {
+                assert_eq!(expr.to_string(), "(((end - start) * 4) + ~1) << 2");
+                assert_eq!(expr.span, Span::new(1, 16, 27));
+            }
+            other => panic!("expected an expression, found {:?}", other),
+        }
 
-    #[test]
-    fn test_has_instruction() {
-        let instruction = "add $rd $rt $rs";
-        assert!(has_instruction(instruction));
-    }
+        match &operand_kinds("lw $t0, SIZE * 2($sp)")[1] {
+            OperandKind::Memory { offset, .. } => {
+                assert_eq!(offset.as_ref().unwrap().to_string(), "SIZE * 2")
+            }
+            other => panic!("expected a memory operand, found {:?}", other),
+        }
 
-    #[test]
-    fn test_has_instruction_whitespace() {
-        let whitespace = "      ";
-        assert!(!has_instruction(whitespace));
-    }
+        let statement = parse_line(".eqv SIZE 0x10", 1).unwrap().statement.unwrap();
+        assert_eq!(statement.operands.len(), 2);
 
-    #[test]
-    fn test_remove_label_from_line() {
-        let expected = " addi $sp, $sp, -8 #adjust stack pointer";
-        let got = remove_label_from_line("fact: addi $sp, $sp, -8 #adjust stack pointer").unwrap();
-        assert_eq!(expected, got);
+        let err = parse_line("addi $t0, $t0, (1 + 2", 1).unwrap_err();
+        assert_eq!(err.message, "expected `)`, found end of line");
     }
+
     #[test]
-    fn test_trim_instruction_string_not_successful() {
-        let faulty_instr = "addi  $t1, $zero, 1   # A comment";
-        let result: Result<String, String> = trim_instruction_string(&faulty_instr);
-        assert!(result.is_err());
+    fn test_parse_directive() {
+        let line = parse_line("msg: .asciiz \"a # b\"", 1).unwrap();
+        let statement = line.statement.unwrap();
+        assert_eq!(statement.kind, StatementKind::Directive);
+        assert_eq!(statement.name, ".asciiz");
+        assert_eq!(statement.operands[0].kind, OperandKind::Str("a # b".to_string()));
     }
 
     #[test]
-    fn test_trim_instruction_string_successful() {
-        let expected = "addi $sp, $sp, -8 #adjust stack pointer";
-        let got = trim_instruction_string(" addi $sp, $sp, -8 #adjust stack pointer").unwrap();
-        assert_eq!(expected, got);
+    fn test_parse_errors() {
+        let err = parse_line("sw $t0 ($t1)", 1).unwrap_err();
+        assert_eq!(err.message, "expected `,` between operands, found `(`");
+        assert_eq!(err.span, Some(Span::new(1, 8, 1)));
+
+        let err = parse_line("lw $t0, 4($t1", 1).unwrap_err();
+        assert_eq!(err.message, "expected `)` after the base register, found end of line");
+
+        let err = parse_line("add $t0, , $t1", 1).unwrap_err();
+        assert_eq!(err.message, "expected an operand, found `,`");
+
+        let err = parse_line("my label: nop", 1).unwrap_err();
+        assert_eq!(err.message, "unexpected `:`");
     }
+
     #[test]
-    fn test_trim_instruction_string_misc() {
-        let expected = "addi";
-        let got = trim_instruction_string(" addi").unwrap();
-        assert_eq!(expected, got);
+    fn test_parse_source_marks_malformed_lines() {
+        let mut diagnostics = Diagnostics::new();
+        let lines = parse_source("nop\nadd $t0,, $t1\n\nexit", "prog.asm", &mut diagnostics);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].malformed);
+        assert!(!lines[2].malformed);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics.iter().next().unwrap().line, 2);
     }
 }
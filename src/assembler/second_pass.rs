@@ -1,141 +1,359 @@
-//! File for encoding instructions and saving them to an output-file + listings file with information
+//! File for encoding instructions and producing the instruction output + listings with information
 //!
 //! File: second_pass.rs
 //! Author: mai17asm, c19hln
 //! Since: 2022-11-24
 //! Version: 1.0
-use crate::assembler::instructions::parse_instruction;
-use darken_assignment1::utils;
-use darken_assignment1::utils::InstrRepresentable;
-use std::collections::HashMap;
-use std::fs::File;
+use crate::assembler::ast::{ExprKind, Line, OperandKind, Statement, StatementKind, UnaryOp};
+use crate::assembler::diagnostics::{Diagnostic, Diagnostics, LineError};
+use crate::assembler::directives::{self, Section};
+use crate::assembler::expressions::{RelocationTarget, Symbols};
+use crate::assembler::instructions::{build_instruction, relocation_operand};
+use crate::assembler::listing::{self, ListingLine};
+use crate::assembler::object::{Relocation, RelocationKind, SourceLocation};
+use crate::utils::InstrRepresentable;
 
 use super::instructions::Instruction;
 
-// TODO: get rid of this
-type ErrorMessage = String;
+/// The result of the second pass: the text and data segments and the listing file.
+pub struct SecondPassOutput {
+    pub instructions: Vec<Instruction>,
+    pub data: Vec<u8>,
+    pub listing: String,
+    /// What every source line was assembled into, for the JSON listing
+    pub lines: Vec<ListingLine>,
+    /// The addresses to patch when linking, if the source was assembled into an object
+    pub relocations: Vec<Relocation>,
+}
 
-/// Fills lines in output_line_instructions and output_line_listing Strings to be printed to file.
-/// Parses instruction, produces hex-string representation of it, adds it to the instruction output.
-/// Then generates a row to add to the listing file and adds that to the listing file output.
-/// Returns: an error if parse_instruction fails due to wrong format, or none if succeeded
-fn append_to_output(
-    line: String,
-    symbol_table: &HashMap<String, u32>,
+/// Encodes the instruction of a line in the text section and adds a row for it to the listing.
+/// An erroneous line still takes up its place in memory, so
+/// the addresses of the lines after it match the symbol table.
+/// Returns: the instruction, or an error if the statement could not be encoded
+fn append_instruction(
+    line: &Line,
+    statement: &Statement,
+    symbols: &Symbols,
     addr_counter: &mut u32,
-    output_line_instructions: &mut String,
     output_line_listing: &mut String,
-) -> Result<Option<Instruction>, ErrorMessage> {
-    let hex_str: String;
-    let addr_counter_str;
-    let full_line = line.clone();
-    let maybe_instruction = parse_instruction(line, &symbol_table, *addr_counter);
-    if maybe_instruction.is_err() {
-        return Err(maybe_instruction.unwrap_err());
+) -> Result<Instruction, LineError> {
+    let maybe_instruction = match statement.kind {
+        StatementKind::Instruction => build_instruction(statement, symbols, *addr_counter),
+        StatementKind::Directive => Err(directives::data_size(statement, symbols).err().unwrap_or_else(|| {
+            LineError::new(format!(
+                "`{}` is only allowed in the .data section",
+                statement.name
+            ))
+            .at(statement.name_span)
+            .with_hint(Some("add `.data` before it".to_string()))
+        })),
+    };
+
+    let addr_counter_str = format!("{:#010x}", addr_counter);
+    *addr_counter += 4;
+    let hex_str = match &maybe_instruction {
+        Ok(instruction) => instruction.to_hex_string(),
+        Err(_) => " ".repeat(10),
+    };
+    output_line_listing
+        .push_str(generate_row_for_listings(&addr_counter_str, &hex_str, &line.text).as_str());
+
+    if let Err(ref err) = maybe_instruction {
+        output_line_listing.push_str(format!("error: {}\n", err.message).as_str());
     }
 
-    if let Ok(Some(ref instruction)) = maybe_instruction {
-        hex_str = instruction.to_hex_string();
-        output_line_instructions.push_str(hex_str.as_str());
-        output_line_instructions.push_str("\n");
-        addr_counter_str = format!("{:#010x}", addr_counter);
-        *addr_counter += 4;
-    } else {
-        hex_str = " ".repeat(10);
-        addr_counter_str = " ".repeat(10);
+    maybe_instruction
+}
+
+/// Lays out the data of a directive in the data section and adds a row for it to the listing.
+/// Returns: an error if the directive is not valid in the data section
+fn append_data(
+    line: &Line,
+    statement: &Statement,
+    symbols: &Symbols,
+    data: &mut Vec<u8>,
+    output_line_listing: &mut String,
+) -> Result<(), LineError> {
+    let (addr_counter_str, result) = match statement.kind {
+        StatementKind::Instruction => (
+            " ".repeat(10),
+            Err(LineError::new(format!(
+                "instruction `{}` in the .data section",
+                statement.name
+            ))
+            .at(statement.name_span)
+            .with_hint(Some("add `.text` before it".to_string()))),
+        ),
+        StatementKind::Directive => {
+            let addr = directives::align(
+                data.len() as u32,
+                directives::data_alignment(statement, symbols),
+            );
+            data.resize(addr as usize, 0);
+            let result = directives::data_size(statement, symbols).and_then(|size| {
+                match directives::encode_data(statement, symbols) {
+                    Ok(bytes) => {
+                        data.extend_from_slice(&bytes);
+                        Ok(())
+                    }
+                    Err(err) => {
+                        // keep the layout of the following data intact
+                        data.resize((addr + size) as usize, 0);
+                        Err(err)
+                    }
+                }
+            });
+            (format!("{:#010x}", addr), result)
+        }
+    };
+
+    output_line_listing.push_str(
+        generate_row_for_listings(&addr_counter_str, &" ".repeat(10), &line.text).as_str(),
+    );
+    if let Err(ref err) = result {
+        output_line_listing.push_str(format!("error: {}\n", err.message).as_str());
     }
 
-    output_line_listing
-        .push_str(generate_row_for_listings(&addr_counter_str, &hex_str, &full_line).as_str());
+    result
+}
 
-    Ok(maybe_instruction.unwrap())
-    // This is a lil ugly
-    //Ok(maybe_instruction)
+/// Finds the addresses in a statement that change when the file is linked with others.
+/// Branches to labels in the same file are relative and need no relocation.
+/// An immediate written `%hi(x)` or `%lo(x)` is patched with that half of the address of `x`,
+/// so `%hi` and `%lo` must be applied to the whole immediate.
+/// Returns: the relocations, or an error if an address is used in a way linking cannot patch
+fn statement_relocations(
+    line: &Line,
+    statement: &Statement,
+    section: Section,
+    addr: u32,
+    symbols: &Symbols,
+    file_name: &str,
+) -> Result<Vec<Relocation>, LineError> {
+    let element_kind = match statement.name.as_str() {
+        ".word" => RelocationKind::Word,
+        ".half" => RelocationKind::Half,
+        _ => RelocationKind::Byte,
+    };
+    let fields: Vec<(u32, RelocationKind, _)> = match statement.kind {
+        StatementKind::Instruction => relocation_operand(statement)
+            .map(|(kind, expr)| (addr, kind, expr))
+            .into_iter()
+            .collect(),
+        StatementKind::Directive if matches!(statement.name.as_str(), ".word" | ".half" | ".byte") => {
+            let element_size = directives::data_size(statement, symbols)? / statement.operands.len() as u32;
+            statement
+                .operands
+                .iter()
+                .enumerate()
+                .filter_map(|(i, operand)| match &operand.kind {
+                    OperandKind::Expression(expr) => Some((addr + i as u32 * element_size, element_kind, expr)),
+                    _ => None,
+                })
+                .collect()
+        }
+        StatementKind::Directive => Vec::new(),
+    };
+
+    let mut relocations = Vec::new();
+    for (offset, kind, expr) in fields {
+        let (kind, expr) = match (&expr.kind, kind) {
+            (
+                ExprKind::Unary(op @ (UnaryOp::High | UnaryOp::Low), operand),
+                RelocationKind::Immediate | RelocationKind::UnsignedImmediate,
+            ) => {
+                let half = if *op == UnaryOp::High { RelocationKind::High } else { RelocationKind::Low };
+                (half, operand.as_ref())
+            }
+            _ => (kind, expr),
+        };
+        let (target, addend) = match symbols.relocation(expr)? {
+            Some((RelocationTarget::Section(_), _)) if kind == RelocationKind::Branch => continue,
+            Some(relocation) => relocation,
+            None => continue,
+        };
+        relocations.push(Relocation {
+            section,
+            offset,
+            kind,
+            target,
+            addend,
+            location: SourceLocation {
+                file: line.file_name(file_name).to_string(),
+                line: line.number,
+                text: line.text.clone(),
+                span: Some(expr.span),
+            },
+        });
+    }
+    Ok(relocations)
 }
 
-/// Reads lines from the given input file, tries to parse instructions
-/// Uses the symbol table containing the address of each label in the
-/// given input file in order to create proper instructions.
+/// Goes through the parsed lines of the source and encodes their instructions and data.
+/// Uses the address of each label and the value of each constant in the
+/// given source in order to create proper instructions.
+/// Every statement that cannot be encoded is reported to `diagnostics`, and encoding
+/// continues with the next line so that all errors in the source are found.
+/// If `relocatable` is set, the source is assembled into an object to be linked, and
+/// the addresses that linking changes are collected as relocations.
+/// Returns: the instructions, the initial data memory and the contents of the listing file
 pub fn parse_write_instructions(
-    input_file_path: &str,
-    instruction_output_path: &str,
-    assembly_listing_path: &str,
-    symbol_table: HashMap<String, u32>,
-) -> Result<Vec<Box< dyn InstrRepresentable>>, ErrorMessage> {
-    File::create(instruction_output_path).expect(
-        format!(
-            "Failed to create instruction output file from path: {}.",
-            instruction_output_path
-        )
-        .as_str(),
-    );
-    File::create(assembly_listing_path).expect(
-        format!(
-            "Failed to create assembly listing file from path: {}.",
-            assembly_listing_path
-        )
-        .as_str(),
-    );
-    let lines = utils::read_lines(input_file_path).expect("Failed to open input file");
+    lines: &[Line],
+    file_name: &str,
+    symbols: &Symbols,
+    relocatable: bool,
+    diagnostics: &mut Diagnostics,
+) -> SecondPassOutput {
     let mut addr_counter = 0;
+    let mut section = Section::Text;
     let mut output_line_listing: String = String::new();
-    let mut output_line_instructions: String = String::new();
-    let mut instr_list: Vec<Box<dyn InstrRepresentable>> = Vec::new();
+    let mut instr_list: Vec<Instruction> = Vec::new();
+    let mut data: Vec<u8> = Vec::new();
+    let mut relocations: Vec<Relocation> = Vec::new();
+    let mut listing_lines: Vec<ListingLine> = Vec::new();
+    // a line that takes up no space
+    let empty_line = |line: &Line| ListingLine {
+        file: line.file_name(file_name).to_string(),
+        line: line.number,
+        source: line.text.clone(),
+        section: None,
+        address: None,
+        size: 0,
+        encoding: None,
+        expansion: None,
+        labels: line.labels.iter().map(|label| label.name.clone()).collect(),
+        error: None,
+    };
 
     for line in lines {
-        if let Err(e) = line {
-            utils::print_to_file(
-                assembly_listing_path,
-                format!("ERROR: Could not parse line from given input"),
-            )
-            .expect("Could not write to file");
-            return Err(e.to_string());
+        let statement = match &line.statement {
+            Some(statement) => statement,
+            None => {
+                let mut listing_line = empty_line(line);
+                let addr_counter_str = if line.malformed && section == Section::Text {
+                    listing_line.section = Some(Section::Text);
+                    listing_line.address = Some(addr_counter);
+                    listing_line.size = 4;
+                    addr_counter += 4;
+                    format!("{:#010x}", addr_counter - 4)
+                } else {
+                    " ".repeat(10)
+                };
+                output_line_listing.push_str(
+                    generate_row_for_listings(&addr_counter_str, &" ".repeat(10), &line.text)
+                        .as_str(),
+                );
+                listing_lines.push(listing_line);
+                continue;
+            }
+        };
+
+        if statement.kind == StatementKind::Directive {
+            // constants and declarations were checked by the first pass
+            if matches!(statement.name.as_str(), ".eqv" | ".equ" | ".globl" | ".extern") {
+                output_line_listing.push_str(
+                    generate_row_for_listings(&" ".repeat(10), &" ".repeat(10), &line.text)
+                        .as_str(),
+                );
+                listing_lines.push(empty_line(line));
+                continue;
+            }
+            if let Some(new_section) = directives::section_directive(statement) {
+                section = new_section;
+                let res = directives::data_size(statement, symbols);
+                output_line_listing.push_str(
+                    generate_row_for_listings(&" ".repeat(10), &" ".repeat(10), &line.text)
+                        .as_str(),
+                );
+                let mut listing_line = empty_line(line);
+                if let Err(err) = res {
+                    listing_line.error = Some(err.message.clone());
+                    diagnostics.push(Diagnostic::from_line_error(line.file_name(file_name), line.number, &line.text, err));
+                }
+                listing_lines.push(listing_line);
+                continue;
+            }
         }
 
-        if let Ok(line) = line {
-            let res = append_to_output(
+        let addr = match section {
+            Section::Text => addr_counter,
+            Section::Data => {
+                directives::align(data.len() as u32, directives::data_alignment(statement, symbols))
+            }
+        };
+        let res = match section {
+            Section::Text => append_instruction(
                 line,
-                &symbol_table,
+                statement,
+                symbols,
                 &mut addr_counter,
-                &mut output_line_instructions,
                 &mut output_line_listing,
-            );
-            if let Err(err) = res {
-                output_line_listing.push_str(err.as_str());
-                break;
-            }
-            if let Ok(Some(instr)) = res {
-                instr_list.push(Box::new(instr));
+            )
+            .map(|instr| instr_list.push(instr)),
+            Section::Data => append_data(
+                line,
+                statement,
+                symbols,
+                &mut data,
+                &mut output_line_listing,
+            ),
+        };
+        let res = match res {
+            Ok(()) if relocatable => {
+                statement_relocations(line, statement, section, addr, symbols, file_name)
+                    .map(|found| relocations.extend(found))
             }
+            res => res,
+        };
+
+        let mut listing_line = empty_line(line);
+        if section == Section::Text || statement.kind == StatementKind::Directive {
+            listing_line.section = Some(section);
+            listing_line.address = Some(addr);
+            listing_line.size = match section {
+                Section::Text => 4,
+                Section::Data => data.len() as u32 - addr,
+            };
+        }
+        if let (Section::Text, Ok(())) = (section, &res) {
+            let instruction = &instr_list[instr_list.len() - 1];
+            listing_line.encoding = Some(instruction.encode());
+            listing_line.expansion = Some(instruction.to_mnemonic_string());
         }
+        listing_line.error = res.as_ref().err().map(|err| err.message.clone());
+        listing_lines.push(listing_line);
 
+        if let Err(err) = res {
+            diagnostics.push(Diagnostic::from_line_error(line.file_name(file_name), line.number, &line.text, err));
+        }
     }
 
-    output_line_listing.push_str("\n");
+    output_line_listing.push('\n');
     // fill output line with the symbol table
-    output_line_listing.push_str(generate_label_row(symbol_table).as_str());
-
-    // print to files
-    utils::print_to_file(assembly_listing_path, output_line_listing)
-        .expect("Could not write to file");
-    utils::print_to_file(instruction_output_path, output_line_instructions)
-        .expect("Could not write to file");
-
-    Ok(instr_list)
+    output_line_listing.push_str(generate_label_row(symbols).as_str());
+
+    SecondPassOutput {
+        instructions: instr_list,
+        data,
+        listing: output_line_listing,
+        lines: listing_lines,
+        relocations,
+    }
 }
 
-/// generates a row for symbols and returns as a string.
-fn generate_label_row(symbols: HashMap<String, u32>) -> String {
+/// generates the symbol table of the listing, one label on each row, sorted by
+/// address and then by name so that the listing is the same on every run.
+fn generate_label_row(symbols: &Symbols) -> String {
     let mut output_line: String = String::new();
     output_line.push_str("Symbols\n");
-    for (key, value) in symbols {
-        output_line.push_str(key.as_str());
+    for symbol in listing::symbol_table(&symbols.labels, &symbols.label_sections) {
+        output_line.push_str(symbol.name.as_str());
         output_line.push_str(" ".repeat(5).as_str());
-        output_line.push_str(format!("{:#010x}", value).as_str());
-        output_line.push_str(" ".repeat(10).as_str());
+        output_line.push_str(format!("{:#010x}", symbol.address).as_str());
+        output_line.push('\n');
     }
 
-    return output_line;
+    output_line
 }
 
 /// Creates a row to be printed to listings file
@@ -157,7 +375,7 @@ fn generate_row_for_listings(counter: &str, hex: &str, line: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use darken_assignment1::utils::InstrRepresentable;
+    use crate::utils::InstrRepresentable;
 
     use crate::assembler::instructions::{
         ITypeInstruction, JRTypeInstruction, JTypeInstruction, MemoryAccessTypeInstruction,
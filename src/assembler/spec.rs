@@ -0,0 +1,687 @@
+//! The instruction set as a table: the mnemonic, the format, the op code, the function
+//! code and the operand syntax of every instruction, along with the control signals the
+//! control unit sets for it and the operation of the ALU. The assembler, the decoder, the
+//! control unit of the simulator and the documentation printed by `darken-isa` are all
+//! derived from it, so an instruction is added by adding a row to `INSTRUCTIONS`.
+//!
+//! Fields are kept as they are encoded: branch offsets in words from the instruction
+//! after the branch and jump targets in words. The printed form uses bytes, as the
+//! assembler reads them.
+//!
+//! File: spec.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::instructions::get_register_name;
+use std::fmt;
+use ux::u5;
+
+/// How the operands of an instruction are laid out in its machine word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// op rs rt rd 0 funct, written `add $rd, $rs, $rt`
+    Register,
+    /// op 0 rt rd shamt funct, written `sll $rd, $rt, shamt`
+    Shift,
+    /// op rs rt imm with a sign extended immediate, written `addi $rt, $rs, imm`
+    Immediate,
+    /// op rs rt imm with a zero extended immediate, written `ori $rt, $rs, imm`
+    UnsignedImmediate,
+    /// op rs rt offset with the offset in words, written `beq $rs, $rt, offset`
+    Branch,
+    /// op base rt offset, written `lw $rt, offset($base)`
+    Memory,
+    /// op target with the target in words, written `j target`
+    Jump,
+    /// op rs 0 funct, written `jr $rs`
+    JumpRegister,
+    /// A single machine word without operands
+    Fixed(u32),
+}
+
+impl Format {
+    /// The name of the format in the documentation.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Register => "R",
+            Format::Shift => "R, shift",
+            Format::Immediate => "I",
+            Format::UnsignedImmediate => "I, zero extended",
+            Format::Branch => "I, branch",
+            Format::Memory => "I, memory",
+            Format::Jump => "J",
+            Format::JumpRegister => "R, jump",
+            Format::Fixed(_) => "fixed word",
+        }
+    }
+}
+
+/// A field of a machine word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Rs,
+    Rt,
+    Rd,
+    Shamt,
+    Funct,
+    Imm,
+    Target,
+}
+
+impl Field {
+    /// The highest and the lowest bit of the field in the machine word.
+    pub fn bits(&self) -> (usize, usize) {
+        match self {
+            Field::Rs => (25, 21),
+            Field::Rt => (20, 16),
+            Field::Rd => (15, 11),
+            Field::Shamt => (10, 6),
+            Field::Funct => (5, 0),
+            Field::Imm => (15, 0),
+            Field::Target => (25, 0),
+        }
+    }
+}
+
+impl Format {
+    /// has_field: whether instructions of this format are shown with a field. A fixed
+    /// word is shown split up like an R-type instruction.
+    ///
+    /// input: field - the field
+    /// returns: true if the field is part of the format
+    ///
+    pub fn has_field(&self, field: Field) -> bool {
+        match self {
+            Format::Register | Format::Shift | Format::Fixed(_) => {
+                matches!(field, Field::Rs | Field::Rt | Field::Rd | Field::Shamt | Field::Funct)
+            }
+            Format::Immediate | Format::UnsignedImmediate | Format::Branch | Format::Memory => {
+                matches!(field, Field::Rs | Field::Rt | Field::Imm)
+            }
+            Format::Jump => field == Field::Target,
+            Format::JumpRegister => matches!(field, Field::Rs | Field::Funct),
+        }
+    }
+}
+
+/// The control signals, as the bits of the output of the control unit.
+pub mod signals {
+    pub const ZERO_EXT: u16 = 1 << 13;
+    pub const EXIT: u16 = 1 << 12;
+    pub const SHIFT: u16 = 1 << 11;
+    pub const JUMP_REG: u16 = 1 << 10;
+    pub const JUMP: u16 = 1 << 9;
+    pub const REG_DST: u16 = 1 << 8;
+    pub const ALU_SRC: u16 = 1 << 7;
+    pub const MEM_TO_REG: u16 = 1 << 6;
+    pub const REG_WRITE: u16 = 1 << 5;
+    pub const MEM_READ: u16 = 1 << 4;
+    pub const MEM_WRITE: u16 = 1 << 3;
+    pub const BRANCH: u16 = 1 << 2;
+    pub const ALU_OP_1: u16 = 1 << 1;
+    pub const ALU_OP_0: u16 = 1;
+
+    /// The signals of the instructions that write the ALU result of two registers to rd
+    pub const R_TYPE: u16 = REG_DST | REG_WRITE | ALU_OP_1;
+
+    /// Every signal with its name, most significant bit first.
+    pub const NAMES: [(u16, &str); 14] = [
+        (ZERO_EXT, "ZeroExt"),
+        (EXIT, "Exit"),
+        (SHIFT, "Shift"),
+        (JUMP_REG, "JumpReg"),
+        (JUMP, "Jump"),
+        (REG_DST, "RegDst"),
+        (ALU_SRC, "ALUSrc"),
+        (MEM_TO_REG, "MemToReg"),
+        (REG_WRITE, "RegWrite"),
+        (MEM_READ, "MemRead"),
+        (MEM_WRITE, "MemWrite"),
+        (BRANCH, "Branch"),
+        (ALU_OP_1, "ALUOp1"),
+        (ALU_OP_0, "ALUOp0"),
+    ];
+}
+
+/// An operation of the ALU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AluOperation {
+    And,
+    Or,
+    Add,
+    Sub,
+    Slt,
+    Nor,
+    Sra,
+    Srl,
+    Sll,
+}
+
+impl AluOperation {
+    const ALL: [AluOperation; 9] = [
+        AluOperation::And,
+        AluOperation::Or,
+        AluOperation::Add,
+        AluOperation::Sub,
+        AluOperation::Slt,
+        AluOperation::Nor,
+        AluOperation::Sra,
+        AluOperation::Srl,
+        AluOperation::Sll,
+    ];
+
+    /// The 4 bit signal from the ALU control that selects the operation.
+    pub fn signal(&self) -> u8 {
+        match self {
+            AluOperation::And => 0,
+            AluOperation::Or => 1,
+            AluOperation::Add => 2,
+            AluOperation::Sub => 6,
+            AluOperation::Slt => 7,
+            AluOperation::Nor => 12,
+            AluOperation::Sra => 13,
+            AluOperation::Srl => 14,
+            AluOperation::Sll => 15,
+        }
+    }
+
+    /// The operation selected by an ALU control signal, none if no operation is.
+    pub fn from_signal(signal: u8) -> Option<AluOperation> {
+        AluOperation::ALL.into_iter().find(|operation| operation.signal() == signal)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AluOperation::And => "and",
+            AluOperation::Or => "or",
+            AluOperation::Add => "add",
+            AluOperation::Sub => "sub",
+            AluOperation::Slt => "slt",
+            AluOperation::Nor => "nor",
+            AluOperation::Sra => "sra",
+            AluOperation::Srl => "srl",
+            AluOperation::Sll => "sll",
+        }
+    }
+}
+
+/// A supported instruction.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InstructionSpec {
+    pub mnemonic: &'static str,
+    pub format: Format,
+    pub opcode: u8,
+    /// The function code of instructions with op code 0, otherwise 0
+    pub funct: u8,
+    /// How the instruction is written, used in hints
+    pub syntax: &'static str,
+    /// The control signals set for the instruction, the bits of `signals`
+    pub control: u16,
+    /// The operation of the ALU, selected by the ALU control from the ALUOp signals and
+    /// the function code
+    pub alu: AluOperation,
+}
+
+/// What kind of work an instruction does, for statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InstructionClass {
+    /// Computes a value in the ALU and writes it to a register
+    Alu,
+    Load,
+    Store,
+    Branch,
+    Jump,
+    /// Stops the program
+    Exit,
+}
+
+impl InstructionClass {
+    pub const ALL: [InstructionClass; 6] = [
+        InstructionClass::Alu,
+        InstructionClass::Load,
+        InstructionClass::Store,
+        InstructionClass::Branch,
+        InstructionClass::Jump,
+        InstructionClass::Exit,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InstructionClass::Alu => "ALU",
+            InstructionClass::Load => "load",
+            InstructionClass::Store => "store",
+            InstructionClass::Branch => "branch",
+            InstructionClass::Jump => "jump",
+            InstructionClass::Exit => "exit",
+        }
+    }
+}
+
+impl InstructionSpec {
+    /// The class of the instruction, from the control signals it sets.
+    pub fn class(&self) -> InstructionClass {
+        let set = |bits: u16| self.control & bits != 0;
+        if set(signals::MEM_READ) {
+            InstructionClass::Load
+        } else if set(signals::MEM_WRITE) {
+            InstructionClass::Store
+        } else if set(signals::BRANCH) {
+            InstructionClass::Branch
+        } else if set(signals::JUMP | signals::JUMP_REG) {
+            InstructionClass::Jump
+        } else if set(signals::EXIT) {
+            InstructionClass::Exit
+        } else {
+            InstructionClass::Alu
+        }
+    }
+
+    /// The names of the control signals set for the instruction.
+    pub fn control_names(&self) -> Vec<&'static str> {
+        signals::NAMES
+            .iter()
+            .filter(|(bit, _)| self.control & bit != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+const fn spec(
+    mnemonic: &'static str,
+    format: Format,
+    (opcode, funct): (u8, u8),
+    syntax: &'static str,
+    control: u16,
+    alu: AluOperation,
+) -> InstructionSpec {
+    InstructionSpec {
+        mnemonic,
+        format,
+        opcode,
+        funct,
+        syntax,
+        control,
+        alu,
+    }
+}
+
+use signals::*;
+use AluOperation::*;
+
+/// Every supported instruction. The fixed words come first, so that the word 0 is
+/// decoded as `nop` rather than as `sll $zero, $zero, 0`, which is how it is executed.
+#[rustfmt::skip]
+pub static INSTRUCTIONS: [InstructionSpec; 18] = [
+    spec("nop",  Format::Fixed(0),            (0, 0),   "nop",                   SHIFT | R_TYPE,                                       Sll),
+    spec("exit", Format::Fixed(0xffff_ffff),  (63, 63), "exit",                  EXIT,                                                 Add),
+    spec("add",  Format::Register,            (0, 32),  "add $rd, $rs, $rt",     R_TYPE,                                               Add),
+    spec("sub",  Format::Register,            (0, 34),  "sub $rd, $rs, $rt",     R_TYPE,                                               Sub),
+    spec("and",  Format::Register,            (0, 36),  "and $rd, $rs, $rt",     R_TYPE,                                               And),
+    spec("or",   Format::Register,            (0, 37),  "or $rd, $rs, $rt",      R_TYPE,                                               Or),
+    spec("nor",  Format::Register,            (0, 39),  "nor $rd, $rs, $rt",     R_TYPE,                                               Nor),
+    spec("slt",  Format::Register,            (0, 42),  "slt $rd, $rs, $rt",     R_TYPE,                                               Slt),
+    spec("sll",  Format::Shift,               (0, 0),   "sll $rd, $rt, shamt",   SHIFT | R_TYPE,                                       Sll),
+    spec("srl",  Format::Shift,               (0, 2),   "srl $rd, $rt, shamt",   SHIFT | R_TYPE,                                       Srl),
+    spec("sra",  Format::Shift,               (0, 3),   "sra $rd, $rt, shamt",   SHIFT | R_TYPE,                                       Sra),
+    spec("jr",   Format::JumpRegister,        (0, 8),   "jr $rs",                JUMP_REG,                                             Add),
+    spec("addi", Format::Immediate,           (8, 0),   "addi $rt, $rs, imm",    ALU_SRC | REG_WRITE,                                  Add),
+    spec("ori",  Format::UnsignedImmediate,   (13, 0),  "ori $rt, $rs, imm",     ZERO_EXT | ALU_SRC | REG_WRITE | ALU_OP_1 | ALU_OP_0, Or),
+    spec("beq",  Format::Branch,              (4, 0),   "beq $rs, $rt, label",   BRANCH | ALU_OP_0,                                    Sub),
+    spec("lw",   Format::Memory,              (35, 0),  "lw $rt, offset($base)", ALU_SRC | MEM_TO_REG | REG_WRITE | MEM_READ,          Add),
+    spec("sw",   Format::Memory,              (43, 0),  "sw $rt, offset($base)", ALU_SRC | MEM_WRITE,                                  Add),
+    spec("j",    Format::Jump,                (2, 0),   "j label",               JUMP,                                                 Add),
+];
+
+/// lookup: the instruction with the given mnemonic.
+///
+/// input: mnemonic - the mnemonic, e.g. `add`
+/// returns: the instruction, none if it is not supported
+///
+pub fn lookup(mnemonic: &str) -> Option<&'static InstructionSpec> {
+    INSTRUCTIONS.iter().find(|spec| spec.mnemonic == mnemonic)
+}
+
+/// find: the instruction with the given op code and, for op code 0, function code.
+/// Instructions that are a fixed word are not found this way.
+///
+/// input: opcode - the op code
+///        funct - the function code
+/// returns: the instruction, none if no supported instruction has these codes
+///
+pub fn find(opcode: u8, funct: u8) -> Option<&'static InstructionSpec> {
+    INSTRUCTIONS.iter().find(|spec| {
+        !matches!(spec.format, Format::Fixed(_)) && spec.opcode == opcode && (opcode != 0 || spec.funct == funct)
+    })
+}
+
+/// executed: the instruction the control unit decodes from an op code and a function
+/// code. Unlike `find`, this finds `exit` by its op code, while the word of `nop` is
+/// executed as `sll`.
+///
+/// input: opcode - the op code
+///        funct - the function code
+/// returns: the instruction, none if no supported instruction has these codes
+///
+pub fn executed(opcode: u8, funct: u8) -> Option<&'static InstructionSpec> {
+    find(opcode, funct).or_else(|| {
+        INSTRUCTIONS
+            .iter()
+            .find(|spec| matches!(spec.format, Format::Fixed(_)) && (spec.opcode, spec.funct) == (opcode, funct))
+    })
+}
+
+/// markdown_table: the instruction set as a markdown table, for the documentation.
+///
+/// returns: the table, one row per instruction
+///
+pub fn markdown_table() -> String {
+    let mut table = String::from(
+        "| Instruction | Syntax | Format | Op code | Function | Control signals | ALU |\n\
+         |---|---|---|---|---|---|---|\n",
+    );
+    for spec in &INSTRUCTIONS {
+        let codes = match spec.format {
+            Format::Fixed(word) => format!("`{:#010x}` | -", word),
+            _ if spec.opcode == 0 => format!("{} | {}", spec.opcode, spec.funct),
+            _ => format!("{} | -", spec.opcode),
+        };
+        table.push_str(&format!(
+            "| `{}` | `{}` | {} | {} | {} | {} |\n",
+            spec.mnemonic,
+            spec.syntax,
+            spec.format.name(),
+            codes,
+            spec.control_names().join(", "),
+            spec.alu.name()
+        ));
+    }
+    table
+}
+
+/// The operand fields of a machine word. Fields the format does not use are 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Fields {
+    pub rs: u8,
+    pub rt: u8,
+    pub rd: u8,
+    pub shamt: u8,
+    pub imm: u16,
+    /// The 26 bit jump target, in words
+    pub target: u32,
+}
+
+impl Fields {
+    /// Every field of a machine word, whether the instruction uses it or not.
+    fn of_word(word: u32) -> Fields {
+        Fields {
+            rs: (word >> 21 & 0x1f) as u8,
+            rt: (word >> 16 & 0x1f) as u8,
+            rd: (word >> 11 & 0x1f) as u8,
+            shamt: (word >> 6 & 0x1f) as u8,
+            imm: word as u16,
+            target: word & 0x3ff_ffff,
+        }
+    }
+}
+
+impl Format {
+    /// used_fields: the fields an instruction of this format has, cut to their width.
+    ///
+    /// input: fields - any values
+    /// returns: the fields with the ones the format does not use set to 0
+    ///
+    pub fn used_fields(&self, fields: Fields) -> Fields {
+        let register = |value: u8| value & 0x1f;
+        match self {
+            Format::Register => Fields {
+                rs: register(fields.rs),
+                rt: register(fields.rt),
+                rd: register(fields.rd),
+                ..Fields::default()
+            },
+            Format::Shift => Fields {
+                rt: register(fields.rt),
+                rd: register(fields.rd),
+                shamt: register(fields.shamt),
+                ..Fields::default()
+            },
+            Format::Immediate | Format::UnsignedImmediate | Format::Branch | Format::Memory => Fields {
+                rs: register(fields.rs),
+                rt: register(fields.rt),
+                imm: fields.imm,
+                ..Fields::default()
+            },
+            Format::Jump => Fields {
+                target: fields.target & 0x3ff_ffff,
+                ..Fields::default()
+            },
+            Format::JumpRegister => Fields {
+                rs: register(fields.rs),
+                ..Fields::default()
+            },
+            Format::Fixed(_) => Fields::default(),
+        }
+    }
+}
+
+/// An instruction and the values of its fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub spec: &'static InstructionSpec,
+    pub fields: Fields,
+}
+
+fn register_name(register: u8) -> String {
+    get_register_name(u5::new(register & 0x1f)).unwrap()
+}
+
+impl DecodedInstruction {
+    /// new: an instruction with the given fields, the ones its format does not use
+    /// dropped.
+    ///
+    /// input: spec - the instruction
+    ///        fields - the values of its fields
+    /// returns: the instruction
+    ///
+    pub fn new(spec: &'static InstructionSpec, fields: Fields) -> DecodedInstruction {
+        DecodedInstruction {
+            spec,
+            fields: spec.format.used_fields(fields),
+        }
+    }
+
+    /// encode: the machine word of the instruction.
+    pub fn encode(&self) -> u32 {
+        let f = &self.fields;
+        let op = (self.spec.opcode as u32) << 26;
+        let rs = (f.rs as u32) << 21;
+        let rt = (f.rt as u32) << 16;
+        let rd = (f.rd as u32) << 11;
+        let shamt = (f.shamt as u32) << 6;
+        let funct = self.spec.funct as u32;
+        match self.spec.format {
+            Format::Register => op | rs | rt | rd | funct,
+            Format::Shift => op | rt | rd | shamt | funct,
+            Format::Immediate | Format::UnsignedImmediate | Format::Branch | Format::Memory => {
+                op | rs | rt | f.imm as u32
+            }
+            Format::Jump => op | f.target,
+            Format::JumpRegister => op | rs | funct,
+            Format::Fixed(word) => word,
+        }
+    }
+
+    /// decode: the instruction a machine word encodes.
+    ///
+    /// input: word - the machine word
+    /// returns: the instruction, or none if the word is not a supported instruction or
+    ///          has bits set that the instruction does not use
+    ///
+    pub fn decode(word: u32) -> Option<DecodedInstruction> {
+        let fixed = INSTRUCTIONS
+            .iter()
+            .find(|spec| spec.format == Format::Fixed(word));
+        let spec = match fixed {
+            Some(spec) => spec,
+            None => find((word >> 26) as u8, (word & 0x3f) as u8)?,
+        };
+        let instruction = DecodedInstruction::new(spec, Fields::of_word(word));
+        match instruction.encode() == word {
+            true => Some(instruction),
+            false => None,
+        }
+    }
+
+    /// The offset of a branch in bytes from the instruction after it.
+    pub fn branch_offset(&self) -> i32 {
+        self.fields.imm as i16 as i32 * 4
+    }
+
+    /// The byte address a jump goes to.
+    pub fn jump_address(&self) -> u32 {
+        self.fields.target << 2
+    }
+
+    /// field_value: the value of a field of the instruction as it is shown, with the
+    /// immediate sign extended and branch offsets and jump targets in bytes.
+    ///
+    /// input: field - the field
+    /// returns: the value, none if the format of the instruction has no such field
+    ///
+    pub fn field_value(&self, field: Field) -> Option<i32> {
+        if !self.spec.format.has_field(field) {
+            return None;
+        }
+        let word = self.encode();
+        let (hi, lo) = field.bits();
+        let value = match (field, self.spec.format) {
+            (Field::Imm, Format::Branch) => self.branch_offset(),
+            (Field::Imm, _) => word as i16 as i32,
+            (Field::Target, _) => self.jump_address() as i32,
+            _ => (word >> lo & ((1 << (hi - lo + 1)) - 1)) as i32,
+        };
+        Some(value)
+    }
+
+    /// to_mnemonic_string: the instruction as it is written in assembly, with branch
+    /// offsets and jump targets as numbers of bytes.
+    ///
+    /// returns: the instruction in assembly
+    ///
+    pub fn to_mnemonic_string(&self) -> String {
+        let f = &self.fields;
+        let name = self.spec.mnemonic;
+        let (rs, rt, rd) = (register_name(f.rs), register_name(f.rt), register_name(f.rd));
+        match self.spec.format {
+            Format::Register => format!("{} {}, {}, {}", name, rd, rs, rt),
+            Format::Shift => format!("{} {}, {}, {}", name, rd, rt, f.shamt),
+            Format::Immediate => format!("{} {}, {}, {}", name, rt, rs, f.imm as i16),
+            Format::UnsignedImmediate => format!("{} {}, {}, {}", name, rt, rs, f.imm),
+            Format::Branch => format!("{} {}, {}, {}", name, rs, rt, self.branch_offset()),
+            Format::Memory => format!("{} {}, {}({})", name, rt, f.imm as i16, rs),
+            Format::Jump => format!("{} {}", name, self.jump_address()),
+            Format::JumpRegister => format!("{} {}", name, rs),
+            Format::Fixed(_) => name.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_mnemonic_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::instructions::Instruction;
+    use crate::utils::InstrRepresentable;
+    use proptest::prelude::*;
+
+    fn fields() -> impl Strategy<Value = Fields> {
+        (any::<u8>(), any::<u8>(), any::<u8>(), any::<u8>(), any::<u16>(), any::<u32>()).prop_map(
+            |(rs, rt, rd, shamt, imm, target)| Fields {
+                rs,
+                rt,
+                rd,
+                shamt,
+                imm,
+                target,
+            },
+        )
+    }
+
+    /// Any instruction, except the ones whose word is that of a fixed word instruction,
+    /// like `sll $zero, $zero, 0`, which is decoded as `nop`.
+    fn instruction() -> impl Strategy<Value = DecodedInstruction> {
+        (0..INSTRUCTIONS.len(), fields())
+            .prop_map(|(index, fields)| DecodedInstruction::new(&INSTRUCTIONS[index], fields))
+            .prop_filter("the word of a fixed word instruction", |instruction| {
+                matches!(instruction.spec.format, Format::Fixed(_))
+                    || INSTRUCTIONS.iter().all(|spec| spec.format != Format::Fixed(instruction.encode()))
+            })
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let table = markdown_table();
+        assert_eq!(table.lines().count(), INSTRUCTIONS.len() + 2);
+        assert!(table.contains(
+            "| `add` | `add $rd, $rs, $rt` | R | 0 | 32 | RegDst, RegWrite, ALUOp1 | add |\n"
+        ));
+        assert!(table.contains("| `lw` | `lw $rt, offset($base)` | I, memory | 35 | - | ALUSrc, MemToReg, RegWrite, MemRead | add |\n"));
+        assert!(table.contains("| `exit` | `exit` | fixed word | `0xffffffff` | - | Exit | add |\n"));
+
+        assert_eq!(lookup("nop").unwrap().class(), InstructionClass::Alu);
+        assert_eq!(lookup("lw").unwrap().class(), InstructionClass::Load);
+        assert_eq!(lookup("jr").unwrap().class(), InstructionClass::Jump);
+        assert_eq!(lookup("exit").unwrap().class(), InstructionClass::Exit);
+        assert_eq!(executed(63, 63).unwrap().mnemonic, "exit");
+        assert_eq!(executed(0, 0).unwrap().mnemonic, "sll");
+        assert_eq!(executed(0, 1), None);
+        assert_eq!(AluOperation::from_signal(15), Some(AluOperation::Sll));
+        assert_eq!(AluOperation::from_signal(3), None);
+    }
+
+    #[test]
+    fn test_table() {
+        for spec in &INSTRUCTIONS {
+            assert_eq!(lookup(spec.mnemonic), Some(spec));
+            assert!(spec.syntax.starts_with(spec.mnemonic));
+            if let Format::Fixed(word) = spec.format {
+                assert_eq!(DecodedInstruction::decode(word).unwrap().spec, spec);
+            } else {
+                assert_eq!(find(spec.opcode, spec.funct), Some(spec));
+            }
+        }
+        let beq = DecodedInstruction::decode(0x1109fffb).unwrap();
+        assert_eq!(beq.to_mnemonic_string(), "beq $t0, $t1, -20");
+        assert_eq!(DecodedInstruction::decode(0x3409ff61).unwrap().to_string(), "ori $t1, $zero, 65377");
+        assert_eq!(DecodedInstruction::decode(0x08000003).unwrap().to_string(), "j 12");
+        assert_eq!(DecodedInstruction::decode(0x00094080).unwrap().to_string(), "sll $t0, $t1, 2");
+        assert!(DecodedInstruction::decode(0x01000408).is_none());
+        assert!(DecodedInstruction::decode(0xfc000000).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn decode_inverts_encode(instruction in instruction()) {
+            prop_assert_eq!(DecodedInstruction::decode(instruction.encode()), Some(instruction));
+        }
+
+        #[test]
+        fn encode_inverts_decode(word in any::<u32>()) {
+            if let Some(instruction) = DecodedInstruction::decode(word) {
+                prop_assert_eq!(instruction.encode(), word);
+            }
+        }
+
+        /// The instruction types the assembler builds hold every field of an instruction,
+        /// so what they encode and print is what the table does.
+        #[test]
+        fn instruction_types_hold_every_field(instruction in instruction()) {
+            let typed = Instruction::decode(instruction.encode()).map(|typed| typed.decoded());
+            prop_assert_eq!(typed, Some(instruction));
+        }
+    }
+}
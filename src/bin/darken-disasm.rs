@@ -0,0 +1,40 @@
+//! Disassembles machine code: an ELF executable, a file of hexadecimal words as
+//! written by the assembler, Intel HEX records, a Logisim image or raw big-endian
+//! words. The output is assembly source that the assembler accepts.
+//!
+//! Usage: 'cargo run --bin darken-disasm path/to/file [more files ...]'
+//!
+//! File: darken-disasm.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use darken_assignment1::disassembler::disassemble_file;
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: 'cargo run --bin darken-disasm path/to/file [more files ...]'");
+        process::exit(1);
+    }
+
+    for (index, path) in paths.iter().enumerate() {
+        let disassembly = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| disassemble_file(&bytes));
+        match disassembly {
+            Ok(disassembly) => {
+                if index > 0 {
+                    println!();
+                }
+                println!("# {}", path);
+                print!("{}", disassembly.to_source());
+            }
+            Err(err) => {
+                eprintln!("error: cannot disassemble `{}`: {}", path, err);
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+//! Prints the supported instruction set as a markdown table: the syntax, the encoding,
+//! the control signals and the ALU operation of every instruction, as the assembler and
+//! the simulator use them.
+//!
+//! Usage: 'cargo run --bin darken-isa > instructions.md'
+//!
+//! File: darken-isa.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use darken_assignment1::assembler::spec::markdown_table;
+
+fn main() {
+    println!("# Instruction set\n");
+    print!("{}", markdown_table());
+}
@@ -0,0 +1,39 @@
+//! Prints the contents of object files written by the assembler with `-c`:
+//! their sections, symbols and relocations.
+//!
+//! Usage: 'cargo run --bin darken-objdump path/to/file.o [more.o ...]'
+//!
+//! File: darken-objdump.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use darken_assignment1::assembler::object::Object;
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: 'cargo run --bin darken-objdump path/to/file.o [more.o ...]'");
+        process::exit(1);
+    }
+
+    for (index, path) in paths.iter().enumerate() {
+        let object = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| Object::from_bytes(&bytes));
+        match object {
+            Ok(object) => {
+                if index > 0 {
+                    println!();
+                }
+                println!("{}:", path);
+                print!("{}", object.dump());
+            }
+            Err(err) => {
+                eprintln!("error: cannot read `{}`: {}", path, err);
+                process::exit(1);
+            }
+        }
+    }
+}
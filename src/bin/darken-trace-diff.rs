@@ -0,0 +1,43 @@
+//! Finds the first instruction where two execution traces differ, as written by the
+//! simulator with `-t`, e.g. the run of a student's program against the run of the
+//! reference solution. Exits with 1 if the traces differ.
+//!
+//! Usage: 'cargo run --bin darken-trace-diff expected.trace actual.trace'
+//!
+//! File: darken-trace-diff.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use darken_assignment1::trace::first_divergence;
+use std::env;
+use std::fs;
+use std::process;
+
+fn read(path: &str) -> String {
+    match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("error: cannot read `{}`: {}", path, err);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.len() != 2 {
+        eprintln!("Usage: 'cargo run --bin darken-trace-diff expected.trace actual.trace'");
+        process::exit(2);
+    }
+
+    match first_divergence(&read(&paths[0]), &read(&paths[1])) {
+        Ok(None) => println!("the traces are the same"),
+        Ok(Some(divergence)) => {
+            print!("{}", divergence);
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    }
+}
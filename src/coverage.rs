@@ -0,0 +1,238 @@
+//! The coverage of a run: which instructions were executed and which directions every
+//! branch went, mapped back to the source lines of the program through its listing. It
+//! is written as the listing with the counts in front of every line, and as an lcov
+//! tracefile for tools like `genhtml`.
+//!
+//! A source line holds more than one instruction when a macro is used on it, the line
+//! counts as executed as often as the most executed of them.
+//!
+//! File: coverage.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::listing::ListingLine;
+use crate::assembler::spec::{DecodedInstruction, InstructionClass};
+use std::collections::{BTreeMap, HashMap};
+
+/// How often a branch was taken and how often it was not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BranchCoverage {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+impl BranchCoverage {
+    /// The number of directions the branch went, out of 2.
+    pub fn directions(&self) -> u64 {
+        (self.taken > 0) as u64 + (self.not_taken > 0) as u64
+    }
+}
+
+/// The coverage of a line of a source file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct LineCoverage {
+    count: u64,
+    branches: Vec<BranchCoverage>,
+}
+
+/// The executions of every address and the directions of every branch.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Coverage {
+    executed: BTreeMap<u32, u64>,
+    branches: BTreeMap<u32, BranchCoverage>,
+}
+
+/// Whether the instruction is a branch, which can go two ways.
+fn is_branch(word: u32) -> bool {
+    DecodedInstruction::decode(word).is_some_and(|instruction| instruction.spec.class() == InstructionClass::Branch)
+}
+
+impl Coverage {
+    pub fn new() -> Coverage {
+        Coverage::default()
+    }
+
+    /// add: counts an execution of the instruction at an address.
+    ///
+    /// input: pc - the address of the instruction
+    ///        word - its machine word
+    ///        taken - whether the instruction branched, only used for branches
+    ///
+    pub fn add(&mut self, pc: u32, word: u32, taken: bool) {
+        *self.executed.entry(pc).or_insert(0) += 1;
+        if is_branch(word) {
+            let branch = self.branches.entry(pc).or_default();
+            match taken {
+                true => branch.taken += 1,
+                false => branch.not_taken += 1,
+            }
+        }
+    }
+
+    /// The number of times the instruction at an address was executed.
+    pub fn count(&self, address: u32) -> u64 {
+        self.executed.get(&address).copied().unwrap_or(0)
+    }
+
+    /// The directions of the branch on a line of the listing, none if the line holds no
+    /// branch.
+    fn branch(&self, line: &ListingLine) -> Option<BranchCoverage> {
+        let address = line.instruction_address()?;
+        match line.encoding.is_some_and(is_branch) {
+            true => Some(self.branches.get(&address).copied().unwrap_or_default()),
+            false => None,
+        }
+    }
+
+    /// summary: how much of the program was covered.
+    ///
+    /// input: source - the listing of the program
+    /// returns: the instructions executed, the instructions, the branch directions taken
+    ///          and the branch directions
+    ///
+    pub fn summary(&self, source: &[ListingLine]) -> (usize, usize, u64, u64) {
+        let addresses: Vec<u32> = source.iter().filter_map(|line| line.instruction_address()).collect();
+        let executed = addresses.iter().filter(|address| self.count(**address) > 0).count();
+        let branches: Vec<BranchCoverage> = source.iter().filter_map(|line| self.branch(line)).collect();
+        let directions = branches.iter().map(|branch| branch.directions()).sum();
+        (executed, addresses.len(), directions, 2 * branches.len() as u64)
+    }
+
+    /// The coverage of every source line with an instruction, by file in the order the
+    /// files first appear in the listing.
+    fn by_line(&self, source: &[ListingLine]) -> Vec<(String, BTreeMap<usize, LineCoverage>)> {
+        let mut files: Vec<(String, BTreeMap<usize, LineCoverage>)> = Vec::new();
+        let mut indices: HashMap<&str, usize> = HashMap::new();
+        for line in source {
+            let address = match line.instruction_address() {
+                Some(address) => address,
+                None => continue,
+            };
+            let index = *indices.entry(line.file.as_str()).or_insert_with(|| {
+                files.push((line.file.clone(), BTreeMap::new()));
+                files.len() - 1
+            });
+            let coverage = files[index].1.entry(line.line).or_default();
+            coverage.count = coverage.count.max(self.count(address));
+            coverage.branches.extend(self.branch(line));
+        }
+        files
+    }
+
+    /// annotated_listing: the listing of the program with the executions of every
+    /// instruction in front of its line, `#####` for the ones never executed, and the
+    /// directions of every branch.
+    ///
+    /// input: source - the listing of the program
+    /// returns: the listing, one line per source line
+    ///
+    pub fn annotated_listing(&self, source: &[ListingLine]) -> String {
+        let (executed, instructions, directions, branch_directions) = self.summary(source);
+        let mut listing = format!(
+            "Coverage: {}/{} instructions, {}/{} branch directions\n{:>8}  {:<24}  source\n",
+            executed, instructions, directions, branch_directions, "count", "branch"
+        );
+        for line in source {
+            let count = match line.instruction_address().map(|address| self.count(address)) {
+                Some(0) => "#####".to_string(),
+                Some(count) => count.to_string(),
+                None => String::new(),
+            };
+            let branch = self.branch(line).map_or(String::new(), |branch| {
+                format!("{} taken, {} not taken", branch.taken, branch.not_taken)
+            });
+            listing.push_str(format!("{:>8}  {:<24}  {}", count, branch, line.source).trim_end());
+            listing.push('\n');
+        }
+        listing
+    }
+
+    /// lcov: the coverage as an lcov tracefile, with a record per source file, a `DA`
+    /// line per source line with an instruction and two `BRDA` lines per branch, for the
+    /// branch being taken and not being taken.
+    ///
+    /// input: source - the listing of the program
+    /// returns: the tracefile
+    ///
+    pub fn lcov(&self, source: &[ListingLine]) -> String {
+        let mut lcov = String::new();
+        for (file, lines) in self.by_line(source) {
+            lcov.push_str(&format!("TN:\nSF:{}\n", file));
+            let (mut found, mut hit) = (0, 0);
+            for (number, line) in &lines {
+                for (block, branch) in line.branches.iter().enumerate() {
+                    for (direction, count) in [branch.taken, branch.not_taken].into_iter().enumerate() {
+                        // lcov writes `-` for branches on lines that never executed
+                        let count = match line.count {
+                            0 => "-".to_string(),
+                            _ => count.to_string(),
+                        };
+                        lcov.push_str(&format!("BRDA:{},{},{},{}\n", number, block, direction, count));
+                    }
+                    found += 2;
+                    hit += branch.directions();
+                }
+            }
+            lcov.push_str(&format!("BRF:{}\nBRH:{}\n", found, hit));
+            for (number, line) in &lines {
+                lcov.push_str(&format!("DA:{},{}\n", number, line.count));
+            }
+            let executed = lines.values().filter(|line| line.count > 0).count();
+            lcov.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", lines.len(), executed));
+        }
+        lcov
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, AssemblerOptions};
+    use crate::interpreter::{Interpreter, Status};
+    use crate::loader::MemoryImage;
+
+    #[test]
+    fn test_coverage() {
+        let source = "addi $t0, $zero, 2\nloop: addi $t0, $t0, -1\nbeq $t0, $zero, done\nj loop\n\
+                      addi $t1, $zero, 1\n# always taken\ndone: beq $zero, $zero, end\naddi $t1, $zero, 2\n\
+                      end: exit\n";
+        let image = MemoryImage::from(assemble(source, &AssemblerOptions::default()).unwrap());
+        let mut interpreter = Interpreter::new(&image);
+        let mut coverage = Coverage::new();
+        loop {
+            let pc = interpreter.pc();
+            let (record, status) = interpreter.step().unwrap();
+            let record = record.unwrap();
+            coverage.add(record.pc, record.word, interpreter.pc() != pc + 4);
+            if status == Status::Exited {
+                break;
+            }
+        }
+
+        assert_eq!(coverage.count(4), 2);
+        assert_eq!(coverage.count(16), 0);
+        assert_eq!(coverage.summary(&image.source), (6, 8, 3, 4));
+
+        let listing = coverage.annotated_listing(&image.source);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines[0], "Coverage: 6/8 instructions, 3/4 branch directions");
+        assert_eq!(lines[4], "       2  1 taken, 1 not taken      beq $t0, $zero, done");
+        assert_eq!(lines[6], "   #####                            addi $t1, $zero, 1");
+        assert_eq!(lines[7], "                                    # always taken");
+        assert_eq!(lines[8], "       1  1 taken, 0 not taken      done: beq $zero, $zero, end");
+
+        let file = &image.source[0].file;
+        assert_eq!(
+            coverage.lcov(&image.source),
+            format!(
+                "TN:\nSF:{}\nBRDA:3,0,0,1\nBRDA:3,0,1,1\nBRDA:7,0,0,1\nBRDA:7,0,1,0\nBRF:4\nBRH:3\n\
+                 DA:1,1\nDA:2,2\nDA:3,2\nDA:4,1\nDA:5,0\nDA:7,1\nDA:8,0\nDA:9,1\nLF:8\nLH:6\nend_of_record\n",
+                file
+            )
+        );
+
+        // the branches of lines that never executed are written as `-`
+        let lcov = Coverage::new().lcov(&image.source);
+        assert!(lcov.contains("BRDA:3,0,0,-\nBRDA:3,0,1,-\n"), "{}", lcov);
+        assert!(lcov.ends_with("LF:8\nLH:0\nend_of_record\n"));
+    }
+}
@@ -0,0 +1,399 @@
+//! Turns machine code back into assembly. Words are decoded into instructions, and every
+//! branch or jump target inside the disassembled code gets a label, taken from the symbol
+//! table when there is one and made up from the address otherwise. The result can be
+//! printed as assembly source or loaded into the simulator.
+//!
+//! File: disassembler.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::formats;
+use crate::assembler::instructions::Instruction;
+use crate::assembler::object::Section;
+use crate::elf;
+use crate::loader::{self, MemoryImage};
+use crate::utils::InstrRepresentable;
+use std::collections::{BTreeMap, HashMap};
+
+const OP_BEQ: u32 = 4;
+const OP_J: u32 = 2;
+
+/// Disassembled machine code.
+#[derive(Debug)]
+pub struct Disassembly {
+    /// The address of the first word.
+    pub base: u32,
+    /// The disassembled words, along with their instruction or none if the word
+    /// is not an instruction the simulator supports.
+    pub words: Vec<(u32, Option<Instruction>)>,
+    /// The labels of the disassembled code by address.
+    pub labels: BTreeMap<u32, String>,
+    /// The address of the first instruction to run.
+    pub entry: u32,
+    /// The initial contents of data memory and its address, if the code came with data.
+    pub data: Option<(u32, Vec<u8>)>,
+}
+
+/// disassemble_word: the instruction a machine word encodes.
+///
+/// input: word - the machine word
+/// returns: the instruction, or none if the word is not a supported instruction
+///
+pub fn disassemble_word(word: u32) -> Option<Box<dyn InstrRepresentable>> {
+    Instruction::decode(word).map(|instruction| Box::new(instruction) as Box<dyn InstrRepresentable>)
+}
+
+/// disassemble: decodes the words placed from address `base` and labels every branch
+/// and jump target among them.
+///
+/// input: words - the machine code
+///        base - the address of the first word
+///        symbols - names for addresses, used instead of made up labels
+/// returns: the disassembly
+///
+pub fn disassemble(words: &[u32], base: u32, symbols: &HashMap<u32, String>) -> Disassembly {
+    let end = base as u64 + words.len() as u64 * 4;
+    let mut labels = BTreeMap::new();
+    for (addr, name) in symbols {
+        if (base as u64..end).contains(&(*addr as u64)) {
+            labels.insert(*addr, name.clone());
+        }
+    }
+    let words: Vec<(u32, Option<Instruction>)> =
+        words.iter().map(|word| (*word, Instruction::decode(*word))).collect();
+    for (index, (word, instruction)) in words.iter().enumerate() {
+        let addr = base.wrapping_add(index as u32 * 4);
+        let target = match instruction {
+            Some(_) => target(*word, addr),
+            None => None,
+        };
+        if let Some(target) = target.filter(|target| (base as u64..end).contains(&(*target as u64))) {
+            labels.entry(target).or_insert_with(|| format!("L_{:08x}", target));
+        }
+    }
+    Disassembly {
+        base,
+        words,
+        labels,
+        entry: base,
+        data: None,
+    }
+}
+
+/// The address a branch or jump at `addr` goes to, none for other instructions.
+fn target(word: u32, addr: u32) -> Option<u32> {
+    let next = addr.wrapping_add(4);
+    match word >> 26 {
+        OP_BEQ => Some(next.wrapping_add(((word as u16 as i16 as i32) << 2) as u32)),
+        OP_J => Some((next & 0xf000_0000) | ((word & 0x3ff_ffff) << 2)),
+        _ => None,
+    }
+}
+
+impl Disassembly {
+    /// The address of the word at `index`.
+    pub fn address(&self, index: usize) -> u32 {
+        self.base.wrapping_add(index as u32 * 4)
+    }
+
+    /// instruction_text: the assembly of the word at `index`, with branch and jump
+    /// targets written as labels where there is one.
+    ///
+    /// input: index - the index of the word
+    /// returns: the instruction, or a `.word` directive if the word is not an instruction
+    ///
+    pub fn instruction_text(&self, index: usize) -> String {
+        let (word, instruction) = &self.words[index];
+        let instruction = match instruction {
+            Some(instruction) => instruction,
+            None => return format!(".word {:#010x}", word),
+        };
+        let target = match target(*word, self.address(index)) {
+            Some(target) => target,
+            None => return instruction.to_mnemonic_string(),
+        };
+        let target = match self.labels.get(&target) {
+            Some(label) => label.clone(),
+            None => target.to_string(),
+        };
+        match word >> 26 {
+            OP_BEQ => format!(
+                "beq {}, {}, {}",
+                register_name(word >> 21),
+                register_name(word >> 16),
+                target
+            ),
+            _ => format!("j {}", target),
+        }
+    }
+
+    /// to_source: the disassembly as assembly source, with the address and encoding of
+    /// every instruction in a comment.
+    ///
+    /// returns: the assembly source
+    ///
+    pub fn to_source(&self) -> String {
+        let mut source = String::new();
+        for index in 0..self.words.len() {
+            let addr = self.address(index);
+            if let Some(label) = self.labels.get(&addr) {
+                source.push_str(&format!("{}:\n", label));
+            }
+            let text = self.instruction_text(index);
+            source.push_str(&format!("\t{:<28}# {:#010x}: {:08x}\n", text, addr, self.words[index].0));
+        }
+        source
+    }
+
+    /// into_image: the disassembled program, ready to be loaded into the simulator.
+    ///
+    /// returns: the image, or an error naming the first word that is not an instruction
+    ///
+    pub fn into_image(self) -> Result<MemoryImage, String> {
+        let base = self.base;
+        let mut text = Vec::new();
+        for (index, (word, instruction)) in self.words.into_iter().enumerate() {
+            let addr = base.wrapping_add(index as u32 * 4);
+            text.push(instruction.ok_or_else(|| {
+                format!("unsupported instruction {:#010x} at address {:#010x}", word, addr)
+            })?);
+        }
+        let (data_base, data) = self.data.unwrap_or((0, Vec::new()));
+        let mut symbols = HashMap::new();
+        let mut label_sections = HashMap::new();
+        for (addr, name) in self.labels {
+            symbols.insert(name.clone(), addr);
+            label_sections.insert(name, Section::Text);
+        }
+        Ok(MemoryImage {
+            text_base: base,
+            text,
+            traps: HashMap::new(),
+            data_base,
+            data,
+            entry: self.entry,
+            symbols,
+            label_sections,
+            source: Vec::new(),
+        })
+    }
+}
+
+/// The name of the register in the low 5 bits of `bits`.
+fn register_name(bits: u32) -> String {
+    crate::assembler::get_register_name(ux::u5::new((bits & 0x1f) as u8)).unwrap()
+}
+
+/// read_words: reads machine code from a file, either text with one hexadecimal word
+/// on each line, as written by the assembler or for `$readmemh`, a Logisim `v2.0 raw`
+/// image, Intel HEX records, or raw big-endian words.
+///
+/// input: bytes - the contents of the file
+/// returns: the address of the first word, which is 0 unless the file has addresses,
+///          and the words, or an error if the file is in none of these formats
+///
+pub fn read_words(bytes: &[u8]) -> Result<(u32, Vec<u32>), String> {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        if text.trim_start().starts_with(':') {
+            let (base, bytes) = formats::read_intel_hex(text)?;
+            if base % 4 != 0 {
+                return Err(format!("the Intel HEX records start at {:#010x}, which is not word aligned", base));
+            }
+            return Ok((base, formats::to_words(&bytes)));
+        }
+        if let Some(words) = read_hex_words(text) {
+            return Ok((0, words));
+        }
+    }
+    if !bytes.len().is_multiple_of(4) {
+        return Err(format!(
+            "not a file of hexadecimal words, and its {} bytes are not a whole number of words",
+            bytes.len()
+        ));
+    }
+    Ok((0, formats::to_words(bytes)))
+}
+
+/// Reads hexadecimal words separated by whitespace, with an optional `0x` prefix.
+/// Comments start with `#` or `//`, and Logisim's `count*word` repeats a word.
+/// Returns: none if the text is not made of such words
+fn read_hex_words(text: &str) -> Option<Vec<u32>> {
+    let mut words = Vec::new();
+    let mut lines = text.lines().peekable();
+    if lines.peek().map(|line| line.trim()) == Some("v2.0 raw") {
+        lines.next();
+    }
+    for line in lines {
+        let line = line.split('#').next()?.split("//").next()?;
+        for token in line.split_whitespace() {
+            let (count, word) = match token.split_once('*') {
+                Some((count, word)) => (count.parse::<usize>().ok()?, word),
+                None => (1, token),
+            };
+            let digits = word.trim_start_matches("0x").trim_start_matches("0X");
+            let word = u32::from_str_radix(digits, 16).ok()?;
+            words.extend(std::iter::repeat_n(word, count));
+        }
+    }
+    match words.is_empty() {
+        true => None,
+        false => Some(words),
+    }
+}
+
+/// load_machine_code: the program in files of machine code, such as the instruction
+/// and data output of the assembler, with the instructions and data both at address 0.
+///
+/// input: text - the contents of the file with the instructions
+///        data - the contents of the file with the initial data memory, if there is one
+/// returns: the program, or an error if a file cannot be read or has a word that is
+///          not an instruction
+///
+pub fn load_machine_code(text: &[u8], data: Option<&[u8]>) -> Result<MemoryImage, String> {
+    let (base, words) = read_words(text)?;
+    let mut disassembly = disassemble(&words, base, &HashMap::new());
+    if let Some(data) = data {
+        let (base, words) = read_words(data)?;
+        let bytes = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+        disassembly.data = Some((base, bytes));
+    }
+    disassembly.into_image()
+}
+
+/// disassemble_file: disassembles an ELF executable or a file of machine code. The
+/// text segment and symbols of an ELF executable are used, other files are placed at address 0.
+///
+/// input: bytes - the contents of the file
+/// returns: the disassembly, or an error if the file cannot be read
+///
+pub fn disassemble_file(bytes: &[u8]) -> Result<Disassembly, String> {
+    if !loader::is_elf(bytes) {
+        let (base, words) = read_words(bytes)?;
+        return Ok(disassemble(&words, base, &HashMap::new()));
+    }
+    let executable = elf::read_executable(bytes)?;
+    let text = executable
+        .segments
+        .iter()
+        .find(|segment| segment.executable)
+        .ok_or_else(|| "the executable has no executable segment".to_string())?;
+    let words: Vec<u32> = formats::to_words(&text.bytes);
+    let mut names: Vec<_> = executable
+        .symbols
+        .iter()
+        .filter(|symbol| symbol.section == Section::Text)
+        .collect();
+    names.sort_by(|a, b| (a.value, &a.name).cmp(&(b.value, &b.name)));
+    let mut symbols = HashMap::new();
+    for symbol in names {
+        symbols.entry(symbol.value).or_insert_with(|| symbol.name.clone());
+    }
+    let mut disassembly = disassemble(&words, text.vaddr, &symbols);
+    disassembly.entry = executable.entry;
+    let data_segment = executable.segments.iter().find(|segment| !segment.executable);
+    disassembly.data = data_segment.map(|segment| (segment.vaddr, segment.bytes.clone()));
+    Ok(disassembly)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, AssemblerOptions};
+
+    #[test]
+    fn test_disassemble_word() {
+        let instruction = disassemble_word(0x012a_4020).unwrap();
+        assert_eq!(instruction.to_mnemonic_string(), "add $t0, $t1, $t2");
+        assert!(disassemble_word(0xfc00_0000).is_none());
+    }
+
+    #[test]
+    fn test_disassembly_reassembles() {
+        let source = "main: addi $t0, $zero, 3\n\
+                      loop: beq $t0, $zero, done\n\
+                      \taddi $t0, $t0, -1\n\
+                      \tsub $t1, $t1, $t0\n\
+                      \tor $t2, $t1, $t0\n\
+                      \tslt $t3, $t1, $t2\n\
+                      \tsw $t1, 8($sp)\n\
+                      \tlw $t1, -4($t2)\n\
+                      \tj loop\n\
+                      \tjr $ra\n\
+                      \tnop\n\
+                      done: exit\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let words: Vec<u32> = program.text.iter().map(|instruction| instruction.encode()).collect();
+
+        let disassembly = disassemble(&words, 0, &HashMap::new());
+        let labels: Vec<&String> = disassembly.labels.values().collect();
+        assert_eq!(labels, vec!["L_00000004", "L_0000002c"]);
+        assert_eq!(disassembly.instruction_text(1), "beq $t0, $zero, L_0000002c");
+
+        let reassembled = assemble(&disassembly.to_source(), &AssemblerOptions::default()).unwrap();
+        assert_eq!(reassembled.instruction_output(), program.instruction_output());
+    }
+
+    #[test]
+    fn test_targets_outside_the_code() {
+        // beq back to address 0 and j to 0x40, from code placed at 8
+        let disassembly = disassemble(&[0x1000_fffd, 0x0800_0010, 0xfc00_0000], 8, &HashMap::new());
+        assert!(disassembly.labels.is_empty());
+        assert_eq!(disassembly.instruction_text(0), "beq $zero, $zero, 0");
+        assert_eq!(disassembly.instruction_text(1), "j 64");
+        assert_eq!(disassembly.instruction_text(2), ".word 0xfc000000");
+        assert_eq!(
+            disassembly.into_image().unwrap_err(),
+            "unsupported instruction 0xfc000000 at address 0x00000010"
+        );
+    }
+
+    #[test]
+    fn test_read_words() {
+        let lines = b"0x20080003\n# a comment\n0xFFFFFFFF\n";
+        assert_eq!(read_words(lines).unwrap(), (0, vec![0x2008_0003, 0xffff_ffff]));
+        assert_eq!(read_words(b"v2.0 raw\n2*1 ff\n").unwrap(), (0, vec![1, 1, 0xff]));
+        assert_eq!(read_words(&[0x20, 0x08, 0, 3]).unwrap(), (0, vec![0x2008_0003]));
+        assert!(read_words(&[0x20, 0x08, 0]).is_err());
+        // Intel HEX as the assembler writes it, with the address of its first record
+        let hex = formats::OutputFormat::IntelHex.write(&[0x2008_0003, 0xffff_ffff], 0x40);
+        assert_eq!(read_words(&hex).unwrap(), (0x40, vec![0x2008_0003, 0xffff_ffff]));
+        assert!(read_words(b":0400000020080003D0\n").unwrap_err().ends_with("wrong checksum"));
+        assert!(read_words(b":01000200FFFE\n").unwrap_err().ends_with("which is not word aligned"));
+    }
+
+    #[test]
+    fn test_load_machine_code() {
+        let source = "main: addi $t0, $zero, 1\n\tj main\n\t.data\n\t.word 7, -1\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let text = program.instruction_output();
+        let data = program.data_output(formats::OutputFormat::BinaryBigEndian);
+        let image = load_machine_code(text.as_bytes(), Some(&data)).unwrap();
+        let words: Vec<u32> = image.text.iter().map(|instruction| instruction.encode()).collect();
+        assert_eq!(words, vec![0x2008_0001, 0x0800_0000]);
+        assert_eq!(image.data, vec![0, 0, 0, 7, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(image.symbols.get("L_00000000"), Some(&0));
+        // the program read back from the Intel HEX output of the assembler
+        let text = program.text_output(formats::OutputFormat::IntelHex);
+        let data = program.data_output(formats::OutputFormat::IntelHex);
+        let image = load_machine_code(&text, Some(&data)).unwrap();
+        assert_eq!(image.text.len(), 2);
+        assert_eq!(image.data, vec![0, 0, 0, 7, 0xff, 0xff, 0xff, 0xff]);
+
+        assert!(load_machine_code(b"0x20080001\n0xfc000000\n", None)
+            .unwrap_err()
+            .starts_with("unsupported instruction 0xfc000000"));
+    }
+
+    #[test]
+    fn test_disassemble_elf() {
+        let disassembly = disassemble_file(include_bytes!("../test_files/elf/sum.elf")).unwrap();
+        assert_eq!(disassembly.entry, 0x0040_0004);
+        assert_eq!(disassembly.labels.get(&0x0040_000c), Some(&"loop".to_string()));
+        assert_eq!(disassembly.instruction_text(7), "beq $zero, $zero, loop");
+        assert_eq!(disassembly.instruction_text(10), "j finish");
+        assert!(disassembly.to_source().starts_with("skipped:\n\taddi $t3, $zero, 1"));
+
+        let image = disassembly.into_image().unwrap();
+        assert_eq!(image.data_base, 0x1000);
+        assert_eq!(image.data, vec![0, 0, 0, 10, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,523 @@
+//! Reads and writes 32-bit big-endian MIPS ELF executables, such as the freestanding
+//! programs built with a MIPS cross toolchain. Only what is needed to run a program is
+//! read: the entry point, the loadable segments and the labels in the symbol table.
+//! Written executables have a `.text` and `.data` section for their segments along with
+//! `.symtab`, `.strtab` and `.shstrtab`, so they can be inspected with binutils.
+//!
+//! File: elf.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::object::Section;
+use crate::loader::MAX_DATA_SIZE;
+use binread::io::{Cursor, Seek, SeekFrom};
+use binread::{BinRead, BinReaderExt};
+
+const ELFCLASS32: u8 = 1;
+const ELFDATA2MSB: u8 = 2;
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const EM_MIPS: u16 = 8;
+const EV_CURRENT: u8 = 1;
+/// The most bytes all the loadable segments of an executable may take up in memory: an
+/// executable segment and the data segments, each at most `MAX_DATA_SIZE`.
+const MAX_LOADED_SIZE: u64 = 2 * MAX_DATA_SIZE as u64;
+const EF_MIPS_ARCH_32_O32: u32 = 0x5000_1000;
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHF_WRITE: u32 = 1;
+const SHF_ALLOC: u32 = 2;
+const SHF_EXECINSTR: u32 = 4;
+const SHN_UNDEF: u16 = 0;
+const SHN_LORESERVE: u16 = 0xff00;
+const SHN_ABS: u16 = 0xfff1;
+const STT_NOTYPE: u8 = 0;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+const FILE_HEADER_SIZE: usize = 52;
+const PROGRAM_HEADER_SIZE: usize = 32;
+const SECTION_HEADER_SIZE: usize = 40;
+const SYMBOL_SIZE: usize = 16;
+
+/// The ELF file header, `Elf32_Ehdr`.
+#[derive(BinRead, Debug)]
+#[br(big, magic = b"\x7fELF")]
+struct FileHeader {
+    class: u8,
+    data: u8,
+    #[br(pad_after = 9)]
+    _ident_version: u8,
+    file_type: u16,
+    machine: u16,
+    _version: u32,
+    entry: u32,
+    program_header_offset: u32,
+    section_header_offset: u32,
+    _flags: u32,
+    _header_size: u16,
+    _program_header_size: u16,
+    program_header_count: u16,
+    _section_header_size: u16,
+    section_header_count: u16,
+    _section_names_index: u16,
+}
+
+/// A program header, `Elf32_Phdr`.
+#[derive(BinRead, Debug)]
+#[br(big)]
+struct ProgramHeader {
+    segment_type: u32,
+    offset: u32,
+    vaddr: u32,
+    _paddr: u32,
+    file_size: u32,
+    memory_size: u32,
+    flags: u32,
+    _align: u32,
+}
+
+/// A section header, `Elf32_Shdr`.
+#[derive(BinRead, Debug)]
+#[br(big)]
+struct SectionHeader {
+    _name: u32,
+    section_type: u32,
+    flags: u32,
+    _addr: u32,
+    offset: u32,
+    size: u32,
+    link: u32,
+    _info: u32,
+    _align: u32,
+    entry_size: u32,
+}
+
+/// A symbol table entry, `Elf32_Sym`.
+#[derive(BinRead, Debug)]
+#[br(big)]
+struct Symbol {
+    name: u32,
+    value: u32,
+    _size: u32,
+    info: u8,
+    _other: u8,
+    section_index: u16,
+}
+
+/// A loadable segment of an executable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// The address the segment is loaded at.
+    pub vaddr: u32,
+    /// The contents of the segment in memory, zero filled past the end of its contents in the file.
+    pub bytes: Vec<u8>,
+    /// Whether the segment holds instructions.
+    pub executable: bool,
+}
+
+/// A label in the symbol table of an executable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfSymbol {
+    pub name: String,
+    /// The address the label refers to.
+    pub value: u32,
+    /// `Text` if the label is in a section holding instructions, otherwise `Data`.
+    pub section: Section,
+}
+
+/// A 32-bit big-endian MIPS executable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfExecutable {
+    /// The address of the first instruction to run.
+    pub entry: u32,
+    /// The `PT_LOAD` segments, in the order of the program headers.
+    pub segments: Vec<Segment>,
+    /// The labels of the symbol table, in the order of the table. Empty if the symbol table is stripped.
+    pub symbols: Vec<ElfSymbol>,
+}
+
+/// read_executable: reads a 32-bit big-endian MIPS ELF executable.
+///
+/// input: bytes - the contents of the ELF file
+/// returns: the executable, or an error describing why it cannot be run
+///
+pub fn read_executable(bytes: &[u8]) -> Result<ElfExecutable, String> {
+    let mut reader = Cursor::new(bytes);
+    let header: FileHeader = reader
+        .read_be()
+        .map_err(|err| format!("not an ELF file: {}", err))?;
+    if header.class != ELFCLASS32 {
+        return Err("not a 32-bit ELF file".to_string());
+    }
+    if header.data != ELFDATA2MSB {
+        return Err("not a big-endian ELF file".to_string());
+    }
+    if header.machine != EM_MIPS {
+        return Err(format!("not a MIPS ELF file, the machine is {}", header.machine));
+    }
+    match header.file_type {
+        ET_EXEC => (),
+        ET_REL => return Err("the ELF file is a relocatable object, link it into an executable first".to_string()),
+        other => return Err(format!("not an ELF executable, the file type is {}", other)),
+    }
+
+    let program_headers: Vec<ProgramHeader> = read_table(
+        &mut reader,
+        header.program_header_offset,
+        header.program_header_count,
+    )
+    .map_err(|err| format!("failed to read the program headers: {}", err))?;
+    let mut segments = Vec::new();
+    let mut loaded_size = 0;
+    for program_header in program_headers {
+        if program_header.segment_type != PT_LOAD || program_header.memory_size == 0 {
+            continue;
+        }
+        if program_header.file_size > program_header.memory_size {
+            return Err(format!(
+                "the segment at {:#010x} is larger in the file than in memory",
+                program_header.vaddr
+            ));
+        }
+        // checked before the segment is allocated, so an executable cannot claim gigabytes
+        loaded_size += program_header.memory_size as u64;
+        if program_header.memory_size > MAX_DATA_SIZE {
+            return Err(format!(
+                "the segment at {:#010x} takes up {} bytes in memory, but a segment may take up at most {}",
+                program_header.vaddr, program_header.memory_size, MAX_DATA_SIZE
+            ));
+        }
+        if loaded_size > MAX_LOADED_SIZE {
+            return Err(format!(
+                "the segments take up more than {} bytes in memory",
+                MAX_LOADED_SIZE
+            ));
+        }
+        let mut contents = slice(bytes, program_header.offset, program_header.file_size)
+            .ok_or_else(|| format!("the segment at {:#010x} is outside of the file", program_header.vaddr))?
+            .to_vec();
+        contents.resize(program_header.memory_size as usize, 0);
+        segments.push(Segment {
+            vaddr: program_header.vaddr,
+            bytes: contents,
+            executable: program_header.flags & PF_X != 0,
+        });
+    }
+
+    let section_headers: Vec<SectionHeader> = read_table(
+        &mut reader,
+        header.section_header_offset,
+        header.section_header_count,
+    )
+    .map_err(|err| format!("failed to read the section headers: {}", err))?;
+    let symbols = read_symbols(bytes, &section_headers)?;
+
+    Ok(ElfExecutable {
+        entry: header.entry,
+        segments,
+        symbols,
+    })
+}
+
+/// Reads `count` consecutive entries of a table starting at `offset` in the file.
+fn read_table<T: BinRead<Args = ()>>(
+    reader: &mut Cursor<&[u8]>,
+    offset: u32,
+    count: u16,
+) -> binread::BinResult<Vec<T>> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    reader.seek(SeekFrom::Start(offset as u64))?;
+    (0..count).map(|_| reader.read_be()).collect()
+}
+
+/// The `size` bytes at `offset` in the file, or none if they are not all in the file.
+fn slice(bytes: &[u8], offset: u32, size: u32) -> Option<&[u8]> {
+    let start = offset as usize;
+    bytes.get(start..start.checked_add(size as usize)?)
+}
+
+/// Collects the labels in the symbol table, that is the named symbols that are
+/// defined in a section and are not the names of sections or files.
+fn read_symbols(bytes: &[u8], section_headers: &[SectionHeader]) -> Result<Vec<ElfSymbol>, String> {
+    let mut symbols = Vec::new();
+    let symbol_table = match section_headers.iter().find(|s| s.section_type == SHT_SYMTAB) {
+        Some(symbol_table) => symbol_table,
+        None => return Ok(symbols),
+    };
+    let invalid = || "the symbol table is outside of the file".to_string();
+    let string_table = section_headers.get(symbol_table.link as usize).ok_or_else(invalid)?;
+    let names = slice(bytes, string_table.offset, string_table.size).ok_or_else(invalid)?;
+    let entries = slice(bytes, symbol_table.offset, symbol_table.size).ok_or_else(invalid)?;
+
+    let entry_size = symbol_table.entry_size.max(16) as usize;
+    for entry in entries.chunks_exact(entry_size) {
+        let symbol: Symbol = Cursor::new(entry)
+            .read_be()
+            .map_err(|err| format!("failed to read the symbol table: {}", err))?;
+        let is_label = matches!(symbol.info & 0xf, STT_NOTYPE | STT_OBJECT | STT_FUNC);
+        let is_defined = symbol.section_index != SHN_UNDEF && symbol.section_index < SHN_LORESERVE;
+        if !is_label || !is_defined || symbol.name == 0 {
+            continue;
+        }
+        let name = names
+            .get(symbol.name as usize..)
+            .and_then(|rest| rest.split(|byte| *byte == 0).next())
+            .ok_or_else(invalid)?;
+        let executable = section_headers
+            .get(symbol.section_index as usize)
+            .is_some_and(|section| section.flags & SHF_EXECINSTR != 0);
+        symbols.push(ElfSymbol {
+            name: String::from_utf8_lossy(name).into_owned(),
+            value: symbol.value,
+            section: if executable { Section::Text } else { Section::Data },
+        });
+    }
+    Ok(symbols)
+}
+
+impl ElfExecutable {
+    /// to_bytes: writes the executable as an ELF file. Every segment that is not empty
+    /// gets a `PT_LOAD` program header and a section, `.text` if it is executable and
+    /// `.data` otherwise. The symbols are written as local labels of the first section
+    /// of their kind.
+    ///
+    /// returns: the contents of the ELF file
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let segments: Vec<&Segment> = self.segments.iter().filter(|s| !s.bytes.is_empty()).collect();
+        let mut section_names = StringTable::new();
+        let mut bytes = vec![0; FILE_HEADER_SIZE + PROGRAM_HEADER_SIZE * segments.len()];
+        let mut program_headers = Vec::new();
+        let mut section_headers = vec![0; SECTION_HEADER_SIZE];
+
+        for segment in &segments {
+            align(&mut bytes);
+            let offset = bytes.len() as u32;
+            bytes.extend(&segment.bytes);
+            let size = segment.bytes.len() as u32;
+            let (name, segment_flags, section_flags) = match segment.executable {
+                true => (".text", PF_R | PF_X, SHF_ALLOC | SHF_EXECINSTR),
+                false => (".data", PF_R | PF_W, SHF_ALLOC | SHF_WRITE),
+            };
+            put_words(
+                &mut program_headers,
+                &[PT_LOAD, offset, segment.vaddr, segment.vaddr, size, size, segment_flags, 4],
+            );
+            put_words(
+                &mut section_headers,
+                &[section_names.add(name), SHT_PROGBITS, section_flags, segment.vaddr, offset, size, 0, 0, 4, 0],
+            );
+        }
+
+        // the first symbol is the null symbol
+        let mut symbol_names = StringTable::new();
+        let mut symbol_table = vec![0; SYMBOL_SIZE];
+        for symbol in &self.symbols {
+            let executable = symbol.section == Section::Text;
+            let section_index = segments
+                .iter()
+                .position(|segment| segment.executable == executable)
+                .map_or(SHN_ABS, |index| index as u16 + 1);
+            put_words(&mut symbol_table, &[symbol_names.add(&symbol.name), symbol.value, 0]);
+            // local binding, no type, default visibility
+            symbol_table.extend([0, 0]);
+            symbol_table.extend(section_index.to_be_bytes());
+        }
+        let symbol_count = symbol_table.len() / SYMBOL_SIZE;
+        let symbol_table_index = segments.len() as u32 + 1;
+
+        let tables = [
+            (".symtab", SHT_SYMTAB, symbol_table, symbol_table_index + 1, symbol_count as u32, SYMBOL_SIZE as u32),
+            (".strtab", SHT_STRTAB, symbol_names.bytes, 0, 0, 0),
+        ];
+        for (name, section_type, contents, link, info, entry_size) in tables {
+            align(&mut bytes);
+            let offset = bytes.len() as u32;
+            let size = contents.len() as u32;
+            bytes.extend(contents);
+            put_words(
+                &mut section_headers,
+                &[section_names.add(name), section_type, 0, 0, offset, size, link, info, 4, entry_size],
+            );
+        }
+        let name = section_names.add(".shstrtab");
+        let offset = bytes.len() as u32;
+        put_words(
+            &mut section_headers,
+            &[name, SHT_STRTAB, 0, 0, offset, section_names.bytes.len() as u32, 0, 0, 1, 0],
+        );
+        bytes.extend(&section_names.bytes);
+
+        align(&mut bytes);
+        let section_header_offset = bytes.len() as u32;
+        let section_count = section_headers.len() / SECTION_HEADER_SIZE;
+        bytes.extend(section_headers);
+
+        let mut header = b"\x7fELF".to_vec();
+        header.extend([ELFCLASS32, ELFDATA2MSB, EV_CURRENT]);
+        header.resize(16, 0);
+        header.extend(ET_EXEC.to_be_bytes());
+        header.extend(EM_MIPS.to_be_bytes());
+        put_words(
+            &mut header,
+            &[
+                EV_CURRENT as u32,
+                self.entry,
+                FILE_HEADER_SIZE as u32,
+                section_header_offset,
+                EF_MIPS_ARCH_32_O32,
+            ],
+        );
+        for half in [
+            FILE_HEADER_SIZE,
+            PROGRAM_HEADER_SIZE,
+            segments.len(),
+            SECTION_HEADER_SIZE,
+            section_count,
+            section_count - 1,
+        ] {
+            header.extend((half as u16).to_be_bytes());
+        }
+        bytes[..FILE_HEADER_SIZE].copy_from_slice(&header);
+        bytes[FILE_HEADER_SIZE..FILE_HEADER_SIZE + program_headers.len()].copy_from_slice(&program_headers);
+        bytes
+    }
+}
+
+/// The names of sections or symbols, each followed by a null byte.
+struct StringTable {
+    bytes: Vec<u8>,
+}
+
+impl StringTable {
+    /// A table holding the empty name at offset 0.
+    fn new() -> StringTable {
+        StringTable { bytes: vec![0] }
+    }
+
+    /// Adds a name to the table and returns its offset.
+    fn add(&mut self, name: &str) -> u32 {
+        let offset = self.bytes.len() as u32;
+        self.bytes.extend(name.as_bytes());
+        self.bytes.push(0);
+        offset
+    }
+}
+
+/// Appends big-endian words to `bytes`.
+fn put_words(bytes: &mut Vec<u8>, words: &[u32]) {
+    for word in words {
+        bytes.extend(word.to_be_bytes());
+    }
+}
+
+/// Pads `bytes` with zeroes to a multiple of 4 bytes.
+fn align(bytes: &mut Vec<u8>) {
+    bytes.resize(bytes.len().next_multiple_of(4), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUM: &[u8] = include_bytes!("../test_files/elf/sum.elf");
+
+    #[test]
+    fn test_read_executable() {
+        let executable = read_executable(SUM).unwrap();
+        assert_eq!(executable.entry, 0x0040_0004);
+        assert_eq!(executable.segments.len(), 2);
+
+        let text = &executable.segments[0];
+        assert_eq!(text.vaddr, 0x0040_0000);
+        assert!(text.executable);
+        assert_eq!(text.bytes.len(), 13 * 4);
+        assert_eq!(text.bytes[4..8], [0x8c, 0x09, 0x10, 0x00]);
+
+        let data = &executable.segments[1];
+        assert_eq!(data.vaddr, 0x0000_1000);
+        assert!(!data.executable);
+        assert_eq!(data.bytes, vec![0, 0, 0, 10, 0, 0, 0, 0]);
+
+        let symbol = |name: &str| executable.symbols.iter().find(|symbol| symbol.name == name);
+        assert_eq!(symbol("__start").map(|s| (s.value, s.section)), Some((0x0040_0004, Section::Text)));
+        assert_eq!(symbol("loop").map(|s| s.value), Some(0x0040_000c));
+        assert_eq!(symbol("total").map(|s| (s.value, s.section)), Some((0x0000_1004, Section::Data)));
+        assert!(symbol(".text").is_none());
+    }
+
+    #[test]
+    fn test_write_executable() {
+        let executable = ElfExecutable {
+            entry: 4,
+            segments: vec![
+                Segment {
+                    vaddr: 0,
+                    bytes: vec![0x20, 0x08, 0x00, 0x01, 0xff, 0xff, 0xff, 0xff],
+                    executable: true,
+                },
+                Segment {
+                    vaddr: 0,
+                    bytes: vec![1, 2, 3],
+                    executable: false,
+                },
+            ],
+            symbols: vec![
+                ElfSymbol {
+                    name: "main".to_string(),
+                    value: 0,
+                    section: Section::Text,
+                },
+                ElfSymbol {
+                    name: "bytes".to_string(),
+                    value: 0,
+                    section: Section::Data,
+                },
+            ],
+        };
+        let bytes = executable.to_bytes();
+        assert_eq!(bytes[..8], [0x7f, b'E', b'L', b'F', 1, 2, 1, 0]);
+        assert_eq!(read_executable(&bytes).unwrap(), executable);
+
+        // the sum fixture survives being written and read back
+        let sum = read_executable(SUM).unwrap();
+        assert_eq!(read_executable(&sum.to_bytes()).unwrap(), sum);
+    }
+
+    #[test]
+    fn test_read_executable_errors() {
+        assert!(read_executable(b"#!/bin/sh\n").unwrap_err().starts_with("not an ELF file"));
+
+        let mut little_endian = SUM.to_vec();
+        little_endian[5] = 1;
+        assert_eq!(read_executable(&little_endian).unwrap_err(), "not a big-endian ELF file");
+
+        let mut relocatable = SUM.to_vec();
+        relocatable[16..18].copy_from_slice(&ET_REL.to_be_bytes());
+        assert_eq!(
+            read_executable(&relocatable).unwrap_err(),
+            "the ELF file is a relocatable object, link it into an executable first"
+        );
+
+        // the memory size of the first program header, which is the text segment
+        let program_headers = u32::from_be_bytes(SUM[28..32].try_into().unwrap()) as usize;
+        let mut oversized = SUM.to_vec();
+        oversized[program_headers + 20..program_headers + 24].copy_from_slice(&0xffff_0000u32.to_be_bytes());
+        assert_eq!(
+            read_executable(&oversized).unwrap_err(),
+            "the segment at 0x00400000 takes up 4294901760 bytes in memory, but a segment may take up at most 1048576"
+        );
+
+        let truncated = &SUM[..0x1010];
+        assert_eq!(
+            read_executable(truncated).unwrap_err(),
+            "the segment at 0x00400000 is outside of the file"
+        );
+    }
+}
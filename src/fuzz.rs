@@ -0,0 +1,101 @@
+//!  Property tests that feed random programs from the generator through the assembler,
+//! the disassembler, the simulator and the reference interpreter. When a property fails,
+//! the program is shrunk to the smallest one that still fails, which is printed along
+//! with its source.
+//!
+//!  Run more cases with e.g. `PROPTEST_CASES=10000 cargo test --bin darken-assignment1 fuzz`.
+//!  
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::lockstep::run_lockstep;
+use darken_assignment1::assembler::{assemble, AssemblerOptions};
+use darken_assignment1::disassembler::disassemble;
+use darken_assignment1::generator::{
+    hot_subset, Block, GeneratedProgram, Operation, DATA_WORDS, R_OPERATIONS, SHIFTS, WRITABLE_REGISTERS,
+};
+use darken_assignment1::loader::MemoryImage;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+/// The number of instructions after which a generated program is taken to loop forever.
+const MAX_CYCLES: u64 = 100_000;
+
+/// Mostly the hot subset of a generator, so that results are used again and loads see
+/// stored values, and otherwise anything in `full`, so that the rest is exercised too.
+fn mostly_hot<T: Clone + std::fmt::Debug + 'static>(
+    hot: Vec<T>,
+    full: impl Strategy<Value = T> + Clone + 'static,
+) -> impl Strategy<Value = T> + Clone {
+    prop_oneof![3 => prop::sample::select(hot), 1 => full]
+}
+
+/// A register an instruction writes.
+fn written() -> impl Strategy<Value = u8> + Clone {
+    mostly_hot(hot_subset().written, 0..WRITABLE_REGISTERS)
+}
+
+/// A register an instruction reads.
+fn read() -> impl Strategy<Value = u8> + Clone {
+    mostly_hot(hot_subset().read, 0..32u8)
+}
+
+/// A data word a load or store goes to.
+fn data_word() -> impl Strategy<Value = u32> + Clone {
+    mostly_hot(hot_subset().words, 0..DATA_WORDS)
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    let dst = written();
+    let src = read();
+    let word = data_word();
+    prop_oneof![
+        (0..R_OPERATIONS.len(), dst.clone(), src.clone(), src.clone())
+            .prop_map(|(op, rd, rs, rt)| Operation::RType(op, rd, rs, rt)),
+        (0..SHIFTS.len(), dst.clone(), src.clone(), 0..32u8)
+            .prop_map(|(op, rd, rt, shamt)| Operation::Shift(op, rd, rt, shamt)),
+        (dst.clone(), src.clone(), any::<i16>()).prop_map(|(rt, rs, imm)| Operation::Addi(rt, rs, imm)),
+        (dst.clone(), src.clone(), any::<u16>()).prop_map(|(rt, rs, imm)| Operation::Ori(rt, rs, imm)),
+        (dst, word.clone()).prop_map(|(rt, word)| Operation::Load(rt, word)),
+        (src, word).prop_map(|(rt, word)| Operation::Store(rt, word)),
+        Just(Operation::Nop),
+    ]
+}
+
+fn block() -> impl Strategy<Value = Block> {
+    prop_oneof![
+        6 => operation().prop_map(Block::Operation),
+        1 => (1..6u8, vec(operation(), 0..6)).prop_map(|(count, body)| Block::Loop(count, body)),
+        1 => (read(), read(), vec(operation(), 0..4)).prop_map(|(rs, rt, body)| Block::SkipIfEqual(rs, rt, body)),
+        1 => (any::<bool>(), vec(operation(), 0..4)).prop_map(|(jr, body)| Block::JumpOver(jr, body)),
+    ]
+}
+
+fn program() -> impl Strategy<Value = GeneratedProgram> {
+    (vec(any::<i32>(), 0..8), vec(block(), 0..24)).prop_map(|(data, blocks)| GeneratedProgram { data, blocks })
+}
+
+fn image(source: &str) -> Result<MemoryImage, TestCaseError> {
+    let program = assemble(source, &AssemblerOptions::default())
+        .map_err(|err| TestCaseError::fail(format!("{:?}\nwhile assembling\n{}", err, source)))?;
+    Ok(MemoryImage::from(program))
+}
+
+proptest! {
+    #[test]
+    fn fuzz_simulator_agrees_with_interpreter(program in program()) {
+        let source = program.to_source();
+        let result = run_lockstep(image(&source)?, MAX_CYCLES);
+        prop_assert!(result.is_ok(), "{}\nwhile running\n{}", result.unwrap_err(), source);
+    }
+
+    #[test]
+    fn fuzz_disassembly_reassembles(program in program()) {
+        let source = program.to_source();
+        let words: Vec<u32> = image(&source)?.text.iter().map(|instruction| instruction.encode()).collect();
+        let disassembled = disassemble(&words, 0, &HashMap::new()).to_source();
+        let reassembled: Vec<u32> =
+            image(&disassembled)?.text.iter().map(|instruction| instruction.encode()).collect();
+        prop_assert_eq!(words, reassembled, "\nwhile reassembling\n{}", disassembled);
+    }
+}
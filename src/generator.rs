@@ -0,0 +1,266 @@
+//! Random but valid programs over the instructions the simulator supports, for fuzzing
+//! the assembler, the simulator and the reference interpreter against each other. A
+//! program is a list of blocks that always ends: loops count down a register no other
+//! instruction writes, branches and jumps only go forward, and loads and stores only
+//! touch words in the first `DATA_WORDS` words of data memory.
+//!
+//! The values are picked by the caller, e.g. by a property testing library that can
+//! then shrink a failing program by dropping blocks and simplifying operands.
+//!
+//! File: generator.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::get_register_name;
+use crate::interpreter::MIN_DATA_SIZE;
+use ux::u5;
+
+/// The number of words of data memory that loads and stores go to, all of it.
+pub const DATA_WORDS: u32 = (MIN_DATA_SIZE / 4) as u32;
+
+/// The registers instructions may write. The two registers after them are used by the
+/// generated loops and jumps.
+pub const WRITABLE_REGISTERS: u8 = 22;
+
+/// The register loops count down.
+const COUNTER: u8 = 23;
+
+/// The register `jr` jumps to.
+const JUMP_REGISTER: u8 = 22;
+
+/// The number of data words at either end of data memory in the hot subset.
+const HOT_WORDS: u32 = 4;
+
+/// A small part of the registers and data words programs may use, for generating
+/// programs where the result of an instruction is often used by the next one and loads
+/// often see stored values, rather than spreading over every register and word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotSubset {
+    /// The registers written, $zero and the first four writable registers after it
+    pub written: Vec<u8>,
+    /// The registers read, those written and the register loops count down
+    pub read: Vec<u8>,
+    /// The data words used, the first and the last words of data memory
+    pub words: Vec<u32>,
+}
+
+/// hot_subset: the registers and data words that focused programs use.
+///
+/// returns: the subset, all of which are valid in a generated program
+///
+pub fn hot_subset() -> HotSubset {
+    let written: Vec<u8> = [0].into_iter().chain(8..12).collect();
+    let read = written.iter().copied().chain([COUNTER]).collect();
+    let words = (0..HOT_WORDS).chain(DATA_WORDS - HOT_WORDS..DATA_WORDS).collect();
+    HotSubset { written, read, words }
+}
+
+/// The R-type instructions with two source registers.
+pub const R_OPERATIONS: [&str; 6] = ["add", "sub", "and", "or", "nor", "slt"];
+
+/// The shift instructions.
+pub const SHIFTS: [&str; 3] = ["sll", "srl", "sra"];
+
+/// A single instruction that does not change the flow of the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// One of `R_OPERATIONS` by index, with rd, rs and rt
+    RType(usize, u8, u8, u8),
+    /// One of `SHIFTS` by index, with rd, rt and the shift amount
+    Shift(usize, u8, u8, u8),
+    Addi(u8, u8, i16),
+    Ori(u8, u8, u16),
+    /// lw of the word with the given index in data memory
+    Load(u8, u32),
+    /// sw to the word with the given index in data memory
+    Store(u8, u32),
+    Nop,
+}
+
+/// A part of a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Operation(Operation),
+    /// Runs the operations the given number of times, counted down with `beq` and `j`
+    Loop(u8, Vec<Operation>),
+    /// Skips the operations with `beq` if the two registers are equal
+    SkipIfEqual(u8, u8, Vec<Operation>),
+    /// Jumps over the operations, with `jr` if set, otherwise with `j`
+    JumpOver(bool, Vec<Operation>),
+}
+
+/// A program: its initial data and its blocks, followed by `exit`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GeneratedProgram {
+    pub data: Vec<i32>,
+    pub blocks: Vec<Block>,
+}
+
+/// The name of a register, wrapped around to the registers that exist.
+fn reg(register: u8) -> String {
+    get_register_name(u5::new(register % 32)).unwrap()
+}
+
+/// A register that may be written, so that the loops and jumps keep working.
+fn dst(register: u8) -> String {
+    reg(register % WRITABLE_REGISTERS)
+}
+
+/// The byte offset of the data word with the given index.
+fn offset(word: u32) -> u32 {
+    word % DATA_WORDS * 4
+}
+
+impl Operation {
+    fn to_source(&self) -> String {
+        match self {
+            Operation::RType(op, rd, rs, rt) => format!(
+                "{} {}, {}, {}",
+                R_OPERATIONS[op % R_OPERATIONS.len()],
+                dst(*rd),
+                reg(*rs),
+                reg(*rt)
+            ),
+            Operation::Shift(op, rd, rt, shamt) => {
+                format!("{} {}, {}, {}", SHIFTS[op % SHIFTS.len()], dst(*rd), reg(*rt), shamt % 32)
+            }
+            Operation::Addi(rt, rs, imm) => format!("addi {}, {}, {}", dst(*rt), reg(*rs), imm),
+            Operation::Ori(rt, rs, imm) => format!("ori {}, {}, {:#x}", dst(*rt), reg(*rs), imm),
+            Operation::Load(rt, word) => format!("lw {}, {}($zero)", dst(*rt), offset(*word)),
+            Operation::Store(rt, word) => format!("sw {}, {}($zero)", reg(*rt), offset(*word)),
+            Operation::Nop => "nop".to_string(),
+        }
+    }
+}
+
+/// The source of a program as it is being written, one instruction per line.
+struct Writer {
+    source: String,
+    instructions: u32,
+    labels: u32,
+}
+
+impl Writer {
+    fn instruction(&mut self, text: &str) {
+        self.source.push_str("    ");
+        self.source.push_str(text);
+        self.source.push('\n');
+        self.instructions += 1;
+    }
+
+    fn operations(&mut self, operations: &[Operation]) {
+        for operation in operations {
+            self.instruction(&operation.to_source());
+        }
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!("L{}", self.labels)
+    }
+}
+
+impl GeneratedProgram {
+    /// to_source: the program as assembly source. Every line is a single instruction,
+    /// so the address of an instruction is four times the number of lines before it.
+    ///
+    /// returns: the assembly source
+    ///
+    pub fn to_source(&self) -> String {
+        let mut writer = Writer {
+            source: String::new(),
+            instructions: 0,
+            labels: 0,
+        };
+        if !self.data.is_empty() {
+            let words: Vec<String> = self.data.iter().map(|word| word.to_string()).collect();
+            writer.source.push_str(&format!(".data\n    .word {}\n.text\n", words.join(", ")));
+        }
+        for block in &self.blocks {
+            match block {
+                Block::Operation(operation) => writer.operations(std::slice::from_ref(operation)),
+                Block::Loop(count, body) => {
+                    let (start, end) = (writer.label(), writer.label());
+                    writer.instruction(&format!("addi {}, $zero, {}", reg(COUNTER), count.max(&1)));
+                    writer.source.push_str(&format!("{}:\n", start));
+                    writer.operations(body);
+                    writer.instruction(&format!("addi {}, {}, -1", reg(COUNTER), reg(COUNTER)));
+                    writer.instruction(&format!("beq {}, $zero, {}", reg(COUNTER), end));
+                    writer.instruction(&format!("j {}", start));
+                    writer.source.push_str(&format!("{}:\n", end));
+                }
+                Block::SkipIfEqual(rs, rt, body) => {
+                    let end = writer.label();
+                    writer.instruction(&format!("beq {}, {}, {}", reg(*rs), reg(*rt), end));
+                    writer.operations(body);
+                    writer.source.push_str(&format!("{}:\n", end));
+                }
+                Block::JumpOver(false, body) => {
+                    let end = writer.label();
+                    writer.instruction(&format!("j {}", end));
+                    writer.operations(body);
+                    writer.source.push_str(&format!("{}:\n", end));
+                }
+                Block::JumpOver(true, body) => {
+                    // the target is after the addi, the jr and the body
+                    let target = (writer.instructions + 2 + body.len() as u32) * 4;
+                    writer.instruction(&format!("addi {}, $zero, {}", reg(JUMP_REGISTER), target));
+                    writer.instruction(&format!("jr {}", reg(JUMP_REGISTER)));
+                    writer.operations(body);
+                }
+            }
+        }
+        writer.instruction("exit");
+        writer.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, AssemblerOptions};
+    use crate::interpreter::{Interpreter, Status};
+    use crate::loader::MemoryImage;
+
+    #[test]
+    fn test_hot_subset() {
+        let subset = hot_subset();
+        assert_eq!(subset.written, vec![0, 8, 9, 10, 11]);
+        assert!(subset.written.iter().all(|register| *register < WRITABLE_REGISTERS));
+        assert_eq!(subset.read.last(), Some(&COUNTER));
+        assert_eq!(subset.words, vec![0, 1, 2, 3, 246, 247, 248, 249]);
+        assert_eq!(offset(*subset.words.last().unwrap()), MIN_DATA_SIZE as u32 - 4);
+    }
+
+    #[test]
+    fn test_to_source() {
+        let program = GeneratedProgram {
+            data: vec![7, -1],
+            blocks: vec![
+                Block::Operation(Operation::Load(8, 1)),
+                Block::Loop(3, vec![Operation::Addi(9, 9, 2)]),
+                Block::SkipIfEqual(0, 0, vec![Operation::Store(9, 252)]),
+                Block::JumpOver(true, vec![Operation::Nop]),
+                Block::JumpOver(false, vec![Operation::Shift(0, 10, 9, 33)]),
+                Block::Operation(Operation::RType(5, 30, 8, 9)),
+                Block::Operation(Operation::Ori(11, 0, 0xffff)),
+            ],
+        };
+        let source = program.to_source();
+        assert_eq!(
+            source,
+            ".data\n    .word 7, -1\n.text\n    lw $t0, 4($zero)\n    addi $s7, $zero, 3\nL1:\n    addi $t1, $t1, 2\n    \
+             addi $s7, $s7, -1\n    beq $s7, $zero, L2\n    j L1\nL2:\n    beq $zero, $zero, L3\n    \
+             sw $t1, 8($zero)\nL3:\n    addi $s6, $zero, 44\n    jr $s6\n    nop\n    j L4\n    \
+             sll $t2, $t1, 1\nL4:\n    slt $t0, $t0, $t1\n    ori $t3, $zero, 0xffff\n    exit\n"
+        );
+
+        let image = MemoryImage::from(assemble(&source, &AssemblerOptions::default()).unwrap());
+        let mut interpreter = Interpreter::new(&image);
+        while interpreter.step().unwrap().1 == Status::Running {}
+        assert_eq!(interpreter.registers()[9], 6);
+        assert_eq!(interpreter.registers()[10], 0);
+        // the data word -1 is less than 6
+        assert_eq!(interpreter.registers()[8], 1);
+        assert_eq!(interpreter.registers()[11], 0xffff);
+    }
+}
@@ -0,0 +1,273 @@
+//! A reference interpreter that runs a program one instruction at a time as the MIPS
+//! instruction set describes it, without modelling the datapath. The simulator is
+//! checked against it: for the same program both must leave the same registers, memory
+//! and PC after every instruction.
+//!
+//! Data memory is as in the simulator: the initial data of the program followed by
+//! zeroes, at least `MIN_DATA_SIZE` bytes, holding big-endian words at addresses that
+//! are multiples of 4. Arithmetic wraps around on overflow.
+//!
+//! File: interpreter.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::loader::MemoryImage;
+use crate::trace::{TraceRecord, Write};
+use std::collections::HashMap;
+
+/// The smallest number of bytes data memory holds.
+pub const MIN_DATA_SIZE: usize = 1000;
+
+/// What running an instruction did to the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The program goes on with the instruction at the PC
+    Running,
+    /// The program ran `exit` or past its last instruction
+    Exited,
+}
+
+/// The registers, memory and PC of a program being interpreted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpreter {
+    text_base: u32,
+    text: Vec<u32>,
+    traps: HashMap<u32, String>,
+    data_base: u32,
+    data: Vec<u8>,
+    registers: [i32; 32],
+    pc: u32,
+    cycle: u64,
+}
+
+impl Interpreter {
+    /// Loads the instructions and data of `image` with the PC at its entry point.
+    pub fn new(image: &MemoryImage) -> Interpreter {
+        let mut data = image.data.clone();
+        data.resize(image.data.len().max(MIN_DATA_SIZE), 0);
+        Interpreter {
+            text_base: image.text_base,
+            text: image
+                .text
+                .iter()
+                .map(|instruction| instruction.encode())
+                .collect(),
+            traps: image.traps.clone(),
+            data_base: image.data_base,
+            data,
+            registers: [0; 32],
+            pc: image.entry,
+            cycle: 0,
+        }
+    }
+
+    pub fn pc(&self) -> u32 {
+        self.pc
+    }
+
+    /// The value of every register, by register number.
+    pub fn registers(&self) -> &[i32; 32] {
+        &self.registers
+    }
+
+    /// The address of the first byte of data memory and its contents.
+    pub fn data(&self) -> (u32, &[u8]) {
+        (self.data_base, &self.data)
+    }
+
+    /// The number of instructions executed.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    /// The index in data memory of the word at `address`, none if it is not a word there.
+    fn word_index(&self, address: u32) -> Option<usize> {
+        let index = address.checked_sub(self.data_base)? as usize;
+        match address.is_multiple_of(4) && index + 4 <= self.data.len() {
+            true => Some(index),
+            false => None,
+        }
+    }
+
+    fn read_word(&self, address: u32) -> Result<i32, String> {
+        let index = self
+            .word_index(address)
+            .ok_or_else(|| format!("lw from {:#010x}, which is not a word in data memory", address))?;
+        let bytes = [self.data[index], self.data[index + 1], self.data[index + 2], self.data[index + 3]];
+        Ok(i32::from_be_bytes(bytes))
+    }
+
+    fn write_register(&mut self, register: u32, value: i32) -> Option<Write<u8>> {
+        if register == 0 {
+            return None;
+        }
+        let old_value = self.registers[register as usize];
+        self.registers[register as usize] = value;
+        Some(Write {
+            location: register as u8,
+            old_value,
+            new_value: value,
+        })
+    }
+
+    /// step: runs the instruction at the PC.
+    ///
+    /// returns: the record of the instruction and whether the program goes on, none
+    ///          if the program ran past its last instruction, or an error if the
+    ///          instruction cannot run
+    ///
+    pub fn step(&mut self) -> Result<(Option<TraceRecord>, Status), String> {
+        let pc = self.pc;
+        if pc < self.text_base || !pc.is_multiple_of(4) {
+            return Err(format!("the PC {:#010x} is not an instruction address", pc));
+        }
+        if let Some(err) = self.traps.get(&pc) {
+            return Err(err.clone());
+        }
+        let index = ((pc - self.text_base) / 4) as usize;
+        let word = match self.text.get(index) {
+            Some(word) => *word,
+            None if index == self.text.len() => return Ok((None, Status::Exited)),
+            None => return Err(format!("the PC {:#010x} is past the last instruction", pc)),
+        };
+
+        let op = word >> 26;
+        let rs = (word >> 21) & 0x1f;
+        let rt = (word >> 16) & 0x1f;
+        let rd = (word >> 11) & 0x1f;
+        let shamt = (word >> 6) & 0x1f;
+        let funct = word & 0x3f;
+        let imm = word as u16 as i16 as i32;
+        let s = self.registers[rs as usize];
+        let t = self.registers[rt as usize];
+        let next = pc.wrapping_add(4);
+
+        let mut record = TraceRecord {
+            cycle: self.cycle,
+            pc,
+            word,
+            register_write: None,
+            memory_write: None,
+        };
+        let mut status = Status::Running;
+        let mut next_pc = next;
+        match (op, funct) {
+            (0, 8) => next_pc = s as u32,
+            (0, _) => {
+                let value = match funct {
+                    0 => t << shamt,
+                    2 => ((t as u32) >> shamt) as i32,
+                    3 => t >> shamt,
+                    32 => s.wrapping_add(t),
+                    34 => s.wrapping_sub(t),
+                    36 => s & t,
+                    37 => s | t,
+                    39 => !(s | t),
+                    42 => (s < t) as i32,
+                    _ => return Err(unsupported(pc, word)),
+                };
+                record.register_write = self.write_register(rd, value);
+            }
+            (2, _) => next_pc = (next & 0xf000_0000) | ((word & 0x3ff_ffff) << 2),
+            (4, _) => {
+                if s == t {
+                    next_pc = next.wrapping_add((imm << 2) as u32);
+                }
+            }
+            (8, _) => record.register_write = self.write_register(rt, s.wrapping_add(imm)),
+            (13, _) => record.register_write = self.write_register(rt, s | (word & 0xffff) as i32),
+            (35, _) => {
+                let value = self.read_word(s.wrapping_add(imm) as u32)?;
+                record.register_write = self.write_register(rt, value);
+            }
+            (43, _) => {
+                let address = s.wrapping_add(imm) as u32;
+                let index = self
+                    .word_index(address)
+                    .ok_or_else(|| format!("sw to {:#010x}, which is not a word in data memory", address))?;
+                let old_value = self.read_word(address)?;
+                self.data[index..index + 4].copy_from_slice(&t.to_be_bytes());
+                record.memory_write = Some(Write {
+                    location: address,
+                    old_value,
+                    new_value: t,
+                });
+            }
+            (63, _) => status = Status::Exited,
+            _ => return Err(unsupported(pc, word)),
+        }
+        self.pc = next_pc;
+        self.cycle += 1;
+        Ok((Some(record), status))
+    }
+}
+
+fn unsupported(pc: u32, word: u32) -> String {
+    format!("unsupported instruction {:#010x} at {:#010x}", word, pc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, AssemblerOptions};
+
+    fn run(source: &str) -> Interpreter {
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let mut interpreter = Interpreter::new(&MemoryImage::from(program));
+        while interpreter.step().unwrap().1 == Status::Running {}
+        interpreter
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let interpreter = run(
+            "addi $t0, $zero, -8\nsll $t1, $t0, 2\nsrl $t2, $t0, 28\nsra $t3, $t0, 1\n\
+             slt $t4, $t0, $zero\nnor $t5, $t0, $zero\nori $t6, $zero, 0xffff\nsub $t7, $t5, $t0\n",
+        );
+        let registers = interpreter.registers();
+        assert_eq!(registers[8], -8);
+        assert_eq!(registers[9], -32);
+        assert_eq!(registers[10], 15);
+        assert_eq!(registers[11], -4);
+        assert_eq!(registers[12], 1);
+        assert_eq!(registers[13], 7);
+        assert_eq!(registers[14], 0xffff);
+        assert_eq!(registers[15], 15);
+        // the program ran past its last instruction
+        assert_eq!(interpreter.cycle(), 8);
+    }
+
+    #[test]
+    fn test_memory_and_control_flow() {
+        let interpreter = run(
+            "addi $t0, $zero, 3\nloop: sw $t0, 0($t1)\naddi $t1, $t1, 4\naddi $t0, $t0, -1\n\
+             beq $t0, $zero, done\nj loop\ndone: lw $t2, 4($zero)\naddi $ra, $zero, 36\njr $ra\n",
+        );
+        assert_eq!(interpreter.registers()[10], 2);
+        assert_eq!(&interpreter.data().1[..12], &[0, 0, 0, 3, 0, 0, 0, 2, 0, 0, 0, 1]);
+        // jumping to just after the last instruction ends the program
+        assert_eq!(interpreter.pc(), 36);
+    }
+
+    #[test]
+    fn test_step_records() {
+        let program = assemble("addi $t0, $zero, 5\nsw $t0, 4($zero)\nexit\n", &AssemblerOptions::default()).unwrap();
+        let mut interpreter = Interpreter::new(&MemoryImage::from(program));
+        let (addi, status) = interpreter.step().unwrap();
+        assert_eq!(status, Status::Running);
+        assert_eq!(addi.unwrap().to_line(), "     0 0x00000000 0x20080005  addi $t0, $zero, 5       | $t0: 0 -> 5");
+        let (sw, _) = interpreter.step().unwrap();
+        assert_eq!(
+            sw.unwrap().memory_write,
+            Some(Write { location: 4, old_value: 0, new_value: 5 })
+        );
+        assert_eq!(interpreter.step().unwrap().1, Status::Exited);
+
+        let program = assemble("addi $t0, $zero, 1\nlw $t1, 2($t0)\n", &AssemblerOptions::default()).unwrap();
+        let mut interpreter = Interpreter::new(&MemoryImage::from(program));
+        interpreter.step().unwrap();
+        assert_eq!(
+            interpreter.step().unwrap_err(),
+            "lw from 0x00000003, which is not a word in data memory"
+        );
+    }
+}
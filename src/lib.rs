@@ -1 +1,11 @@
+pub mod assembler;
+pub mod coverage;
+pub mod disassembler;
+pub mod elf;
+pub mod generator;
+pub mod interpreter;
+pub mod loader;
+pub mod profile;
+pub mod stats;
+pub mod trace;
 pub mod utils;
@@ -0,0 +1,351 @@
+//! The contents of the simulator's memories before a program starts: the instructions,
+//! the initial data and where both are placed, along with the entry point and the labels.
+//! An image is made from an assembled program or read from an ELF executable.
+//!
+//! File: loader.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::instructions::{Instruction, NopTypeInstruction};
+use crate::assembler::listing::ListingLine;
+use crate::assembler::object::Section;
+use crate::assembler::Program;
+use crate::elf::{self, ElfExecutable};
+use std::collections::HashMap;
+
+/// The largest range of addresses the data segments of an executable may span.
+pub const MAX_DATA_SIZE: u32 = 1 << 20;
+
+/// The largest range of addresses the executable segments of an executable may span.
+pub const MAX_TEXT_SIZE: u32 = 1 << 20;
+
+/// A program ready to be loaded into the simulator.
+#[derive(Debug)]
+pub struct MemoryImage {
+    /// The address of the first instruction.
+    pub text_base: u32,
+    /// The instructions, placed one after the other from `text_base`.
+    pub text: Vec<Instruction>,
+    /// The addresses in the text that hold no instruction the simulator can run, with
+    /// the error running one of them stops the program with. The text has a `nop` there.
+    pub traps: HashMap<u32, String>,
+    /// The address of the first byte of data.
+    pub data_base: u32,
+    /// The initial contents of data memory from `data_base`.
+    pub data: Vec<u8>,
+    /// The address of the first instruction to run.
+    pub entry: u32,
+    /// The labels of the program and the addresses they refer to.
+    pub symbols: HashMap<String, u32>,
+    /// The section of every label, telling instruction and data addresses apart.
+    pub label_sections: HashMap<String, Section>,
+    /// The source lines the program was assembled from, empty if it was loaded
+    /// without its source.
+    pub source: Vec<ListingLine>,
+}
+
+impl From<Program> for MemoryImage {
+    fn from(program: Program) -> MemoryImage {
+        MemoryImage {
+            text_base: 0,
+            text: program.text,
+            traps: HashMap::new(),
+            data_base: 0,
+            data: program.data,
+            entry: 0,
+            symbols: program.symbols,
+            label_sections: program.label_sections,
+            source: program.listing_lines,
+        }
+    }
+}
+
+impl MemoryImage {
+    /// The labels of instructions by the address they refer to. Labels sharing
+    /// an address are joined with commas, in alphabetical order.
+    pub fn instruction_labels(&self) -> HashMap<u32, String> {
+        let mut names: Vec<(&String, &u32)> = self
+            .symbols
+            .iter()
+            .filter(|(name, _)| self.label_sections.get(*name) == Some(&Section::Text))
+            .collect();
+        names.sort();
+        let mut labels: HashMap<u32, String> = HashMap::new();
+        for (name, addr) in names {
+            labels
+                .entry(*addr)
+                .and_modify(|label| {
+                    label.push_str(", ");
+                    label.push_str(name);
+                })
+                .or_insert_with(|| name.clone());
+        }
+        labels
+    }
+}
+
+/// is_elf: whether the contents of a file start like an ELF file.
+///
+/// input: bytes - the contents of the file
+/// returns: true if the file starts with the ELF magic number
+///
+pub fn is_elf(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"\x7fELF")
+}
+
+/// load_elf: reads a 32-bit big-endian MIPS ELF executable into a memory image.
+/// The executable segments are decoded into instructions, and the other segments are
+/// placed in data memory. Words that are not instructions the simulator supports, such
+/// as constants placed among the instructions, are kept as traps, so that the program
+/// only stops if it runs one.
+///
+/// input: bytes - the contents of the ELF file
+/// returns: the image, or an error if the file is not an executable the simulator can run
+///
+pub fn load_elf(bytes: &[u8]) -> Result<MemoryImage, String> {
+    image_from_executable(elf::read_executable(bytes)?)
+}
+
+fn image_from_executable(executable: ElfExecutable) -> Result<MemoryImage, String> {
+    let (text_segments, data_segments): (Vec<_>, Vec<_>) = executable
+        .segments
+        .into_iter()
+        .partition(|segment| segment.executable);
+    if text_segments.is_empty() {
+        return Err("the executable has no executable segment".to_string());
+    }
+
+    let mut text_end = 0;
+    for segment in &text_segments {
+        if !segment.vaddr.is_multiple_of(4) {
+            return Err(format!(
+                "the executable segment at {:#010x} is not word aligned",
+                segment.vaddr
+            ));
+        }
+        // every address of an instruction is below the end, so computing them cannot overflow
+        let end = u32::try_from(segment.bytes.len().div_ceil(4))
+            .ok()
+            .and_then(|words| words.checked_mul(4))
+            .and_then(|size| segment.vaddr.checked_add(size))
+            .ok_or_else(|| {
+                format!(
+                    "the executable segment at {:#010x} runs past the end of the address space",
+                    segment.vaddr
+                )
+            })?;
+        text_end = text_end.max(end);
+    }
+    let text_base = text_segments.iter().map(|segment| segment.vaddr).min().unwrap_or(0);
+    if text_end - text_base > MAX_TEXT_SIZE {
+        return Err(format!(
+            "the executable segments span {} bytes, but instruction memory holds at most {}",
+            text_end - text_base,
+            MAX_TEXT_SIZE
+        ));
+    }
+
+    // the words of the executable segments, none between segments
+    let mut words = vec![None; ((text_end - text_base) / 4) as usize];
+    for segment in &text_segments {
+        let start = ((segment.vaddr - text_base) / 4) as usize;
+        for (index, word) in segment.bytes.chunks(4).enumerate() {
+            let mut bytes = [0; 4];
+            bytes[..word.len()].copy_from_slice(word);
+            words[start + index] = Some(u32::from_be_bytes(bytes));
+        }
+    }
+
+    let entry = executable.entry;
+    let entry_index = entry.checked_sub(text_base).map(|offset| (offset / 4) as usize);
+    if !entry.is_multiple_of(4) || entry_index.and_then(|index| words.get(index).copied().flatten()).is_none() {
+        return Err(format!(
+            "the entry point {:#010x} is not an instruction in an executable segment",
+            entry
+        ));
+    }
+
+    let mut text = Vec::new();
+    let mut traps = HashMap::new();
+    for (index, word) in words.into_iter().enumerate() {
+        let addr = text_base + index as u32 * 4;
+        match word.map(|word| (word, Instruction::decode(word))) {
+            Some((_, Some(instruction))) => {
+                text.push(instruction);
+                continue;
+            }
+            Some((word, None)) => traps.insert(
+                addr,
+                format!("unsupported instruction {:#010x} at address {:#010x}", word, addr),
+            ),
+            None => traps.insert(addr, format!("the address {:#010x} is not in an executable segment", addr)),
+        };
+        text.push(Instruction::NopType(NopTypeInstruction {}));
+    }
+
+    let data_base = data_segments.iter().map(|segment| segment.vaddr).min().unwrap_or(0);
+    let data_end = data_segments
+        .iter()
+        .map(|segment| segment.vaddr as u64 + segment.bytes.len() as u64)
+        .max()
+        .unwrap_or(0);
+    if data_end.saturating_sub(data_base as u64) > MAX_DATA_SIZE as u64 {
+        return Err(format!(
+            "the data segments span {} bytes, but data memory holds at most {}",
+            data_end - data_base as u64,
+            MAX_DATA_SIZE
+        ));
+    }
+    let mut data = vec![0; data_end.saturating_sub(data_base as u64) as usize];
+    for segment in &data_segments {
+        let start = (segment.vaddr - data_base) as usize;
+        data[start..start + segment.bytes.len()].copy_from_slice(&segment.bytes);
+    }
+
+    let mut symbols = HashMap::new();
+    let mut label_sections = HashMap::new();
+    for symbol in executable.symbols {
+        symbols.entry(symbol.name.clone()).or_insert(symbol.value);
+        label_sections.entry(symbol.name).or_insert(symbol.section);
+    }
+
+    Ok(MemoryImage {
+        text_base,
+        text,
+        traps,
+        data_base,
+        data,
+        entry,
+        symbols,
+        label_sections,
+        source: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf::Segment;
+    use crate::utils::InstrRepresentable;
+
+    #[test]
+    fn test_load_elf() {
+        let image = load_elf(include_bytes!("../test_files/elf/sum.elf")).unwrap();
+        assert_eq!(image.text_base, 0x0040_0000);
+        assert_eq!(image.entry, 0x0040_0004);
+        assert_eq!(image.text.len(), 13);
+        assert_eq!(image.text[1].to_hex_string(), "0x8c091000");
+        assert_eq!(image.text[12].to_hex_string(), "0xFFFFFFFF");
+        assert_eq!(image.data_base, 0x0000_1000);
+        assert_eq!(image.data, vec![0, 0, 0, 10, 0, 0, 0, 0]);
+        assert_eq!(image.instruction_labels().get(&0x0040_0030), Some(&"finish".to_string()));
+        assert_eq!(image.label_sections.get("count"), Some(&Section::Data));
+        assert!(!image.instruction_labels().values().any(|label| label == "count"));
+    }
+
+    #[test]
+    fn test_load_elf_errors() {
+        let executable = |segments: Vec<Segment>| ElfExecutable {
+            entry: 0x1000,
+            segments,
+            symbols: Vec::new(),
+        };
+        let segment = |vaddr: u32, bytes: Vec<u8>, executable: bool| Segment {
+            vaddr,
+            bytes,
+            executable,
+        };
+
+        let err = image_from_executable(executable(vec![segment(0x2000, vec![0; 4], true)]));
+        assert_eq!(
+            err.unwrap_err(),
+            "the entry point 0x00001000 is not an instruction in an executable segment"
+        );
+
+        let err = image_from_executable(executable(vec![
+            segment(0x0ff0, vec![0; 4], true),
+            segment(0x1004, vec![0; 4], true),
+        ]));
+        assert_eq!(
+            err.unwrap_err(),
+            "the entry point 0x00001000 is not an instruction in an executable segment"
+        );
+
+        let err = image_from_executable(executable(vec![
+            segment(0x1000, vec![0; 4], true),
+            segment(0x1000_0000, vec![0; 4], true),
+        ]));
+        assert!(err.unwrap_err().starts_with("the executable segments span"));
+
+        let err = image_from_executable(executable(vec![
+            segment(0x1000, vec![0; 4], true),
+            segment(0x1000_0000, vec![0; 4], false),
+            segment(0x2000_0000, vec![0; 4], false),
+        ]));
+        assert!(err.unwrap_err().starts_with("the data segments span"));
+
+        let err = image_from_executable(executable(vec![segment(0xffff_fff0, vec![0; 16], true)]));
+        assert_eq!(
+            err.unwrap_err(),
+            "the executable segment at 0xfffffff0 runs past the end of the address space"
+        );
+    }
+
+    #[test]
+    fn test_load_elf_traps() {
+        let executable = ElfExecutable {
+            entry: 0x1000,
+            segments: vec![
+                Segment {
+                    vaddr: 0x1000,
+                    bytes: vec![0x20, 0x08, 0x00, 0x03, 0xfc, 0, 0, 0],
+                    executable: true,
+                },
+                Segment {
+                    vaddr: 0x100c,
+                    bytes: vec![0xff; 4],
+                    executable: true,
+                },
+            ],
+            symbols: Vec::new(),
+        };
+        let image = image_from_executable(executable).unwrap();
+        assert_eq!(image.text_base, 0x1000);
+        let words: Vec<String> = image.text.iter().map(|instruction| instruction.to_hex_string()).collect();
+        assert_eq!(words, ["0x20080003", "0x00000000", "0x00000000", "0xFFFFFFFF"]);
+        assert_eq!(
+            image.traps,
+            HashMap::from([
+                (0x1004, "unsupported instruction 0xfc000000 at address 0x00001004".to_string()),
+                (0x1008, "the address 0x00001008 is not in an executable segment".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_instruction_labels() {
+        let image = MemoryImage {
+            text_base: 0,
+            text: Vec::new(),
+            traps: HashMap::new(),
+            data_base: 0,
+            data: Vec::new(),
+            entry: 0,
+            symbols: HashMap::from([
+                ("main".to_string(), 0),
+                ("loop".to_string(), 8),
+                ("_start".to_string(), 0),
+                ("value".to_string(), 0),
+            ]),
+            label_sections: HashMap::from([
+                ("main".to_string(), Section::Text),
+                ("loop".to_string(), Section::Text),
+                ("_start".to_string(), Section::Text),
+                ("value".to_string(), Section::Data),
+            ]),
+            source: Vec::new(),
+        };
+        let labels = image.instruction_labels();
+        assert_eq!(labels.get(&0), Some(&"_start, main".to_string()));
+        assert_eq!(labels.get(&8), Some(&"loop".to_string()));
+    }
+}
@@ -0,0 +1,188 @@
+//!  Runs the simulator and the reference interpreter side by side on the same program and
+//! stops at the first instruction after which the registers, memory or PC differ. The
+//! interpreter follows the instruction set rather than the datapath, so a difference
+//! points at a bug in how the datapath implements an instruction.
+//!
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::mips::simulator::{RunResult, Simulator};
+use darken_assignment1::assembler::get_register_name;
+use darken_assignment1::interpreter::{Interpreter, Status};
+use darken_assignment1::loader::MemoryImage;
+use darken_assignment1::trace::{difference, TraceRecord};
+use std::panic::{self, AssertUnwindSafe};
+
+/// The reference interpreter running alongside a simulator.
+pub struct Lockstep {
+    reference: Interpreter,
+}
+
+impl Lockstep {
+    /// Starts the reference interpreter on the program the simulator is loaded with.
+    pub fn new(image: &MemoryImage) -> Lockstep {
+        Lockstep {
+            reference: Interpreter::new(image),
+        }
+    }
+
+    /// step: runs one instruction on both and compares what they did.
+    ///
+    /// input: simulator - the simulator, at the same point of the program as the interpreter
+    /// returns: the result of the simulator, or a description of where the two differ
+    ///
+    pub fn step(&mut self, simulator: &mut Simulator) -> Result<RunResult, String> {
+        let cycle = simulator.get_cycle_count();
+        let pc = simulator.get_current_pc() as u32;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| simulator.step()))
+            .unwrap_or_else(|payload| {
+                let msg = payload
+                    .downcast_ref::<&str>()
+                    .map(|msg| msg.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                RunResult::Failure(format!("the simulator panicked: {}", msg))
+            });
+        let mismatch = |reason: String| format!("mismatch in cycle {} at {:#010x}: {}", cycle, pc, reason);
+
+        let (record, status) = match (self.reference.step(), &result) {
+            (Err(err), RunResult::Failure(msg)) => {
+                return Err(format!("both stopped in cycle {} at {:#010x}: {}, and {}", cycle, pc, err, msg))
+            }
+            (Err(err), _) => {
+                return Err(mismatch(format!("the reference interpreter stopped: {}", err)))
+            }
+            (Ok(_), RunResult::Failure(msg)) => return Err(mismatch(msg.clone())),
+            (Ok(step), _) => step,
+        };
+
+        let executed = match simulator.get_cycle_count() > cycle {
+            true => simulator.get_last_cycle().map(|cycle| cycle.to_record()),
+            false => None,
+        };
+        match (&record, &executed) {
+            (Some(expected), Some(actual)) => {
+                if let Some(reason) = difference(expected, actual) {
+                    return Err(mismatch(format!("{}\n{}", reason, comparison(expected, actual))));
+                }
+            }
+            (Some(expected), None) => {
+                return Err(mismatch(format!("the simulator did not run `{}`", expected.to_line().trim())))
+            }
+            (None, Some(actual)) => {
+                return Err(mismatch(format!(
+                    "the simulator ran `{}` past the end of the program",
+                    actual.to_line().trim()
+                )))
+            }
+            (None, None) => {}
+        }
+
+        let completed = result == RunResult::Completed;
+        if completed != (status == Status::Exited) {
+            return Err(mismatch(match completed {
+                true => "the simulator exited, the reference interpreter did not".to_string(),
+                false => "the reference interpreter exited, the simulator did not".to_string(),
+            }));
+        }
+        self.compare_state(simulator).map_err(mismatch)?;
+        // where the PC is once the program has exited does not matter
+        let pc = simulator.get_current_pc() as u32;
+        if !completed && pc != self.reference.pc() {
+            return Err(mismatch(format!("the PC is {:#010x} instead of {:#010x}", pc, self.reference.pc())));
+        }
+        Ok(result)
+    }
+
+    /// Compares the registers and the data memory of the simulator with the reference
+    /// interpreter.
+    fn compare_state(&self, simulator: &Simulator) -> Result<(), String> {
+        let mut registers = [0; 32];
+        for (reg, val) in simulator.get_registers() {
+            registers[u8::from(reg) as usize] = val;
+        }
+        for (reg, (actual, expected)) in registers.iter().zip(self.reference.registers()).enumerate() {
+            if actual != expected {
+                let name = get_register_name(ux::u5::new(reg as u8)).unwrap();
+                return Err(format!("{} is {} instead of {}", name, actual, expected));
+            }
+        }
+
+        let (base, expected) = self.reference.data();
+        let actual = simulator.get_data_mem();
+        if actual.len() != expected.len() || actual.first().map(|(addr, _)| *addr) != Some(base) {
+            return Err("data memory is not the same size".to_string());
+        }
+        if let Some(index) = actual.iter().zip(expected).position(|((_, actual), expected)| actual != expected) {
+            let addr = base as usize + index;
+            return Err(format!(
+                "the byte at {:#010x} is {} instead of {}",
+                addr, actual[index].1, expected[index]
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The two records one above the other, the reference first.
+fn comparison(expected: &TraceRecord, actual: &TraceRecord) -> String {
+    format!("reference: {}\nsimulator: {}", expected, actual)
+}
+
+/// run_lockstep: runs a program on the simulator and the reference interpreter until
+/// it exits or they differ.
+///
+/// input: image - the program
+///        max_cycles - the number of instructions after which the program is stopped
+/// returns: the number of instructions run, or where the two differ
+///
+pub fn run_lockstep(image: MemoryImage, max_cycles: u64) -> Result<u64, String> {
+    let mut lockstep = Lockstep::new(&image);
+    let mut simulator = Simulator::load(image);
+    while simulator.get_cycle_count() < max_cycles {
+        if lockstep.step(&mut simulator)? == RunResult::Completed {
+            return Ok(simulator.get_cycle_count());
+        }
+    }
+    Ok(simulator.get_cycle_count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use darken_assignment1::assembler::{assemble, AssemblerOptions};
+
+    fn image(source: &str) -> MemoryImage {
+        MemoryImage::from(assemble(source, &AssemblerOptions::default()).unwrap())
+    }
+
+    #[test]
+    fn test_lockstep_agrees() {
+        let source = "addi $t0, $zero, -8\nsll $t1, $t0, 2\nsrl $t2, $t0, 28\nsra $t3, $t0, 1\n\
+                      slt $t4, $t0, $zero\nnor $t5, $t0, $zero\nsub $t7, $t5, $t0\nnop\n\
+                      addi $t0, $zero, 3\nloop: sw $t0, 0($s0)\naddi $s0, $s0, 4\naddi $t0, $t0, -1\n\
+                      beq $t0, $zero, done\nj loop\ndone: lw $s1, 4($zero)\nexit\n";
+        assert_eq!(run_lockstep(image(source), 1000), Ok(25));
+        // a program without exit runs past its last instruction
+        assert_eq!(run_lockstep(image("addi $t0, $zero, 1\n"), 1000), Ok(1));
+    }
+
+    #[test]
+    fn test_lockstep_mismatch() {
+        let err = run_lockstep(image("addi $t0, $zero, 1\nlw $t1, 2($t0)\n"), 1000).unwrap_err();
+        assert!(err.starts_with("both stopped in cycle 1 at 0x00000004: lw from 0x00000003"), "{}", err);
+
+        let mut lockstep = Lockstep::new(&image("addi $t0, $zero, 1\naddi $t1, $zero, 2\n"));
+        let mut simulator = Simulator::load(image("addi $t0, $zero, 1\naddi $t1, $zero, 3\n"));
+        assert_eq!(lockstep.step(&mut simulator), Ok(RunResult::Success));
+        let err = lockstep.step(&mut simulator).unwrap_err();
+        assert_eq!(
+            err,
+            "mismatch in cycle 1 at 0x00000004: the instruction is 0x20090003 instead of 0x20090002\n\
+             reference:      1 0x00000004 0x20090002  addi $t1, $zero, 2       | $t1: 0 -> 2\n\
+             simulator:      1 0x00000004 0x20090003  addi $t1, $zero, 3       | $t1: 0 -> 3"
+        );
+
+        // ori zero extends its immediate
+        assert_eq!(run_lockstep(image("ori $t0, $zero, 0x8000\nsw $t0, 4($zero)\nexit\n"), 1000), Ok(3));
+    }
+}
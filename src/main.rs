@@ -22,18 +22,53 @@ use ui::App;
 
 use self::assembler::run_assembler;
 use self::mips::simulator::RunResult;
+use darken_assignment1::assembler;
 
-mod assembler;
+#[cfg(test)]
+mod fuzz;
+mod lockstep;
 mod mips;
+mod runner;
 mod ui;
 mod utils;
 mod valwriter;
+mod vcd;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
-    let vec = run_assembler(args).expect("Failed to get instruction list from assembler!");
-
-    let simulator = mips::simulator::Simulator::new(vec);
+    let (run_options, args) = match runner::parse_run_options(args) {
+        Ok(parsed) => parsed,
+        Err(err_msg) => {
+            eprintln!("error: {}", err_msg);
+            std::process::exit(1);
+        }
+    };
+    let image = match run_assembler(args) {
+        Ok(Some(image)) => image,
+        // `-c` only writes object files, there is no program to simulate
+        Ok(None) => return Ok(()),
+        Err(err_msg) => {
+            eprintln!("error: {}", err_msg);
+            std::process::exit(1);
+        }
+    };
+
+    let lockstep = match run_options.check {
+        true => Some(lockstep::Lockstep::new(&image)),
+        false => None,
+    };
+    let mut simulator = mips::simulator::Simulator::load(image);
+
+    if run_options.headless {
+        match runner::run(&mut simulator, &run_options, lockstep) {
+            Ok(report) => print!("{}", report),
+            Err(err_msg) => {
+                eprintln!("error: {}", err_msg);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
 
     //// setup terminal for ui
     enable_raw_mode()?;
@@ -98,6 +133,7 @@ fn run_app<B: Backend>(
                     KeyCode::Char('h') => app.set_val_writer('h'),
                     KeyCode::Char('b') => app.set_val_writer('b'),
                     KeyCode::Char('r') => app.reset(),
+                    KeyCode::Char('p') => app.toggle_datapath(), // p as in processor
                     KeyCode::Char('g') => app.toggle_run(), // g as in GO, todo should be running here..
                     KeyCode::Char('j') => app.reg_file_forward(), // Vim-down
                     KeyCode::Char('k') => app.reg_file_backward(), // Vim-up
@@ -121,18 +157,20 @@ mod tests {
     use ux::u5;
 
     use crate::{mips::Simulator};
+    use darken_assignment1::assembler::{assemble, AssemblerOptions};
+    use darken_assignment1::utils::InstrRepresentable;
 
     use super::*;
 
+    fn assemble_fixture(source: &str) -> Vec<Box<dyn InstrRepresentable>> {
+        assemble(source, &AssemblerOptions::default())
+            .unwrap()
+            .into_instructions()
+    }
+
     #[test]
     fn simple_loop_test() {
-        let args = vec![
-            "".to_string(), // program name
-            "test_files/integration_test1.asm".to_string(),
-            "test_files/integration_test1_instruction_output.txt".to_string(),
-            "test_files/integration_test1_listing_output.txt".to_string()
-            ];
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/integration_test1.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
@@ -163,14 +201,7 @@ mod tests {
 
     #[test]
     fn sw_integration_test() {
-        let args = vec![
-            "".to_string(),
-            "test_files/sw_integration_test.asm".to_string(),
-            "test_files/sw_integration_test_instruction_output.txt".to_string(),
-            "test_files/sw_integration_test_listing_output.txt".to_string(),
-        ];
-
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/sw_integration_test.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
@@ -210,14 +241,7 @@ mod tests {
 
     #[test]
     fn lw_integration_test() {
-        let args = vec![
-            "".to_string(),
-            "test_files/lw_integration_test.asm".to_string(),
-            "test_files/lw_integration_test_instruction_output.txt".to_string(),
-            "test_files/lw_integration_test_listing_output.txt".to_string(),
-        ];
-
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/lw_integration_test.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
@@ -240,14 +264,7 @@ mod tests {
 
     #[test]
     fn lw_with_offset_integration_test() {
-        let args = vec![
-            "".to_string(),
-            "test_files/lw_with_offset.asm".to_string(),
-            "test_files/lw_with_offset_integration_test_instruction_output.txt".to_string(),
-            "test_files/lw_with_offset_integration_test_listing_output.txt".to_string(),
-        ];
-
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/lw_with_offset.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
@@ -267,14 +284,7 @@ mod tests {
 
     #[test]
     fn sw_lw_neg_val_test() {
-        let args = vec![
-            "".to_string(),
-            "test_files/sw_lw_neg_val_test.asm".to_string(),
-            "test_files/sw_lw_neg_val_test_instr.txt".to_string(),
-            "test_files/sw_lw_neg_val_test_listing.txt".to_string()
-        ];
-
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/sw_lw_neg_val_test.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
@@ -313,14 +323,7 @@ mod tests {
 
     #[test]
     fn beq_backwards() {
-        let args = vec![
-            "".to_string(),
-            "test_files/beq_backwards.asm".to_string(),
-            "test_files/beq_backwards_test_instr.txt".to_string(),
-            "test_files/beq_backwards_test_listing.txt".to_string()
-        ];
-
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/beq_backwards.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
@@ -340,14 +343,7 @@ mod tests {
 
     #[test]
     fn jr_forwards() {
-        let args = vec![
-            "".to_string(),
-            "test_files/jr_forwards.asm".to_string(),
-            "test_files/jr_forwards_instr.txt".to_string(),
-            "test_files/jr_forwards_listing.txt".to_string(),
-        ];
-
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/jr_forwards.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
@@ -368,14 +364,7 @@ mod tests {
 
     #[test]
     fn jr_backwards() {
-        let args = vec![
-            "".to_string(),
-            "test_files/jr_backwards.asm".to_string(),
-            "test_files/jr_backwards_instr.txt".to_string(),
-            "test_files/jr_backwards_listing.txt".to_string(),
-        ];
-
-        let instructions = run_assembler(args).unwrap();
+        let instructions = assemble_fixture(include_str!("../test_files/jr_backwards.asm"));
         let mut sim = Simulator::new(instructions);
         loop {
             let step_res = sim.step();
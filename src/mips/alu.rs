@@ -27,12 +27,14 @@ impl Alu {
         match signal_val {
             0 => Box::new(|x, y| x & y),
             1 => Box::new(|x, y| x | y),
-            2 => Box::new(|x, y| x + y),
-            6 => Box::new(|x, y| x - y),
+            // add and sub wrap around on overflow, there are no exceptions to trap it
+            2 => Box::new(|x: i32, y| x.wrapping_add(y)),
+            6 => Box::new(|x: i32, y| x.wrapping_sub(y)),
             7 => Box::new(|x, y| if x < y { 1 } else { 0 }),
             12 => Box::new(|x, y| !(x | y)),
             13 => Box::new(|x, y| x >> y), 
             14 => Box::new(|x, y| ((x as u32) >> y) as i32),
+            15 => Box::new(|x, y| x << y),
             _ => panic!("Invalid control signal sent to ALU!"),
         }
     }
@@ -68,6 +70,15 @@ mod tests {
 
         op = Alu::get_operation_from_signal(u4::new(12));
         assert_eq!(-4, op(operand_1, operand_2)); // dealing with i32's here
+
+        op = Alu::get_operation_from_signal(u4::new(15));
+        assert_eq!(4, op(operand_1, operand_2));
+
+        op = Alu::get_operation_from_signal(u4::new(2));
+        assert_eq!(i32::MIN, op(i32::MAX, 1));
+
+        op = Alu::get_operation_from_signal(u4::new(6));
+        assert_eq!(i32::MAX, op(i32::MIN, 1));
     }
 
     #[test]
@@ -3,6 +3,7 @@
 //! Author: mai21asm, c19hln
 //! Since: 2022-11-25
 //! Version: 1.0
+use darken_assignment1::assembler::spec;
 use ux::{u4, u6};
 
 pub fn get_alu_signal(alu_op_0: bool, alu_op_1: bool, funct_field: u6) -> u4 {
@@ -20,19 +21,12 @@ pub fn get_alu_signal(alu_op_0: bool, alu_op_1: bool, funct_field: u6) -> u4 {
     }
 }
 
+/// The ALU operation of the R-type instruction with the function code, as given in the
+/// instruction table.
 pub fn get_rtype_signal(funct_field: u6) -> u4 {
-    let funct_field_val: u8 = funct_field.into();
-    match funct_field_val {
-        32 | 8 => u4::new(2),
-        34 => u4::new(6),
-        36 => u4::new(0),
-        37 => u4::new(1),
-        42 => u4::new(7),
-        3 => u4::new(13),  
-        2 => u4::new(14),  
-        0 => u4::new(0),   
-        39 => u4::new(12), 
-        _ => panic!(
+    match spec::find(0, funct_field.into()) {
+        Some(instruction) => u4::new(instruction.alu.signal()),
+        None => panic!(
             "Invalid funct_field for R-Type ALU-signal! Got value: {}",
             funct_field
         ),
@@ -84,5 +78,22 @@ mod tests {
 
         let srl_sig = get_alu_signal(false, true, u6::new(2));
         assert_eq!(srl_sig, u4::new(14));
+
+        let sll_sig = get_alu_signal(false, true, u6::new(0));
+        assert_eq!(sll_sig, u4::new(15));
+    }
+
+    #[test]
+    fn test_alu_signal_of_every_instruction() {
+        // the ALUOp signals of the control unit and the function code select the ALU
+        // operation the instruction table gives
+        for instruction in &spec::INSTRUCTIONS {
+            let signal = get_alu_signal(
+                instruction.control & spec::signals::ALU_OP_0 != 0,
+                instruction.control & spec::signals::ALU_OP_1 != 0,
+                u6::new(instruction.funct),
+            );
+            assert_eq!(signal, u4::new(instruction.alu.signal()), "{}", instruction.mnemonic);
+        }
     }
 }
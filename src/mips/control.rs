@@ -1,12 +1,16 @@
 //!  The control unit of the MIPS processor. It is responsible for setting the control signals
-//! so that each instruction is set properly. 
+//! so that each instruction is set properly. The signals of every instruction are taken
+//! from the instruction table of the assembler.
 //!  
 //! Author: mai21asm, c19hln
 //! Since: 2022-11-25
 //! Version: 1.0
-use ux::{u6, u13};
+use super::datapath::ControlSignals;
+use darken_assignment1::assembler::spec::{self, signals};
+use ux::{u6, u14};
 
 pub struct Control {
+    zero_extend: bool,
     exit: bool,
     shift: bool,
     jump_reg: bool,
@@ -25,6 +29,7 @@ pub struct Control {
 impl Control {
     pub fn new() -> Control {
         Control {
+            zero_extend: false,
             exit: false,
             shift: false,
             jump_reg: false,
@@ -45,45 +50,31 @@ impl Control {
     /// set output bits according to the given op_bits
     pub fn set_output_flags(&mut self, op_bits: u6, funct_bits: u6) {
         let op_bits_val: u8 = op_bits.into();
-        match op_bits_val {
-            0 => {
-                let funct_bits_val: u8 = funct_bits.into();
-                match funct_bits_val {
-                    8 => self.set_output_flags_to_pattern(u13::new(1024)), // jr
-                    3 => self.set_output_flags_to_pattern(u13::new(2338)), // sra 
-                    2 => self.set_output_flags_to_pattern(u13::new(2338)),// srl
-                    _ => self.set_output_flags_to_pattern(u13::new(290))
-                }
-            },
-            35 => self.set_output_flags_to_pattern(u13::new(240)),
-            43 => self.set_output_flags_to_pattern(u13::new(136)),
-            4 => self.set_output_flags_to_pattern(u13::new(5)), 
-            8 => self.set_output_flags_to_pattern(u13::new(160)), // this was 161, but that set alu_op_0 for addi, which we probably don't want
-            13 => self.set_output_flags_to_pattern(u13::new(163)), 
-            2 => self.set_output_flags_to_pattern(u13::new(512)), 
-            63 => self.set_output_flags_to_pattern(u13::new(4096)), // exit
-            _ => panic!(
+        match spec::executed(op_bits_val, funct_bits.into()) {
+            Some(instruction) => self.set_output_flags_to_pattern(u14::new(instruction.control)),
+            None => panic!(
                 "Got invalid input pattern to Control! Got value {}",
                 op_bits_val
             ),
         }
     }
-    // ori: 0010100011
-    fn set_output_flags_to_pattern(&mut self, output_pattern: u13) {
+
+    fn set_output_flags_to_pattern(&mut self, output_pattern: u14) {
         let output_pattern_val: u16 = output_pattern.into();
-        self.exit = output_pattern_val & 4096 > 0;
-        self.shift = output_pattern_val & 2048 > 0;
-        self.jump_reg = output_pattern_val & 1024 > 0;
-        self.jump = output_pattern_val & 512 > 0;
-        self.reg_dest = output_pattern_val & 256 > 0;
-        self.alu_src = output_pattern_val & 128 > 0;
-        self.mem_to_reg = output_pattern_val & 64 > 0;
-        self.reg_write = output_pattern_val & 32 > 0;
-        self.mem_read = output_pattern_val & 16 > 0;
-        self.mem_write = output_pattern_val & 8 > 0;
-        self.branch = output_pattern_val & 4 > 0;
-        self.alu_op_1 = output_pattern_val & 2 > 0;
-        self.alu_op_0 = output_pattern_val & 1 > 0;
+        self.zero_extend = output_pattern_val & signals::ZERO_EXT > 0;
+        self.exit = output_pattern_val & signals::EXIT > 0;
+        self.shift = output_pattern_val & signals::SHIFT > 0;
+        self.jump_reg = output_pattern_val & signals::JUMP_REG > 0;
+        self.jump = output_pattern_val & signals::JUMP > 0;
+        self.reg_dest = output_pattern_val & signals::REG_DST > 0;
+        self.alu_src = output_pattern_val & signals::ALU_SRC > 0;
+        self.mem_to_reg = output_pattern_val & signals::MEM_TO_REG > 0;
+        self.reg_write = output_pattern_val & signals::REG_WRITE > 0;
+        self.mem_read = output_pattern_val & signals::MEM_READ > 0;
+        self.mem_write = output_pattern_val & signals::MEM_WRITE > 0;
+        self.branch = output_pattern_val & signals::BRANCH > 0;
+        self.alu_op_1 = output_pattern_val & signals::ALU_OP_1 > 0;
+        self.alu_op_0 = output_pattern_val & signals::ALU_OP_0 > 0;
     }
 
     pub fn reg_dest(&self) -> bool {
@@ -138,6 +129,30 @@ impl Control {
         self.exit
     }
 
+    pub fn zero_extend(&self) -> bool {
+        self.zero_extend
+    }
+
+    /// The current output of the control unit.
+    pub fn signals(&self) -> ControlSignals {
+        ControlSignals {
+            reg_dest: self.reg_dest,
+            alu_src: self.alu_src,
+            mem_to_reg: self.mem_to_reg,
+            reg_write: self.reg_write,
+            mem_read: self.mem_read,
+            mem_write: self.mem_write,
+            branch: self.branch,
+            jump: self.jump,
+            jump_reg: self.jump_reg,
+            shift: self.shift,
+            alu_op_0: self.alu_op_0,
+            alu_op_1: self.alu_op_1,
+            exit: self.exit,
+            zero_extend: self.zero_extend,
+        }
+    }
+
 }
 
 #[cfg(test)]
@@ -205,6 +220,7 @@ mod tests {
         assert!(control.reg_write());
         assert!(control.alu_src());
 
+        assert!(!control.zero_extend());
         assert!(!control.reg_dest());
         assert!(!control.jump());
         assert!(!control.branch());
@@ -221,6 +237,7 @@ mod tests {
         let ori_op_bits = u6::new(13);
         control.set_output_flags(ori_op_bits, u6::new(0));
 
+        assert!(control.zero_extend());
         assert!(control.reg_write());
         assert!(control.alu_src());
         assert!(control.alu_op_0());
@@ -277,6 +294,21 @@ mod tests {
         assert!(!control.mem_write());
     }
 
+    #[test]
+    fn test_output_flags_sll() {
+        let mut control = Control::new();
+        control.set_output_flags(u6::new(0), u6::new(0));
+
+        assert!(control.alu_op_1());
+        assert!(control.reg_dest());
+        assert!(control.reg_write());
+        assert!(control.shift());
+
+        assert!(!control.alu_op_0());
+        assert!(!control.alu_src());
+        assert!(!control.mem_write());
+    }
+
     #[test]
     fn test_output_flags_slt() {
         let mut control = Control::new();
@@ -300,4 +332,39 @@ mod tests {
         assert!(!control.jump_reg());
     }
 
+    #[test]
+    fn test_signals_of_every_instruction() {
+        for instruction in &spec::INSTRUCTIONS {
+            let mut control = Control::new();
+            control.set_output_flags(u6::new(instruction.opcode), u6::new(instruction.funct));
+            let signals = control.signals();
+            assert_eq!(signals.bits(), instruction.control, "{}", instruction.mnemonic);
+            let named: Vec<&str> = signals.named().iter().filter(|(_, set)| *set).map(|(name, _)| *name).collect();
+            assert_eq!(named, instruction.control_names(), "{}", instruction.mnemonic);
+        }
+    }
+
+    #[test]
+    fn test_output_flags_exit_and_jumps() {
+        let mut control = Control::new();
+        control.set_output_flags(u6::new(63), u6::new(63));
+        assert!(control.exit());
+        assert!(!control.reg_write());
+
+        control.set_output_flags(u6::new(0), u6::new(8));
+        assert!(control.jump_reg());
+        assert!(!control.exit());
+        assert!(!control.reg_write());
+
+        control.set_output_flags(u6::new(2), u6::new(0));
+        assert!(control.jump());
+        assert!(!control.jump_reg());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_output_flags_unknown_funct() {
+        Control::new().set_output_flags(u6::new(0), u6::new(1));
+    }
+
 }
@@ -1,25 +1,43 @@
 //!  This file contains the datamemory of the MIPS processor. 
-//! It is built as a vector of 1000 bytes, and each word is 4 bytes in this implementation.
+//! It is built as a vector of at least 1000 bytes, and each word is 4 bytes in this implementation.
+//! The memory starts at a base address, 0 unless a program places its data elsewhere.
 //!  
 //! Author: mai21asm, c19hln
 //! Since: 2022-11-25
 //! Version: 1.0
 
+const MIN_SIZE: usize = 1000;
+
 pub struct DataMemory {
     contents: Vec<u8>,
+    /// address of the first byte
+    base: usize,
+    /// the contents the memory is reset to
+    initial: Vec<u8>,
 }
 
 impl DataMemory {
     /// Creates 1000 byte of byte-addressable
     /// memory, each byte initialized to 0.
     pub fn new() -> DataMemory {
+        DataMemory::load(Vec::new(), 0)
+    }
+
+    /// Creates memory starting at address `base` that holds `initial` followed by zeroes,
+    /// at least 1000 bytes in total.
+    pub fn load(initial: Vec<u8>, base: usize) -> DataMemory {
+        let mut contents = initial.clone();
+        contents.resize(initial.len().max(MIN_SIZE), 0);
         DataMemory {
-            contents: vec![0; 1000],
+            contents,
+            base,
+            initial,
         }
     }
 
     #[allow(unused)] // might be needed later
-    pub fn get_byte(&self, idx: usize) -> Option<u8> {
+    pub fn get_byte(&self, addr: usize) -> Option<u8> {
+        let idx = addr.checked_sub(self.base)?;
         if idx >= self.contents.len() {
             return None;
         }
@@ -27,8 +45,9 @@ impl DataMemory {
         Some(self.contents[idx])
     }
 
-    pub fn get_word(&self, word_addr: usize, mem_read_sig: bool) -> Option<i32> {
+    pub fn get_word(&self, addr: usize, mem_read_sig: bool) -> Option<i32> {
         if mem_read_sig {
+            let word_addr = addr.checked_sub(self.base)?;
             if word_addr % 4 == 0 && word_addr < self.contents.len() {
                 
                 // all word addresses should be multiples of 4 I think
@@ -56,7 +75,12 @@ impl DataMemory {
                 return Err("Only allowed to write words on addresses that are multiples of 4.");
             }
 
-            if address + 4 >= self.contents.len() {
+            let address = match address.checked_sub(self.base) {
+                Some(address) => address,
+                None => panic!("Trying to write outside of data memory!"),
+            };
+
+            if address + 4 > self.contents.len() {
                 panic!("Trying to write outside of data memory!");
             }
 
@@ -76,14 +100,15 @@ impl DataMemory {
     pub fn get_contents(&self) -> Vec<(u32, u8)> {
         let mut v:Vec<(u32, u8)> = Vec::new();
         for (idx, val) in self.contents.iter().enumerate() {
-            v.push((idx as u32, *val));
+            v.push(((self.base + idx) as u32, *val));
         }
 
         v
     }
 
     pub fn reset_contents(&mut self) {
-        self.contents = vec![0; 1000];
+        self.contents = self.initial.clone();
+        self.contents.resize(self.initial.len().max(MIN_SIZE), 0);
     }
 }
 
@@ -121,9 +146,33 @@ mod tests {
 
     #[test]
     #[should_panic]
-    fn test_write_word_last_byte_outside_mem() {
+    fn test_write_word_past_end_of_mem() {
+        let mut data_mem = DataMemory::new();
+        let _write_res = data_mem.write_word_to_address(1000, 123, true);
+    }
+
+    #[test]
+    fn test_write_word_to_last_word() {
         let mut data_mem = DataMemory::new();
-        let _write_res = data_mem.write_word_to_address(996, 123, true);
+        let write_res = data_mem.write_word_to_address(996, 123, true);
+        assert!(write_res.is_ok());
+        assert_eq!(data_mem.get_word(996, true), Some(123));
+        assert_eq!(data_mem.get_byte(999), Some(123));
+    }
+
+    #[test]
+    fn test_write_word_to_last_word_at_base() {
+        let mut data_mem = DataMemory::load(vec![0; 8], 0x10010000);
+        let last = 0x10010000 + MIN_SIZE - 4;
+        assert!(data_mem.write_word_to_address(last, -1, true).is_ok());
+        assert_eq!(data_mem.get_word(last, true), Some(-1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_write_word_past_end_of_mem_at_base() {
+        let mut data_mem = DataMemory::load(vec![0; 8], 0x10010000);
+        let _write_res = data_mem.write_word_to_address(0x10010000 + MIN_SIZE, 123, true);
     }
 
     #[test]
@@ -134,4 +183,17 @@ mod tests {
         let read_res = data_mem.get_word(8, true).unwrap();
         assert_eq!(read_res, 200);
     }
+
+    #[test]
+    fn test_load_at_base() {
+        let mut data_mem = DataMemory::load(vec![0, 0, 0, 10], 0x10010000);
+        assert_eq!(data_mem.get_word(0x10010000, true).unwrap(), 10);
+        assert!(data_mem.get_word(0, true).is_none());
+
+        data_mem.write_word_to_address(0x10010000, 7, true).unwrap();
+        assert_eq!(data_mem.get_word(0x10010000, true).unwrap(), 7);
+        data_mem.reset_contents();
+        assert_eq!(data_mem.get_word(0x10010000, true).unwrap(), 10);
+        assert_eq!(data_mem.get_contents()[1], (0x10010001, 0));
+    }
 }
@@ -0,0 +1,168 @@
+//!  The values on the wires of the single cycle datapath while an instruction is executed.
+//! The simulator keeps them for the instruction just executed, so that the datapath can be
+//! drawn, and can record them for every cycle, so that tests and graders can check what
+//! the datapath did rather than only the final state of the registers and memory.
+//!  
+//! Since: 2026-10-18
+//! Version: 1.0
+use darken_assignment1::assembler::spec::{signals, AluOperation};
+use darken_assignment1::trace::{TraceRecord, Write};
+
+/// The output of the control unit for an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ControlSignals {
+    pub reg_dest: bool,
+    pub alu_src: bool,
+    pub mem_to_reg: bool,
+    pub reg_write: bool,
+    pub mem_read: bool,
+    pub mem_write: bool,
+    pub branch: bool,
+    pub jump: bool,
+    pub jump_reg: bool,
+    pub shift: bool,
+    pub alu_op_0: bool,
+    pub alu_op_1: bool,
+    pub exit: bool,
+    pub zero_extend: bool,
+}
+
+impl ControlSignals {
+    /// The signals as the output pattern of the control unit, a bit per signal.
+    pub fn bits(&self) -> u16 {
+        [
+            (signals::ZERO_EXT, self.zero_extend),
+            (signals::EXIT, self.exit),
+            (signals::SHIFT, self.shift),
+            (signals::JUMP_REG, self.jump_reg),
+            (signals::JUMP, self.jump),
+            (signals::REG_DST, self.reg_dest),
+            (signals::ALU_SRC, self.alu_src),
+            (signals::MEM_TO_REG, self.mem_to_reg),
+            (signals::REG_WRITE, self.reg_write),
+            (signals::MEM_READ, self.mem_read),
+            (signals::MEM_WRITE, self.mem_write),
+            (signals::BRANCH, self.branch),
+            (signals::ALU_OP_1, self.alu_op_1),
+            (signals::ALU_OP_0, self.alu_op_0),
+        ]
+        .iter()
+        .filter(|(_, set)| *set)
+        .fold(0, |bits, (bit, _)| bits | bit)
+    }
+
+    /// Every signal along with its name, in the order of the bits of the control unit's
+    /// output pattern, most significant first.
+    pub fn named(&self) -> [(&'static str, bool); signals::NAMES.len()] {
+        let bits = self.bits();
+        signals::NAMES.map(|(bit, name)| (name, bits & bit != 0))
+    }
+}
+
+/// The input each of the eight multiplexors of the datapath selected, true for the
+/// input that is only used by some instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MuxSelections {
+    /// rd rather than rt as the register to write
+    pub reg_dest: bool,
+    /// the immediate rather than rt as the second ALU operand
+    pub alu_src: bool,
+    /// the zero extended rather than the sign extended immediate
+    pub zero_extend: bool,
+    /// rt and the shift amount as the ALU operands, used for both of them
+    pub shift: bool,
+    /// the word read from memory rather than the ALU result as the data to write
+    pub mem_to_reg: bool,
+    /// the branch target rather than pc + 4
+    pub branch: bool,
+    /// the jump target
+    pub jump: bool,
+    /// the value of rs as the next pc
+    pub jump_reg: bool,
+}
+
+/// A word read from or written to data memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub address: u32,
+    /// The word read, or the word written
+    pub value: i32,
+    /// The word at the address before it was written, none for a read
+    pub old_value: Option<i32>,
+}
+
+/// A value written to the register file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterWrite {
+    pub register: u8,
+    pub old_value: i32,
+    pub new_value: i32,
+}
+
+/// The values on the wires of the datapath for one instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleTrace {
+    /// The number of the cycle, starting at 0 for the first instruction after a reset
+    pub cycle: u64,
+    /// The address the instruction was fetched from
+    pub pc: u32,
+    pub instruction: u32,
+    pub pc_plus_4: u32,
+    pub control: ControlSignals,
+    /// Read register 1 and 2 of the register file and the values read from them
+    pub read_reg_1: u8,
+    pub read_reg_2: u8,
+    pub read_data_1: i32,
+    pub read_data_2: i32,
+    /// The sign extended immediate and the shift amount
+    pub imm: i32,
+    pub shamt: u32,
+    /// The operation selected by the ALU control
+    pub alu_signal: u8,
+    pub alu_input_1: i32,
+    pub alu_input_2: i32,
+    pub alu_result: i32,
+    pub zero: bool,
+    /// The word read from data memory, if it was read
+    pub mem_read_data: Option<i32>,
+    /// The register written to and the value on the write data port
+    pub write_reg: u8,
+    pub write_data: i32,
+    pub branch_target: u32,
+    pub jump_target: u32,
+    /// The address of the next instruction
+    pub next_pc: u32,
+    pub muxes: MuxSelections,
+    /// The word loaded from data memory
+    pub load: Option<MemoryAccess>,
+    /// The word stored to data memory
+    pub store: Option<MemoryAccess>,
+    /// The register written, none if no register was written or the write was to $zero
+    pub register_write: Option<RegisterWrite>,
+}
+
+impl CycleTrace {
+    /// The line of the execution trace for the instruction, with what it wrote.
+    pub fn to_record(&self) -> TraceRecord {
+        TraceRecord {
+            cycle: self.cycle,
+            pc: self.pc,
+            word: self.instruction,
+            register_write: self.register_write.map(|write| Write {
+                location: write.register,
+                old_value: write.old_value,
+                new_value: write.new_value,
+            }),
+            memory_write: self.store.map(|store| Write {
+                location: store.address,
+                old_value: store.old_value.unwrap_or(0),
+                new_value: store.value,
+            }),
+        }
+    }
+}
+
+/// The name of the operation the ALU performs for an ALU control signal.
+pub fn alu_operation_name(alu_signal: u8) -> &'static str {
+    AluOperation::from_signal(alu_signal).map_or("?", |operation| operation.name())
+}
@@ -12,6 +12,8 @@ use darken_assignment1::utils::InstrRepresentable;
 //#[derive(Debug)]
 pub struct InstructionMemory {
     instructions: Vec<Box<dyn InstrRepresentable>>,
+    /// address of the first instruction
+    base: usize,
 }
 
 impl InstructionMemory {
@@ -19,19 +21,29 @@ impl InstructionMemory {
     pub fn new() -> InstructionMemory {
         InstructionMemory {
             instructions: Vec::new(),
+            base: 0,
         }
     }
 
     pub fn load_instruction_memory(
         instrs: Vec<Box<dyn InstrRepresentable>>,
+    ) -> Box<InstructionMemory> {
+        InstructionMemory::load_instruction_memory_at(instrs, 0)
+    }
+
+    /// Loads the instructions so that the first one is at address `base`.
+    pub fn load_instruction_memory_at(
+        instrs: Vec<Box<dyn InstrRepresentable>>,
+        base: usize,
     ) -> Box<InstructionMemory> {
         Box::new(InstructionMemory {
             instructions: instrs,
+            base,
         })
     }
 
     pub fn get_instruction_from_pc(&self, pc: usize) -> Option<&Box<dyn InstrRepresentable>> {
-        let maybe_instruction = self.instructions.get(pc / 4);
+        let maybe_instruction = self.instructions.get(self.get_index_from_pc(pc)?);
         if maybe_instruction.is_none() {
             return None;
         }
@@ -45,15 +57,24 @@ impl InstructionMemory {
         self.instructions.get(idx)
     }
 
+    /// The index of the instruction at address `pc`, none if `pc` is below the first instruction.
+    pub fn get_index_from_pc(&self, pc: usize) -> Option<usize> {
+        Some(pc.checked_sub(self.base)? / 4)
+    }
+
     pub fn get_nb_instructions(&self) -> usize {
         self.instructions.len()
     }
 
-    pub fn get_contents(&self) -> Vec<(String, &Box<dyn InstrRepresentable>)> {
+    pub fn get_base_address(&self) -> usize {
+        self.base
+    }
+
+    pub fn get_contents(&self) -> Vec<(usize, &Box<dyn InstrRepresentable>)> {
         let mut v = Vec::new();
         for (idx, instr) in self.instructions.iter().enumerate() {
-            let addr = idx * 4;
-            v.push((addr.to_string(), instr));
+            let addr = self.base + idx * 4;
+            v.push((addr, instr));
         }
         v
     }
@@ -130,4 +151,24 @@ mod tests {
         );
         assert!(instruction_memory.get_instruction_from_pc(8).is_none());
     }
+
+    #[test]
+    pub fn test_get_instruction_from_pc_with_base() {
+        let v: Vec<Box<dyn InstrRepresentable>> = vec![Box::new(
+            RTypeInstruction::new("add", "$t1", "$t2", "$t0")
+                .expect("Could not create RTypeInstruction"),
+        )];
+
+        let instruction_memory = InstructionMemory::load_instruction_memory_at(v, 0x400000);
+
+        assert!(instruction_memory.get_instruction_from_pc(0).is_none());
+        assert_eq!(
+            instruction_memory
+                .get_instruction_from_pc(0x400000)
+                .unwrap()
+                .to_hex_string(),
+            "0x012a4020".to_string()
+        );
+        assert_eq!(instruction_memory.get_contents()[0].0, 0x400000);
+    }
 }
@@ -2,6 +2,7 @@ mod alu;
 mod alu_control;
 mod control;
 mod data_memory;
+pub mod datapath;
 mod instruction_memory;
 mod register_file;
 pub mod simulator;
@@ -17,7 +17,6 @@ impl Multiplexor {
         self.signal = signal;
     }
 
-    #[allow(unused)] // might be needed
     pub fn get_signal(&self) -> bool {
         self.signal
     }
@@ -11,9 +11,15 @@
 use super::{
     alu::Alu, alu_control, data_memory::DataMemory, multiplexor::Multiplexor, Control,
     InstructionMemory, RegisterFile, adder::Adder, shift_left_2::{self, ShiftLeftTwo},
+    datapath::{CycleTrace, MemoryAccess, MuxSelections, RegisterWrite},
 };
 
+use darken_assignment1::assembler::listing::ListingLine;
+use darken_assignment1::assembler::object::Section;
+use darken_assignment1::loader::MemoryImage;
+use darken_assignment1::stats::RunStats;
 use darken_assignment1::utils::InstrRepresentable;
+use std::collections::HashMap;
 use ux::{u26, u5, u6};
 
 pub struct Simulator {
@@ -23,6 +29,22 @@ pub struct Simulator {
     alu: Box<Alu>,
     control: Box<Control>,
     pc: usize,
+    /// where the pc starts and is reset to
+    entry: usize,
+    /// the labels of the program by address
+    labels: HashMap<u32, String>,
+    /// the addresses that hold no instruction the simulator can run, with the error running one gives
+    traps: HashMap<u32, String>,
+    /// the source lines of the program, if it was assembled from source
+    source: Vec<ListingLine>,
+    /// the number of instructions executed since the last reset
+    cycle: u64,
+    /// the values on the wires of the datapath for the instruction executed last
+    last_cycle: Option<CycleTrace>,
+    /// the trace of every cycle since recording started, if it is being recorded
+    trace: Option<Vec<CycleTrace>>,
+    /// the counts of the instructions executed since the last reset
+    stats: RunStats,
     write_reg_mplex: Box<Multiplexor>,
     alu_input_mplex: Box<Multiplexor>,
     data_mem_output_mplex: Box<Multiplexor>,
@@ -30,6 +52,7 @@ pub struct Simulator {
     jump_mplex: Box<Multiplexor>,
     shift_mplex: Box<Multiplexor>,
     jr_mplex: Box<Multiplexor>,
+    extend_mplex: Box<Multiplexor>,
     adder: Box<Adder>,
 }
 
@@ -42,13 +65,58 @@ pub enum RunResult {
 
 impl Simulator {
     pub fn new(instruction_mem: Vec<Box<dyn InstrRepresentable>>) -> Simulator {
+        Simulator::with_memories(
+            InstructionMemory::load_instruction_memory(instruction_mem),
+            DataMemory::new(),
+            0,
+            HashMap::new(),
+            HashMap::new(),
+            Vec::new(),
+        )
+    }
+
+    /// Creates a simulator with the instructions and data of `image` loaded at their
+    /// addresses and the pc at the entry point of the image.
+    pub fn load(image: MemoryImage) -> Simulator {
+        let labels = image.instruction_labels();
+        let instructions = image
+            .text
+            .into_iter()
+            .map(|instruction| Box::new(instruction) as Box<dyn InstrRepresentable>)
+            .collect();
+        Simulator::with_memories(
+            InstructionMemory::load_instruction_memory_at(instructions, image.text_base as usize),
+            DataMemory::load(image.data, image.data_base as usize),
+            image.entry as usize,
+            labels,
+            image.traps,
+            image.source,
+        )
+    }
+
+    fn with_memories(
+        instruction_mem: Box<InstructionMemory>,
+        data_mem: DataMemory,
+        entry: usize,
+        labels: HashMap<u32, String>,
+        traps: HashMap<u32, String>,
+        source: Vec<ListingLine>,
+    ) -> Simulator {
         Simulator {
-            instruction_mem: InstructionMemory::load_instruction_memory(instruction_mem),
-            data_mem: Box::new(DataMemory::new()),
+            instruction_mem,
+            data_mem: Box::new(data_mem),
             register_file: Box::new(RegisterFile::new()),
             alu: Box::new(Alu::new()),
             control: Box::new(Control::new()),
-            pc: 0,
+            pc: entry,
+            entry,
+            labels,
+            traps,
+            source,
+            cycle: 0,
+            last_cycle: None,
+            trace: None,
+            stats: RunStats::new(),
             write_reg_mplex: Box::new(Multiplexor::new()),
             alu_input_mplex: Box::new(Multiplexor::new()),
             data_mem_output_mplex: Box::new(Multiplexor::new()),
@@ -56,12 +124,17 @@ impl Simulator {
             jump_mplex: Box::new(Multiplexor::new()),
             jr_mplex: Box::new(Multiplexor::new()),
             shift_mplex: Box::new(Multiplexor::new()),
+            extend_mplex: Box::new(Multiplexor::new()),
             adder:Box::new(Adder {  })
         }
     }
 
     pub fn step(&mut self) -> RunResult {
         /* FETCH */
+        let fetched_pc = self.pc as u32;
+        if let Some(err_msg) = self.traps.get(&fetched_pc) {
+            return RunResult::Failure(err_msg.clone());
+        }
         let mby_instr = self.instruction_mem.get_instruction_from_pc(self.pc);
         self.pc = self.adder.add(self.pc as isize as i32, 4) as usize;
 
@@ -97,6 +170,7 @@ impl Simulator {
                 .set_signal(self.control.mem_to_reg());
             self.shift_mplex.set_signal(self.control.shift());
             self.jr_mplex.set_signal(self.control.jump_reg());
+            self.extend_mplex.set_signal(self.control.zero_extend());
 
             // have to check if control.reg_dst is set here, determines what we send to register file
             let reg_1 = self.get_reg_1(instr);
@@ -115,13 +189,14 @@ impl Simulator {
             );
 
             let imm = self.get_imm(instr);
+            let imm = self.extend_mplex.multiplex(Some(imm as u16 as i32), imm as i32);
  
             let shamt = self.get_shamt(instr);
             let shamt_val: u32 = shamt.into();
 
             let fst_alu_op = self.shift_mplex.multiplex(Some(read_val_2), read_val_1);
 
-            let snd_alu_op = self.alu_input_mplex.multiplex(Some(imm as i32), read_val_2);
+            let snd_alu_op = self.alu_input_mplex.multiplex(Some(imm), read_val_2);
 
             let snd_alu_op = self
                 .shift_mplex
@@ -132,6 +207,7 @@ impl Simulator {
             let alu_res = self.alu.perform_op(fst_alu_op, snd_alu_op, alu_signal);
 
             /* MEMREAD/MEMWRITE */
+            let old_word = self.data_mem.get_word(alu_res as usize, self.control.mem_write());
             self.data_mem
                 .write_word_to_address(alu_res as usize, read_val_2, self.control.mem_write())
                 .expect("Failed to write word to data memory!");
@@ -148,6 +224,7 @@ impl Simulator {
             let dst_reg = self
                 .write_reg_mplex
                 .multiplex(Some(u32::from(reg_3) as i32), u32::from(reg_2) as i32);
+            let old_reg_val = self.register_file.read_from_register(u5::new(dst_reg as u8));
 
             self.register_file.write_to_register(
                 u5::new(dst_reg as u8),
@@ -156,24 +233,90 @@ impl Simulator {
             );
 
             let shifter = ShiftLeftTwo::new();
-            let jump_target = shifter.shift(self.get_jump_target(instr).into()) as i32;
+            // the target replaces the low 28 bits of the pc, the region of the pc is kept
+            let jump_target =
+                ((self.pc as u32 & 0xf000_0000) | shifter.shift(self.get_jump_target(instr).into())) as i32;
 
             self.beq_mplex.set_signal(self.control.branch() && self.alu.zero());
-            let beq_target = self.adder.add(self.pc as isize as i32, imm as isize as i32) as i32;
+            let beq_offset = shifter.shift(imm as u32) as i32;
+            let beq_target = self.adder.add(self.pc as isize as i32, beq_offset);
             let pc_or_beq_target = self.beq_mplex.multiplex(Some(beq_target), self.pc as isize as i32);
             
 
             // either pc just stays the way it is or it is the new value from jumping..
-            let pc_or_beq_or_jmp = self.jump_mplex.multiplex(Some(jump_target), pc_or_beq_target as i32) as usize; 
+            let pc_or_beq_or_jmp = self.jump_mplex.multiplex(Some(jump_target), pc_or_beq_target) as usize; 
             self.pc = self.jr_mplex.multiplex(Some(read_val_1), pc_or_beq_or_jmp as isize as i32) as usize;
 
 
+            let control = self.control.signals();
+            let new_reg_val = self.register_file.read_from_register(u5::new(dst_reg as u8));
+            let cycle = CycleTrace {
+                cycle: self.cycle,
+                pc: fetched_pc,
+                instruction: u32::from_str_radix(&instr.get_bits(31, 0).unwrap(), 2).unwrap(),
+                pc_plus_4: fetched_pc.wrapping_add(4),
+                control,
+                read_reg_1: reg_1.into(),
+                read_reg_2: reg_2.into(),
+                read_data_1: read_val_1,
+                read_data_2: read_val_2,
+                imm,
+                shamt: shamt_val,
+                alu_signal: alu_signal.into(),
+                alu_input_1: fst_alu_op,
+                alu_input_2: snd_alu_op,
+                alu_result: alu_res,
+                zero: self.alu.zero(),
+                mem_read_data: read_data,
+                write_reg: dst_reg as u8,
+                write_data: write_to_reg_val,
+                branch_target: beq_target as u32,
+                jump_target: jump_target as u32,
+                next_pc: self.pc as u32,
+                muxes: MuxSelections {
+                    reg_dest: control.reg_dest,
+                    alu_src: control.alu_src,
+                    zero_extend: control.zero_extend,
+                    shift: control.shift,
+                    mem_to_reg: control.mem_to_reg,
+                    branch: self.beq_mplex.get_signal(),
+                    jump: control.jump,
+                    jump_reg: control.jump_reg,
+                },
+                load: read_data.map(|value| MemoryAccess {
+                    address: alu_res as u32,
+                    value,
+                    old_value: None,
+                }),
+                store: old_word.map(|old_value| MemoryAccess {
+                    address: alu_res as u32,
+                    value: read_val_2,
+                    old_value: Some(old_value),
+                }),
+                register_write: match control.reg_write && dst_reg != 0 {
+                    true => Some(RegisterWrite {
+                        register: dst_reg as u8,
+                        old_value: old_reg_val,
+                        new_value: new_reg_val,
+                    }),
+                    false => None,
+                },
+            };
+            self.cycle += 1;
+            self.stats.add(cycle.instruction, cycle.muxes.branch);
+            if let Some(trace) = &mut self.trace {
+                trace.push(cycle.clone());
+            }
+            self.last_cycle = Some(cycle);
+
             if self.control.exit() {
                 return RunResult::Completed;
             }
             
             RunResult::Success
-        } else if (self.pc / 4) - 1 == self.instruction_mem.get_nb_instructions() {
+        } else if self.instruction_mem.get_index_from_pc(self.pc)
+            == Some(self.instruction_mem.get_nb_instructions() + 1)
+        {
             return RunResult::Completed;
         } else {
             let err_msg = format!(
@@ -251,12 +394,78 @@ impl Simulator {
         self.data_mem.get_contents()
     }
 
-    pub fn get_instr_mem(&self) -> Vec<(String, &Box<dyn InstrRepresentable>)> {
+    pub fn get_instr_mem(&self) -> Vec<(usize, &Box<dyn InstrRepresentable>)> {
         self.instruction_mem.get_contents()
     }
 
+    /// The index in instruction memory of the instruction the pc points at.
+    pub fn get_current_instr_idx(&self) -> Option<usize> {
+        self.instruction_mem.get_index_from_pc(self.pc)
+    }
+
+    /// The labels of the instruction at the given address, separated by commas, if there are any.
+    pub fn get_label(&self, addr: usize) -> Option<&str> {
+        self.labels.get(&(addr as u32)).map(|label| label.as_str())
+    }
+
+    /// The labels of the instructions by address, labels at the same address separated by commas.
+    pub fn get_labels(&self) -> &HashMap<u32, String> {
+        &self.labels
+    }
+
+    /// The source lines of the program, empty if it was loaded without its source.
+    pub fn get_source(&self) -> &[ListingLine] {
+        &self.source
+    }
+
+    /// The index in the source lines of the instruction the pc points at.
+    pub fn get_current_source_idx(&self) -> Option<usize> {
+        self.source.iter().position(|line| {
+            line.section == Some(Section::Text) && line.size > 0 && line.address == Some(self.pc as u32)
+        })
+    }
+
+    /// The values on the wires of the datapath for the instruction executed last,
+    /// none before the first instruction.
+    pub fn get_last_cycle(&self) -> Option<&CycleTrace> {
+        self.last_cycle.as_ref()
+    }
+
+    /// The number of instructions executed since the last reset.
+    pub fn get_cycle_count(&self) -> u64 {
+        self.cycle
+    }
+
+    /// The counts of the instructions executed since the last reset.
+    pub fn get_stats(&self) -> &RunStats {
+        &self.stats
+    }
+
+    /// Starts or stops recording the trace of every cycle. Starting clears the trace
+    /// recorded so far.
+    pub fn record_trace(&mut self, record: bool) {
+        self.trace = match record {
+            true => Some(Vec::new()),
+            false => None,
+        };
+    }
+
+    /// Takes the cycles recorded since recording started or the trace was last taken.
+    pub fn take_trace(&mut self) -> Vec<CycleTrace> {
+        match &mut self.trace {
+            Some(trace) => std::mem::take(trace),
+            None => Vec::new(),
+        }
+    }
+
     pub fn reset(&mut self) {
-        self.pc = 0;
+        self.pc = self.entry;
+        self.cycle = 0;
+        self.last_cycle = None;
+        self.stats = RunStats::new();
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
         self.data_mem.reset_contents();
         self.register_file.reset_registers();
     }
@@ -523,8 +732,176 @@ mod tests {
         
     }
 
+    #[test]
+    fn test_run_elf_executable() {
+        let image = darken_assignment1::loader::load_elf(include_bytes!("../../test_files/elf/sum.elf")).unwrap();
+        let mut simulator = Simulator::load(image);
+        assert_eq!(simulator.get_current_pc(), 0x400004);
+        assert_eq!(simulator.get_label(0x40000c), Some("loop"));
+
+        let mut steps = 0;
+        while simulator.step() == RunResult::Success {
+            steps += 1;
+            assert!(steps < 1000, "the program should stop at exit");
+        }
 
-    
+        let t2: u8 = get_register_number("$t2").unwrap().into();
+        let t3: u8 = get_register_number("$t3").unwrap().into();
+        assert_eq!(simulator.get_registers()[t2 as usize].1, 55);
+        // the instruction before the entry point is never run
+        assert_eq!(simulator.get_registers()[t3 as usize].1, 0);
+        assert_eq!(simulator.data_mem.get_word(0x1004, true), Some(55));
 
-    
+        simulator.reset();
+        assert_eq!(simulator.get_current_pc(), 0x400004);
+        assert_eq!(simulator.data_mem.get_word(0x1000, true), Some(10));
+        assert_eq!(simulator.data_mem.get_word(0x1004, true), Some(0));
+    }
+
+    #[test]
+    fn test_trap_only_when_run() {
+        let source = "j end\nnop\nend: addi $t0, $zero, 1\nnop\nexit";
+        let program = crate::assembler::assemble(source, &crate::assembler::AssemblerOptions::default()).unwrap();
+        let mut image = MemoryImage::from(program);
+        image.traps.insert(4, "unsupported instruction 0xfc000000 at address 0x00000004".to_string());
+        image.traps.insert(12, "unsupported instruction 0xfc000000 at address 0x0000000c".to_string());
+        let mut simulator = Simulator::load(image);
+        assert_eq!(simulator.step(), RunResult::Success);
+        assert_eq!(simulator.step(), RunResult::Success);
+        assert_eq!(
+            simulator.step(),
+            RunResult::Failure("unsupported instruction 0xfc000000 at address 0x0000000c".to_string())
+        );
+    }
+
+    #[test]
+    fn test_assembled_data_is_loaded() {
+        let source = "main: lw $t0, value($zero)\n\tnop\n\t.data\n\t.word 0\nvalue: .word 42\n";
+        let program = crate::assembler::assemble(source, &crate::assembler::AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        assert_eq!(simulator.get_label(0), Some("main"));
+        simulator.step();
+        let t0: u8 = get_register_number("$t0").unwrap().into();
+        assert_eq!(simulator.get_registers()[t0 as usize].1, 42);
+        // `value` is a data label, not the label of the second instruction
+        assert_eq!(simulator.get_label(4), None);
+    }
+
+    #[test]
+    fn test_run_machine_code() {
+        let source = "addi $t0, $zero, 3\nloop: beq $t0, $zero, end\n\taddi $t0, $t0, -1\n\tadd $t1, $t1, $t0\n\tj loop\nend: exit\n";
+        let program = crate::assembler::assemble(source, &crate::assembler::AssemblerOptions::default()).unwrap();
+        let text = program.instruction_output();
+        let image = darken_assignment1::disassembler::load_machine_code(text.as_bytes(), None).unwrap();
+        let mut simulator = Simulator::load(image);
+        assert_eq!(simulator.get_label(4), Some("L_00000004"));
+
+        let mut steps = 0;
+        while simulator.step() == RunResult::Success {
+            steps += 1;
+            assert!(steps < 1000, "the program should stop at exit");
+        }
+        let t1: u8 = get_register_number("$t1").unwrap().into();
+        assert_eq!(simulator.get_registers()[t1 as usize].1, 3);
+    }
+
+    #[test]
+    fn test_current_source_line() {
+        let source = "# counts down\nmain: addi $t0, $zero, 1\n\nloop: beq $t0, $zero, end\n\taddi $t0, $t0, -1\n\tj loop\nend: exit\n";
+        let program = crate::assembler::assemble(source, &crate::assembler::AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        assert_eq!(simulator.get_source().len(), 7);
+        assert_eq!(simulator.get_current_source_idx(), Some(1));
+        simulator.step();
+        assert_eq!(simulator.get_current_source_idx(), Some(3));
+        simulator.step();
+        simulator.step();
+        assert_eq!(simulator.get_source()[simulator.get_current_source_idx().unwrap()].source, "\tj loop");
+
+        // a program without its source has no current line
+        let simulator = Simulator::new(Vec::new());
+        assert_eq!(simulator.get_current_source_idx(), None);
+    }
+
+    #[test]
+    fn test_beq_offset_is_in_words() {
+        // the offset field counts words from the next instruction, it is shifted left by two
+        let source = "beq $zero, $zero, forward\nback: exit\nnop\nforward: beq $zero, $zero, back\n";
+        let program = crate::assembler::assemble(source, &crate::assembler::AssemblerOptions::default()).unwrap();
+        assert_eq!(program.text[0].to_hex_string(), "0x10000002");
+        assert_eq!(program.text[3].to_hex_string(), "0x1000fffd");
+        let instructions = program
+            .text
+            .into_iter()
+            .map(|instruction| Box::new(instruction) as Box<dyn InstrRepresentable>)
+            .collect();
+        let mut simulator = Simulator::new(instructions);
+
+        assert_eq!(simulator.step(), RunResult::Success);
+        assert_eq!(simulator.get_current_pc(), 12);
+        assert_eq!(simulator.step(), RunResult::Success);
+        assert_eq!(simulator.get_current_pc(), 4);
+        assert_eq!(simulator.step(), RunResult::Completed);
+    }
+
+    #[test]
+    fn test_cycle_trace() {
+        let source = "addi $t0, $zero, 5\nsw $t0, 4($zero)\nlw $t1, 4($zero)\nbeq $t0, $t1, end\nnop\nend: exit\n";
+        let program = crate::assembler::assemble(source, &crate::assembler::AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        assert!(simulator.get_last_cycle().is_none());
+        simulator.record_trace(true);
+        while simulator.step() == RunResult::Success {}
+        let trace = simulator.take_trace();
+        assert_eq!(trace.len(), 5);
+        assert_eq!(simulator.get_cycle_count(), 5);
+
+        let addi = &trace[0];
+        assert_eq!((addi.cycle, addi.pc, addi.instruction, addi.next_pc), (0, 0, 0x2008_0005, 4));
+        assert!(addi.control.alu_src && addi.control.reg_write && !addi.control.reg_dest);
+        assert!(addi.muxes.alu_src && !addi.muxes.mem_to_reg);
+        assert_eq!((addi.alu_input_1, addi.alu_input_2, addi.alu_result), (0, 5, 5));
+        let write = RegisterWrite { register: 8, old_value: 0, new_value: 5 };
+        assert_eq!(addi.register_write, Some(write));
+        assert_eq!((addi.load, addi.store), (None, None));
+
+        let sw = &trace[1];
+        assert_eq!(sw.store, Some(MemoryAccess { address: 4, value: 5, old_value: Some(0) }));
+        assert_eq!(sw.register_write, None);
+        assert_eq!(
+            sw.to_record().to_line(),
+            "     1 0x00000004 0xac080004  sw $t0, 4($zero)         | mem[0x00000004]: 0 -> 5"
+        );
+
+        let lw = &trace[2];
+        assert!(lw.control.mem_read && lw.muxes.mem_to_reg);
+        assert_eq!(lw.load, Some(MemoryAccess { address: 4, value: 5, old_value: None }));
+        assert_eq!((lw.write_reg, lw.write_data), (9, 5));
+
+        let beq = &trace[3];
+        assert!(beq.control.branch && beq.zero && beq.muxes.branch);
+        assert_eq!((beq.branch_target, beq.next_pc), (20, 20));
+        assert!(trace[4].control.exit);
+        assert_eq!(simulator.get_last_cycle(), trace.last());
+
+        simulator.reset();
+        assert!(simulator.get_last_cycle().is_none());
+        assert_eq!(simulator.get_cycle_count(), 0);
+    }
+
+    #[test]
+    fn test_run_stats() {
+        let source = "addi $t0, $zero, 1\nloop: addi $t0, $t0, -1\nbeq $t0, $zero, loop\nexit\n";
+        let program = crate::assembler::assemble(source, &crate::assembler::AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        while simulator.step() == RunResult::Success {}
+
+        let stats = simulator.get_stats();
+        assert_eq!(stats.instructions, 6);
+        assert_eq!(stats.by_mnemonic["addi"], 3);
+        assert_eq!((stats.branches_taken, stats.branches_not_taken), (1, 1));
+
+        simulator.reset();
+        assert_eq!(simulator.get_stats().instructions, 0);
+    }
 }
@@ -0,0 +1,323 @@
+//! A profile of a run: how often the instruction at every address was executed and the
+//! cycles it took on a model of the datapath, added up per label to find the parts of a
+//! program where the time goes. Every instruction belongs to the closest label before it,
+//! so that a label starting a loop or a function collects the instructions of its body.
+//!
+//! File: profile.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::listing::ListingLine;
+use crate::assembler::spec::DecodedInstruction;
+use crate::stats::Engine;
+use std::collections::{BTreeMap, HashMap};
+
+/// The name the instructions before the first label are counted under.
+pub const NO_LABEL: &str = "(no label)";
+
+/// The executions of the instruction at an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressProfile {
+    pub word: u32,
+    pub count: u64,
+    pub cycles: u64,
+}
+
+/// The executions of the instructions from a label up to the next label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelProfile {
+    pub label: String,
+    /// The address of the label, none for the instructions before the first label
+    pub address: Option<u32>,
+    pub count: u64,
+    pub cycles: u64,
+}
+
+/// The executions of every address, with the cycles modelled on an engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    engine: Engine,
+    addresses: BTreeMap<u32, AddressProfile>,
+}
+
+/// The share of `part` in `total` as a percentage.
+fn share(part: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        total => part as f64 * 100.0 / total as f64,
+    }
+}
+
+impl Profile {
+    /// Starts an empty profile with cycles as `engine` takes them.
+    pub fn new(engine: Engine) -> Profile {
+        Profile {
+            engine,
+            addresses: BTreeMap::new(),
+        }
+    }
+
+    /// add: counts an execution of the instruction at an address.
+    ///
+    /// input: pc - the address of the instruction
+    ///        word - its machine word
+    ///
+    pub fn add(&mut self, pc: u32, word: u32) {
+        let cycles = self.engine.word_cycles(word);
+        let entry = self.addresses.entry(pc).or_insert(AddressProfile {
+            word,
+            count: 0,
+            cycles: 0,
+        });
+        entry.word = word;
+        entry.count += 1;
+        entry.cycles += cycles;
+    }
+
+    /// The executions of the instruction at an address, none if it never ran.
+    pub fn at(&self, address: u32) -> Option<&AddressProfile> {
+        self.addresses.get(&address)
+    }
+
+    pub fn total_count(&self) -> u64 {
+        self.addresses.values().map(|address| address.count).sum()
+    }
+
+    pub fn total_cycles(&self) -> u64 {
+        self.addresses.values().map(|address| address.cycles).sum()
+    }
+
+    /// hot_spots: every address that ran, the most cycles first.
+    ///
+    /// returns: the addresses and their executions
+    ///
+    pub fn hot_spots(&self) -> Vec<(u32, AddressProfile)> {
+        let mut addresses: Vec<(u32, AddressProfile)> = self
+            .addresses
+            .iter()
+            .map(|(address, profile)| (*address, *profile))
+            .collect();
+        addresses.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles).then(a.0.cmp(&b.0)));
+        addresses
+    }
+
+    /// by_label: the executions added up per label, the most cycles first.
+    ///
+    /// input: labels - the labels of the program by address
+    /// returns: every label that instructions ran under
+    ///
+    pub fn by_label(&self, labels: &HashMap<u32, String>) -> Vec<LabelProfile> {
+        let starts: BTreeMap<u32, &String> = labels
+            .iter()
+            .map(|(address, label)| (*address, label))
+            .collect();
+        let mut totals: BTreeMap<Option<u32>, (u64, u64)> = BTreeMap::new();
+        for (address, profile) in &self.addresses {
+            let start = starts
+                .range(..=address)
+                .next_back()
+                .map(|(start, _)| *start);
+            let total = totals.entry(start).or_insert((0, 0));
+            total.0 += profile.count;
+            total.1 += profile.cycles;
+        }
+        let mut labels: Vec<LabelProfile> = totals
+            .into_iter()
+            .map(|(address, (count, cycles))| LabelProfile {
+                label: address.map_or(NO_LABEL.to_string(), |address| starts[&address].clone()),
+                address,
+                count,
+                cycles,
+            })
+            .collect();
+        labels.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.address.cmp(&b.address)));
+        labels
+    }
+
+    /// report: the hot-spot report, the labels and then the addresses that took the most
+    /// cycles.
+    ///
+    /// input: labels - the labels of the program by address
+    ///        source - the listing of the program, used to show the source of an
+    ///                 instruction, the disassembly is shown without it
+    /// returns: the report
+    ///
+    pub fn report(&self, labels: &HashMap<u32, String>, source: &[ListingLine]) -> String {
+        let total_count = self.total_count();
+        let total_cycles = self.total_cycles();
+        let mut report = format!(
+            "Profile: {} instructions, {} cycles on the {} datapath\n\nBy label:\n",
+            total_count,
+            total_cycles,
+            self.engine.name()
+        );
+        report.push_str(&format!(
+            "  {:<20} {:>10} {:>10} {:>7}\n",
+            "label", "count", "cycles", "share"
+        ));
+        for label in self.by_label(labels) {
+            report.push_str(&format!(
+                "  {:<20} {:>10} {:>10} {:>6.1}%\n",
+                label.label,
+                label.count,
+                label.cycles,
+                share(label.cycles, total_cycles)
+            ));
+        }
+
+        let text: HashMap<u32, &str> = source
+            .iter()
+            .filter_map(|line| Some((line.instruction_address()?, line.source.trim())))
+            .collect();
+        report.push_str("\nBy address:\n");
+        report.push_str(&format!(
+            "  {:<10} {:>10} {:>10} {:>7}  instruction\n",
+            "address", "count", "cycles", "share"
+        ));
+        for (address, profile) in self.hot_spots() {
+            let instruction = match text.get(&address) {
+                Some(text) => text.to_string(),
+                None => DecodedInstruction::decode(profile.word)
+                    .map_or(format!(".word {:#010x}", profile.word), |instruction| {
+                        instruction.to_string()
+                    }),
+            };
+            report.push_str(&format!(
+                "  {:#010x} {:>10} {:>10} {:>6.1}%  {}\n",
+                address,
+                profile.count,
+                profile.cycles,
+                share(profile.cycles, total_cycles),
+                instruction
+            ));
+        }
+        report
+    }
+
+    /// annotated_listing: the listing of the program with the executions of every
+    /// instruction in front of its line. Lines that are not instructions get no counts.
+    ///
+    /// input: source - the listing of the program
+    /// returns: the listing, one line per source line
+    ///
+    pub fn annotated_listing(&self, source: &[ListingLine]) -> String {
+        let mut listing = format!(
+            "Cycles on the {} datapath\n{:>10} {:>10}  {:<10}  source\n",
+            self.engine.name(),
+            "count",
+            "cycles",
+            "address"
+        );
+        for line in source {
+            let counts = match line.instruction_address() {
+                Some(address) => {
+                    let profile = self.at(address);
+                    format!(
+                        "{:>10} {:>10}  {:#010x}",
+                        profile.map_or(0, |profile| profile.count),
+                        profile.map_or(0, |profile| profile.cycles),
+                        address
+                    )
+                }
+                None => String::new(),
+            };
+            listing.push_str(format!("{:<33}  {}", counts, line.source).trim_end());
+            listing.push('\n');
+        }
+        listing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, AssemblerOptions};
+    use crate::interpreter::{Interpreter, Status};
+    use crate::loader::MemoryImage;
+
+    #[test]
+    fn test_profile() {
+        let source = "addi $t0, $zero, 2\nloop: lw $t1, 0($zero)\naddi $t0, $t0, -1\n\
+                      beq $t0, $zero, done\nj loop\n# the end\ndone: exit\n";
+        let image = MemoryImage::from(assemble(source, &AssemblerOptions::default()).unwrap());
+        let mut interpreter = Interpreter::new(&image);
+        let mut profile = Profile::new(Engine::MultiCycle);
+        loop {
+            let (record, status) = interpreter.step().unwrap();
+            let record = record.unwrap();
+            profile.add(record.pc, record.word);
+            if status == Status::Exited {
+                break;
+            }
+        }
+
+        assert_eq!(profile.total_count(), 9);
+        assert_eq!(
+            profile.at(4),
+            Some(&AddressProfile {
+                word: 0x8c09_0000,
+                count: 2,
+                cycles: 10
+            })
+        );
+        assert_eq!(profile.at(24), None);
+        assert_eq!(profile.hot_spots()[0].0, 4);
+
+        let labels = image.instruction_labels();
+        let by_label = profile.by_label(&labels);
+        assert_eq!(
+            by_label,
+            vec![
+                LabelProfile {
+                    label: "loop".to_string(),
+                    address: Some(4),
+                    count: 7,
+                    cycles: 27
+                },
+                LabelProfile {
+                    label: NO_LABEL.to_string(),
+                    address: None,
+                    count: 1,
+                    cycles: 4
+                },
+                LabelProfile {
+                    label: "done".to_string(),
+                    address: Some(20),
+                    count: 1,
+                    cycles: 3
+                },
+            ]
+        );
+
+        let report = profile.report(&labels, &image.source);
+        assert!(
+            report.starts_with("Profile: 9 instructions, 34 cycles on the multi-cycle datapath\n\nBy label:\n")
+        );
+        assert!(report.contains("  loop                          7         27   79.4%\n"));
+        assert!(
+            report.contains("  0x00000004          2         10   29.4%  loop: lw $t1, 0($zero)\n")
+        );
+
+        let listing = profile.annotated_listing(&image.source);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines[0], "Cycles on the multi-cycle datapath");
+        assert_eq!(
+            lines[3],
+            "         2         10  0x00000004  loop: lw $t1, 0($zero)"
+        );
+        assert_eq!(lines[7], "                                   # the end");
+        // a word that is not an instruction takes as many cycles as in the statistics
+        let mut unknown = Profile::new(Engine::MultiCycle);
+        unknown.add(0, 0xfc00_0000);
+        let mut stats = crate::stats::RunStats::new();
+        stats.add(0xfc00_0000, false);
+        assert_eq!(unknown.total_cycles(), stats.cycles(Engine::MultiCycle));
+
+        // without the source, the report shows the disassembly
+        assert!(profile
+            .report(&HashMap::new(), &[])
+            .contains("  (no label)"));
+        assert!(profile
+            .report(&HashMap::new(), &[])
+            .contains("lw $t1, 0($zero)\n"));
+    }
+}
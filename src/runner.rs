@@ -0,0 +1,328 @@
+//!  Runs a program without the terminal user interface, for scripts and graders. The
+//! program runs until it exits, after which the registers that are not zero are printed,
+//! followed by the statistics of the run. A profile of the run can be written as a
+//! hot-spot report or as the listing with the executions of every instruction, and the
+//! coverage of the run as an annotated listing or as an lcov tracefile.
+//!  
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::lockstep::Lockstep;
+use crate::mips::simulator::{RunResult, Simulator};
+use crate::vcd::VcdWriter;
+use darken_assignment1::coverage::Coverage;
+use darken_assignment1::profile::Profile;
+use darken_assignment1::stats::Engine;
+use std::fs;
+
+/// The number of cycles after which a program is taken to never exit.
+pub const MAX_CYCLES: u64 = 1_000_000;
+
+/// The datapath the cycles of a profile are modelled on unless `-P` is given. Every
+/// instruction takes a cycle on the single cycle datapath the simulator implements, so
+/// the multi-cycle one tells more about where the time goes.
+pub const PROFILE_ENGINE: Engine = Engine::MultiCycle;
+
+/// The options of the simulator on the command line, the others are for the assembler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOptions {
+    /// Run without the user interface, given with `-r`
+    pub headless: bool,
+    /// Where to write the signals of every cycle as a VCD file, given with `-w`
+    pub vcd_path: Option<String>,
+    /// Where to write the instructions executed as a text trace, given with `-t`
+    pub trace_path: Option<String>,
+    /// Where to write the hot-spot report of the run, given with `-p`
+    pub profile_path: Option<String>,
+    /// Where to write the listing with the executions of every instruction, given with `-a`
+    pub annotated_path: Option<String>,
+    /// The datapath the cycles of the profile are modelled on, given with `-P`
+    pub profile_engine: Engine,
+    /// Where to write the listing with the coverage of every line, given with `-v`
+    pub coverage_path: Option<String>,
+    /// Where to write the coverage as an lcov tracefile, given with `-V`
+    pub lcov_path: Option<String>,
+    /// Check every instruction against the reference interpreter, given with `-x`
+    pub check: bool,
+    /// The number of cycles the program may run for
+    pub max_cycles: u64,
+}
+
+impl Default for RunOptions {
+    fn default() -> RunOptions {
+        RunOptions {
+            headless: false,
+            vcd_path: None,
+            trace_path: None,
+            profile_path: None,
+            annotated_path: None,
+            profile_engine: PROFILE_ENGINE,
+            coverage_path: None,
+            lcov_path: None,
+            check: false,
+            max_cycles: MAX_CYCLES,
+        }
+    }
+}
+
+/// parse_run_options: takes the options of the simulator out of the command line.
+/// `-w`, `-t`, `-p`, `-a`, `-v`, `-V` and `-x` run the program without the user
+/// interface, as they act on every instruction while running.
+///
+/// input: args - the command line, starting with the program name
+/// returns: the options and the rest of the command line, or an error if `-w`, `-t`,
+///          `-p`, `-a`, `-v` or `-V` has no file or `-P` no known datapath
+///
+pub fn parse_run_options(args: Vec<String>) -> Result<(RunOptions, Vec<String>), String> {
+    let mut options = RunOptions::default();
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-r" => options.headless = true,
+            "-x" => {
+                options.check = true;
+                options.headless = true;
+            }
+            "-w" => {
+                let path = args.next().ok_or_else(|| "`-w` expects an argument after it".to_string())?;
+                options.vcd_path = Some(path);
+                options.headless = true;
+            }
+            "-t" => {
+                let path = args.next().ok_or_else(|| "`-t` expects an argument after it".to_string())?;
+                options.trace_path = Some(path);
+                options.headless = true;
+            }
+            "-p" => {
+                let path = args.next().ok_or_else(|| "`-p` expects an argument after it".to_string())?;
+                options.profile_path = Some(path);
+                options.headless = true;
+            }
+            "-a" => {
+                let path = args.next().ok_or_else(|| "`-a` expects an argument after it".to_string())?;
+                options.annotated_path = Some(path);
+                options.headless = true;
+            }
+            "-P" => {
+                let name = args.next().ok_or_else(|| "`-P` expects an argument after it".to_string())?;
+                options.profile_engine = Engine::from_name(&name).ok_or_else(|| {
+                    let names: Vec<&str> = Engine::ALL.iter().map(|engine| engine.name()).collect();
+                    format!("unknown datapath `{}`, expected one of: {}", name, names.join(", "))
+                })?;
+            }
+            "-v" => {
+                let path = args.next().ok_or_else(|| "`-v` expects an argument after it".to_string())?;
+                options.coverage_path = Some(path);
+                options.headless = true;
+            }
+            "-V" => {
+                let path = args.next().ok_or_else(|| "`-V` expects an argument after it".to_string())?;
+                options.lcov_path = Some(path);
+                options.headless = true;
+            }
+            _ => rest.push(arg),
+        }
+    }
+    Ok((options, rest))
+}
+
+/// run: runs the program until it exits and prints the registers that are not zero and
+/// the statistics of the run.
+///
+/// input: simulator - the simulator with the program loaded
+///        options - what to write while running
+///        lockstep - the reference interpreter to check every instruction against, if any
+/// returns: the report of the run, or an error if the program failed, did not exit or
+///          differs from the reference interpreter
+///
+pub fn run(simulator: &mut Simulator, options: &RunOptions, mut lockstep: Option<Lockstep>) -> Result<String, String> {
+    let mut vcd = options.vcd_path.as_ref().map(|_| VcdWriter::new());
+    let mut trace = options.trace_path.as_ref().map(|_| String::new());
+    let mut profile = (options.profile_path.is_some() || options.annotated_path.is_some())
+        .then(|| Profile::new(options.profile_engine));
+    let mut coverage = (options.coverage_path.is_some() || options.lcov_path.is_some()).then(Coverage::new);
+    let result = loop {
+        if simulator.get_cycle_count() >= options.max_cycles {
+            break Err(format!("the program did not exit within {} cycles", options.max_cycles));
+        }
+        let result = match &mut lockstep {
+            Some(lockstep) => lockstep.step(simulator).unwrap_or_else(RunResult::Failure),
+            None => simulator.step(),
+        };
+        if let (Some(vcd), Some(cycle)) = (&mut vcd, simulator.get_last_cycle()) {
+            vcd.add_cycle(cycle);
+        }
+        if let (Some(trace), Some(cycle)) = (&mut trace, simulator.get_last_cycle()) {
+            trace.push_str(&cycle.to_record().to_line());
+            trace.push('\n');
+        }
+        if let (Some(profile), Some(cycle)) = (&mut profile, simulator.get_last_cycle()) {
+            profile.add(cycle.pc, cycle.instruction);
+        }
+        if let (Some(coverage), Some(cycle)) = (&mut coverage, simulator.get_last_cycle()) {
+            coverage.add(cycle.pc, cycle.instruction, cycle.muxes.branch);
+        }
+        match result {
+            RunResult::Success => continue,
+            RunResult::Completed => break Ok(()),
+            RunResult::Failure(msg) => break Err(msg),
+        }
+    };
+    // the files are written even if the program failed, to show how it got there
+    if let (Some(vcd), Some(path)) = (vcd, &options.vcd_path) {
+        fs::write(path, vcd.finish()).map_err(|err| format!("failed to write VCD file {}: {}", path, err))?;
+    }
+    if let (Some(trace), Some(path)) = (trace, &options.trace_path) {
+        fs::write(path, trace).map_err(|err| format!("failed to write trace file {}: {}", path, err))?;
+    }
+    if let (Some(profile), Some(path)) = (&profile, &options.profile_path) {
+        let report = profile.report(simulator.get_labels(), simulator.get_source());
+        fs::write(path, report).map_err(|err| format!("failed to write profile {}: {}", path, err))?;
+    }
+    if let (Some(profile), Some(path)) = (&profile, &options.annotated_path) {
+        let listing = profile.annotated_listing(simulator.get_source());
+        fs::write(path, listing).map_err(|err| format!("failed to write annotated listing {}: {}", path, err))?;
+    }
+    if let (Some(coverage), Some(path)) = (&coverage, &options.coverage_path) {
+        let listing = coverage.annotated_listing(simulator.get_source());
+        fs::write(path, listing).map_err(|err| format!("failed to write coverage {}: {}", path, err))?;
+    }
+    if let (Some(coverage), Some(path)) = (&coverage, &options.lcov_path) {
+        let lcov = coverage.lcov(simulator.get_source());
+        fs::write(path, lcov).map_err(|err| format!("failed to write lcov tracefile {}: {}", path, err))?;
+    }
+    result?;
+
+    let mut report = format!("Completed after {} cycles\n", simulator.get_cycle_count());
+    if lockstep.is_some() {
+        report.push_str("The simulator agrees with the reference interpreter\n");
+    }
+    for (reg, val) in simulator.get_registers() {
+        if val != 0 {
+            let name = crate::assembler::instructions::get_register_name(reg).unwrap();
+            report.push_str(&format!("{} = {}\n", name, val));
+        }
+    }
+    report.push('\n');
+    for line in simulator.get_stats().to_lines() {
+        report.push_str(&line);
+        report.push('\n');
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use darken_assignment1::assembler::{assemble, AssemblerOptions};
+    use darken_assignment1::loader::MemoryImage;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_run_options() {
+        let (options, rest) = parse_run_options(args(&["darken", "-r", "a.asm", "-o", "out.txt"])).unwrap();
+        assert_eq!(options, RunOptions { headless: true, ..RunOptions::default() });
+        assert_eq!(rest, args(&["darken", "a.asm", "-o", "out.txt"]));
+
+        let (options, rest) = parse_run_options(args(&["darken", "a.asm", "-w", "a.vcd"])).unwrap();
+        assert_eq!(options, RunOptions { headless: true, vcd_path: Some("a.vcd".to_string()), ..RunOptions::default() });
+        assert_eq!(rest, args(&["darken", "a.asm"]));
+
+        let (options, _) = parse_run_options(args(&["darken", "-x", "a.asm"])).unwrap();
+        assert_eq!(options, RunOptions { headless: true, check: true, ..RunOptions::default() });
+
+        let (options, _) = parse_run_options(args(&["darken", "a.asm", "-t", "a.trace"])).unwrap();
+        assert_eq!(options, RunOptions { headless: true, trace_path: Some("a.trace".to_string()), ..RunOptions::default() });
+
+        let (options, _) = parse_run_options(args(&["darken", "a.asm", "-p", "a.prof", "-a", "a.lst"])).unwrap();
+        assert_eq!(
+            options,
+            RunOptions {
+                headless: true,
+                profile_path: Some("a.prof".to_string()),
+                annotated_path: Some("a.lst".to_string()),
+                ..RunOptions::default()
+            }
+        );
+
+        assert!(parse_run_options(args(&["darken", "a.asm", "-w"])).is_err());
+        let (options, _) = parse_run_options(args(&["darken", "a.asm", "-v", "a.cov", "-V", "a.info"])).unwrap();
+        assert_eq!(
+            options,
+            RunOptions {
+                headless: true,
+                coverage_path: Some("a.cov".to_string()),
+                lcov_path: Some("a.info".to_string()),
+                ..RunOptions::default()
+            }
+        );
+
+        let (options, _) = parse_run_options(args(&["darken", "a.asm", "-P", "single-cycle"])).unwrap();
+        assert_eq!(options, RunOptions { profile_engine: Engine::SingleCycle, ..RunOptions::default() });
+        assert_eq!(
+            parse_run_options(args(&["darken", "a.asm", "-P", "pipelined"])).unwrap_err(),
+            "unknown datapath `pipelined`, expected one of: single-cycle, multi-cycle"
+        );
+
+        assert!(parse_run_options(args(&["darken", "a.asm", "-p"])).is_err());
+        assert!(parse_run_options(args(&["darken", "a.asm", "-V"])).is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let program = assemble("addi $t0, $zero, 5\nadd $t1, $t0, $t0\nexit\n", &AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        let report = run(&mut simulator, &RunOptions::default(), None).unwrap();
+        assert_eq!(
+            report,
+            "Completed after 3 cycles\n$t0 = 5\n$t1 = 10\n\nInstructions: 3\n\
+             Cycles (single-cycle): 3, CPI 1.00\nCycles (multi-cycle): 11, CPI 3.67\n\
+             Classes: ALU 2, load 0, store 0, branch 0, jump 0, exit 1\nBranches: 0 taken, 0 not taken\n\
+             \x20 add    1\n  addi   1\n  exit   1\n"
+        );
+
+        let program = assemble("addi $t0, $zero, 5\nsll $t1, $t0, 1\nexit\n", &AssemblerOptions::default()).unwrap();
+        let image = MemoryImage::from(program);
+        let lockstep = Lockstep::new(&image);
+        let mut simulator = Simulator::load(image);
+        let report = run(&mut simulator, &RunOptions::default(), Some(lockstep)).unwrap();
+        assert!(report.starts_with(
+            "Completed after 3 cycles\nThe simulator agrees with the reference interpreter\n$t0 = 5\n$t1 = 10\n\n"
+        ));
+        assert!(report.ends_with("  addi   1\n  exit   1\n  sll    1\n"), "{}", report);
+
+        let source = "addi $t0, $zero, 2\nloop: addi $t0, $t0, -1\nbeq $t0, $zero, done\nj loop\ndone: exit\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        let dir = std::env::temp_dir();
+        let profile_path = dir.join(format!("darken-runner-{}.prof", std::process::id()));
+        let annotated_path = dir.join(format!("darken-runner-{}.lst", std::process::id()));
+        let lcov_path = dir.join(format!("darken-runner-{}.info", std::process::id()));
+        let options = RunOptions {
+            profile_path: Some(profile_path.to_string_lossy().to_string()),
+            annotated_path: Some(annotated_path.to_string_lossy().to_string()),
+            lcov_path: Some(lcov_path.to_string_lossy().to_string()),
+            ..RunOptions::default()
+        };
+        run(&mut simulator, &options, None).unwrap();
+        let profile = fs::read_to_string(&profile_path).unwrap();
+        let annotated = fs::read_to_string(&annotated_path).unwrap();
+        let lcov = fs::read_to_string(&lcov_path).unwrap();
+        fs::remove_file(profile_path).unwrap();
+        fs::remove_file(annotated_path).unwrap();
+        fs::remove_file(lcov_path).unwrap();
+        assert!(profile.starts_with("Profile: 7 instructions, 24 cycles on the multi-cycle datapath\n"), "{}", profile);
+        assert!(profile.contains("  loop                          5         17"), "{}", profile);
+        assert!(annotated.contains("         2          8  0x00000004  loop: addi $t0, $t0, -1\n"), "{}", annotated);
+        assert!(lcov.contains("BRDA:3,0,0,1\nBRDA:3,0,1,1\nBRF:2\nBRH:2\nDA:1,1\nDA:2,2\n"), "{}", lcov);
+
+        let program = assemble("loop: j loop\n", &AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        let options = RunOptions { max_cycles: 100, ..RunOptions::default() };
+        let err = run(&mut simulator, &options, None).unwrap_err();
+        assert_eq!(err, "the program did not exit within 100 cycles");
+    }
+}
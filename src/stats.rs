@@ -0,0 +1,208 @@
+//! Statistics of a run: the number of instructions executed, by mnemonic and by class,
+//! how often branches were taken and the number of cycles the run takes on different
+//! implementations of the datapath.
+//!
+//! The simulator implements the single cycle datapath, where every instruction takes a
+//! cycle. The multi-cycle datapath is modelled by the number of steps each class of
+//! instruction takes on it: 5 for loads, 4 for stores and ALU instructions and 3 for
+//! branches, jumps and `exit`, which end after computing the target or decoding. A word
+//! that is not an instruction ends once it is decoded too, taking as long as `exit`.
+//!
+//! File: stats.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::spec::{DecodedInstruction, InstructionClass};
+use std::collections::BTreeMap;
+
+/// An implementation of the datapath that takes a number of cycles per instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    SingleCycle,
+    MultiCycle,
+}
+
+impl Engine {
+    pub const ALL: [Engine; 2] = [Engine::SingleCycle, Engine::MultiCycle];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Engine::SingleCycle => "single-cycle",
+            Engine::MultiCycle => "multi-cycle",
+        }
+    }
+
+    /// The engine with the given name, none if there is no such engine.
+    pub fn from_name(name: &str) -> Option<Engine> {
+        Engine::ALL.into_iter().find(|engine| engine.name() == name)
+    }
+
+    /// The number of cycles an instruction of the class takes.
+    pub fn cycles(&self, class: InstructionClass) -> u64 {
+        match (self, class) {
+            (Engine::SingleCycle, _) => 1,
+            (Engine::MultiCycle, InstructionClass::Load) => 5,
+            (Engine::MultiCycle, InstructionClass::Alu | InstructionClass::Store) => 4,
+            (Engine::MultiCycle, _) => 3,
+        }
+    }
+
+    /// The number of cycles a word that is not an instruction takes.
+    pub fn unknown_cycles(&self) -> u64 {
+        self.cycles(InstructionClass::Exit)
+    }
+
+    /// The number of cycles the instruction in a word takes.
+    pub fn word_cycles(&self, word: u32) -> u64 {
+        match DecodedInstruction::decode(word) {
+            Some(instruction) => self.cycles(instruction.spec.class()),
+            None => self.unknown_cycles(),
+        }
+    }
+}
+
+/// The counts of the instructions executed in a run.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RunStats {
+    pub instructions: u64,
+    /// The number of times each instruction was executed, by mnemonic. Words that are
+    /// not an instruction are counted as `.word`, but not in a class
+    pub by_mnemonic: BTreeMap<&'static str, u64>,
+    pub by_class: BTreeMap<InstructionClass, u64>,
+    pub branches_taken: u64,
+    pub branches_not_taken: u64,
+}
+
+impl RunStats {
+    pub fn new() -> RunStats {
+        RunStats::default()
+    }
+
+    /// add: counts an executed instruction.
+    ///
+    /// input: word - the machine word of the instruction
+    ///        taken - whether the instruction branched, only used for branches
+    ///
+    pub fn add(&mut self, word: u32, taken: bool) {
+        self.instructions += 1;
+        let spec = DecodedInstruction::decode(word).map(|instruction| instruction.spec);
+        *self.by_mnemonic.entry(spec.map_or(".word", |spec| spec.mnemonic)).or_insert(0) += 1;
+        let class = match spec {
+            Some(spec) => spec.class(),
+            None => return,
+        };
+        *self.by_class.entry(class).or_insert(0) += 1;
+        if class == InstructionClass::Branch {
+            match taken {
+                true => self.branches_taken += 1,
+                false => self.branches_not_taken += 1,
+            }
+        }
+    }
+
+    /// The number of instructions of a class executed.
+    pub fn class_count(&self, class: InstructionClass) -> u64 {
+        self.by_class.get(&class).copied().unwrap_or(0)
+    }
+
+    /// The number of cycles the instructions take on an engine.
+    pub fn cycles(&self, engine: Engine) -> u64 {
+        let unknown = self.by_mnemonic.get(".word").copied().unwrap_or(0);
+        self.by_class
+            .iter()
+            .map(|(class, count)| engine.cycles(*class) * count)
+            .sum::<u64>()
+            + unknown * engine.unknown_cycles()
+    }
+
+    /// The average number of cycles per instruction on an engine, none before the first
+    /// instruction.
+    pub fn cpi(&self, engine: Engine) -> Option<f64> {
+        match self.instructions {
+            0 => None,
+            instructions => Some(self.cycles(engine) as f64 / instructions as f64),
+        }
+    }
+
+    /// to_lines: the statistics as text, a line for the instruction count, one per
+    /// engine, one for the classes, one for the branches and one per mnemonic.
+    ///
+    /// returns: the lines
+    ///
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Instructions: {}", self.instructions)];
+        for engine in Engine::ALL {
+            let cpi = self.cpi(engine).map_or("-".to_string(), |cpi| format!("{:.2}", cpi));
+            lines.push(format!("Cycles ({}): {}, CPI {}", engine.name(), self.cycles(engine), cpi));
+        }
+        let classes: Vec<String> = InstructionClass::ALL
+            .iter()
+            .map(|class| format!("{} {}", class.name(), self.class_count(*class)))
+            .collect();
+        lines.push(format!("Classes: {}", classes.join(", ")));
+        lines.push(format!(
+            "Branches: {} taken, {} not taken",
+            self.branches_taken, self.branches_not_taken
+        ));
+        let mut mnemonics: Vec<(&str, u64)> = self.by_mnemonic.iter().map(|(name, count)| (*name, *count)).collect();
+        // the most executed first
+        mnemonics.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        for (mnemonic, count) in mnemonics {
+            lines.push(format!("  {:<6} {}", mnemonic, count));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::{assemble, AssemblerOptions};
+    use crate::interpreter::{Interpreter, Status};
+    use crate::loader::MemoryImage;
+
+    #[test]
+    fn test_run_stats() {
+        let source = "addi $t0, $zero, 2\nloop: lw $t1, 0($zero)\naddi $t0, $t0, -1\n\
+                      beq $t0, $zero, done\nj loop\ndone: sw $t1, 4($zero)\nexit\n";
+        let program = assemble(source, &AssemblerOptions::default()).unwrap();
+        let mut interpreter = Interpreter::new(&MemoryImage::from(program));
+        let mut stats = RunStats::new();
+        loop {
+            let pc = interpreter.pc();
+            let (record, status) = interpreter.step().unwrap();
+            let record = record.unwrap();
+            stats.add(record.word, interpreter.pc() != pc + 4);
+            if status == Status::Exited {
+                break;
+            }
+        }
+
+        assert_eq!(stats.instructions, 10);
+        assert_eq!(stats.by_mnemonic["addi"], 3);
+        assert_eq!(stats.class_count(InstructionClass::Alu), 3);
+        assert_eq!(stats.class_count(InstructionClass::Load), 2);
+        assert_eq!(stats.class_count(InstructionClass::Jump), 1);
+        assert_eq!((stats.branches_taken, stats.branches_not_taken), (1, 1));
+        assert_eq!(stats.cycles(Engine::SingleCycle), 10);
+        // 3 ALU, 2 loads, 1 store, 2 branches, 1 jump and exit
+        assert_eq!(stats.cycles(Engine::MultiCycle), 3 * 4 + 2 * 5 + 4 + 2 * 3 + 3 + 3);
+        assert_eq!(
+            stats.to_lines()[..6],
+            [
+                "Instructions: 10",
+                "Cycles (single-cycle): 10, CPI 1.00",
+                "Cycles (multi-cycle): 38, CPI 3.80",
+                "Classes: ALU 3, load 2, store 1, branch 2, jump 1, exit 1",
+                "Branches: 1 taken, 1 not taken",
+                "  addi   3",
+            ]
+        );
+
+        let mut unknown = RunStats::new();
+        unknown.add(0xfc00_0000, false);
+        assert_eq!(unknown.by_mnemonic[".word"], 1);
+        assert_eq!(unknown.cycles(Engine::SingleCycle), 1);
+        assert_eq!(unknown.cycles(Engine::MultiCycle), Engine::MultiCycle.word_cycles(0xfc00_0000));
+        assert_eq!(RunStats::new().cpi(Engine::MultiCycle), None);
+    }
+}
@@ -0,0 +1,351 @@
+//! A trace of the instructions a program executed, one line per instruction, in a
+//! format that stays the same between runs so that two traces can be compared. A
+//! line holds the cycle, the PC, the machine word and its disassembly, followed by
+//! the register and the memory word written, if any:
+//!
+//! `     1 0x00000004 0x01084820  add $t1, $t0, $t0        | $t1: 0 -> 10`
+//!
+//! File: trace.rs
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::assembler::get_register_name;
+use crate::disassembler::disassemble_word;
+use std::fmt;
+
+/// The width the disassembly is padded to, so that the writes line up.
+const TEXT_WIDTH: usize = 24;
+
+/// A value that replaced another in a register or in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Write<T> {
+    /// The register number or the memory address written
+    pub location: T,
+    pub old_value: i32,
+    pub new_value: i32,
+}
+
+/// One executed instruction and what it changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub cycle: u64,
+    pub pc: u32,
+    pub word: u32,
+    pub register_write: Option<Write<u8>>,
+    pub memory_write: Option<Write<u32>>,
+}
+
+fn register_name(register: u8) -> String {
+    get_register_name(ux::u5::new(register & 0x1f)).unwrap()
+}
+
+impl TraceRecord {
+    /// to_line: the record as a line of a trace, without the line break.
+    ///
+    /// returns: the line
+    ///
+    pub fn to_line(&self) -> String {
+        let text = match disassemble_word(self.word) {
+            Some(instruction) => instruction.to_mnemonic_string(),
+            None => format!(".word {:#010x}", self.word),
+        };
+        let mut line = format!(
+            "{:>6} {:#010x} {:#010x}  {:<width$}",
+            self.cycle,
+            self.pc,
+            self.word,
+            text,
+            width = TEXT_WIDTH
+        );
+        if let Some(write) = &self.register_write {
+            line.push_str(&format!(
+                " | {}: {} -> {}",
+                register_name(write.location),
+                write.old_value,
+                write.new_value
+            ));
+        }
+        if let Some(write) = &self.memory_write {
+            line.push_str(&format!(
+                " | mem[{:#010x}]: {} -> {}",
+                write.location, write.old_value, write.new_value
+            ));
+        }
+        line.trim_end().to_string()
+    }
+
+    /// parse: reads a line of a trace. The disassembly is not read, as the machine
+    /// word already says which instruction ran.
+    ///
+    /// input: line - the line
+    /// returns: the record, or an error if the line is not a trace line
+    ///
+    pub fn parse(line: &str) -> Result<TraceRecord, String> {
+        let mut parts = line.split(" | ");
+        let mut fields = parts.next().unwrap_or("").split_whitespace();
+        let mut next_field = |name: &str| {
+            fields
+                .next()
+                .ok_or_else(|| format!("the line has no {}", name))
+        };
+        let cycle = next_field("cycle")?;
+        let cycle = cycle
+            .parse()
+            .map_err(|_| format!("`{}` is not a cycle", cycle))?;
+        let pc = parse_hex(next_field("PC")?)?;
+        let word = parse_hex(next_field("machine word")?)?;
+
+        let mut record = TraceRecord {
+            cycle,
+            pc,
+            word,
+            register_write: None,
+            memory_write: None,
+        };
+        for part in parts {
+            let (location, change) = part
+                .split_once(": ")
+                .ok_or_else(|| format!("`{}` is not a write", part))?;
+            let (old_value, new_value) = change
+                .split_once(" -> ")
+                .ok_or_else(|| format!("`{}` is not a write", part))?;
+            let old_value = parse_value(old_value)?;
+            let new_value = parse_value(new_value)?;
+            if let Some(address) = location.strip_prefix("mem[").and_then(|rest| rest.strip_suffix(']')) {
+                record.memory_write = Some(Write {
+                    location: parse_hex(address)?,
+                    old_value,
+                    new_value,
+                });
+            } else {
+                let register = (0..32)
+                    .find(|register| register_name(*register) == location)
+                    .ok_or_else(|| format!("`{}` is not a register", location))?;
+                record.register_write = Some(Write {
+                    location: register,
+                    old_value,
+                    new_value,
+                });
+            }
+        }
+        Ok(record)
+    }
+}
+
+impl fmt::Display for TraceRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_line())
+    }
+}
+
+fn parse_hex(text: &str) -> Result<u32, String> {
+    text.strip_prefix("0x")
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+        .ok_or_else(|| format!("`{}` is not a hexadecimal word", text))
+}
+
+fn parse_value(text: &str) -> Result<i32, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("`{}` is not a value", text.trim()))
+}
+
+/// parse_trace: reads every line of a trace. Empty lines and lines starting with `#`
+/// are skipped.
+///
+/// input: text - the trace
+/// returns: the records and the line number each was read from, or an error naming
+///          the line that is not a trace line
+///
+pub fn parse_trace(text: &str) -> Result<Vec<(usize, TraceRecord)>, String> {
+    let mut records = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let record = TraceRecord::parse(line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+        records.push((index + 1, record));
+    }
+    Ok(records)
+}
+
+/// Where two traces first differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The expected record and the line it is on, none if the expected trace ended
+    pub expected: Option<(usize, TraceRecord)>,
+    /// The actual record and the line it is on, none if the actual trace ended
+    pub actual: Option<(usize, TraceRecord)>,
+    /// What differs between the records
+    pub reason: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "the traces differ: {}", self.reason)?;
+        for (name, record) in [("expected", &self.expected), ("actual", &self.actual)] {
+            match record {
+                Some((line, record)) => writeln!(f, "{:<8} line {:>5}: {}", name, line, record)?,
+                None => writeln!(f, "{:<8} (no more instructions)", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// difference: what differs between two records of the same instruction.
+///
+/// input: expected - the record to compare against
+///        actual - the record being checked
+/// returns: the first field that differs, none if the records are the same
+///
+pub fn difference(expected: &TraceRecord, actual: &TraceRecord) -> Option<String> {
+    let reason = if expected.pc != actual.pc {
+        format!("the PC is {:#010x} instead of {:#010x}", actual.pc, expected.pc)
+    } else if expected.word != actual.word {
+        format!(
+            "the instruction is {:#010x} instead of {:#010x}",
+            actual.word, expected.word
+        )
+    } else if expected.register_write != actual.register_write {
+        match (&expected.register_write, &actual.register_write) {
+            (Some(expected), Some(actual)) if expected.location == actual.location => format!(
+                "{} is written with {} instead of {}",
+                register_name(actual.location),
+                actual.new_value,
+                expected.new_value
+            ),
+            (Some(expected), Some(actual)) => format!(
+                "{} is written instead of {}",
+                register_name(actual.location),
+                register_name(expected.location)
+            ),
+            (Some(expected), None) => format!("{} is not written", register_name(expected.location)),
+            (None, Some(actual)) => format!("{} is written", register_name(actual.location)),
+            (None, None) => unreachable!(),
+        }
+    } else if expected.memory_write != actual.memory_write {
+        match (&expected.memory_write, &actual.memory_write) {
+            (Some(expected), Some(actual)) if expected.location == actual.location => format!(
+                "mem[{:#010x}] is written with {} instead of {}",
+                actual.location, actual.new_value, expected.new_value
+            ),
+            (Some(expected), Some(actual)) => format!(
+                "mem[{:#010x}] is written instead of mem[{:#010x}]",
+                actual.location, expected.location
+            ),
+            (Some(expected), None) => format!("mem[{:#010x}] is not written", expected.location),
+            (None, Some(actual)) => format!("mem[{:#010x}] is written", actual.location),
+            (None, None) => unreachable!(),
+        }
+    } else if expected.cycle != actual.cycle {
+        format!("the cycle is {} instead of {}", actual.cycle, expected.cycle)
+    } else {
+        return None;
+    };
+    Some(reason)
+}
+
+/// first_divergence: compares two traces record by record, e.g. the run of a
+/// student's program against the run of the reference solution.
+///
+/// input: expected - the trace to compare against
+///        actual - the trace being checked
+/// returns: where they first differ, none if they are the same, or an error if either
+///          is not a trace
+///
+pub fn first_divergence(expected: &str, actual: &str) -> Result<Option<Divergence>, String> {
+    let expected = parse_trace(expected).map_err(|err| format!("expected trace: {}", err))?;
+    let actual = parse_trace(actual).map_err(|err| format!("actual trace: {}", err))?;
+    let mut expected = expected.into_iter();
+    let mut actual = actual.into_iter();
+    loop {
+        let divergence = match (expected.next(), actual.next()) {
+            (None, None) => return Ok(None),
+            (Some(expected), Some(actual)) => match difference(&expected.1, &actual.1) {
+                Some(reason) => Divergence {
+                    expected: Some(expected),
+                    actual: Some(actual),
+                    reason,
+                },
+                None => continue,
+            },
+            (Some(expected), None) => Divergence {
+                expected: Some(expected),
+                actual: None,
+                reason: "the actual trace ends early".to_string(),
+            },
+            (None, Some(actual)) => Divergence {
+                expected: None,
+                actual: Some(actual),
+                reason: "the actual trace runs past the end of the expected one".to_string(),
+            },
+        };
+        return Ok(Some(divergence));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_record() -> TraceRecord {
+        TraceRecord {
+            cycle: 1,
+            pc: 4,
+            word: 0x0108_4820,
+            register_write: Some(Write {
+                location: 9,
+                old_value: 0,
+                new_value: 10,
+            }),
+            memory_write: None,
+        }
+    }
+
+    #[test]
+    fn test_to_line() {
+        assert_eq!(
+            add_record().to_line(),
+            "     1 0x00000004 0x01084820  add $t1, $t0, $t0        | $t1: 0 -> 10"
+        );
+        let store = TraceRecord {
+            cycle: 2,
+            pc: 8,
+            word: 0xad09_0004,
+            register_write: None,
+            memory_write: Some(Write {
+                location: 4,
+                old_value: 0,
+                new_value: -10,
+            }),
+        };
+        assert_eq!(
+            store.to_line(),
+            "     2 0x00000008 0xad090004  sw $t1, 4($t0)           | mem[0x00000004]: 0 -> -10"
+        );
+        assert_eq!(TraceRecord::parse(&store.to_line()), Ok(store));
+        assert_eq!(TraceRecord::parse(&add_record().to_line()), Ok(add_record()));
+        assert!(TraceRecord::parse("1 0x0 0x0 nop | $x9: 0 -> 1").is_err());
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let expected = format!("# reference\n{}\n", add_record());
+        assert_eq!(first_divergence(&expected, &add_record().to_line()), Ok(None));
+
+        let mut wrong = add_record();
+        wrong.register_write.as_mut().unwrap().new_value = 11;
+        let divergence = first_divergence(&expected, &wrong.to_line()).unwrap().unwrap();
+        assert_eq!(divergence.reason, "$t1 is written with 11 instead of 10");
+        assert_eq!(divergence.expected, Some((2, add_record())));
+        assert_eq!(divergence.actual, Some((1, wrong)));
+
+        let divergence = first_divergence(&expected, "").unwrap().unwrap();
+        assert_eq!(divergence.reason, "the actual trace ends early");
+        assert_eq!(divergence.actual, None);
+
+        let err = first_divergence(&expected, "not a trace").unwrap_err();
+        assert_eq!(err, "actual trace: line 1: `not` is not a cycle");
+    }
+}
@@ -2,13 +2,17 @@ use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
 
 use crate::{
-    mips::{simulator::RunResult, Simulator},
+    mips::{
+        datapath::{alu_operation_name, CycleTrace},
+        simulator::RunResult,
+        Simulator,
+    },
     valwriter::{BinValWriter, DecValWriter, HexValWriter, NumValWriter},
 };
 
@@ -58,6 +62,7 @@ impl<T> StatefulTable<T> {
 
 pub struct App {
     instr_mem: StatefulTable<InstructionViewModel>,
+    source: StatefulTable<SourceViewModel>,
     //instr_mem: StatefulList<(&'a str, usize, usize, usize, usize, usize)>,
     data_mem: StatefulTable<DataViewModel>,
     registers: StatefulTable<RegisterViewModel>,
@@ -66,6 +71,8 @@ pub struct App {
     val_writer: Box<dyn NumValWriter>,
     run_flag: bool,
     finish: bool,
+    /// show the datapath diagram in place of the instruction memory
+    show_datapath: bool,
 }
 
 impl App {
@@ -105,6 +112,10 @@ impl App {
     pub fn is_finished(&self) -> bool {
         self.finish
     }
+
+    pub fn toggle_datapath(&mut self) {
+        self.show_datapath = !self.show_datapath;
+    }
 }
 
 struct InstructionViewModel {
@@ -151,6 +162,22 @@ impl InstructionViewModel {
     }
 }
 
+struct SourceViewModel {
+    position: String,
+    text: String,
+    has_labels: bool,
+}
+
+impl SourceViewModel {
+    fn new(position: String, text: String, has_labels: bool) -> SourceViewModel {
+        SourceViewModel {
+            position,
+            text,
+            has_labels,
+        }
+    }
+}
+
 struct DataViewModel {
     addr: String,
     contents: String,
@@ -180,6 +207,7 @@ impl App {
     pub fn new(simulator: Simulator) -> App {
         App {
             instr_mem: StatefulTable::new(),
+            source: StatefulTable::new(),
             data_mem: StatefulTable::new(),
             registers: StatefulTable::new(),
             pc: String::from("0"),
@@ -187,6 +215,7 @@ impl App {
             val_writer: Box::new(DecValWriter {}),
             run_flag: false,
             finish: false,
+            show_datapath: false,
         }
     }
 
@@ -207,13 +236,18 @@ impl App {
         self.update_data_mem();
         self.update_regs();
         self.update_instr_mem();
+        self.update_source();
     }
 
     fn update_instr_mem(&mut self) {
         self.instr_mem.items = Vec::new();
         for (addr, val) in self.simulator.get_instr_mem() {
+            let mnemonic = match self.simulator.get_label(addr) {
+                Some(label) => format!("{}: {}", label, val.to_mnemonic_string()),
+                None => val.to_mnemonic_string(),
+            };
             let instrvm = InstructionViewModel::new(
-                addr,
+                addr.to_string(),
                 val.to_hex_string(),
                 self.val_writer.write(Some(val.get_op_val())),
                 self.val_writer.write(val.get_rs_val()),
@@ -223,14 +257,33 @@ impl App {
                 self.val_writer.write(val.get_shamt_val()),
                 self.val_writer.write(val.get_funct_val()),
                 self.val_writer.write(val.get_jump_address_val()),
-                val.to_mnemonic_string(),
+                mnemonic,
             );
             //let instr_vm = InstructionViewModel::new(addr, val);
             //self.instr_mem.items.push(instr_vm);
             self.instr_mem.items.push(instrvm);
         }
-        let current_instr_idx = self.simulator.get_current_pc() / 4;
-        self.instr_mem.state.select(Some(current_instr_idx));
+        let current_instr_idx = self.simulator.get_current_instr_idx();
+        self.instr_mem.state.select(current_instr_idx);
+    }
+
+    fn update_source(&mut self) {
+        let source = self.simulator.get_source();
+        // only name the files when lines from several files are shown
+        let several_files = source.iter().any(|line| line.file != source[0].file);
+        self.source.items = source
+            .iter()
+            .map(|line| {
+                let position = match several_files {
+                    true => format!("{}:{}", line.file, line.line),
+                    false => line.line.to_string(),
+                };
+                // tabs are not expanded by the terminal backend
+                SourceViewModel::new(position, line.source.replace('\t', "    "), !line.labels.is_empty())
+            })
+            .collect();
+        let current_source_idx = self.simulator.get_current_source_idx();
+        self.source.state.select(current_source_idx);
     }
 
     fn update_data_mem(&mut self) {
@@ -344,6 +397,228 @@ fn draw_instruction_mem_widget<'a, B: Backend>(frame: &mut Frame<B>, app: &mut A
     frame.render_stateful_widget(im_widget, area, &mut app.instr_mem.state);
 }
 
+/// Splits a source line into its labels, its statement and its comment, so that
+/// they can be told apart by color. A `#` or `:` inside a string is part of the statement.
+fn split_source_line(text: &str, has_labels: bool) -> (&str, &str, &str) {
+    let mut in_string = false;
+    let mut labels_end = 0;
+    let mut comment_start = text.len();
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' if prev != '\\' => in_string = !in_string,
+            '#' if !in_string => {
+                comment_start = i;
+                break;
+            }
+            ':' if !in_string && has_labels => labels_end = i + 1,
+            _ => {}
+        }
+        prev = c;
+    }
+    (&text[..labels_end], &text[labels_end..comment_start], &text[comment_start..])
+}
+
+fn draw_source_widget<B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rect) {
+    let header_cells = ["Line", "Source"]
+        .iter()
+        .map(|header| Cell::from(*header).style(Style::default().fg(Color::LightRed)));
+
+    let header = Row::new(header_cells)
+        .style(Style::default())
+        .height(1)
+        .bottom_margin(1);
+
+    let rows = app.source.items.iter().map(|item| {
+        let (labels, statement, comment) = split_source_line(&item.text, item.has_labels);
+        let text = Spans::from(vec![
+            Span::styled(labels.to_string(), Style::default().fg(Color::LightYellow)),
+            Span::raw(statement.to_string()),
+            Span::styled(comment.to_string(), Style::default().fg(Color::DarkGray)),
+        ]);
+        Row::new(vec![Cell::from(item.position.clone()), Cell::from(text)])
+    });
+
+    let source_widget = Table::new(rows)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title("Source"))
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("--> ")
+        .widths(&[Constraint::Percentage(15), Constraint::Percentage(85)]);
+
+    frame.render_stateful_widget(source_widget, area, &mut app.source.state);
+}
+
+/// A piece of the datapath diagram: a wire or component that is either used by the
+/// instruction just executed or not, or a plain label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DiagramPart {
+    Label(String),
+    Active(String),
+    Inactive(String),
+}
+
+fn part(text: String, active: bool) -> DiagramPart {
+    match active {
+        true => DiagramPart::Active(text),
+        false => DiagramPart::Inactive(text),
+    }
+}
+
+fn label(text: &str) -> DiagramPart {
+    DiagramPart::Label(text.to_string())
+}
+
+/// Lays out the datapath for the instruction just executed, one row for every part of
+/// the datapath from fetching the instruction to choosing the next pc. Every
+/// multiplexor shows the input it selected, and the wires it did not select are inactive.
+fn datapath_diagram(wires: &CycleTrace, writer: &dyn NumValWriter) -> Vec<Vec<DiagramPart>> {
+    let c = wires.control;
+    let reg = |number: u8| crate::assembler::instructions::get_register_name(ux::u5::new(number)).unwrap();
+    let val = |value: i32| writer.write(Some(value));
+    let addr = |value: u32| writer.write_unsigned(Some(value));
+    let mux = |name: &str, signal: bool| DiagramPart::Label(format!(" ─▶ {} [{}] ─▶ ", name, signal as u8));
+    let rd = ((wires.instruction >> 11) & 0x1f) as u8;
+
+    let control_row = std::iter::once(label("Control      "))
+        .chain(c.named().iter().map(|(name, signal)| part(format!("{}={} ", name, *signal as u8), *signal)))
+        .collect();
+    vec![
+        vec![
+            label("Fetch        "),
+            part(format!("PC {}", addr(wires.pc)), true),
+            label(" ─▶ Instruction memory ─▶ "),
+            part(format!("{:#010x}", wires.instruction), true),
+            label(" ─▶ Control, Registers"),
+        ],
+        control_row,
+        vec![
+            label("ALU input 1  "),
+            part(format!("rs {} = {}", reg(wires.read_reg_1), val(wires.read_data_1)), !c.shift),
+            label(", "),
+            part(format!("rt {} = {}", reg(wires.read_reg_2), val(wires.read_data_2)), c.shift),
+            mux("Shift", c.shift),
+            part(val(wires.alu_input_1), true),
+        ],
+        vec![
+            label("ALU input 2  "),
+            part(format!("rt {} = {}", reg(wires.read_reg_2), val(wires.read_data_2)), !c.alu_src && !c.shift),
+            label(", "),
+            part(format!("imm {}", val(wires.imm)), c.alu_src && !c.shift),
+            mux("ALUSrc", c.alu_src),
+            label("and "),
+            part(format!("shamt {}", wires.shamt), c.shift),
+            mux("Shift", c.shift),
+            part(val(wires.alu_input_2), true),
+        ],
+        vec![
+            label("ALU          "),
+            part(
+                format!(
+                    "{} {}, {} = {}",
+                    alu_operation_name(wires.alu_signal),
+                    val(wires.alu_input_1),
+                    val(wires.alu_input_2),
+                    val(wires.alu_result)
+                ),
+                true,
+            ),
+            label("  "),
+            part(format!("zero = {}", wires.zero as u8), c.branch),
+        ],
+        vec![
+            label("Data memory  "),
+            part(format!("address {}", val(wires.alu_result)), c.mem_read || c.mem_write),
+            label(", "),
+            part(format!("write {}", val(wires.read_data_2)), c.mem_write),
+            label(", "),
+            part(format!("read {}", writer.write(wires.mem_read_data)), c.mem_read),
+        ],
+        vec![
+            label("Write data   "),
+            part(format!("ALU {}", val(wires.alu_result)), !c.mem_to_reg),
+            label(", "),
+            part(format!("memory {}", writer.write(wires.mem_read_data)), c.mem_to_reg),
+            mux("MemToReg", c.mem_to_reg),
+            part(val(wires.write_data), c.reg_write),
+        ],
+        vec![
+            label("Write reg    "),
+            part(format!("rt {}", reg(wires.read_reg_2)), !c.reg_dest),
+            label(", "),
+            part(format!("rd {}", reg(rd)), c.reg_dest),
+            mux("RegDst", c.reg_dest),
+            part(reg(wires.write_reg), c.reg_write),
+        ],
+        vec![
+            label("Next PC      "),
+            part(format!("pc+4 {}", addr(wires.pc_plus_4)), !wires.muxes.branch),
+            label(", "),
+            part(format!("branch {}", addr(wires.branch_target)), wires.muxes.branch),
+            mux("Branch", wires.muxes.branch),
+            part(format!("jump {}", addr(wires.jump_target)), c.jump),
+            mux("Jump", c.jump),
+            part(format!("rs {}", val(wires.read_data_1)), c.jump_reg),
+            mux("JumpReg", c.jump_reg),
+            part(format!("PC {}", addr(wires.next_pc)), true),
+        ],
+    ]
+}
+
+fn draw_datapath_widget<B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rect) {
+    let rows: Vec<Spans> = match app.simulator.get_last_cycle() {
+        Some(wires) => datapath_diagram(wires, app.val_writer.as_ref())
+            .into_iter()
+            .map(|row| {
+                let spans: Vec<Span> = row
+                    .into_iter()
+                    .map(|part| match part {
+                        DiagramPart::Label(text) => Span::raw(text),
+                        DiagramPart::Active(text) => Span::styled(
+                            text,
+                            Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+                        ),
+                        DiagramPart::Inactive(text) => Span::styled(text, Style::default().fg(Color::DarkGray)),
+                    })
+                    .collect();
+                Spans::from(spans)
+            })
+            .collect(),
+        None => vec![Spans::from("Step the program to see the datapath of an instruction")],
+    };
+    // a blank line between the rows makes the diagram easier to follow
+    let mut text = Vec::new();
+    for row in rows {
+        text.push(row);
+        text.push(Spans::default());
+    }
+    let widget = Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Datapath"));
+    frame.render_widget(widget, area);
+}
+
+fn draw_top_half<B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rect) {
+    let chunks = match app.source.items.is_empty() {
+        // a program loaded without its source has nothing to show in the source pane
+        true => vec![area],
+        false => Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+            .split(area),
+    };
+    match app.show_datapath {
+        true => draw_datapath_widget(frame, app, chunks[0]),
+        false => draw_instruction_mem_widget(frame, app, chunks[0]),
+    }
+    if let Some(source_area) = chunks.get(1) {
+        draw_source_widget(frame, app, *source_area);
+    }
+}
+
 fn draw_data_mem_widget<'a, B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rect) {
     let header_cells = ["(Byte) Address", "Contents"]
         .iter()
@@ -441,13 +716,26 @@ fn draw_pc_widget<'a, B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rec
     frame.render_widget(pc_widget, area)
 }
 
+fn draw_stats_widget<B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rect) {
+    let lines: Vec<Spans> = app
+        .simulator
+        .get_stats()
+        .to_lines()
+        .into_iter()
+        .map(Spans::from)
+        .collect();
+    let widget = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Statistics"));
+    frame.render_widget(widget, area);
+}
+
 fn draw_bottom_half<'a, B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(35), Constraint::Percentage(30)].as_ref())
         .split(area);
     draw_registers_and_pc(frame, app, chunks[0]);
     draw_data_mem_widget(frame, app, chunks[1]);
+    draw_stats_widget(frame, app, chunks[2]);
 }
 
 fn draw_registers_and_pc<'a, B: Backend>(frame: &mut Frame<B>, app: &mut App, area: Rect) {
@@ -466,6 +754,82 @@ pub fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(f.size());
 
-    draw_instruction_mem_widget(f, app, main_chunks[0]);
+    draw_top_half(f, app, main_chunks[0]);
     draw_bottom_half(f, app, main_chunks[1]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datapath_diagram() {
+        let wires = CycleTrace {
+            cycle: 2,
+            pc: 8,
+            instruction: 0x8d28_0004, // lw $t0, 4($t1)
+            pc_plus_4: 12,
+            control: crate::mips::datapath::ControlSignals {
+                alu_src: true,
+                mem_to_reg: true,
+                reg_write: true,
+                mem_read: true,
+                ..Default::default()
+            },
+            read_reg_1: 9,
+            read_reg_2: 8,
+            read_data_1: 16,
+            read_data_2: 0,
+            imm: 4,
+            shamt: 0,
+            alu_signal: 2,
+            alu_input_1: 16,
+            alu_input_2: 4,
+            alu_result: 20,
+            zero: false,
+            mem_read_data: Some(7),
+            write_reg: 8,
+            write_data: 7,
+            branch_target: 28,
+            jump_target: 0x00a0_0010,
+            next_pc: 12,
+            muxes: crate::mips::datapath::MuxSelections {
+                alu_src: true,
+                mem_to_reg: true,
+                ..Default::default()
+            },
+            load: None,
+            store: None,
+            register_write: None,
+        };
+        let diagram = datapath_diagram(&wires, &DecValWriter {});
+        let active: Vec<String> = diagram
+            .iter()
+            .flatten()
+            .filter_map(|part| match part {
+                DiagramPart::Active(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(active.contains(&"imm 4".to_string()));
+        assert!(active.contains(&"add 16, 4 = 20".to_string()));
+        assert!(active.contains(&"memory 7".to_string()));
+        assert!(active.contains(&"MemRead=1 ".to_string()));
+        assert!(!active.contains(&"rd $zero".to_string()));
+        assert!(active.contains(&"pc+4 12".to_string()));
+        assert!(!active.iter().any(|text| text.starts_with("branch") || text.starts_with("write ")));
+    }
+
+    #[test]
+    fn test_split_source_line() {
+        assert_eq!(
+            split_source_line("loop: beq $t0, $zero, end # done?", true),
+            ("loop:", " beq $t0, $zero, end ", "# done?")
+        );
+        assert_eq!(split_source_line("    add $t0, $t1, $t2", false), ("", "    add $t0, $t1, $t2", ""));
+        assert_eq!(
+            split_source_line("msg: .asciiz \"a: #1\" # text", true),
+            ("msg:", " .asciiz \"a: #1\" ", "# text")
+        );
+    }
+}
@@ -4,6 +4,7 @@
 //! Since: 2022-11-24
 //! Version: 1.0
 #![allow(dead_code)]
+use crate::assembler::spec::{DecodedInstruction, Field, Format};
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::BufWriter;
@@ -23,31 +24,87 @@ where
 }
 
 // TODO: Move this to instruction or somewhere more sensible
-/// Trait which represents that an object can be represented as a MIPS-instruction
+/// Trait which represents that an object can be represented as a MIPS-instruction.
+/// Everything but `decoded` is derived from the instruction table.
 pub trait InstrRepresentable {
-    fn to_hex_string(&self) -> String;
-    fn to_bin_string(&self) -> String;
+    /// decoded: the instruction as a row of the instruction table and its fields.
+    fn decoded(&self) -> DecodedInstruction;
+
+    fn to_hex_string(&self) -> String {
+        let instruction = self.decoded();
+        match instruction.spec.format {
+            // exit is written in upper case in the instruction output files
+            Format::Fixed(_) if instruction.spec.mnemonic == "exit" => {
+                format!("{:#010X}", instruction.encode())
+            }
+            _ => format!("{:#010x}", instruction.encode()),
+        }
+    }
+    fn to_bin_string(&self) -> String {
+        format!("{:032b}", self.decoded().encode())
+    }
     fn get_op(&self) -> String {
         return self
             .get_bits(31, 26)
             .expect("Every instruction should have an op-code in the 6 m.s.b.");
     }
-    fn get_rs_str(&self) -> Option<String>;
-    fn get_rt_str(&self) -> Option<String>;
-    fn get_rd_str(&self) -> Option<String>;
-    fn get_shamt_str(&self) -> Option<String>;
-    fn get_funct_str(&self) -> Option<String>;
-    fn get_imm_str(&self) -> Option<String>;
-    fn get_jump_address_str(&self) -> Option<String>;
-    fn to_mnemonic_string(&self) -> String;
-    fn get_op_val(&self) -> i32;
-    fn get_rs_val(&self) -> Option<i32>;
-    fn get_rt_val(&self) -> Option<i32>;
-    fn get_rd_val(&self) -> Option<i32>;
-    fn get_shamt_val(&self) -> Option<i32>;
-    fn get_funct_val(&self) -> Option<i32>;
-    fn get_imm_val(&self) -> Option<i32>;
-    fn get_jump_address_val(&self) -> Option<i32>;
+    fn get_rs_str(&self) -> Option<String> {
+        self.get_field_str(Field::Rs)
+    }
+    fn get_rt_str(&self) -> Option<String> {
+        self.get_field_str(Field::Rt)
+    }
+    fn get_rd_str(&self) -> Option<String> {
+        self.get_field_str(Field::Rd)
+    }
+    fn get_shamt_str(&self) -> Option<String> {
+        self.get_field_str(Field::Shamt)
+    }
+    fn get_funct_str(&self) -> Option<String> {
+        self.get_field_str(Field::Funct)
+    }
+    fn get_imm_str(&self) -> Option<String> {
+        self.get_field_str(Field::Imm)
+    }
+    fn get_jump_address_str(&self) -> Option<String> {
+        self.get_field_str(Field::Target)
+    }
+    fn to_mnemonic_string(&self) -> String {
+        self.decoded().to_mnemonic_string()
+    }
+    fn get_op_val(&self) -> i32 {
+        self.decoded().spec.opcode as i32
+    }
+    fn get_rs_val(&self) -> Option<i32> {
+        self.decoded().field_value(Field::Rs)
+    }
+    fn get_rt_val(&self) -> Option<i32> {
+        self.decoded().field_value(Field::Rt)
+    }
+    fn get_rd_val(&self) -> Option<i32> {
+        self.decoded().field_value(Field::Rd)
+    }
+    fn get_shamt_val(&self) -> Option<i32> {
+        self.decoded().field_value(Field::Shamt)
+    }
+    fn get_funct_val(&self) -> Option<i32> {
+        self.decoded().field_value(Field::Funct)
+    }
+    fn get_imm_val(&self) -> Option<i32> {
+        self.decoded().field_value(Field::Imm)
+    }
+    fn get_jump_address_val(&self) -> Option<i32> {
+        self.decoded().field_value(Field::Target)
+    }
+
+    /// get_field_str: the bits of a field, none if the instruction has no such field.
+    fn get_field_str(&self, field: Field) -> Option<String> {
+        let (hi, lo) = field.bits();
+        match self.decoded().spec.format.has_field(field) {
+            true => self.get_bits(hi, lo),
+            false => None,
+        }
+    }
 
     fn get_bits(&self, hi: usize, lo: usize) -> Option<String> {
         // need to check that hi is <= 31
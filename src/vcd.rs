@@ -0,0 +1,166 @@
+//!  Writes the datapath signals of every cycle as a Value Change Dump, to be compared with
+//! a hardware design in a waveform viewer such as GTKWave. Every cycle takes 10 ns, with
+//! the rising edge of `clk` at its start.
+//!  
+//! Since: 2026-10-18
+//! Version: 1.0
+use crate::mips::datapath::CycleTrace;
+
+/// The length of a cycle in the timescale of the dump.
+const CYCLE_TIME: u64 = 10;
+
+type Probe = fn(&CycleTrace) -> Option<u32>;
+
+/// The signals in the dump: their name, their width in bits and how to get their value
+/// from the trace of a cycle. A signal without a value in a cycle is dumped as unknown.
+const SIGNALS: [(&str, u32, Probe); 36] = [
+    ("pc", 32, |t| Some(t.pc)),
+    ("instruction", 32, |t| Some(t.instruction)),
+    ("pc_plus_4", 32, |t| Some(t.pc_plus_4)),
+    ("next_pc", 32, |t| Some(t.next_pc)),
+    ("read_reg_1", 5, |t| Some(t.read_reg_1 as u32)),
+    ("read_reg_2", 5, |t| Some(t.read_reg_2 as u32)),
+    ("read_data_1", 32, |t| Some(t.read_data_1 as u32)),
+    ("read_data_2", 32, |t| Some(t.read_data_2 as u32)),
+    ("write_reg", 5, |t| Some(t.write_reg as u32)),
+    ("write_data", 32, |t| Some(t.write_data as u32)),
+    ("imm", 32, |t| Some(t.imm as u32)),
+    ("shamt", 5, |t| Some(t.shamt)),
+    ("alu_control", 4, |t| Some(t.alu_signal as u32)),
+    ("alu_a", 32, |t| Some(t.alu_input_1 as u32)),
+    ("alu_b", 32, |t| Some(t.alu_input_2 as u32)),
+    ("alu_result", 32, |t| Some(t.alu_result as u32)),
+    ("zero", 1, |t| Some(t.zero as u32)),
+    ("mem_address", 32, |t| Some(t.alu_result as u32)),
+    ("mem_write_data", 32, |t| Some(t.read_data_2 as u32)),
+    ("mem_read_data", 32, |t| t.load.map(|load| load.value as u32)),
+    ("branch_target", 32, |t| Some(t.branch_target)),
+    ("jump_target", 32, |t| Some(t.jump_target)),
+    ("zero_extend", 1, |t| Some(t.control.zero_extend as u32)),
+    ("exit", 1, |t| Some(t.control.exit as u32)),
+    ("shift", 1, |t| Some(t.control.shift as u32)),
+    ("jump_reg", 1, |t| Some(t.control.jump_reg as u32)),
+    ("jump", 1, |t| Some(t.control.jump as u32)),
+    ("reg_dst", 1, |t| Some(t.control.reg_dest as u32)),
+    ("alu_src", 1, |t| Some(t.control.alu_src as u32)),
+    ("mem_to_reg", 1, |t| Some(t.control.mem_to_reg as u32)),
+    ("reg_write", 1, |t| Some(t.control.reg_write as u32)),
+    ("mem_read", 1, |t| Some(t.control.mem_read as u32)),
+    ("mem_write", 1, |t| Some(t.control.mem_write as u32)),
+    ("branch", 1, |t| Some(t.control.branch as u32)),
+    ("alu_op_1", 1, |t| Some(t.control.alu_op_1 as u32)),
+    ("alu_op_0", 1, |t| Some(t.control.alu_op_0 as u32)),
+];
+
+/// The identifier of the clock, the signals are identified by the characters after it.
+const CLOCK_ID: char = '!';
+
+/// The identifier of the signal at `index` in `SIGNALS`.
+fn signal_id(index: usize) -> char {
+    (CLOCK_ID as u8 + 1 + index as u8) as char
+}
+
+/// The value of a signal as it is written in the dump, followed by its identifier.
+fn value_change(width: u32, value: Option<u32>, id: char) -> String {
+    match (width, value) {
+        (1, Some(value)) => format!("{}{}", value, id),
+        (1, None) => format!("x{}", id),
+        (_, Some(value)) => format!("b{:b} {}", value, id),
+        (_, None) => format!("bx {}", id),
+    }
+}
+
+/// A Value Change Dump being written one cycle at a time.
+pub struct VcdWriter {
+    output: String,
+    /// The value of every signal in the last cycle, none before the first one
+    last: Option<Vec<Option<u32>>>,
+    cycles: u64,
+}
+
+impl VcdWriter {
+    /// Starts a dump by declaring the clock and every signal.
+    pub fn new() -> VcdWriter {
+        let mut output = String::new();
+        output.push_str("$version darken-assignment1 $end\n");
+        output.push_str("$timescale 1ns $end\n");
+        output.push_str("$scope module datapath $end\n");
+        output.push_str(&format!("$var wire 1 {} clk $end\n", CLOCK_ID));
+        for (index, (name, width, _)) in SIGNALS.iter().enumerate() {
+            output.push_str(&format!("$var wire {} {} {} $end\n", width, signal_id(index), name));
+        }
+        output.push_str("$upscope $end\n$enddefinitions $end\n");
+        VcdWriter {
+            output,
+            last: None,
+            cycles: 0,
+        }
+    }
+
+    /// Adds a cycle to the dump, writing the signals that changed since the last cycle.
+    pub fn add_cycle(&mut self, trace: &CycleTrace) {
+        let time = self.cycles * CYCLE_TIME;
+        let values: Vec<Option<u32>> = SIGNALS.iter().map(|(_, _, probe)| probe(trace)).collect();
+        self.output.push_str(&format!("#{}\n1{}\n", time, CLOCK_ID));
+        let first = self.last.is_none();
+        if first {
+            self.output.push_str("$dumpvars\n");
+        }
+        for (index, (_, width, _)) in SIGNALS.iter().enumerate() {
+            let changed = match &self.last {
+                Some(last) => last[index] != values[index],
+                None => true,
+            };
+            if changed {
+                self.output.push_str(&value_change(*width, values[index], signal_id(index)));
+                self.output.push('\n');
+            }
+        }
+        if first {
+            self.output.push_str("$end\n");
+        }
+        self.output.push_str(&format!("#{}\n0{}\n", time + CYCLE_TIME / 2, CLOCK_ID));
+        self.last = Some(values);
+        self.cycles += 1;
+    }
+
+    /// Ends the dump after the last cycle.
+    /// returns: the contents of the dump file
+    pub fn finish(mut self) -> String {
+        self.output.push_str(&format!("#{}\n", self.cycles * CYCLE_TIME));
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mips::simulator::{RunResult, Simulator};
+    use darken_assignment1::assembler::{assemble, AssemblerOptions};
+    use darken_assignment1::loader::MemoryImage;
+
+    #[test]
+    fn test_vcd() {
+        let program = assemble("addi $t0, $zero, 5\nadd $t1, $t0, $t0\nexit\n", &AssemblerOptions::default()).unwrap();
+        let mut simulator = Simulator::load(MemoryImage::from(program));
+        let mut vcd = VcdWriter::new();
+        loop {
+            let result = simulator.step();
+            vcd.add_cycle(simulator.get_last_cycle().unwrap());
+            if result != RunResult::Success {
+                break;
+            }
+        }
+        let vcd = vcd.finish();
+
+        assert!(vcd.starts_with("$version darken-assignment1 $end\n$timescale 1ns $end\n"));
+        assert!(vcd.contains("$var wire 1 ! clk $end\n$var wire 32 \" pc $end\n"));
+        assert!(vcd.contains("#0\n1!\n$dumpvars\nb0 \"\nb100000000010000000000000000101 #\n"));
+        // the memory is not read, the reg_write flag stays set in the second cycle
+        assert!(vcd.contains("bx 5\n"));
+        let second = &vcd[vcd.find("#10\n").unwrap()..vcd.find("#15\n").unwrap()];
+        assert!(second.contains("b100 \"\n"));
+        assert!(!second.contains(&format!("1{}", signal_id(29))));
+        assert!(vcd.ends_with("#25\n0!\n#30\n"));
+    }
+}
@@ -0,0 +1,8 @@
+# Counts $t0 up to 7 by branching backwards, then increments $t1
+        addi $t1, $zero, 7
+        addi $t3, $zero, 1
+loop:   addi $t0, $t0, 1
+        slt $t2, $t0, $t1
+        beq $t2, $t3, loop      # branch back while $t0 < $t1
+        addi $t1, $t1, 1
+        exit
@@ -0,0 +1,150 @@
+#!/usr/bin/env python3
+"""Links a big-endian MIPS ELF32 relocatable object into an executable.
+
+There is no MIPS linker in the environment the fixtures were made in, so this
+places `.text` and `.data` of a single object produced by llvm-mc, applies its
+relocations and writes an ET_EXEC file with one PT_LOAD segment per section
+and the symbol table of the object.
+
+Usage: link_fixture.py input.o output.elf
+"""
+import struct
+import sys
+
+TEXT_BASE = 0x00400000
+DATA_BASE = 0x00001000
+ENTRY_SYMBOL = "__start"
+
+R_MIPS_32, R_MIPS_26, R_MIPS_HI16, R_MIPS_LO16 = 2, 4, 5, 6
+
+
+def read_object(data):
+    ident = data[:16]
+    assert ident[:4] == b"\x7fELF" and ident[4] == 1 and ident[5] == 2, "not a 32-bit big-endian ELF"
+    (e_type, e_machine, _, _, _, e_shoff, _, _, _, _, e_shentsize, e_shnum, e_shstrndx) = struct.unpack(
+        ">HHIIIIIHHHHHH", data[16:52])
+    assert e_type == 1 and e_machine == 8, "not a MIPS relocatable object"
+    sections = []
+    for i in range(e_shnum):
+        fields = struct.unpack(">IIIIIIIIII", data[e_shoff + i * e_shentsize:e_shoff + (i + 1) * e_shentsize])
+        sections.append(dict(zip(
+            ["name", "type", "flags", "addr", "offset", "size", "link", "info", "addralign", "entsize"], fields)))
+    names = sections[e_shstrndx]
+    for section in sections:
+        start = names["offset"] + section["name"]
+        section["name"] = data[start:data.index(b"\0", start)].decode()
+        section["data"] = bytearray(data[section["offset"]:section["offset"] + section["size"]])
+    return sections
+
+
+def string(table, offset):
+    return table[offset:table.index(b"\0", offset)].decode()
+
+
+def main(input_path, output_path):
+    with open(input_path, "rb") as file:
+        sections = read_object(file.read())
+    by_name = {section["name"]: index for index, section in enumerate(sections)}
+    text, data = sections[by_name[".text"]], sections[by_name[".data"]]
+    bases = {by_name[".text"]: TEXT_BASE, by_name[".data"]: DATA_BASE}
+
+    symtab = next(section for section in sections if section["type"] == 2)
+    strtab = sections[symtab["link"]]["data"]
+    symbols = []
+    for offset in range(0, symtab["size"], 16):
+        name, value, size, info, other, shndx = struct.unpack(">IIIBBH", symtab["data"][offset:offset + 16])
+        symbols.append((string(strtab, name), value + bases.get(shndx, 0), size, info, other, shndx))
+
+    for section in sections:
+        if section["type"] != 9 or section["info"] not in bases:
+            continue
+        target = sections[section["info"]]
+        place = bases[section["info"]]
+        relocations = [struct.unpack(">II", section["data"][i:i + 8]) for i in range(0, section["size"], 8)]
+        for index, (offset, info) in enumerate(relocations):
+            kind, value = info & 0xff, symbols[info >> 8][1]
+            word = struct.unpack(">I", target["data"][offset:offset + 4])[0]
+            if kind == R_MIPS_32:
+                word = (word + value) & 0xffffffff
+            elif kind == R_MIPS_26:
+                address = (((word & 0x3ffffff) << 2) | ((place + offset) & 0xf0000000)) + value
+                word = (word & 0xfc000000) | ((address >> 2) & 0x3ffffff)
+            elif kind == R_MIPS_HI16:
+                # the addend is split between this and the next R_MIPS_LO16
+                low_offset = next(o for o, i in relocations[index:] if i & 0xff == R_MIPS_LO16)
+                low = struct.unpack(">h", target["data"][low_offset + 2:low_offset + 4])[0]
+                address = ((word & 0xffff) << 16) + low + value
+                word = (word & 0xffff0000) | (((address + 0x8000) >> 16) & 0xffff)
+            elif kind == R_MIPS_LO16:
+                address = struct.unpack(">h", target["data"][offset + 2:offset + 4])[0] + value
+                word = (word & 0xffff0000) | (address & 0xffff)
+            else:
+                raise ValueError("unsupported relocation type %d" % kind)
+            target["data"][offset:offset + 4] = struct.pack(">I", word)
+
+    # sections of the executable: null, .text, .data, .symtab, .strtab, .shstrtab
+    new_index = {by_name[".text"]: 1, by_name[".data"]: 2}
+    symbol_bytes = bytearray()
+    symbol_names = bytearray(b"\0")
+    locals_count = 0
+    entry = None
+    for name, value, size, info, other, shndx in symbols:
+        if shndx not in new_index and name:
+            continue
+        if name == ENTRY_SYMBOL:
+            entry = value
+        name_offset = 0
+        if name:
+            name_offset = len(symbol_names)
+            symbol_names += name.encode() + b"\0"
+        if info >> 4 == 0:
+            locals_count += 1
+        symbol_bytes += struct.pack(">IIIBBH", name_offset, value, size, info, other, new_index.get(shndx, 0))
+    assert entry is not None, "no %s symbol" % ENTRY_SYMBOL
+
+    section_names = bytearray(b"\0")
+
+    def section_name(name):
+        offset = len(section_names)
+        section_names.extend(name.encode() + b"\0")
+        return offset
+
+    program_headers_offset = 52
+    text_offset = 0x1000
+    data_offset = text_offset + ((len(text["data"]) + 0xfff) & ~0xfff)
+    symtab_offset = data_offset + len(data["data"])
+    strtab_offset = symtab_offset + len(symbol_bytes)
+    section_headers = [struct.pack(">IIIIIIIIII", *([0] * 10))]
+    section_headers.append(struct.pack(">IIIIIIIIII", section_name(".text"), 1, 6, TEXT_BASE, text_offset,
+                                       len(text["data"]), 0, 0, 4, 0))
+    section_headers.append(struct.pack(">IIIIIIIIII", section_name(".data"), 1, 3, DATA_BASE, data_offset,
+                                       len(data["data"]), 0, 0, 4, 0))
+    section_headers.append(struct.pack(">IIIIIIIIII", section_name(".symtab"), 2, 0, 0, symtab_offset,
+                                       len(symbol_bytes), 4, locals_count, 4, 16))
+    section_headers.append(struct.pack(">IIIIIIIIII", section_name(".strtab"), 3, 0, 0, strtab_offset,
+                                       len(symbol_names), 0, 0, 1, 0))
+    shstrtab_name = section_name(".shstrtab")
+    shstrtab_offset = strtab_offset + len(symbol_names)
+    section_headers.append(struct.pack(">IIIIIIIIII", shstrtab_name, 3, 0, 0, shstrtab_offset,
+                                       len(section_names), 0, 0, 1, 0))
+    section_headers_offset = (shstrtab_offset + len(section_names) + 3) & ~3
+
+    output = bytearray(b"\x7fELF\x01\x02\x01" + b"\0" * 9)
+    output += struct.pack(">HHIIIIIHHHHHH", 2, 8, 1, entry, program_headers_offset, section_headers_offset,
+                          0x50001000, 52, 32, 2, 40, len(section_headers), 5)
+    output += struct.pack(">IIIIIIII", 1, text_offset, TEXT_BASE, TEXT_BASE, len(text["data"]),
+                          len(text["data"]), 5, 0x1000)
+    output += struct.pack(">IIIIIIII", 1, data_offset, DATA_BASE, DATA_BASE, len(data["data"]),
+                          len(data["data"]), 6, 0x1000)
+    output += b"\0" * (text_offset - len(output))
+    output += text["data"]
+    output += b"\0" * (data_offset - len(output))
+    output += data["data"] + symbol_bytes + symbol_names + section_names
+    output += b"\0" * (section_headers_offset - len(output))
+    output += b"".join(section_headers)
+    with open(output_path, "wb") as file:
+        file.write(output)
+
+
+if __name__ == "__main__":
+    main(*sys.argv[1:])
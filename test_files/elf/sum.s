@@ -0,0 +1,26 @@
+# Sums the numbers from `count` down to 1 into `total`.
+# Assembled with llvm-mc and linked with link_fixture.py, see README.
+	.set noreorder
+	.text
+skipped:
+	addi $t3, $zero, 1	# before the entry point, never executed
+	.globl __start
+__start:
+	lw $t1, %lo(count)($zero)
+	addi $t2, $zero, 0
+loop:
+	beq $t1, $zero, done
+	nop
+	add $t2, $t2, $t1
+	addi $t1, $t1, -1
+	beq $zero, $zero, loop
+	nop
+done:
+	sw $t2, %lo(total)($zero)
+	j finish
+	nop
+finish:
+	.word 0xffffffff	# exit
+	.data
+count:	.word 10
+total:	.word 0
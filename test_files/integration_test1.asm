@@ -0,0 +1,7 @@
+# Counts $t1 up to 3 while counting $t0 down to 0
+        addi $t0, $zero, 3
+loop:   beq $t0, $zero, end
+        addi $t0, $t0, -1
+        addi $t1, $t1, 1
+        j loop
+end:    exit
@@ -0,0 +1,10 @@
+# Jumps forwards to start, then back to back using jr
+        j start
+back:   addi $t1, $zero, 10
+        addi $t2, $zero, 20
+        j end
+start:  addi $t0, $zero, 10
+        addi $t3, $zero, 1
+        addi $t4, $zero, 4      # address of back
+        jr $t4
+end:    exit
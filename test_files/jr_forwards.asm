@@ -0,0 +1,6 @@
+# Jumps over the instructions setting $t1 using jr
+        addi $t0, $zero, 16     # address of target
+        jr $t0
+        addi $t1, $zero, 1      # skipped
+        addi $t1, $zero, 2      # skipped
+target: addi $t2, $zero, 10
@@ -0,0 +1,4 @@
+# Stores 10000 in the word at address 4 and loads it back
+        addi $t0, $zero, 10000
+        sw $t0, 4($zero)
+        lw $t1, 4($zero)
@@ -0,0 +1,5 @@
+# Stores and loads the word at address 8 using a base register and an offset
+        addi $t0, $zero, 4      # base address
+        addi $t2, $zero, 200
+        sw $t2, 4($t0)
+        lw $t1, 4($t0)
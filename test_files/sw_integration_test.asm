@@ -0,0 +1,10 @@
+# Stores 10, 9, ..., 0 in the words at addresses 4, 8, ..., 44
+        addi $t0, $zero, 10     # value to store
+        addi $t1, $zero, 4      # address to store it at
+        addi $t2, $zero, 1
+loop:   sw $t0, 0($t1)
+        beq $t0, $zero, end
+        sub $t0, $t0, $t2
+        addi $t1, $t1, 4
+        j loop
+end:    exit
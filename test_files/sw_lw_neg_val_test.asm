@@ -0,0 +1,4 @@
+# Stores a negative value in memory and loads it back
+        addi $t0, $zero, -100
+        sw $t0, 4($zero)
+        lw $t2, 4($zero)